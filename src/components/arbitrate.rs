@@ -0,0 +1,583 @@
+//! Arbitrate escrow dispute component.
+
+use bitcoin::{Amount, consensus, hex::DisplayHex};
+use dioxus::prelude::*;
+
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::{info, trace};
+
+use crate::{
+    ESPLORA_ENDPOINT, NETWORK, Route,
+    error::Error,
+    esplora::{FeeEstimate, create_client, get_fee_estimates},
+    tx::{
+        ArbitratorFee, FeePolicy, PayoutAmount, funding_value, merged_dust, payout_fee,
+        payout_psbt, ruling_payouts,
+    },
+    util::{parse_dust_policy, parse_fee_rate, parse_network, parse_percent},
+    validation::{ValidationField, validate_input},
+};
+
+use super::{
+    ArbitratorsInput, ContinueButton, CopyButton, DustPolicyInput, FeeOutput, FeeRateSelector,
+    Footer, FundingInput, InternalKeyInput, NetworkInput, PartiesInput, PrimaryButton, RefundInput,
+    ThresholdInput, TimelockInput, TransactionOutput, has_funding_errors, has_parties_errors,
+    has_refund_errors, parse_escrow_contract, parse_funding, parse_payout_addresses, parse_refund,
+    use_arbitrators, use_funding, use_parties, use_refund, validate_arbitrators, validate_funding,
+    validate_parties, validate_refund, validate_threshold, validate_timelock,
+};
+
+/// Arbitrate escrow dispute component.
+///
+/// The arbitrator drafts the unsigned dispute transaction of a ruling,
+/// to be signed by the arbitrators and the winning party.
+#[component]
+pub(crate) fn Arbitrate() -> Element {
+    let parties = use_parties();
+    let cooperative_threshold = use_signal(String::new);
+    let arbitrated_threshold = use_signal(String::new);
+    let arbitrators = use_arbitrators();
+    let arbitrator_threshold = use_signal(String::new);
+    let refund = use_refund();
+    let timelock_days = use_signal(String::new);
+    let timelock_hours = use_signal(String::new);
+    let timelock_mode = use_signal(String::new);
+    let timelock_absolute = use_signal(String::new);
+    let internal_key = use_signal(String::new);
+    let funding = use_funding();
+    let mut winner = use_signal(|| "1".to_string());
+    let mut fee_arbitrator = use_signal(|| "1".to_string());
+    let mut arbitrator_fee = use_signal(String::new);
+    let mut arbitrator_fee_unit = use_signal(|| "%".to_string());
+    let mut fee_payer = use_signal(|| "Winner".to_string());
+    let mut fee_rate = use_signal(String::new);
+    let dust_policy = use_signal(String::new);
+    let fee_estimates = use_signal(|| Option::<FeeEstimate>::None);
+    let mut escrow_script_str = use_signal(String::new);
+    let mut ruling_transaction = use_signal(String::new);
+    let mut ruling_psbt_str = use_signal(String::new);
+    let mut ruling_fee_str = use_signal(String::new);
+    let mut dust_warning = use_signal(|| Option::<String>::None);
+
+    let mut cooperative_threshold_error = use_signal(|| None);
+    let mut arbitrated_threshold_error = use_signal(|| None);
+    let mut arbitrator_threshold_error = use_signal(|| None);
+    let timelock_days_error = use_signal(|| None);
+    let timelock_hours_error = use_signal(|| None);
+    let timelock_absolute_error = use_signal(|| Option::<String>::None);
+    let mut arbitrator_fee_error = use_signal(|| Option::<String>::None);
+    let mut fee_rate_error = use_signal(|| None);
+    let mut contract_error = use_signal(|| Option::<String>::None);
+
+    let has_ruling_form_errors = move || {
+        has_parties_errors(&parties.read())
+            || cooperative_threshold_error.read().is_some()
+            || arbitrated_threshold_error.read().is_some()
+            || has_parties_errors(&arbitrators.read())
+            || arbitrator_threshold_error.read().is_some()
+            || timelock_days_error.read().is_some()
+            || timelock_hours_error.read().is_some()
+            || timelock_absolute_error.read().is_some()
+            || has_funding_errors(&funding.read())
+            || arbitrator_fee_error.read().is_some()
+            || fee_rate_error.read().is_some()
+            || has_refund_errors(refund)
+    };
+
+    // The arbitrator fee is optional, in percent of the escrow or in BTC.
+    let validate_arbitrator_fee = move |fee: &str, unit: &str| {
+        let field = if unit == "%" {
+            ValidationField::Percent
+        } else {
+            ValidationField::Amount
+        };
+        validate_input(fee, field, false)
+            .err()
+            .map(|e| e.to_string())
+    };
+
+    let mut validate_ruling_form = move || {
+        validate_parties(&parties.read(), false);
+        let party_count = parties.read().len();
+        cooperative_threshold_error.set(validate_threshold(
+            &cooperative_threshold.read(),
+            party_count,
+        ));
+        validate_arbitrators(&arbitrators.read());
+        arbitrator_threshold_error.set(validate_threshold(
+            &arbitrator_threshold.read(),
+            arbitrators.read().len(),
+        ));
+        arbitrated_threshold_error.set(validate_threshold(
+            &arbitrated_threshold.read(),
+            party_count,
+        ));
+        validate_timelock(
+            &timelock_mode.read(),
+            &timelock_days.read(),
+            &timelock_hours.read(),
+            &timelock_absolute.read(),
+            timelock_days_error,
+            timelock_hours_error,
+            timelock_absolute_error,
+        );
+        validate_refund(refund);
+        validate_funding(&funding.read());
+        arbitrator_fee_error.set(validate_arbitrator_fee(
+            &arbitrator_fee.read(),
+            &arbitrator_fee_unit.read(),
+        ));
+        fee_rate_error.set(
+            validate_input(&fee_rate.read(), ValidationField::FeeRate, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+    };
+
+    use_effect(move || {
+        to_owned![fee_estimates];
+
+        spawn(async move {
+            let esplora_client = create_client(&ESPLORA_ENDPOINT.read()).unwrap();
+            match get_fee_estimates(&esplora_client).await {
+                Ok(estimates) => {
+                    #[cfg(debug_assertions)]
+                    trace!(?estimates, "Fee estimates fetched successfully");
+                    fee_estimates.set(Some(estimates));
+                }
+                Err(e) => {
+                    #[cfg(debug_assertions)]
+                    trace!(%e, "Error fetching fee estimates: {}", e);
+                    // Fall back to 1 sat/vB
+                    fee_rate.set("1".to_string());
+                }
+            }
+        });
+    });
+
+    let arbitrator_fee_class = if arbitrator_fee_error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        main { class: "max-w-7xl mx-auto py-6 sm:px-6 lg:px-8",
+            div { class: "px-4 py-6 sm:px-0",
+                h1 { class: "text-2xl font-bold text-gray-900 mb-6", "Arbitrate Dispute" }
+
+                div { class: "bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            h3 { class: "text-lg font-medium text-gray-900", "Escrow" }
+
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                PartiesInput {
+                                    parties,
+                                    amounts: false,
+                                    fee_shares: false,
+                                    payout_addresses: true,
+                                }
+
+                                ThresholdInput {
+                                    update_var: cooperative_threshold,
+                                    label: "Cooperative Threshold",
+                                    id: "cooperative_threshold",
+                                    parties: parties.read().len(),
+                                    placeholder: "All parties",
+                                    error: cooperative_threshold_error,
+                                }
+
+                                ArbitratorsInput { arbitrators }
+
+                                ThresholdInput {
+                                    update_var: arbitrator_threshold,
+                                    label: "Arbitrator Threshold",
+                                    id: "arbitrator_threshold",
+                                    parties: arbitrators.read().len(),
+                                    placeholder: "All arbitrators",
+                                    error: arbitrator_threshold_error,
+                                }
+
+                                ThresholdInput {
+                                    update_var: arbitrated_threshold,
+                                    label: "Arbitrated Threshold",
+                                    id: "arbitrated_threshold",
+                                    parties: parties.read().len(),
+                                    placeholder: "1",
+                                    error: arbitrated_threshold_error,
+                                }
+
+                                TimelockInput {
+                                    update_mode_var: timelock_mode,
+                                    update_day_var: timelock_days,
+                                    update_hour_var: timelock_hours,
+                                    update_absolute_var: timelock_absolute,
+                                    day_error: timelock_days_error,
+                                    hour_error: timelock_hours_error,
+                                    absolute_error: timelock_absolute_error,
+                                    required: true,
+                                }
+
+                                RefundInput {
+                                    refund,
+                                    parties: parties.read().len(),
+                                    payout: false,
+                                }
+
+                                NetworkInput { id: "network", label: "Bitcoin Network" }
+
+                                InternalKeyInput { update_var: internal_key }
+
+                                FundingInput {
+                                    id: "funding",
+                                    label: "Funding",
+                                    hint: "Every output that pays to the escrow address is spent as its own input. The funding outputs must add up to the escrow exactly.",
+                                    funding,
+                                }
+                            }
+
+                            div { class: "border-t border-gray-200 pt-6",
+                                h3 { class: "text-lg font-medium text-gray-900", "Ruling" }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                    div { class: "sm:col-span-2",
+                                        label {
+                                            r#for: "winner",
+                                            class: "block text-sm font-medium text-gray-700",
+                                            "Winning Party"
+                                        }
+                                        div { class: "mt-1",
+                                            select {
+                                                id: "winner",
+                                                class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                                                value: winner,
+                                                oninput: move |event| {
+                                                    #[cfg(debug_assertions)]
+                                                    trace!(% winner, event_value =% event.value(), "Set winning party");
+                                                    winner.set(event.value());
+                                                },
+                                                for i in 1..=parties.read().len() {
+                                                    option { key: "{i}", value: "{i}", "Party {i}" }
+                                                }
+                                            }
+                                        }
+                                        p { class: "mt-2 text-xs text-gray-500",
+                                            "Gets the whole escrow after the arbitrator fee and its share of the fee."
+                                        }
+                                    }
+
+                                    div { class: "sm:col-span-2",
+                                        label {
+                                            r#for: "arbitrator_fee",
+                                            class: "block text-sm font-medium text-gray-700",
+                                            "Arbitrator Fee (optional)"
+                                        }
+                                        div { class: "mt-1 flex gap-2",
+                                            input {
+                                                r#type: "number",
+                                                min: "0",
+                                                step: "any",
+                                                id: "arbitrator_fee",
+                                                class: arbitrator_fee_class,
+                                                placeholder: "0",
+                                                value: arbitrator_fee,
+                                                oninput: move |event| {
+                                                    #[cfg(debug_assertions)]
+                                                    trace!(% arbitrator_fee, event_value =% event.value(), "Set arbitrator fee");
+                                                    arbitrator_fee.set(event.value());
+                                                    arbitrator_fee_error
+                                                        .set(
+                                                            validate_arbitrator_fee(&event.value(), &arbitrator_fee_unit.read()),
+                                                        );
+                                                },
+                                            }
+                                            select {
+                                                id: "arbitrator_fee_unit",
+                                                class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block sm:text-sm border-gray-300 rounded-md p-2 border",
+                                                value: arbitrator_fee_unit,
+                                                oninput: move |event| {
+                                                    arbitrator_fee_unit.set(event.value());
+                                                    arbitrator_fee_error
+                                                        .set(validate_arbitrator_fee(&arbitrator_fee.read(), &event.value()));
+                                                },
+                                                option { value: "%", "%" }
+                                                option { value: "BTC", "BTC" }
+                                            }
+                                        }
+                                        if let Some(error_msg) = arbitrator_fee_error.read().as_ref() {
+                                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                                        }
+                                    }
+
+                                    div { class: "sm:col-span-2",
+                                        label {
+                                            r#for: "fee_arbitrator",
+                                            class: "block text-sm font-medium text-gray-700",
+                                            "Paid Arbitrator"
+                                        }
+                                        div { class: "mt-1",
+                                            select {
+                                                id: "fee_arbitrator",
+                                                class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                                                value: fee_arbitrator,
+                                                oninput: move |event| {
+                                                    #[cfg(debug_assertions)]
+                                                    trace!(% fee_arbitrator, event_value =% event.value(), "Set paid arbitrator");
+                                                    fee_arbitrator.set(event.value());
+                                                },
+                                                for i in 1..=arbitrators.read().len() {
+                                                    option { key: "{i}", value: "{i}", "Arbitrator {i}" }
+                                                }
+                                            }
+                                        }
+                                        p { class: "mt-2 text-xs text-gray-500",
+                                            "The fee is paid to the resolution address of the arbitrator's npub."
+                                        }
+                                    }
+
+                                    div { class: "sm:col-span-2",
+                                        label {
+                                            r#for: "fee_payer",
+                                            class: "block text-sm font-medium text-gray-700",
+                                            "Transaction Fee Payer"
+                                        }
+                                        div { class: "mt-1",
+                                            select {
+                                                id: "fee_payer",
+                                                class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                                                value: fee_payer,
+                                                oninput: move |event| {
+                                                    #[cfg(debug_assertions)]
+                                                    trace!(% fee_payer, event_value =% event.value(), "Set fee payer");
+                                                    fee_payer.set(event.value());
+                                                },
+                                                option { value: "Winner", "The winner pays" }
+                                                option { value: "Arbitrator", "The arbitrator fee pays" }
+                                                option { value: "Equal", "Equal shares" }
+                                                option { value: "Proportional", "Proportional to the payouts" }
+                                            }
+                                        }
+                                        p { class: "mt-2 text-xs text-gray-500",
+                                            "Only the arbitrator fee output can pay the fee when the arbitrator is paid."
+                                        }
+                                    }
+
+                                    FeeRateSelector {
+                                        id: "fee",
+                                        label_input: "Fee rate (sats/vByte)",
+                                        label_dropdown: "Target Blocks",
+                                        update_var: fee_rate,
+                                        fee_estimates,
+                                        error: fee_rate_error,
+                                    }
+
+                                    DustPolicyInput { update_var: dust_policy }
+                                }
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end",
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_ruling_form();
+                                            if has_ruling_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot generate ruling");
+                                                return;
+                                            }
+                                            #[cfg(debug_assertions)]
+                                            trace!(
+                                                parties = % parties.read().len(), arbitrators = % arbitrators.read().len(),
+                                                % winner, % arbitrator_fee, % arbitrator_fee_unit, % fee_payer, % fee_rate,
+                                                % NETWORK,
+                                                "Clicked Generate Ruling"
+                                            );
+                                            let network = parse_network(&NETWORK.read()).unwrap();
+                                            let funding = parse_funding(&funding.read());
+                                            let fee_rate = parse_fee_rate(&fee_rate.read()).unwrap();
+                                            let winner = winner.read().parse::<usize>().unwrap() - 1;
+                                            let dust_policy = parse_dust_policy(&dust_policy.read()).unwrap();
+                                            let fee_policy = match fee_payer.read().as_str() {
+                                                "Arbitrator" => FeePolicy::Payer(1),
+                                                "Equal" => FeePolicy::Equal,
+                                                "Proportional" => FeePolicy::Proportional,
+                                                _ => FeePolicy::Payer(0),
+                                            };
+                                            let resolved = parse_escrow_contract(
+                                                    &parties.read(),
+                                                    &cooperative_threshold.read(),
+                                                    &arbitrators.read(),
+                                                    &arbitrator_threshold.read(),
+                                                    &arbitrated_threshold.read(),
+                                                    &timelock_mode.read(),
+                                                    &timelock_days.read(),
+                                                    &timelock_hours.read(),
+                                                    &timelock_absolute.read(),
+                                                    &internal_key.read(),
+                                                )
+                                                .and_then(|contract| parse_refund(contract, refund))
+                                                .and_then(|contract| parse_payout_addresses(contract, &parties.read(), network))
+                                                .and_then(|contract| {
+                                                    let fee = if arbitrator_fee.read().is_empty() {
+                                                        None
+                                                    } else {
+                                                        let amount = if *arbitrator_fee_unit.read() == "%" {
+                                                            PayoutAmount::Percent(parse_percent(&arbitrator_fee.read())?)
+                                                        } else {
+                                                            PayoutAmount::Fixed(
+                                                                Amount::from_btc(arbitrator_fee.read().parse::<f64>().unwrap())
+                                                                    .unwrap(),
+                                                            )
+                                                        };
+                                                        let index = fee_arbitrator.read().parse::<usize>().unwrap() - 1;
+                                                        let Some(arbitrator) = contract.arbitrators().get(index) else {
+                                                            return Err(
+                                                                Error::WrongInputs(
+                                                                    format!("Arbitrator {} is not an arbitrator of the escrow", index + 1),
+                                                                ),
+                                                            );
+                                                        };
+                                                        Some(ArbitratorFee {
+                                                            arbitrator: *arbitrator,
+                                                            amount,
+                                                        })
+                                                    };
+                                                    let escrow_value = funding_value(&funding)?;
+                                                    // The weight does not depend on who pays the fee.
+                                                    let (escrow_script, payouts) = ruling_payouts(
+                                                        &contract,
+                                                        winner,
+                                                        fee.as_ref(),
+                                                        escrow_value,
+                                                        Amount::ZERO,
+                                                        &FeePolicy::Equal,
+                                                        network,
+                                                    )?;
+                                                    let ruling_fee = payout_fee(
+                                                        &contract,
+                                                        escrow_script,
+                                                        &payouts,
+                                                        &funding,
+                                                        fee_rate,
+                                                    )?;
+                                                    let (escrow_script, payouts) = ruling_payouts(
+                                                        &contract,
+                                                        winner,
+                                                        fee.as_ref(),
+                                                        escrow_value,
+                                                        ruling_fee.fee,
+                                                        &fee_policy,
+                                                        network,
+                                                    )?;
+                                                    let psbt = payout_psbt(
+                                                        &contract,
+                                                        escrow_script,
+                                                        &payouts,
+                                                        &funding,
+                                                        ruling_fee.fee,
+                                                        dust_policy,
+                                                    )?;
+                                                    let dust = merged_dust(&psbt, ruling_fee.fee)?;
+                                                    Ok((escrow_script, ruling_fee, psbt, dust))
+                                                });
+                                            let (escrow_script, ruling_fee, psbt, dust) = match resolved {
+                                                Ok(resolved) => resolved,
+                                                Err(e) => {
+                                                    contract_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            };
+                                            contract_error.set(None);
+                                            let resolved_ruling_transaction = consensus::serialize(&psbt.unsigned_tx)
+                                                .as_hex()
+                                                .to_string();
+                                            #[cfg(debug_assertions)]
+                                            info!(
+                                                ? escrow_script, % resolved_ruling_transaction, % psbt,
+                                                "Derived ruling transaction"
+                                            );
+                                            escrow_script_str.set(format!("{escrow_script:?}"));
+                                            ruling_transaction.set(resolved_ruling_transaction);
+                                            ruling_psbt_str.set(psbt.to_string());
+                                            ruling_fee_str.set(ruling_fee.to_string());
+                                            dust_warning
+                                                .set(
+                                                    (dust > Amount::ZERO)
+                                                        .then(|| {
+                                                            format!(
+                                                                "{} sats of dust outputs were merged into the fee. Check the outputs before signing.",
+                                                                dust.to_sat(),
+                                                            )
+                                                        }),
+                                                );
+                                        },
+                                        text: "Generate Ruling",
+                                    }
+                                }
+                            }
+                            if let Some(error_msg) = contract_error.read().as_ref() {
+                                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                            }
+                        }
+                    }
+                }
+
+                // Result Section (would be shown after button click)
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        h3 { class: "text-lg leading-6 font-medium text-gray-900",
+                            "Unsigned Ruling"
+                        }
+                        if !escrow_script_str.read().is_empty() {
+                            p { class: "mt-2 text-sm text-gray-500",
+                                "Spends the dispute leaf {escrow_script_str}, to be signed by the arbitrators and the winning party."
+                            }
+                        }
+
+                        div { class: "mt-5 border-t border-gray-200 pt-5",
+                            dl { class: "grid grid-cols-1 gap-x-4 gap-y-6 sm:grid-cols-2",
+                                TransactionOutput {
+                                    update_var: ruling_transaction,
+                                    label: "Unsigned Ruling Transaction",
+                                    id: "ruling-tx",
+                                    placeholder: "Transaction data will appear here...",
+                                }
+
+                                TransactionOutput {
+                                    update_var: ruling_psbt_str,
+                                    label: "Unsigned Ruling PSBT",
+                                    id: "ruling-psbt",
+                                    placeholder: "PSBT data will appear here...",
+                                }
+
+                                FeeOutput {
+                                    update_var: ruling_fee_str,
+                                    label: "Ruling Size and Fee",
+                                    id: "ruling-fee",
+                                }
+
+                                if let Some(warning) = dust_warning.read().as_ref() {
+                                    p { class: "sm:col-span-2 text-xs text-red-600", "{warning}" }
+                                }
+                            }
+                        }
+
+                        div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
+                            CopyButton {
+                                text: "Transaction",
+                                clipboard_text: ruling_transaction,
+                            }
+                            CopyButton { text: "PSBT", clipboard_text: ruling_psbt_str }
+                            ContinueButton {
+                                to: Route::Sign {},
+                                text: "Continue to Sign",
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Footer {}
+    }
+}
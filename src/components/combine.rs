@@ -1,60 +1,114 @@
 //! Combine escrow signatures component.
 
-use bitcoin::{Transaction, consensus, hex::DisplayHex};
+use std::collections::BTreeMap;
+
+use bitcoin::{Psbt, Transaction, consensus, hex::DisplayHex};
 use dioxus::prelude::*;
 
 #[cfg(debug_assertions)]
 use dioxus::logger::tracing::{info, trace};
-use secp256k1::schnorr;
 
 use crate::{
-    Route,
-    scripts::{escrow_scripts, escrow_spend_info},
-    sign::combine_signatures,
-    util::{days_to_blocks, hours_to_blocks, parse_escrow_type, parse_npub},
+    NETWORK, Route,
+    error::Error,
+    scripts::{contract_address, contract_scripts, contract_spend_info},
+    sign::{
+        combine_contract_signatures, escrow_prevouts, finalize_escrow_psbt,
+        map_contract_signatures, verify_contract_signatures, verify_escrow_psbt,
+    },
+    util::{
+        npub_to_x_only_public_key, parse_escrow_type, parse_network, parse_npub, parse_signatures,
+        parse_spent_outputs,
+    },
     validation::{ValidationField, validate_input},
 };
 
 use super::{
-    ContinueButton, CopyButton, EscrowTypeInput, Footer, NpubInput, PrimaryButton, SignatureInput,
-    TimelockInput, TransactionInput, TransactionOutput,
+    ArbitratorsInput, ContinueButton, CopyButton, EscrowTypeInput, Footer, InternalKeyInput,
+    PartiesInput, PrimaryButton, PsbtInput, RefundInput, SecondaryButton, SignatureInput,
+    SpentOutputsInput, ThresholdInput, TimelockInput, TransactionInput, TransactionOutput,
+    has_arbitrators, has_parties_errors, has_refund_errors, parse_escrow_contract, parse_refund,
+    use_arbitrators, use_parties, use_refund, validate_arbitrators, validate_parties,
+    validate_refund, validate_threshold, validate_timelock,
 };
 
 /// Combine escrow transaction component.
 #[component]
 pub(crate) fn Combine() -> Element {
+    // One signed PSBT per signer, only the first one is required.
+    let mut psbts = use_signal(|| {
+        vec![
+            (Signal::new(String::new()), Signal::new(None)),
+            (Signal::new(String::new()), Signal::new(None)),
+        ]
+    });
+
+    // The invalid signatures of the combined PSBT, skipped when finalizing it.
+    let mut psbt_signature_errors = use_signal(Vec::<String>::new);
+
+    let has_combine_psbt_form_errors =
+        move || psbts.read().iter().any(|(_, error)| error.read().is_some());
+
+    let validate_combine_psbt_form = move || {
+        for (i, (psbt, mut error)) in psbts.read().iter().copied().enumerate() {
+            error.set(
+                validate_input(&psbt.read(), ValidationField::Psbt, i == 0)
+                    .err()
+                    .map(|e| e.to_string()),
+            );
+        }
+    };
+
+    // The signatures of each signer in any order, at least one is required.
+    let mut signatures = use_signal(|| {
+        vec![
+            (Signal::new(String::new()), Signal::new(None)),
+            (Signal::new(String::new()), Signal::new(None)),
+        ]
+    });
     let unsigned_tx = use_signal(String::new);
+    let spent_outputs = use_signal(String::new);
     let mut signed_tx_str = use_signal(String::new);
     let escrow_type = use_signal(String::new);
-    let npub_buyer = use_signal(String::new);
-    let npub_seller = use_signal(String::new);
-    let signature_1 = use_signal(String::new);
-    let signature_2 = use_signal(String::new);
-    let npub_arbitrator = use_signal(String::new);
+    let internal_key = use_signal(String::new);
+    let parties = use_parties();
+    let cooperative_threshold = use_signal(String::new);
+    let arbitrated_threshold = use_signal(String::new);
+    let arbitrators = use_arbitrators();
+    let arbitrator_threshold = use_signal(String::new);
+    let refund = use_refund();
     let timelock_days = use_signal(String::new);
     let timelock_hours = use_signal(String::new);
-    let signature_arbitrator = use_signal(String::new);
+    let timelock_mode = use_signal(String::new);
+    let timelock_absolute = use_signal(String::new);
 
     let mut unsigned_tx_error = use_signal(|| None);
-    let mut npub_buyer_error = use_signal(|| None);
-    let mut npub_seller_error = use_signal(|| None);
-    let mut npub_arbitrator_error: Signal<Option<String>> = use_signal(|| None);
-    let mut timelock_days_error = use_signal(|| None);
-    let mut timelock_hours_error = use_signal(|| None);
-    let mut signature_1_error = use_signal(|| None);
-    let mut signature_2_error = use_signal(|| None);
-    let mut signature_arbitrator_error = use_signal(|| None);
+    let mut spent_outputs_error = use_signal(|| None);
+    let mut cooperative_threshold_error = use_signal(|| None);
+    let mut arbitrated_threshold_error = use_signal(|| None);
+    let mut arbitrator_threshold_error = use_signal(|| None);
+    let timelock_days_error = use_signal(|| None);
+    let timelock_hours_error = use_signal(|| None);
+    let timelock_absolute_error = use_signal(|| Option::<String>::None);
+    let mut combine_error = use_signal(|| Option::<String>::None);
+    let mut signature_errors = use_signal(Vec::<String>::new);
 
     let has_combine_form_errors = move || {
         unsigned_tx_error.read().is_some()
-            || npub_buyer_error.read().is_some()
-            || npub_seller_error.read().is_some()
-            || signature_1_error.read().is_some()
-            || signature_2_error.read().is_some()
-            || npub_arbitrator_error.read().is_some()
+            || spent_outputs_error.read().is_some()
+            || signatures
+                .read()
+                .iter()
+                .any(|(_, error)| error.read().is_some())
+            || has_parties_errors(&parties.read())
+            || cooperative_threshold_error.read().is_some()
+            || arbitrated_threshold_error.read().is_some()
+            || has_parties_errors(&arbitrators.read())
+            || arbitrator_threshold_error.read().is_some()
             || timelock_days_error.read().is_some()
             || timelock_hours_error.read().is_some()
-            || signature_arbitrator_error.read().is_some()
+            || timelock_absolute_error.read().is_some()
+            || has_refund_errors(refund)
     };
 
     let mut validate_combine_form = move || {
@@ -63,55 +117,48 @@ pub(crate) fn Combine() -> Element {
                 .err()
                 .map(|e| e.to_string()),
         );
-        npub_buyer_error.set(
-            validate_input(&npub_buyer.read(), ValidationField::Npub, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-        npub_seller_error.set(
-            validate_input(&npub_seller.read(), ValidationField::Npub, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-        signature_1_error.set(
-            validate_input(&signature_1.read(), ValidationField::Signature, true)
+        spent_outputs_error.set(
+            validate_input(&spent_outputs.read(), ValidationField::SpentOutputs, true)
                 .err()
                 .map(|e| e.to_string()),
         );
-        signature_2_error.set(
-            validate_input(&signature_2.read(), ValidationField::Signature, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-
-        let arbitrator_filled = !npub_arbitrator.read().is_empty();
-        npub_arbitrator_error.set(
-            validate_input(&npub_arbitrator.read(), ValidationField::Npub, false)
-                .err()
-                .map(|e| e.to_string()),
-        );
-
-        if arbitrator_filled {
-            timelock_days_error.set(
-                validate_input(&timelock_days.read(), ValidationField::TimelockDays, true)
-                    .err()
-                    .map(|e| e.to_string()),
-            );
-            timelock_hours_error.set(
-                validate_input(&timelock_hours.read(), ValidationField::TimelockHours, true)
+        for (i, (signature, mut error)) in signatures.read().iter().copied().enumerate() {
+            error.set(
+                validate_input(&signature.read(), ValidationField::Signature, i == 0)
                     .err()
                     .map(|e| e.to_string()),
             );
-            signature_arbitrator_error.set(
-                validate_input(
-                    &signature_arbitrator.read(),
-                    ValidationField::Signature,
-                    true,
-                )
-                .err()
-                .map(|e| e.to_string()),
+        }
+        validate_parties(&parties.read(), false);
+        let party_count = parties.read().len();
+        cooperative_threshold_error.set(validate_threshold(
+            &cooperative_threshold.read(),
+            party_count,
+        ));
+
+        validate_arbitrators(&arbitrators.read());
+        let arbitrator_filled = has_arbitrators(&arbitrators.read());
+
+        if arbitrator_filled {
+            arbitrator_threshold_error.set(validate_threshold(
+                &arbitrator_threshold.read(),
+                arbitrators.read().len(),
+            ));
+            arbitrated_threshold_error.set(validate_threshold(
+                &arbitrated_threshold.read(),
+                party_count,
+            ));
+            validate_timelock(
+                &timelock_mode.read(),
+                &timelock_days.read(),
+                &timelock_hours.read(),
+                &timelock_absolute.read(),
+                timelock_days_error,
+                timelock_hours_error,
+                timelock_absolute_error,
             );
         }
+        validate_refund(refund);
     };
 
     rsx! {
@@ -122,6 +169,100 @@ pub(crate) fn Combine() -> Element {
                 div { class: "bg-white shadow overflow-hidden sm:rounded-lg",
                     div { class: "px-4 py-5 sm:p-6",
                         div { class: "space-y-6",
+                            h3 { class: "text-lg font-medium text-gray-900", "Combine PSBTs" }
+
+                            for (i , (psbt , error)) in psbts.read().iter().copied().enumerate() {
+                                PsbtInput {
+                                    key: "{i}",
+                                    update_var: psbt,
+                                    label: format!("Signed PSBT {}", i + 1),
+                                    id: format!("psbt{}", i + 1),
+                                    error,
+                                    required: i == 0,
+                                }
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end",
+                                    SecondaryButton {
+                                        onclick: move |_| {
+                                            psbts.write().push((Signal::new(String::new()), Signal::new(None)));
+                                        },
+                                        text: "Add PSBT",
+                                    }
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_combine_psbt_form();
+                                            if has_combine_psbt_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot combine PSBTs");
+                                                return;
+                                            }
+                                            let mut psbt_1_error = psbts.read()[0].1;
+                                            let mut psbts = psbts
+                                                .read()
+                                                .iter()
+                                                .map(|(psbt, _)| psbt.read().trim().to_string())
+                                                .filter(|p| !p.is_empty())
+                                                .map(|p| p.parse::<Psbt>().unwrap())
+                                                .collect::<Vec<Psbt>>()
+                                                .into_iter();
+                                            let mut psbt = psbts.next().unwrap();
+                                            for other in psbts {
+                                                if let Err(e) = psbt.combine(other) {
+                                                    psbt_1_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            }
+                                            match verify_escrow_psbt(&psbt) {
+                                                Ok(invalid) => {
+                                                    psbt_signature_errors
+                                                        .set(
+                                                            invalid
+                                                                .iter()
+                                                                .map(|invalid| {
+                                                                    format!(
+                                                                        "The signature of {} for input {} is invalid and was skipped: {}.",
+                                                                        invalid.x_only_pk,
+                                                                        invalid.index,
+                                                                        invalid.fault,
+                                                                    )
+                                                                })
+                                                                .collect(),
+                                                        );
+                                                }
+                                                Err(e) => {
+                                                    psbt_1_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            }
+                                            match finalize_escrow_psbt(psbt) {
+                                                Ok(signed_tx) => {
+                                                    let signed_tx = consensus::serialize(&signed_tx).as_hex().to_string();
+                                                    #[cfg(debug_assertions)]
+                                                    info!(% signed_tx, "Finalized PSBTs into a signed transaction");
+                                                    signed_tx_str.set(signed_tx);
+                                                }
+                                                Err(e) => {
+                                                    psbt_1_error.set(Some(e.to_string()));
+                                                }
+                                            }
+                                        },
+                                        text: "Combine and Finalize PSBTs",
+                                    }
+                                }
+                                for error_msg in psbt_signature_errors.read().iter() {
+                                    p { class: "mt-2 text-xs text-red-600 text-right", "{error_msg}" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            h3 { class: "text-lg font-medium text-gray-900", "Combine Raw Signatures" }
 
                             TransactionInput {
                                 update_var: unsigned_tx,
@@ -132,39 +273,59 @@ pub(crate) fn Combine() -> Element {
 
                             div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
 
-                                NpubInput {
-                                    id: "npub_1",
-                                    label: "First Nostr Public Key (npub)",
-                                    update_var: npub_buyer,
-                                    error: npub_buyer_error,
-                                    required: true,
+                                SpentOutputsInput {
+                                    update_var: spent_outputs,
+                                    label: "Funding Transactions or PSBT",
+                                    id: "spent-outputs",
+                                    error: spent_outputs_error,
+                                    unsigned_tx,
+                                    unsigned_tx_error,
                                 }
 
-                                NpubInput {
-                                    id: "npub_2",
-                                    label: "Second Nostr Public Key (npub)",
-                                    update_var: npub_seller,
-                                    error: npub_seller_error,
-                                    required: true,
+                                for (i , (signature , error)) in signatures.read().iter().copied().enumerate() {
+                                    SignatureInput {
+                                        key: "{i}",
+                                        update_var: signature,
+                                        label: format!("Signatures {}", i + 1),
+                                        id: format!("signatures_{}", i + 1),
+                                        error,
+                                        required: i == 0,
+                                    }
                                 }
 
-                                SignatureInput {
-                                    update_var: signature_1,
-                                    label: "First Signature",
-                                    id: "signature1",
-                                    error: signature_1_error,
-                                    required: true,
+                                div { class: "sm:col-span-6",
+                                    p { class: "text-xs text-gray-500",
+                                        "Paste the signatures of each signer in any order, they are matched to the keys of the escrow."
+                                    }
+                                    div { class: "mt-2 flex justify-end",
+                                        SecondaryButton {
+                                            onclick: move |_| {
+                                                signatures.write().push((Signal::new(String::new()), Signal::new(None)));
+                                            },
+                                            text: "Add Signatures",
+                                        }
+                                    }
+                                }
+
+                                PartiesInput {
+                                    parties,
+                                    amounts: false,
+                                    fee_shares: false,
+                                    payout_addresses: false,
                                 }
 
-                                SignatureInput {
-                                    update_var: signature_2,
-                                    label: "Second Signature",
-                                    id: "signature2",
-                                    error: signature_2_error,
-                                    required: true,
+                                ThresholdInput {
+                                    update_var: cooperative_threshold,
+                                    label: "Cooperative Threshold",
+                                    id: "cooperative_threshold",
+                                    parties: parties.read().len(),
+                                    placeholder: "All parties",
+                                    error: cooperative_threshold_error,
                                 }
 
                                 EscrowTypeInput { update_var: escrow_type }
+
+                                InternalKeyInput { update_var: internal_key }
                             }
 
                             div {
@@ -176,27 +337,47 @@ pub(crate) fn Combine() -> Element {
 
                                 div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
 
-                                    NpubInput {
-                                        id: "npub_arbitrator",
-                                        label: "Arbitrator Nostr Public Key (npub)",
-                                        update_var: npub_arbitrator,
-                                        error: npub_arbitrator_error,
+                                    ArbitratorsInput { arbitrators }
+
+                                    ThresholdInput {
+                                        update_var: arbitrator_threshold,
+                                        label: "Arbitrator Threshold",
+                                        id: "arbitrator_threshold",
+                                        parties: arbitrators.read().len(),
+                                        placeholder: "All arbitrators",
+                                        error: arbitrator_threshold_error,
+                                    }
+
+                                    ThresholdInput {
+                                        update_var: arbitrated_threshold,
+                                        label: "Arbitrated Threshold",
+                                        id: "arbitrated_threshold",
+                                        parties: parties.read().len(),
+                                        placeholder: "1",
+                                        error: arbitrated_threshold_error,
                                     }
 
                                     TimelockInput {
+                                        update_mode_var: timelock_mode,
                                         update_day_var: timelock_days,
                                         update_hour_var: timelock_hours,
+                                        update_absolute_var: timelock_absolute,
                                         day_error: timelock_days_error,
                                         hour_error: timelock_hours_error,
-                                        required: !npub_arbitrator.read().is_empty(),
+                                        absolute_error: timelock_absolute_error,
+                                        required: has_arbitrators(&arbitrators.read()),
                                     }
+                                }
+                            }
 
-                                    SignatureInput {
-                                        update_var: signature_arbitrator,
-                                        label: "Arbitrator Signature",
-                                        id: "signaturearb",
-                                        error: signature_arbitrator_error,
-                                        required: !npub_arbitrator.read().is_empty(),
+                            div { class: "border-t border-gray-200 pt-6",
+                                h3 { class: "text-lg font-medium text-gray-900", "Refund Details" }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                    RefundInput {
+                                        refund,
+                                        parties: parties.read().len(),
+                                        payout: false,
                                     }
                                 }
                             }
@@ -213,87 +394,105 @@ pub(crate) fn Combine() -> Element {
                                             }
                                             #[cfg(debug_assertions)]
                                             trace!(
-                                                % npub_buyer, % npub_seller, % signature_1, % signature_2, % npub_arbitrator,
-                                                % signature_arbitrator, % timelock_days, % timelock_hours, % escrow_type,
-                                                "Clicked Combine Signatures"
+                                                parties = % parties.read().len(), % cooperative_threshold, arbitrators = %
+                                                arbitrators.read().len(), % arbitrator_threshold, % arbitrated_threshold, %
+                                                timelock_days, % timelock_hours, % escrow_type, "Clicked Combine Signatures"
                                             );
-                                            let npub_buyer = parse_npub(&npub_buyer.read()).unwrap();
-                                            let npub_seller = parse_npub(&npub_seller.read()).unwrap();
+                                            signature_errors.set(Vec::new());
                                             let escrow_type = parse_escrow_type(&escrow_type.read()).unwrap();
                                             let unsigned_tx: Transaction = consensus::encode::deserialize_hex(
                                                     &unsigned_tx.read(),
                                                 )
                                                 .unwrap();
-                                            let signatures: Vec<schnorr::Signature> = vec![
-                                                signature_1.read(),
-                                                signature_2.read(),
-                                                signature_arbitrator.read(),
-                                            ]
-                                                .into_iter()
-                                                .filter(|s| !s.is_empty())
-                                                .map(|s| s.parse::<schnorr::Signature>().unwrap())
-                                                .collect();
-                                            let signed_tx = if !npub_arbitrator.read().is_empty() {
-                                                #[cfg(debug_assertions)]
-                                                trace!("dispute escrow combine signatures");
-                                                let npub_arbitrator = parse_npub(&npub_arbitrator.read()).unwrap();
-                                                let timelock_hours = hours_to_blocks(
-                                                    timelock_hours.read().parse::<u32>().unwrap(),
-                                                );
-                                                let timelock_days = days_to_blocks(
-                                                    timelock_days.read().parse::<u32>().unwrap(),
-                                                );
-                                                let timelock_duration = timelock_days + timelock_hours;
-                                                let locking_script = escrow_scripts(
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        Some(&npub_arbitrator),
-                                                        Some(timelock_duration),
-                                                        escrow_type,
-                                                    )
-                                                    .unwrap();
-                                                let taproot_spend_info = escrow_spend_info(
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        Some(&npub_arbitrator),
-                                                        Some(timelock_duration),
-                                                    )
-                                                    .unwrap();
-                                                let signed_tx = combine_signatures(
-                                                    unsigned_tx,
-                                                    0,
-                                                    signatures.iter().collect::<Vec<&schnorr::Signature>>(),
-                                                    &locking_script,
-                                                    &taproot_spend_info,
-                                                );
-                                                consensus::serialize(&signed_tx).as_hex().to_string()
-                                            } else {
-                                                #[cfg(debug_assertions)]
-                                                trace!("collaborative escrow combine signatures");
-                                                let locking_script = escrow_scripts(
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        None,
-                                                        None,
-                                                        escrow_type,
-                                                    )
-                                                    .unwrap();
-                                                let taproot_spend_info = escrow_spend_info(
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        None,
-                                                        None,
+                                            let signed_tx = match parse_escrow_contract(
+                                                    &parties.read(),
+                                                    &cooperative_threshold.read(),
+                                                    &arbitrators.read(),
+                                                    &arbitrator_threshold.read(),
+                                                    &arbitrated_threshold.read(),
+                                                    &timelock_mode.read(),
+                                                    &timelock_days.read(),
+                                                    &timelock_hours.read(),
+                                                    &timelock_absolute.read(),
+                                                    &internal_key.read(),
+                                                )
+                                                .and_then(|contract| parse_refund(contract, refund))
+                                                .and_then(|contract| {
+                                                    let locking_script = contract_scripts(&contract, escrow_type)?;
+                                                    let taproot_spend_info = contract_spend_info(&contract)?;
+                                                    // Verify every signature against the real prevouts before combining them.
+                                                    let escrow_address = contract_address(
+                                                        &contract,
+                                                        parse_network(&NETWORK.read())?,
+                                                    )?;
+                                                    let prevouts = escrow_prevouts(
+                                                        &unsigned_tx,
+                                                        &parse_spent_outputs(&spent_outputs.read())?,
+                                                        &escrow_address.script_pubkey(),
+                                                    )?;
+                                                    // Work out the signer of each set of signatures.
+                                                    let signer_signatures = signatures
+                                                        .read()
+                                                        .iter()
+                                                        .filter(|(signature, _)| !signature.read().trim().is_empty())
+                                                        .map(|(signature, _)| parse_signatures(&signature.read()))
+                                                        .collect::<Result<Vec<_>, _>>()?;
+                                                    let signatures = map_contract_signatures(
+                                                        &unsigned_tx,
+                                                        &signer_signatures,
+                                                        &prevouts,
+                                                        &locking_script,
+                                                        &taproot_spend_info,
+                                                    )?;
+                                                    let mut signers = BTreeMap::new();
+                                                    for party in parties.read().iter().chain(arbitrators.read().iter()) {
+                                                        if party.npub.read().is_empty() {
+                                                            continue;
+                                                        }
+                                                        let npub = parse_npub(&party.npub.read())?;
+                                                        signers.insert(npub_to_x_only_public_key(&npub)?, npub);
+                                                    }
+                                                    let invalid = verify_contract_signatures(
+                                                        &unsigned_tx,
+                                                        &signatures,
+                                                        &prevouts,
+                                                        &locking_script,
+                                                        &taproot_spend_info,
+                                                    )?;
+                                                    signature_errors
+                                                        .set(
+                                                            invalid
+                                                                .iter()
+                                                                .map(|invalid| {
+                                                                    format!(
+                                                                        "The signature of {} for input {} is invalid: {}.",
+                                                                        signers
+                                                                            .get(&invalid.x_only_pk)
+                                                                            .map_or(invalid.x_only_pk.to_string(), ToString::to_string),
+                                                                        invalid.index,
+                                                                        invalid.fault,
+                                                                    )
+                                                                })
+                                                                .collect(),
+                                                        );
+                                                    if !invalid.is_empty() {
+                                                        return Err(Error::InvalidSignatures(invalid.len()));
+                                                    }
+                                                    combine_contract_signatures(
+                                                        unsigned_tx,
+                                                        &signatures,
+                                                        &locking_script,
+                                                        &taproot_spend_info,
                                                     )
-                                                    .unwrap();
-                                                let signed_tx = combine_signatures(
-                                                    unsigned_tx,
-                                                    0,
-                                                    signatures.iter().collect::<Vec<&schnorr::Signature>>(),
-                                                    &locking_script,
-                                                    &taproot_spend_info,
-                                                );
-                                                consensus::serialize(&signed_tx).as_hex().to_string()
+                                                })
+                                            {
+                                                Ok(signed_tx) => consensus::serialize(&signed_tx).as_hex().to_string(),
+                                                Err(e) => {
+                                                    combine_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
                                             };
+                                            combine_error.set(None);
                                             #[cfg(debug_assertions)]
                                             info!(% signed_tx, "Combined signatures into a signed transaction");
                                             signed_tx_str.set(signed_tx);
@@ -301,6 +500,12 @@ pub(crate) fn Combine() -> Element {
                                         text: "Combine Signatures",
                                     }
                                 }
+                                if let Some(error_msg) = combine_error.read().as_ref() {
+                                    p { class: "mt-2 text-xs text-red-600 text-right", "{error_msg}" }
+                                }
+                                for error_msg in signature_errors.read().iter() {
+                                    p { class: "mt-2 text-xs text-red-600 text-right", "{error_msg}" }
+                                }
                             }
                         }
                     }
@@ -1,6 +1,6 @@
 //! Create escrow transaction component.
 
-use bitcoin::{Amount, Txid, consensus, hex::DisplayHex};
+use bitcoin::{Address, Amount, consensus, hex::DisplayHex};
 use dioxus::prelude::*;
 
 #[cfg(debug_assertions)]
@@ -8,117 +8,156 @@ use dioxus::logger::tracing::{info, trace};
 
 use crate::{
     ESPLORA_ENDPOINT, NETWORK, Route,
-    esplora::{FeeEstimate, create_client, get_fee_estimates},
-    scripts::escrow_address,
-    tx::escrow_tx,
+    esplora::{FeeEstimate, create_client, get_fee_estimates, get_funding_outputs},
+    scripts::contract_address,
+    tx::{
+        FeePolicy, TxFee, contract_fee, contract_psbt, merged_dust, payout_fee, payout_psbt,
+        refund_fee, refund_psbt,
+    },
     util::{
-        P2TR_TX_VBYTE_C, days_to_blocks, hours_to_blocks, npub_to_address, parse_network,
+        npub_to_address, parse_dust_policy, parse_escrow_type, parse_fee_rate, parse_network,
         parse_npub,
     },
     validation::{ValidationField, validate_input},
 };
 
 use super::{
-    BitcoinInput, ContinueButton, CopyButton, DerivedAddressOutput, FeeRateSelector, Footer,
-    NetworkInput, NpubInput, NpubInputDerivedAddress, PrimaryButton, TimelockInput,
-    TransactionOutput, TxidInput,
+    ArbitratorsInput, ContinueButton, CopyButton, DerivedAddressOutput, DustPolicyInput,
+    EscrowTypeInput, FeeOutput, FeePolicyInput, FeeRateSelector, Footer, FundingFields,
+    FundingInput, InternalKeyInput, NetworkInput, PartiesInput, PayoutsInput, PrimaryButton,
+    RefundInput, SecondaryButton, ThresholdInput, TimelockInput, TransactionOutput,
+    has_arbitrators, has_funding_errors, has_parties_errors, has_payouts_errors, has_refund_errors,
+    parse_escrow_contract, parse_fee_policy, parse_funding, parse_payout_addresses, parse_payouts,
+    parse_refund, use_arbitrators, use_funding, use_parties, use_payouts, use_refund,
+    validate_arbitrators, validate_fee_shares, validate_funding, validate_parties,
+    validate_payouts, validate_refund, validate_threshold, validate_timelock,
 };
 
 /// Create escrow transaction component.
 #[component]
 pub(crate) fn Create() -> Element {
-    let npub_buyer = use_signal(String::new);
-    let npub_seller = use_signal(String::new);
-    let npub_arbitrator = use_signal(String::new);
-    let amount_buyer = use_signal(String::new);
-    let amount_seller = use_signal(String::new);
+    let parties = use_parties();
+    let cooperative_threshold = use_signal(String::new);
+    let arbitrated_threshold = use_signal(String::new);
+    let arbitrators = use_arbitrators();
+    let arbitrator_threshold = use_signal(String::new);
+    let refund = use_refund();
     let mut fee_rate = use_signal(String::new);
     let fee_estimates = use_signal(|| Option::<FeeEstimate>::None);
+    let fee_policy = use_signal(|| "Equal".to_string());
+    let dust_policy = use_signal(String::new);
+    let payouts = use_payouts();
     let timelock_days = use_signal(String::new);
     let timelock_hours = use_signal(String::new);
-    let funding_txid = use_signal(String::new);
+    let timelock_mode = use_signal(String::new);
+    let timelock_absolute = use_signal(String::new);
+    let mut funding = use_funding();
+    let mut funding_warnings = use_signal(Vec::<String>::new);
+    let mut fetching_funding = use_signal(|| false);
+    let escrow_type = use_signal(String::new);
+    let internal_key = use_signal(String::new);
     let mut escrow_address_str = use_signal(String::new);
     let mut escrow_transaction = use_signal(String::new);
-    let mut derived_address_buyer = use_signal(String::new);
-    let mut derived_address_seller = use_signal(String::new);
-
-    let mut npub_buyer_error = use_signal(|| None);
-    let mut npub_seller_error = use_signal(|| None);
-    let mut npub_arbitrator_error = use_signal(|| None);
-    let mut amount_buyer_error = use_signal(|| None);
-    let mut amount_seller_error = use_signal(|| None);
+    let mut escrow_psbt_str = use_signal(String::new);
+    let mut refund_psbt_str = use_signal(String::new);
+    let mut escrow_fee_str = use_signal(String::new);
+    let mut refund_fee_str = use_signal(String::new);
+    let mut dust_warning = use_signal(|| Option::<String>::None);
+
+    let mut cooperative_threshold_error = use_signal(|| None);
+    let mut arbitrated_threshold_error = use_signal(|| None);
+    let mut arbitrator_threshold_error = use_signal(|| None);
     let mut fee_rate_error = use_signal(|| None);
-    let mut timelock_days_error = use_signal(|| Option::<String>::None);
-    let mut timelock_hours_error = use_signal(|| Option::<String>::None);
-    let mut funding_txid_error = use_signal(|| Option::<String>::None);
+    let timelock_days_error = use_signal(|| Option::<String>::None);
+    let timelock_hours_error = use_signal(|| Option::<String>::None);
+    let timelock_absolute_error = use_signal(|| Option::<String>::None);
+    let mut contract_error = use_signal(|| Option::<String>::None);
+    let mut funding_error = use_signal(|| Option::<String>::None);
 
     let has_address_form_errors = move || {
-        npub_buyer_error.read().is_some()
-            || npub_seller_error.read().is_some()
-            || amount_buyer_error.read().is_some()
-            || amount_seller_error.read().is_some()
+        has_parties_errors(&parties.read())
+            || cooperative_threshold_error.read().is_some()
+            || arbitrated_threshold_error.read().is_some()
             || fee_rate_error.read().is_some()
-            || npub_arbitrator_error.read().is_some()
+            || has_parties_errors(&arbitrators.read())
+            || arbitrator_threshold_error.read().is_some()
             || timelock_days_error.read().is_some()
             || timelock_hours_error.read().is_some()
+            || timelock_absolute_error.read().is_some()
+            || has_refund_errors(refund)
     };
 
     let mut validate_address_form = move || {
-        npub_buyer_error.set(
-            validate_input(&npub_buyer.read(), ValidationField::Npub, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-        npub_seller_error.set(
-            validate_input(&npub_seller.read(), ValidationField::Npub, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-        amount_buyer_error.set(
-            validate_input(&amount_buyer.read(), ValidationField::Amount, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-        amount_seller_error.set(
-            validate_input(&amount_seller.read(), ValidationField::Amount, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
+        validate_parties(&parties.read(), true);
+        let party_count = parties.read().len();
+        cooperative_threshold_error.set(validate_threshold(
+            &cooperative_threshold.read(),
+            party_count,
+        ));
         fee_rate_error.set(
             validate_input(&fee_rate.read(), ValidationField::FeeRate, true)
                 .err()
                 .map(|e| e.to_string()),
         );
 
-        let arbitrator_filled = !npub_arbitrator.read().is_empty();
-        npub_arbitrator_error.set(
-            validate_input(&npub_arbitrator.read(), ValidationField::Npub, false)
-                .err()
-                .map(|e| e.to_string()),
-        );
+        validate_arbitrators(&arbitrators.read());
+        let arbitrator_filled = has_arbitrators(&arbitrators.read());
 
         if arbitrator_filled {
-            timelock_days_error.set(
-                validate_input(&timelock_days.read(), ValidationField::TimelockDays, true)
-                    .err()
-                    .map(|e| e.to_string()),
-            );
-            timelock_hours_error.set(
-                validate_input(&timelock_hours.read(), ValidationField::TimelockHours, true)
-                    .err()
-                    .map(|e| e.to_string()),
+            arbitrator_threshold_error.set(validate_threshold(
+                &arbitrator_threshold.read(),
+                arbitrators.read().len(),
+            ));
+            arbitrated_threshold_error.set(validate_threshold(
+                &arbitrated_threshold.read(),
+                party_count,
+            ));
+            validate_timelock(
+                &timelock_mode.read(),
+                &timelock_days.read(),
+                &timelock_hours.read(),
+                &timelock_absolute.read(),
+                timelock_days_error,
+                timelock_hours_error,
+                timelock_absolute_error,
             );
         }
+        validate_refund(refund);
     };
 
-    let has_transaction_form_errors = move || funding_txid_error.read().is_some();
+    let escrow_contract = move || {
+        parse_escrow_contract(
+            &parties.read(),
+            &cooperative_threshold.read(),
+            &arbitrators.read(),
+            &arbitrator_threshold.read(),
+            &arbitrated_threshold.read(),
+            &timelock_mode.read(),
+            &timelock_days.read(),
+            &timelock_hours.read(),
+            &timelock_absolute.read(),
+            &internal_key.read(),
+        )
+        .and_then(|contract| parse_refund(contract, refund))
+        .and_then(|contract| {
+            parse_payout_addresses(
+                contract,
+                &parties.read(),
+                parse_network(&NETWORK.read()).unwrap(),
+            )
+        })
+    };
 
-    let mut validate_transaction_form = move || {
-        funding_txid_error.set(
-            validate_input(&funding_txid.read(), ValidationField::Txid, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
+    let has_transaction_form_errors = move || {
+        has_funding_errors(&funding.read())
+            || has_parties_errors(&parties.read())
+            || has_payouts_errors(&payouts.read())
+    };
+
+    let validate_transaction_form = move || {
+        validate_funding(&funding.read());
+        validate_fee_shares(&fee_policy.read(), &parties.read());
+        validate_payouts(&payouts.read());
     };
 
     use_effect(move || {
@@ -152,36 +191,20 @@ pub(crate) fn Create() -> Element {
                         div { class: "space-y-6",
                             div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
 
-                                NpubInputDerivedAddress {
-                                    id: "npub_buyer",
-                                    label: "Buyer Nostr Public Key (npub)",
-                                    update_var: npub_buyer,
-                                    update_address: derived_address_buyer,
-                                    col_span: 3,
-                                    error: npub_buyer_error,
-                                }
-
-                                NpubInputDerivedAddress {
-                                    id: "npub_seller",
-                                    label: "Seller Nostr Public Key (npub)",
-                                    update_var: npub_seller,
-                                    update_address: derived_address_seller,
-                                    col_span: 3,
-                                    error: npub_seller_error,
-                                }
-
-                                BitcoinInput {
-                                    id: "amount_buyer",
-                                    label: "Buyer Escrow Amount (BTC)",
-                                    update_var: amount_buyer,
-                                    error: amount_buyer_error,
+                                PartiesInput {
+                                    parties,
+                                    amounts: true,
+                                    fee_shares: *fee_policy.read() == "Fixed",
+                                    payout_addresses: true,
                                 }
 
-                                BitcoinInput {
-                                    id: "amount_seller",
-                                    label: "Seller Escrow Amount (BTC)",
-                                    update_var: amount_seller,
-                                    error: amount_seller_error,
+                                ThresholdInput {
+                                    update_var: cooperative_threshold,
+                                    label: "Cooperative Threshold",
+                                    id: "cooperative_threshold",
+                                    parties: parties.read().len(),
+                                    placeholder: "All parties",
+                                    error: cooperative_threshold_error,
                                 }
 
                                 FeeRateSelector {
@@ -193,7 +216,17 @@ pub(crate) fn Create() -> Element {
                                     error: fee_rate_error,
                                 }
 
+                                FeePolicyInput {
+                                    update_var: fee_policy,
+                                    parties: parties.read().len(),
+                                    arbitrators: false,
+                                }
+
+                                DustPolicyInput { update_var: dust_policy }
+
                                 NetworkInput { id: "network", label: "Bitcoin Network" }
+
+                                InternalKeyInput { update_var: internal_key }
                             }
 
                             div { class: "border-t border-gray-200 pt-6",
@@ -203,30 +236,47 @@ pub(crate) fn Create() -> Element {
 
                                 div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
 
-                                    NpubInput {
-                                        id: "npub_arbitrator",
-                                        label: "Arbitrator Nostr Public Key (npub)",
-                                        update_var: npub_arbitrator,
-                                        error: npub_arbitrator_error,
+                                    ArbitratorsInput { arbitrators }
+
+                                    ThresholdInput {
+                                        update_var: arbitrator_threshold,
+                                        label: "Arbitrator Threshold",
+                                        id: "arbitrator_threshold",
+                                        parties: arbitrators.read().len(),
+                                        placeholder: "All arbitrators",
+                                        error: arbitrator_threshold_error,
+                                    }
+
+                                    ThresholdInput {
+                                        update_var: arbitrated_threshold,
+                                        label: "Arbitrated Threshold",
+                                        id: "arbitrated_threshold",
+                                        parties: parties.read().len(),
+                                        placeholder: "1",
+                                        error: arbitrated_threshold_error,
                                     }
 
                                     TimelockInput {
+                                        update_mode_var: timelock_mode,
                                         update_day_var: timelock_days,
                                         update_hour_var: timelock_hours,
+                                        update_absolute_var: timelock_absolute,
                                         day_error: timelock_days_error,
                                         hour_error: timelock_hours_error,
-                                        required: !npub_arbitrator.read().is_empty(),
+                                        absolute_error: timelock_absolute_error,
+                                        required: has_arbitrators(&arbitrators.read()),
                                     }
                                 }
                             }
 
                             div { class: "border-t border-gray-200 pt-6",
-                                div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
-                                    DerivedAddressOutput {
-                                        update_var: escrow_address_str,
-                                        label: "Deposit Address",
-                                        id: "escrow-address",
-                                        col_span: 3,
+                                h3 { class: "text-lg font-medium text-gray-900", "Optional Refund" }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                    RefundInput {
+                                        refund,
+                                        parties: parties.read().len(),
+                                        payout: true,
                                     }
                                 }
                             }
@@ -234,22 +284,14 @@ pub(crate) fn Create() -> Element {
                             div { class: "border-t border-gray-200 pt-6",
                                 div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
                                     DerivedAddressOutput {
-                                        update_var: derived_address_buyer,
-                                        label: "Buyer's Resolution Address",
-                                        id: "buyer-address",
-                                        col_span: 3,
-                                    }
-
-                                    DerivedAddressOutput {
-                                        update_var: derived_address_seller,
-                                        label: "Seller's Resolution Address",
-                                        id: "seller-address",
+                                        update_var: escrow_address_str,
+                                        label: "Deposit Address",
+                                        id: "escrow-address",
                                         col_span: 3,
                                     }
                                 }
                             }
 
-
                             div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
                                 CopyButton {
                                     text: "Deposit Address",
@@ -265,45 +307,27 @@ pub(crate) fn Create() -> Element {
                                         }
                                         #[cfg(debug_assertions)]
                                         trace!(
-                                            % npub_buyer, % npub_seller, % amount_buyer, % amount_seller, % fee_rate, %
-                                            NETWORK, % npub_arbitrator, % timelock_days, % timelock_hours,
+                                            parties = % parties.read().len(), % cooperative_threshold, % fee_rate, % NETWORK,
+                                            arbitrators = % arbitrators.read().len(), % arbitrator_threshold, % arbitrated_threshold, % timelock_days, % timelock_hours,
                                             "Clicked Generate Address"
                                         );
-                                        let npub_buyer = parse_npub(&npub_buyer.read()).unwrap();
-                                        let npub_seller = parse_npub(&npub_seller.read()).unwrap();
                                         let network = parse_network(&NETWORK.read()).unwrap();
-                                        *derived_address_buyer.write() = npub_to_address(&npub_buyer, network)
-                                            .unwrap()
-                                            .to_string();
-                                        *derived_address_seller.write() = npub_to_address(&npub_seller, network)
-                                            .unwrap()
-                                            .to_string();
-                                        let resolved_escrow_address = if !npub_arbitrator.read().is_empty() {
-                                            #[cfg(debug_assertions)]
-                                            trace!("dispute escrow address");
-                                            let npub_arbitrator = parse_npub(&npub_arbitrator.read()).unwrap();
-                                            let timelock_hours = hours_to_blocks(
-                                                timelock_hours.read().parse::<u32>().unwrap(),
-                                            );
-                                            let timelock_days = days_to_blocks(
-                                                timelock_days.read().parse::<u32>().unwrap(),
-                                            );
-                                            escrow_address(
-                                                    &npub_buyer,
-                                                    &npub_seller,
-                                                    Some(&npub_arbitrator),
-                                                    Some(timelock_days + timelock_hours),
-                                                    network,
-                                                )
-                                                .unwrap()
-                                                .to_string()
-                                        } else {
-                                            #[cfg(debug_assertions)]
-                                            trace!("collaborative escrow address");
-                                            escrow_address(&npub_buyer, &npub_seller, None, None, network)
-                                                .unwrap()
-                                                .to_string()
+                                        for mut party in parties.read().iter().copied() {
+                                            let npub = parse_npub(&party.npub.read()).unwrap();
+                                            party
+                                                .derived_address
+                                                .set(npub_to_address(&npub, network).unwrap().to_string());
+                                        }
+                                        let resolved_escrow_address = match escrow_contract()
+                                            .and_then(|contract| contract_address(&contract, network))
+                                        {
+                                            Ok(address) => address.to_string(),
+                                            Err(e) => {
+                                                contract_error.set(Some(e.to_string()));
+                                                return;
+                                            }
                                         };
+                                        contract_error.set(None);
                                         #[cfg(debug_assertions)]
                                         info!(% resolved_escrow_address, "Derived escrow address");
                                         escrow_address_str.set(resolved_escrow_address);
@@ -311,6 +335,9 @@ pub(crate) fn Create() -> Element {
                                     text: "Generate Address",
                                 }
                             }
+                            if let Some(error_msg) = contract_error.read().as_ref() {
+                                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                            }
                         }
                     }
                 }
@@ -322,17 +349,76 @@ pub(crate) fn Create() -> Element {
                             "Escrow Details"
                         }
                         div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
-                            TxidInput {
-                                update_var: funding_txid,
-                                label: "Escrow funding Transaction ID",
-                                warning: "Deposit a single transaction to the escrow address and inform the transaction ID.
-                                This transaction will be used to fund the escrow address.
-                                Note that it should be a coinjoin transaction between buyer and seller,
-                                i.e. should have only one output: the escrow address with the whole total escrow amount.",
-                                error: funding_txid_error,
+                            FundingInput {
+                                    id: "funding",
+                                    label: "Funding",
+                                    hint: "Every output that pays to the escrow address is spent as its own input. The funding outputs must add up to the escrow exactly.",
+                                    funding,
+                                }
+
+                            div { class: "sm:col-span-6",
+                                for warning in funding_warnings.read().iter() {
+                                    p { class: "mt-2 text-xs text-red-600", "{warning}" }
+                                }
+                                if let Some(error_msg) = funding_error.read().as_ref() {
+                                    p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                                }
+                                div { class: "mt-3 flex justify-end",
+                                    SecondaryButton {
+                                        onclick: move |_| {
+                                            if *fetching_funding.read() {
+                                                return;
+                                            }
+                                            let network = parse_network(&NETWORK.read()).unwrap();
+                                            let Some(escrow_address) = escrow_address_str
+                                                .read()
+                                                .parse::<Address<_>>()
+                                                .ok()
+                                                .and_then(|address| address.require_network(network).ok()) else {
+                                                funding_error
+                                                    .set(Some("Generate the deposit address first.".to_string()));
+                                                return;
+                                            };
+                                            #[cfg(debug_assertions)]
+                                            trace!(% escrow_address, "Clicked Fetch Funding Outputs");
+                                            fetching_funding.set(true);
+                                            spawn(async move {
+                                                let esplora_client = create_client(&ESPLORA_ENDPOINT.read()).unwrap();
+                                                match get_funding_outputs(&esplora_client, &escrow_address).await {
+                                                    Ok(fetched) => {
+                                                        #[cfg(debug_assertions)]
+                                                        trace!(? fetched, "Funding outputs fetched successfully");
+                                                        if !fetched.utxos.is_empty() {
+                                                            funding.set(fetched.utxos.iter().map(FundingFields::with_utxo).collect());
+                                                        }
+                                                        funding_warnings
+                                                            .set(fetched.warnings.iter().map(|warning| warning.to_string()).collect());
+                                                        funding_error.set(None);
+                                                    }
+                                                    Err(e) => {
+                                                        #[cfg(debug_assertions)]
+                                                        trace!(% e, "Error fetching funding outputs");
+                                                        funding_error.set(Some(e.to_string()));
+                                                    }
+                                                }
+                                                fetching_funding.set(false);
+                                            });
+                                        },
+                                        text: if *fetching_funding.read() { "Fetching Funding Outputs..." } else { "Fetch Funding Outputs" },
+                                    }
+                                }
                             }
+
+                            EscrowTypeInput { update_var: escrow_type }
                         }
 
+                        div { class: "mt-5 border-t border-gray-200 pt-5",
+                            h3 { class: "text-lg font-medium text-gray-900", "Optional Payouts" }
+
+                            div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                PayoutsInput { payouts, parties: parties.read().len() }
+                            }
+                        }
 
                         div { class: "mt-5 border-t border-gray-200 pt-5",
                             dl { class: "grid grid-cols-1 gap-x-4 gap-y-6 sm:grid-cols-2",
@@ -342,6 +428,38 @@ pub(crate) fn Create() -> Element {
                                     id: "escrow-tx",
                                     placeholder: "Transaction data will appear here...",
                                 }
+
+                                TransactionOutput {
+                                    update_var: escrow_psbt_str,
+                                    label: "Unsigned Escrow Resolution PSBT",
+                                    id: "escrow-psbt",
+                                    placeholder: "PSBT data will appear here...",
+                                }
+
+                                FeeOutput {
+                                    update_var: escrow_fee_str,
+                                    label: "Escrow Resolution Size and Fee",
+                                    id: "escrow-fee",
+                                }
+
+                                if let Some(warning) = dust_warning.read().as_ref() {
+                                    p { class: "sm:col-span-2 text-xs text-red-600", "{warning}" }
+                                }
+
+                                if refund.is_enabled() {
+                                    TransactionOutput {
+                                        update_var: refund_psbt_str,
+                                        label: "Unsigned Refund PSBT",
+                                        id: "refund-psbt",
+                                        placeholder: "Refund PSBT data will appear here...",
+                                    }
+
+                                    FeeOutput {
+                                        update_var: refund_fee_str,
+                                        label: "Refund Size and Fee",
+                                        id: "refund-fee",
+                                    }
+                                }
                             }
 
                             div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
@@ -349,6 +467,10 @@ pub(crate) fn Create() -> Element {
                                     text: "Transaction",
                                     clipboard_text: escrow_transaction,
                                 }
+                                CopyButton { text: "PSBT", clipboard_text: escrow_psbt_str }
+                                if refund.is_enabled() {
+                                    CopyButton { text: "Refund PSBT", clipboard_text: refund_psbt_str }
+                                }
                                 PrimaryButton {
                                     onclick: move |_| {
                                         validate_transaction_form();
@@ -359,64 +481,152 @@ pub(crate) fn Create() -> Element {
                                         }
                                         #[cfg(debug_assertions)]
                                         trace!(
-                                            % npub_buyer, % npub_seller, % amount_buyer, % amount_seller, % fee_rate, %
-                                            NETWORK, % npub_arbitrator, % timelock_days, % timelock_hours,
+                                            parties = % parties.read().len(), % cooperative_threshold, % fee_rate, % NETWORK,
+                                            arbitrators = % arbitrators.read().len(), % arbitrator_threshold, % arbitrated_threshold, % timelock_days, % timelock_hours,
                                             "Clicked Generate Transaction"
                                         );
-                                        let npub_buyer = parse_npub(&npub_buyer.read()).unwrap();
-                                        let npub_seller = parse_npub(&npub_seller.read()).unwrap();
-                                        let btc_amount_buyer = Amount::from_btc(
-                                                amount_buyer.read().parse::<f64>().unwrap(),
-                                            )
-                                            .unwrap();
-                                        let btc_amount_seller = Amount::from_btc(
-                                                amount_seller.read().parse::<f64>().unwrap(),
-                                            )
-                                            .unwrap();
-                                        let fee_rate = fee_rate.read().parse::<u64>().unwrap();
-                                        let fee = Amount::from_sat(fee_rate * P2TR_TX_VBYTE_C);
+                                        let escrow_amounts: Vec<Amount> = parties
+                                            .read()
+                                            .iter()
+                                            .map(|party| {
+                                                Amount::from_btc(party.amount.read().parse::<f64>().unwrap())
+                                                    .unwrap()
+                                            })
+                                            .collect();
+                                        let fee_rate = parse_fee_rate(&fee_rate.read()).unwrap();
                                         let network = parse_network(&NETWORK.read()).unwrap();
-                                        let funding_txid = funding_txid.read().parse::<Txid>().unwrap();
-                                        let resolved_escrow_transaction = if !npub_arbitrator.read().is_empty() {
-                                            #[cfg(debug_assertions)]
-                                            trace!("dispute escrow address");
-                                            let timelock_hours = hours_to_blocks(
-                                                timelock_hours.read().parse::<u32>().unwrap(),
-                                            );
-                                            let timelock_days = days_to_blocks(
-                                                timelock_days.read().parse::<u32>().unwrap(),
-                                            );
-                                            let escrow_tx = escrow_tx(
-                                                    &npub_buyer,
-                                                    &npub_seller,
-                                                    Some(timelock_days + timelock_hours),
-                                                    btc_amount_buyer,
-                                                    btc_amount_seller,
-                                                    funding_txid,
-                                                    fee,
+                                        let funding = parse_funding(&funding.read());
+                                        let escrow_type = parse_escrow_type(&escrow_type.read()).unwrap();
+                                        let contract = match escrow_contract() {
+                                            Ok(contract) => contract,
+                                            Err(e) => {
+                                                contract_error.set(Some(e.to_string()));
+                                                return;
+                                            }
+                                        };
+                                        let fee_policy = match parse_fee_policy(&fee_policy.read(), &parties.read()) {
+                                            Ok(fee_policy) => fee_policy,
+                                            Err(e) => {
+                                                contract_error.set(Some(e.to_string()));
+                                                return;
+                                            }
+                                        };
+                                        let dust_policy = parse_dust_policy(&dust_policy.read()).unwrap();
+                                        let resolved = if payouts.read().is_empty() {
+                                            contract_fee(
+                                                    &contract,
+                                                    escrow_type,
+                                                    &escrow_amounts,
+                                                    &funding,
+                                                    fee_rate,
                                                     network,
                                                 )
-                                                .unwrap();
-                                            consensus::serialize(&escrow_tx).as_hex().to_string()
+                                                .and_then(|escrow_fee| {
+                                                    let escrow_fee = fixed_fee(escrow_fee, &fee_policy);
+                                                    let psbt = contract_psbt(
+                                                        &contract,
+                                                        escrow_type,
+                                                        &escrow_amounts,
+                                                        &funding,
+                                                        escrow_fee.fee,
+                                                        &fee_policy,
+                                                        dust_policy,
+                                                        network,
+                                                    )?;
+                                                    Ok((escrow_fee, psbt))
+                                                })
                                         } else {
-                                            #[cfg(debug_assertions)]
-                                            trace!("collaborative escrow address");
-                                            let escrow_tx = escrow_tx(
-                                                    &npub_buyer,
-                                                    &npub_seller,
-                                                    None,
-                                                    btc_amount_buyer,
-                                                    btc_amount_seller,
-                                                    funding_txid,
-                                                    fee,
+                                            parse_payouts(&payouts.read(), &contract, network)
+                                                .and_then(|payouts| {
+                                                    let escrow_fee = payout_fee(
+                                                        &contract,
+                                                        escrow_type,
+                                                        &payouts,
+                                                        &funding,
+                                                        fee_rate,
+                                                    )?;
+                                                    let psbt = payout_psbt(
+                                                        &contract,
+                                                        escrow_type,
+                                                        &payouts,
+                                                        &funding,
+                                                        escrow_fee.fee,
+                                                        dust_policy,
+                                                    )?;
+                                                    Ok((escrow_fee, psbt))
+                                                })
+                                        };
+                                        let (escrow_fee, resolved_escrow_psbt) = match resolved {
+                                            Ok(resolved) => resolved,
+                                            Err(e) => {
+                                                contract_error.set(Some(e.to_string()));
+                                                return;
+                                            }
+                                        };
+                                        let escrow_dust = match merged_dust(&resolved_escrow_psbt, escrow_fee.fee) {
+                                            Ok(dust) => dust,
+                                            Err(e) => {
+                                                contract_error.set(Some(e.to_string()));
+                                                return;
+                                            }
+                                        };
+                                        let mut refund_dust = Amount::ZERO;
+                                        if refund.is_enabled() {
+                                            let split = *refund.split.read();
+                                            match refund_fee(
+                                                    &contract,
+                                                    &escrow_amounts,
+                                                    split,
+                                                    &funding,
+                                                    fee_rate,
                                                     network,
                                                 )
-                                                .unwrap();
-                                            consensus::serialize(&escrow_tx).as_hex().to_string()
-                                        };
+                                                .and_then(|refund_fee| {
+                                                    // The fixed shares only apply to the pre-agreed outputs.
+                                                    let refund_fee = if split {
+                                                        fixed_fee(refund_fee, &fee_policy)
+                                                    } else {
+                                                        refund_fee
+                                                    };
+                                                    let psbt = refund_psbt(
+                                                        &contract,
+                                                        &escrow_amounts,
+                                                        split,
+                                                        &funding,
+                                                        refund_fee.fee,
+                                                        &fee_policy,
+                                                        dust_policy,
+                                                        network,
+                                                    )?;
+                                                    let dust = merged_dust(&psbt, refund_fee.fee)?;
+                                                    Ok((psbt, refund_fee, dust))
+                                                })
+                                            {
+                                                Ok((psbt, refund_fee, dust)) => {
+                                                    refund_psbt_str.set(psbt.to_string());
+                                                    refund_fee_str.set(refund_fee.to_string());
+                                                    refund_dust = dust;
+                                                }
+                                                Err(e) => {
+                                                    contract_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            }
+                                        }
+                                        let resolved_escrow_transaction = consensus::serialize(
+                                                &resolved_escrow_psbt.unsigned_tx,
+                                            )
+                                            .as_hex()
+                                            .to_string();
                                         #[cfg(debug_assertions)]
-                                        info!(% resolved_escrow_transaction, "Derived escrow transaction");
+                                        info!(
+                                            % resolved_escrow_transaction, % resolved_escrow_psbt,
+                                            "Derived escrow transaction"
+                                        );
                                         escrow_transaction.set(resolved_escrow_transaction);
+                                        escrow_psbt_str.set(resolved_escrow_psbt.to_string());
+                                        escrow_fee_str.set(escrow_fee.to_string());
+                                        dust_warning.set(dust_warning_message(escrow_dust, refund_dust));
                                     },
                                     text: "Generate Transaction",
                                 }
@@ -433,3 +643,37 @@ pub(crate) fn Create() -> Element {
         Footer {}
     }
 }
+
+/// Explains the dust outputs merged into the fees of the escrow resolution and the refund, if any.
+fn dust_warning_message(escrow_dust: Amount, refund_dust: Amount) -> Option<String> {
+    let merged = [("escrow resolution", escrow_dust), ("refund", refund_dust)]
+        .into_iter()
+        .filter(|(_, dust)| *dust > Amount::ZERO)
+        .map(|(tx, dust)| {
+            format!(
+                "{} sats of dust outputs were merged into the fee of the {tx}",
+                dust.to_sat()
+            )
+        })
+        .collect::<Vec<_>>();
+    if merged.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "{}. Check the outputs before signing.",
+        merged.join(", and ")
+    ))
+}
+
+/// The [`TxFee`] of a [`FeePolicy::Fixed`] is the sum of the fixed shares,
+/// at the effective rate of the weight of `tx_fee`.
+fn fixed_fee(tx_fee: TxFee, fee_policy: &FeePolicy) -> TxFee {
+    match fee_policy {
+        FeePolicy::Fixed(shares) => TxFee {
+            fee: shares.iter().copied().sum(),
+            ..tx_fee
+        },
+        _ => tx_fee,
+    }
+}
@@ -0,0 +1,514 @@
+//! Collaborative escrow funding component.
+
+use bitcoin::{Address, Amount, Psbt};
+use dioxus::prelude::*;
+
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::{info, trace};
+
+use crate::{
+    ESPLORA_ENDPOINT, NETWORK, Route,
+    error::Error,
+    esplora::{FeeEstimate, create_client, get_fee_estimates, get_utxos},
+    sign::sign_funding_psbt,
+    tx::{FundingContribution, funding_psbt},
+    util::{
+        npub_to_address, parse_fee_rate, parse_network, parse_npub, parse_nsec,
+        parse_payout_address,
+    },
+    validation::{ValidationField, validate_input},
+};
+
+use super::{
+    AddressInput, BitcoinInput, ContinueButton, CopyButton, DerivedAddressOutput, FeeOutput,
+    FeeRateSelector, Footer, FundingFields, FundingInput, NetworkInput, NpubInputDerivedAddress,
+    NsecInput, PayoutAddressInput, PrimaryButton, PsbtInput, SecondaryButton, TransactionOutput,
+    has_funding_errors, parse_funding, validate_funding,
+};
+
+/// The input fields of a party that funds the escrow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FunderFields {
+    npub: Signal<String>,
+    npub_error: Signal<Option<String>>,
+    derived_address: Signal<String>,
+    amount: Signal<String>,
+    amount_error: Signal<Option<String>>,
+    change_address: Signal<String>,
+    change_address_error: Signal<Option<String>>,
+    utxos: Signal<Vec<FundingFields>>,
+    utxos_error: Signal<Option<String>>,
+    fetching_utxos: Signal<bool>,
+}
+
+impl FunderFields {
+    /// Creates empty [`FunderFields`], owned by the current component.
+    fn new() -> Self {
+        Self {
+            npub: Signal::new(String::new()),
+            npub_error: Signal::new(None),
+            derived_address: Signal::new(String::new()),
+            amount: Signal::new(String::new()),
+            amount_error: Signal::new(None),
+            change_address: Signal::new(String::new()),
+            change_address_error: Signal::new(None),
+            utxos: Signal::new(vec![FundingFields::new()]),
+            utxos_error: Signal::new(None),
+            fetching_utxos: Signal::new(false),
+        }
+    }
+}
+
+/// Collaborative escrow funding component.
+///
+/// Builds a single funding transaction, i.e. a coinjoin, that spends the UTXOs of the resolution
+/// addresses of every party, pays the escrow address exactly, and returns the change to every party.
+/// Every party then signs its own inputs, and the signed PSBTs are combined on the Combine page.
+#[component]
+pub(crate) fn Fund() -> Element {
+    let escrow_address = use_signal(String::new);
+    let mut fee_rate = use_signal(String::new);
+    let fee_estimates = use_signal(|| Option::<FeeEstimate>::None);
+    let mut funders = use_signal(|| vec![FunderFields::new(), FunderFields::new()]);
+    let mut unsigned_psbt_str = use_signal(String::new);
+    let mut fee_str = use_signal(String::new);
+    let mut psbt = use_signal(String::new);
+    let nsec = use_signal(String::new);
+    let mut signed_psbt_str = use_signal(String::new);
+
+    let mut escrow_address_error = use_signal(|| None);
+    let mut fee_rate_error = use_signal(|| None);
+    let mut build_error = use_signal(|| Option::<String>::None);
+    let mut psbt_error = use_signal(|| None);
+    let mut nsec_error = use_signal(|| None);
+
+    let has_fund_form_errors = move || {
+        escrow_address_error.read().is_some()
+            || fee_rate_error.read().is_some()
+            || funders.read().iter().any(|funder| {
+                funder.npub_error.read().is_some()
+                    || funder.amount_error.read().is_some()
+                    || funder.change_address_error.read().is_some()
+                    || has_funding_errors(&funder.utxos.read())
+            })
+    };
+
+    let mut validate_fund_form = move || {
+        escrow_address_error.set(
+            validate_input(&escrow_address.read(), ValidationField::Address, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        fee_rate_error.set(
+            validate_input(&fee_rate.read(), ValidationField::FeeRate, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        for mut funder in funders.read().iter().copied() {
+            funder.npub_error.set(
+                validate_input(&funder.npub.read(), ValidationField::Npub, true)
+                    .err()
+                    .map(|e| e.to_string()),
+            );
+            funder.amount_error.set(
+                validate_input(&funder.amount.read(), ValidationField::Amount, true)
+                    .err()
+                    .map(|e| e.to_string()),
+            );
+            funder.change_address_error.set(
+                validate_input(
+                    &funder.change_address.read(),
+                    ValidationField::PayoutAddress,
+                    false,
+                )
+                .err()
+                .map(|e| e.to_string()),
+            );
+            validate_funding(&funder.utxos.read());
+        }
+    };
+
+    let has_sign_form_errors = move || psbt_error.read().is_some() || nsec_error.read().is_some();
+
+    let mut validate_sign_form = move || {
+        psbt_error.set(
+            validate_input(&psbt.read(), ValidationField::Psbt, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        nsec_error.set(
+            validate_input(&nsec.read(), ValidationField::Nsec, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+    };
+
+    use_effect(move || {
+        to_owned![fee_estimates];
+
+        spawn(async move {
+            let esplora_client = create_client(&ESPLORA_ENDPOINT.read()).unwrap();
+            match get_fee_estimates(&esplora_client).await {
+                Ok(estimates) => {
+                    #[cfg(debug_assertions)]
+                    trace!(?estimates, "Fee estimates fetched successfully",);
+                    fee_estimates.set(Some(estimates));
+                }
+                Err(e) => {
+                    #[cfg(debug_assertions)]
+                    trace!(%e, "Error fetching fee estimates: {}", e);
+                    // Fall back to 1 sat/vB
+                    fee_rate.set("1".to_string());
+                }
+            }
+        });
+    });
+
+    let funder_count = funders.read().len();
+
+    rsx! {
+        main { class: "max-w-7xl mx-auto py-6 sm:px-6 lg:px-8",
+            div { class: "px-4 py-6 sm:px-0",
+                h1 { class: "text-2xl font-bold text-gray-900 mb-6", "Fund Escrow Together" }
+
+                div { class: "bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                NetworkInput { id: "network", label: "Bitcoin Network" }
+
+                                AddressInput {
+                                    update_var: escrow_address,
+                                    error: escrow_address_error,
+                                    id: "escrow-address",
+                                    label: "Escrow Address",
+                                }
+
+                                FeeRateSelector {
+                                    id: "fee",
+                                    label_input: "Fee rate (sats/vByte)",
+                                    label_dropdown: "Target Blocks",
+                                    update_var: fee_rate,
+                                    fee_estimates,
+                                    error: fee_rate_error,
+                                }
+                            }
+
+                            for (i , funder) in funders.read().iter().copied().enumerate() {
+                                div {
+                                    key: "{i}",
+                                    class: "border-t border-gray-200 pt-6",
+                                    h3 { class: "text-lg font-medium text-gray-900", "Party {i + 1}" }
+
+                                    div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                        NpubInputDerivedAddress {
+                                            id: "npub_{i}",
+                                            label: "Nostr Public Key (npub)",
+                                            update_var: funder.npub,
+                                            update_address: funder.derived_address,
+                                            col_span: 3,
+                                            error: funder.npub_error,
+                                        }
+
+                                        DerivedAddressOutput {
+                                            update_var: funder.derived_address,
+                                            label: "Resolution Address",
+                                            id: "derived_address_{i}",
+                                            col_span: 3,
+                                        }
+
+                                        BitcoinInput {
+                                            id: "amount_{i}",
+                                            label: "Escrow Amount (BTC)",
+                                            update_var: funder.amount,
+                                            error: funder.amount_error,
+                                        }
+
+                                        PayoutAddressInput {
+                                            update_var: funder.change_address,
+                                            label: "Change Address or Descriptor (optional, defaults to the resolution address)",
+                                            id: "change_address_{i}",
+                                            error: funder.change_address_error,
+                                        }
+
+                                        FundingInput {
+                                            id: "utxo_{i}",
+                                            label: "UTXO",
+                                            hint: "The UTXOs of the resolution address that the party spends. Fetch them from Esplora, or enter them manually.",
+                                            funding: funder.utxos,
+                                        }
+
+                                        div { class: "sm:col-span-6",
+                                            if let Some(error_msg) = funder.utxos_error.read().as_ref() {
+                                                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                                            }
+                                            div { class: "mt-3 flex justify-end",
+                                                SecondaryButton {
+                                                    onclick: move |_| {
+                                                        let (mut npub_error, mut utxos, mut utxos_error, mut fetching_utxos) = (
+                                                            funder.npub_error,
+                                                            funder.utxos,
+                                                            funder.utxos_error,
+                                                            funder.fetching_utxos,
+                                                        );
+                                                        npub_error
+                                                            .set(
+                                                                validate_input(&funder.npub.read(), ValidationField::Npub, true)
+                                                                    .err()
+                                                                    .map(|e| e.to_string()),
+                                                            );
+                                                        if npub_error.read().is_some() || *fetching_utxos.read() {
+                                                            return;
+                                                        }
+                                                        let network = parse_network(&NETWORK.read()).unwrap();
+                                                        let npub = parse_npub(&funder.npub.read()).unwrap();
+                                                        let address = match npub_to_address(&npub, network) {
+                                                            Ok(address) => address,
+                                                            Err(e) => {
+                                                                npub_error.set(Some(e.to_string()));
+                                                                return;
+                                                            }
+                                                        };
+                                                        #[cfg(debug_assertions)]
+                                                        trace!(% address, "Clicked Fetch UTXOs");
+                                                        fetching_utxos.set(true);
+                                                        spawn(async move {
+                                                            let esplora_client = create_client(&ESPLORA_ENDPOINT.read()).unwrap();
+                                                            match get_utxos(&esplora_client, &address).await {
+                                                                Ok(fetched) if fetched.is_empty() => {
+                                                                    utxos_error
+                                                                        .set(Some("No UTXOs found for the resolution address.".to_string()));
+                                                                }
+                                                                Ok(fetched) => {
+                                                                    #[cfg(debug_assertions)]
+                                                                    trace!(? fetched, "UTXOs fetched successfully");
+                                                                    utxos.set(fetched.iter().map(FundingFields::with_utxo).collect());
+                                                                    utxos_error.set(None);
+                                                                }
+                                                                Err(e) => {
+                                                                    #[cfg(debug_assertions)]
+                                                                    trace!(% e, "Error fetching UTXOs");
+                                                                    utxos_error.set(Some(e.to_string()));
+                                                                }
+                                                            }
+                                                            fetching_utxos.set(false);
+                                                        });
+                                                    },
+                                                    text: if *funder.fetching_utxos.read() { "Fetching UTXOs..." } else { "Fetch UTXOs" },
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end",
+                                    if funder_count > 1 {
+                                        SecondaryButton {
+                                            onclick: move |_| {
+                                                #[cfg(debug_assertions)]
+                                                trace!(funders = % funder_count, "Removed party");
+                                                funders.write().pop();
+                                            },
+                                            text: "Remove Party",
+                                        }
+                                    }
+                                    SecondaryButton {
+                                        onclick: move |_| {
+                                            #[cfg(debug_assertions)]
+                                            trace!(funders = % funder_count, "Added party");
+                                            funders.write().push(FunderFields::new());
+                                        },
+                                        text: "Add Party",
+                                    }
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_fund_form();
+                                            if has_fund_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot build funding PSBT");
+                                                return;
+                                            }
+                                            #[cfg(debug_assertions)]
+                                            trace!(
+                                                % escrow_address, % fee_rate, funders = % funder_count,
+                                                "Clicked Build Funding PSBT"
+                                            );
+                                            let network = parse_network(&NETWORK.read()).unwrap();
+                                            let escrow_address = escrow_address
+                                                .read()
+                                                .parse::<Address<_>>()
+                                                .unwrap()
+                                                .require_network(network);
+                                            let escrow_address = match escrow_address {
+                                                Ok(escrow_address) => escrow_address,
+                                                Err(e) => {
+                                                    escrow_address_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            };
+                                            let fee_rate = parse_fee_rate(&fee_rate.read()).unwrap();
+                                            let contributions = funders
+                                                .read()
+                                                .iter()
+                                                .map(|funder| {
+                                                    let change_address = funder.change_address.read();
+                                                    let change_address = if change_address.trim().is_empty() {
+                                                        None
+                                                    } else {
+                                                        Some(parse_payout_address(&change_address, network)?)
+                                                    };
+                                                    Ok(FundingContribution {
+                                                        npub: parse_npub(&funder.npub.read())?,
+                                                        utxos: parse_funding(&funder.utxos.read()),
+                                                        amount: Amount::from_btc(funder.amount.read().parse::<f64>().unwrap())
+                                                            .unwrap(),
+                                                        change_address,
+                                                    })
+                                                })
+                                                .collect::<Result<Vec<_>, Error>>();
+                                            let psbt_result = contributions
+                                                .and_then(|contributions| {
+                                                    funding_psbt(&escrow_address, &contributions, fee_rate, network)
+                                                });
+                                            let unsigned_psbt = match psbt_result {
+                                                Ok(unsigned_psbt) => unsigned_psbt,
+                                                Err(e) => {
+                                                    build_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            };
+                                            build_error.set(None);
+                                            let fee = unsigned_psbt.fee().map(|fee| fee.to_sat()).unwrap_or_default();
+                                            let unsigned_psbt = unsigned_psbt.to_string();
+                                            #[cfg(debug_assertions)]
+                                            info!(% unsigned_psbt, % fee, "Built funding PSBT");
+                                            fee_str.set(fee.to_string());
+                                            unsigned_psbt_str.set(unsigned_psbt.clone());
+                                            // Prefill the PSBT to sign.
+                                            psbt.set(unsigned_psbt);
+                                            psbt_error.set(None);
+                                        },
+                                        text: "Build Funding PSBT",
+                                    }
+                                }
+                                if let Some(error_msg) = build_error.read().as_ref() {
+                                    p { class: "mt-2 text-xs text-red-600 text-right", "{error_msg}" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        h3 { class: "text-lg leading-6 font-medium text-gray-900",
+                            "Unsigned Funding PSBT"
+                        }
+
+                        TransactionOutput {
+                            update_var: unsigned_psbt_str,
+                            label: "",
+                            id: "unsigned-psbt",
+                            placeholder: "Unsigned funding PSBT will appear here...",
+                        }
+
+                        FeeOutput {
+                            update_var: fee_str,
+                            label: "Total Fee",
+                            id: "fee-output",
+                        }
+
+                        div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
+                            CopyButton {
+                                text: "PSBT",
+                                clipboard_text: unsigned_psbt_str,
+                            }
+                        }
+                    }
+                }
+
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            h3 { class: "text-lg font-medium text-gray-900", "Sign Your Inputs" }
+
+                            PsbtInput {
+                                update_var: psbt,
+                                label: "Funding PSBT",
+                                id: "funding-psbt",
+                                error: psbt_error,
+                            }
+
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                NsecInput { update_var: nsec, error: nsec_error }
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end",
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_sign_form();
+                                            if has_sign_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot sign funding PSBT");
+                                                return;
+                                            }
+                                            let mut funding_psbt = psbt.read().trim().parse::<Psbt>().unwrap();
+                                            let nsec = parse_nsec(&nsec.read()).unwrap();
+                                            match sign_funding_psbt(&mut funding_psbt, &nsec) {
+                                                Ok(0) => {
+                                                    psbt_error
+                                                        .set(Some("None of the inputs belong to your resolution address.".to_string()));
+                                                }
+                                                Ok(signatures) => {
+                                                    let signed_psbt = funding_psbt.to_string();
+                                                    #[cfg(debug_assertions)]
+                                                    info!(% signed_psbt, % signatures, "Signed funding PSBT");
+                                                    signed_psbt_str.set(signed_psbt);
+                                                    psbt_error.set(None);
+                                                }
+                                                Err(e) => {
+                                                    psbt_error.set(Some(e.to_string()));
+                                                }
+                                            }
+                                        },
+                                        text: "Sign Your Inputs",
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        h3 { class: "text-lg leading-6 font-medium text-gray-900",
+                            "Signed Funding PSBT"
+                        }
+
+                        TransactionOutput {
+                            update_var: signed_psbt_str,
+                            label: "",
+                            id: "signed-psbt",
+                            placeholder: "Signed funding PSBT will appear here...",
+                        }
+
+                        div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
+                            CopyButton {
+                                text: "PSBT",
+                                clipboard_text: signed_psbt_str,
+                            }
+                            ContinueButton {
+                                to: Route::Combine {},
+                                text: "Continue to Combine",
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Footer {}
+    }
+}
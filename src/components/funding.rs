@@ -0,0 +1,249 @@
+//! Escrow Funding Components.
+
+use bitcoin::{Amount, OutPoint, Txid};
+use dioxus::prelude::*;
+
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::trace;
+
+use crate::{
+    tx::Utxo,
+    validation::{ValidationField, validate_input},
+};
+
+use super::SecondaryButton;
+
+/// The input fields of an output that funds the escrow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct FundingFields {
+    pub(crate) txid: Signal<String>,
+    pub(crate) txid_error: Signal<Option<String>>,
+    pub(crate) vout: Signal<String>,
+    pub(crate) vout_error: Signal<Option<String>>,
+    pub(crate) amount: Signal<String>,
+    pub(crate) amount_error: Signal<Option<String>>,
+}
+
+impl FundingFields {
+    /// Creates empty [`FundingFields`] of the first output, owned by the current component.
+    pub(crate) fn new() -> Self {
+        Self {
+            txid: Signal::new(String::new()),
+            txid_error: Signal::new(None),
+            vout: Signal::new("0".to_string()),
+            vout_error: Signal::new(None),
+            amount: Signal::new(String::new()),
+            amount_error: Signal::new(None),
+        }
+    }
+
+    /// Creates [`FundingFields`] filled in with a [`Utxo`], owned by the current component.
+    pub(crate) fn with_utxo(utxo: &Utxo) -> Self {
+        Self {
+            txid: Signal::new(utxo.outpoint.txid.to_string()),
+            txid_error: Signal::new(None),
+            vout: Signal::new(utxo.outpoint.vout.to_string()),
+            vout_error: Signal::new(None),
+            amount: Signal::new(utxo.value.to_btc().to_string()),
+            amount_error: Signal::new(None),
+        }
+    }
+}
+
+/// Creates the [`FundingFields`] of a single funding output.
+///
+/// More funding outputs, e.g. one deposit per party, can be added with [`FundingInput`].
+pub(crate) fn use_funding() -> Signal<Vec<FundingFields>> {
+    use_signal(|| vec![FundingFields::new()])
+}
+
+/// Validates a field of a funding output.
+fn validate_funding_field(value: &str, field: ValidationField) -> Option<String> {
+    validate_input(value, field, true)
+        .err()
+        .map(|e| e.to_string())
+}
+
+/// Validates the [`FundingFields`] of every funding output.
+pub(crate) fn validate_funding(funding: &[FundingFields]) {
+    for mut output in funding.iter().copied() {
+        output.txid_error.set(validate_funding_field(
+            &output.txid.read(),
+            ValidationField::Txid,
+        ));
+        output.vout_error.set(validate_funding_field(
+            &output.vout.read(),
+            ValidationField::Vout,
+        ));
+        output.amount_error.set(validate_funding_field(
+            &output.amount.read(),
+            ValidationField::Amount,
+        ));
+    }
+}
+
+/// Whether any of the [`FundingFields`] has a validation error.
+pub(crate) fn has_funding_errors(funding: &[FundingFields]) -> bool {
+    funding.iter().any(|output| {
+        output.txid_error.read().is_some()
+            || output.vout_error.read().is_some()
+            || output.amount_error.read().is_some()
+    })
+}
+
+/// Parses validated [`FundingFields`] into the [`Utxo`]s that the escrow transaction spends.
+pub(crate) fn parse_funding(funding: &[FundingFields]) -> Vec<Utxo> {
+    funding
+        .iter()
+        .map(|output| Utxo {
+            outpoint: OutPoint {
+                txid: output.txid.read().parse::<Txid>().unwrap(),
+                vout: output.vout.read().trim().parse::<u32>().unwrap(),
+            },
+            value: Amount::from_btc(output.amount.read().parse::<f64>().unwrap()).unwrap(),
+            confirmed: true,
+        })
+        .collect()
+}
+
+/// Escrow funding input component.
+///
+/// Renders the transaction ID, the output index and the amount of every output
+/// that funds the escrow address, e.g. one deposit per party from different wallets.
+/// The `id` prefixes the element IDs, so that several lists can share a page.
+#[component]
+pub(crate) fn FundingInput(
+    id: String,
+    label: String,
+    hint: String,
+    mut funding: Signal<Vec<FundingFields>>,
+) -> Element {
+    let funding_count = funding.read().len();
+
+    let input_class = |error: Signal<Option<String>>| {
+        if error.read().is_some() {
+            "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+        } else {
+            "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+        }
+    };
+
+    rsx! {
+        div { class: "sm:col-span-6 space-y-6",
+            if !hint.is_empty() {
+                p { class: "text-xs text-gray-500", "{hint}" }
+            }
+
+            for (i , output) in funding.read().iter().copied().enumerate() {
+                div {
+                    key: "{i}",
+                    class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                    div { class: "sm:col-span-3",
+                        label {
+                            r#for: "{id}_txid_{i}",
+                            class: "block text-sm font-medium text-gray-700",
+                            "{label} {i + 1} Transaction ID"
+                        }
+                        div { class: "mt-1",
+                            input {
+                                r#type: "text",
+                                id: "{id}_txid_{i}",
+                                class: input_class(output.txid_error),
+                                placeholder: "txid",
+                                value: output.txid,
+                                oninput: move |event| {
+                                    let (mut txid, mut txid_error) = (output.txid, output.txid_error);
+                                    #[cfg(debug_assertions)]
+                                    trace!(% txid, event_value =% event.value(), "Set funding txid");
+                                    txid.set(event.value());
+                                    txid_error.set(validate_funding_field(&event.value(), ValidationField::Txid));
+                                },
+                            }
+                        }
+                        if let Some(error_msg) = output.txid_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                        }
+                    }
+
+                    div { class: "sm:col-span-1",
+                        label {
+                            r#for: "{id}_vout_{i}",
+                            class: "block text-sm font-medium text-gray-700",
+                            "Output Index"
+                        }
+                        div { class: "mt-1",
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                id: "{id}_vout_{i}",
+                                class: input_class(output.vout_error),
+                                placeholder: "0",
+                                value: output.vout,
+                                oninput: move |event| {
+                                    let (mut vout, mut vout_error) = (output.vout, output.vout_error);
+                                    #[cfg(debug_assertions)]
+                                    trace!(% vout, event_value =% event.value(), "Set funding vout");
+                                    vout.set(event.value());
+                                    vout_error.set(validate_funding_field(&event.value(), ValidationField::Vout));
+                                },
+                            }
+                        }
+                        if let Some(error_msg) = output.vout_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                        }
+                    }
+
+                    div { class: "sm:col-span-2",
+                        label {
+                            r#for: "{id}_amount_{i}",
+                            class: "block text-sm font-medium text-gray-700",
+                            "{label} {i + 1} Amount (BTC)"
+                        }
+                        div { class: "mt-1",
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "any",
+                                id: "{id}_amount_{i}",
+                                class: input_class(output.amount_error),
+                                placeholder: "0.00",
+                                value: output.amount,
+                                oninput: move |event| {
+                                    let (mut amount, mut amount_error) = (output.amount, output.amount_error);
+                                    #[cfg(debug_assertions)]
+                                    trace!(% amount, event_value =% event.value(), "Set funding amount");
+                                    amount.set(event.value());
+                                    amount_error.set(validate_funding_field(&event.value(), ValidationField::Amount));
+                                },
+                            }
+                        }
+                        if let Some(error_msg) = output.amount_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                        }
+                    }
+                }
+            }
+
+            div { class: "flex justify-end",
+                if funding_count > 1 {
+                    SecondaryButton {
+                        onclick: move |_| {
+                            #[cfg(debug_assertions)]
+                            trace!(funding = % funding_count, "Removed funding output");
+                            funding.write().pop();
+                        },
+                        text: "Remove {label} Output",
+                    }
+                }
+                SecondaryButton {
+                    onclick: move |_| {
+                        #[cfg(debug_assertions)]
+                        trace!(funding = % funding_count, "Added funding output");
+                        funding.write().push(FundingFields::new());
+                    },
+                    text: "Add {label} Output",
+                }
+            }
+        }
+    }
+}
@@ -139,7 +139,7 @@ pub(crate) fn Home() -> Element {
                             }
                         }
                         h2 { class: "text-2xl font-semibold text-gray-900 mb-4", "Getting Started" }
-                        div { class: "grid md:grid-cols-5 gap-6",
+                        div { class: "grid md:grid-cols-3 gap-6",
                             Link {
                                 to: Route::Create {},
                                 class: "block bg-white shadow-sm rounded-lg p-6 hover:shadow-md transition-shadow",
@@ -150,11 +150,21 @@ pub(crate) fn Home() -> Element {
                                     "Set up a new escrow address using npubs and specify amounts."
                                 }
                             }
+                            Link {
+                                to: Route::Fund {},
+                                class: "block bg-white shadow-sm rounded-lg p-6 hover:shadow-md transition-shadow",
+                                h3 { class: "text-lg font-semibold text-gray-900 mb-2",
+                                    "2. Fund Escrow"
+                                }
+                                p { class: "text-gray-600",
+                                    "Fund the escrow address together in a single transaction, each party signing its own inputs."
+                                }
+                            }
                             Link {
                                 to: Route::Sign {},
                                 class: "block bg-white shadow-sm rounded-lg p-6 hover:shadow-md transition-shadow",
                                 h3 { class: "text-lg font-semibold text-gray-900 mb-2",
-                                    "2. Sign Transaction"
+                                    "3. Sign Transaction"
                                 }
                                 p { class: "text-gray-600",
                                     "Sign the transaction using your nsec key."
@@ -164,7 +174,7 @@ pub(crate) fn Home() -> Element {
                                 to: Route::Combine {},
                                 class: "block bg-white shadow-sm rounded-lg p-6 hover:shadow-md transition-shadow",
                                 h3 { class: "text-lg font-semibold text-gray-900 mb-2",
-                                    "3. Combine Signatures"
+                                    "4. Combine Signatures"
                                 }
                                 p { class: "text-gray-600",
                                     "Combine the signatures into a signed transaction."
@@ -174,7 +184,7 @@ pub(crate) fn Home() -> Element {
                                 to: Route::Broadcast {},
                                 class: "block bg-white shadow-sm rounded-lg p-6 hover:shadow-md transition-shadow",
                                 h3 { class: "text-lg font-semibold text-gray-900 mb-2",
-                                    "4. Broadcast"
+                                    "5. Broadcast"
                                 }
                                 p { class: "text-gray-600",
                                     "Broadcast the signed transaction to the Bitcoin network."
@@ -184,7 +194,7 @@ pub(crate) fn Home() -> Element {
                                 to: Route::Spend {},
                                 class: "block bg-white shadow-sm rounded-lg p-6 hover:shadow-md transition-shadow",
                                 h3 { class: "text-lg font-semibold text-gray-900 mb-2",
-                                    "5. Spend"
+                                    "6. Spend"
                                 }
                                 p { class: "text-gray-600",
                                     "Spend from the resolution address derived from your npub using your nsec."
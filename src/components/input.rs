@@ -1,5 +1,6 @@
 //! Input Validation Components.
 
+use bitcoin::{consensus, hex::DisplayHex};
 use dioxus::prelude::*;
 
 #[cfg(debug_assertions)]
@@ -7,11 +8,17 @@ use dioxus::logger::tracing::trace;
 
 use crate::{
     ESPLORA_ENDPOINT, NETWORK,
-    esplora::FeeEstimate,
-    util::{npub_to_address, parse_network, parse_npub},
+    error::ValidationError,
+    esplora::{FeeEstimate, create_client, get_funding_transactions},
+    util::{
+        days_hours_to_relative_lock_time, npub_to_address, parse_network, parse_npub,
+        parse_transaction_or_psbt,
+    },
     validation::{ValidationField, validate_input},
 };
 
+use super::SecondaryButton;
+
 /// Nostr `npub` input validation component.
 #[component]
 pub(crate) fn NpubInput(
@@ -197,6 +204,112 @@ pub(crate) fn BitcoinInput(
     }
 }
 
+/// Satoshi amount input validation component.
+#[component]
+pub(crate) fn SatsInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    error: Signal<Option<String>>,
+) -> Element {
+    let mut on_validate_sats = move |input: &str| {
+        update_var.set(input.to_string());
+        let error_msg = validate_input(input, ValidationField::Sats, true)
+            .err()
+            .map(|e| e.to_string());
+        error.set(error_msg);
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-3",
+            label {
+                r#for: id.as_str(),
+                class: "block text-sm font-medium text-gray-700",
+                {label}
+            }
+            div { class: "mt-1",
+                input {
+                    r#type: "number",
+                    min: "0",
+                    step: "1",
+                    name: id.as_str(),
+                    id: id.as_str(),
+                    class: input_class,
+                    placeholder: "0",
+                    value: update_var,
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set sats amount");
+                        on_validate_sats(&event.value());
+                    },
+                }
+            }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+        }
+    }
+}
+
+/// Fee rate input validation component, in sats/vByte.
+#[component]
+pub(crate) fn FeeRateInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    error: Signal<Option<String>>,
+) -> Element {
+    let mut on_validate_fee_rate = move |input: &str| {
+        update_var.set(input.to_string());
+        let error_msg = validate_input(input, ValidationField::FeeRate, true)
+            .err()
+            .map(|e| e.to_string());
+        error.set(error_msg);
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-3",
+            label {
+                r#for: id.as_str(),
+                class: "block text-sm font-medium text-gray-700",
+                {label}
+            }
+            div { class: "mt-1",
+                input {
+                    r#type: "number",
+                    min: "0.1",
+                    step: "0.1",
+                    name: id.as_str(),
+                    id: id.as_str(),
+                    class: input_class,
+                    placeholder: "1",
+                    value: update_var,
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set fee rate");
+                        on_validate_fee_rate(&event.value());
+                    },
+                }
+            }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+        }
+    }
+}
+
 /// Component to select the resolution transaction fee rate via input field or
 /// dropdown with fees fetched from Esplora and their expected confirmation targets.
 #[component]
@@ -236,7 +349,8 @@ pub(crate) fn FeeRateSelector(
         if let Some(estimates) = fee_estimates.read().as_ref()
             && let Some(fee) = estimates.get(&selected_target.read().parse::<u16>().unwrap_or(3))
         {
-            let rounded_fee = fee.ceil() as u64;
+            // Fractional rates are rounded up to a tenth of sat/vB.
+            let rounded_fee = (fee * 10.0).ceil() / 10.0;
             update_var.set(rounded_fee.to_string());
 
             #[cfg(debug_assertions)]
@@ -258,8 +372,8 @@ pub(crate) fn FeeRateSelector(
                     span { class: "block text-sm font-medium text-gray-700", {label_input} }
                     input {
                         r#type: "number",
-                        min: "1",
-                        step: "1",
+                        min: "0.1",
+                        step: "0.1",
                         name: id.as_str(),
                         id: id.as_str(),
                         class: input_class,
@@ -412,17 +526,81 @@ pub(crate) fn EsploraInput(
     }
 }
 
+/// Validates the timelock fields of the `mode` of [`TimelockInput`], clearing the errors of the other mode.
+pub(crate) fn validate_timelock(
+    mode: &str,
+    days: &str,
+    hours: &str,
+    absolute: &str,
+    mut day_error: Signal<Option<String>>,
+    mut hour_error: Signal<Option<String>>,
+    mut absolute_error: Signal<Option<String>>,
+) {
+    let validate = |input: &str, field: ValidationField| {
+        validate_input(input, field, true)
+            .err()
+            .map(|e| e.to_string())
+    };
+    match mode {
+        "Height" | "Date" => {
+            let field = if mode == "Height" {
+                ValidationField::TimelockHeight
+            } else {
+                ValidationField::TimelockDate
+            };
+            absolute_error.set(validate(absolute, field));
+            day_error.set(None);
+            hour_error.set(None);
+        }
+        _ => {
+            day_error.set(validate(days, ValidationField::TimelockDays));
+            hour_error.set(validate(hours, ValidationField::TimelockHours));
+            absolute_error.set(None);
+            if day_error.read().is_none() && hour_error.read().is_none() {
+                day_error.set(validate_relative_timelock(mode, days, hours));
+            }
+        }
+    }
+}
+
+/// Validates that `days` and `hours` fit in a BIP68 relative timelock of the `mode` of [`TimelockInput`].
+pub(crate) fn validate_relative_timelock(mode: &str, days: &str, hours: &str) -> Option<String> {
+    let (Ok(days), Ok(hours)) = (days.parse::<u32>(), hours.parse::<u32>()) else {
+        return None;
+    };
+    days_hours_to_relative_lock_time(days, hours, mode == "Time")
+        .err()
+        .map(|_| ValidationError::InvalidRelativeTimelock.to_string())
+}
+
 /// Timelock input validation component.
+///
+/// The timelock is either a `Duration` in days and hours relative to the funding confirmation,
+/// in blocks or in BIP68 512-second intervals (`Time`), or an absolute block `Height` or `Date`.
 #[component]
 pub(crate) fn TimelockInput(
+    mut update_mode_var: Signal<String>,
     mut update_day_var: Signal<String>,
     mut update_hour_var: Signal<String>,
+    mut update_absolute_var: Signal<String>,
     day_error: Signal<Option<String>>,
     hour_error: Signal<Option<String>>,
+    absolute_error: Signal<Option<String>>,
     required: Option<bool>,
 ) -> Element {
     let required = required.unwrap_or(false);
 
+    // Initialize the signal with "Duration" when the component is first created
+    use_effect(move || {
+        // Only set the default value if the current value is empty
+        if update_mode_var.read().is_empty() {
+            update_mode_var.set("Duration".to_string());
+        }
+    });
+
+    #[allow(clippy::redundant_closure)]
+    let current_mode = use_memo(move || update_mode_var());
+
     let mut on_validate_days = move |input: &str| {
         update_day_var.set(input.to_string());
         let error_msg = validate_input(input, ValidationField::TimelockDays, required)
@@ -438,87 +616,170 @@ pub(crate) fn TimelockInput(
             .map(|e| e.to_string());
         hour_error.set(error_msg);
     };
-    let days_input_class = if day_error.read().is_some() {
-        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
-    } else {
-        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+
+    let mut on_validate_absolute = move |input: &str| {
+        update_absolute_var.set(input.to_string());
+        let field = if current_mode() == "Height" {
+            ValidationField::TimelockHeight
+        } else {
+            ValidationField::TimelockDate
+        };
+        let error_msg = validate_input(input, field, required)
+            .err()
+            .map(|e| e.to_string());
+        absolute_error.set(error_msg);
     };
 
-    let hours_input_class = if hour_error.read().is_some() {
-        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
-    } else {
-        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    let input_class = |error: Signal<Option<String>>| {
+        if error.read().is_some() {
+            "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+        } else {
+            "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+        }
     };
+    let days_input_class = input_class(day_error);
+    let hours_input_class = input_class(hour_error);
+    let absolute_input_class = input_class(absolute_error);
 
     rsx! {
         div { class: "sm:col-span-3",
-            div { class: "grid grid-cols-2 gap-4",
-                div {
-                    label {
-                        r#for: "timelock-days",
-                        class: "block text-sm font-medium text-gray-700",
-                        "Timelock (Days)"
-                    }
-                    div { class: "mt-1",
-                        input {
-                            r#type: "number",
-                            min: "0",
-                            step: "1",
-                            name: "timelock-days",
-                            id: "timelock-days",
-                            class: days_input_class,
-                            placeholder: "0",
-                            oninput: move |event| {
-                                #[cfg(debug_assertions)]
-                                trace!(% update_day_var, event_value =% event.value(), "Set timelock days");
-                                on_validate_days(&event.value());
-                            },
+            label {
+                r#for: "timelock-mode",
+                class: "block text-sm font-medium text-gray-700",
+                "Timelock Type"
+            }
+            div { class: "mt-1",
+                select {
+                    id: "timelock-mode",
+                    name: "timelock-mode",
+                    class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_mode_var, event_value =% event.value(), "Set timelock mode");
+                        update_mode_var.set(event.value());
+                        update_absolute_var.set(String::new());
+                        absolute_error.set(None);
+                    },
+                    value: current_mode,
+                    option { value: "Duration", "Duration after funding, in blocks (OP_CSV)" }
+                    option { value: "Time", "Duration after funding, in time (OP_CSV)" }
+                    option { value: "Height", "Block height (OP_CLTV)" }
+                    option { value: "Date", "Date (OP_CLTV)" }
+                }
+            }
+        }
+
+        if matches!(current_mode().as_str(), "Duration" | "Time") {
+            div { class: "sm:col-span-3",
+                div { class: "grid grid-cols-2 gap-4",
+                    div {
+                        label {
+                            r#for: "timelock-days",
+                            class: "block text-sm font-medium text-gray-700",
+                            "Timelock (Days)"
+                        }
+                        div { class: "mt-1",
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "1",
+                                name: "timelock-days",
+                                id: "timelock-days",
+                                class: days_input_class,
+                                placeholder: "0",
+                                value: update_day_var,
+                                oninput: move |event| {
+                                    #[cfg(debug_assertions)]
+                                    trace!(% update_day_var, event_value =% event.value(), "Set timelock days");
+                                    on_validate_days(&event.value());
+                                },
+                            }
+                        }
+                        if let Some(error_msg) = day_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
                         }
                     }
-                    if let Some(error_msg) = day_error.read().as_ref() {
-                        p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                    div {
+                        label {
+                            r#for: "timelock-hours",
+                            class: "block text-sm font-medium text-gray-700",
+                            "Timelock (Hours)"
+                        }
+                        div { class: "mt-1",
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "1",
+                                max: "23",
+                                name: "timelock-hours",
+                                id: "timelock-hours",
+                                class: hours_input_class,
+                                placeholder: "0",
+                                value: update_hour_var,
+                                oninput: move |event| {
+                                    #[cfg(debug_assertions)]
+                                    trace!(% update_hour_var, event_value =% event.value(), "Set timelock hours");
+                                    on_validate_hours(&event.value());
+                                },
+                            }
+                        }
+                        if let Some(error_msg) = hour_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                        }
                     }
                 }
-                div {
-                    label {
-                        r#for: "timelock-hours",
-                        class: "block text-sm font-medium text-gray-700",
-                        "Timelock (Hours)"
-                    }
-                    div { class: "mt-1",
-                        input {
-                            r#type: "number",
-                            min: "0",
-                            step: "1",
-                            max: "23",
-                            name: "timelock-hours",
-                            id: "timelock-hours",
-                            class: hours_input_class,
-                            placeholder: "0",
-                            oninput: move |event| {
-                                #[cfg(debug_assertions)]
-                                trace!(% update_hour_var, event_value =% event.value(), "Set timelock hours");
-                                on_validate_hours(&event.value());
-                            },
-                        }
+            }
+        } else {
+            div { class: "sm:col-span-3",
+                label {
+                    r#for: "timelock-absolute",
+                    class: "block text-sm font-medium text-gray-700",
+                    if current_mode() == "Height" {
+                        "Timelock (Block Height)"
+                    } else {
+                        "Timelock (Date, UTC)"
                     }
-                    if let Some(error_msg) = hour_error.read().as_ref() {
-                        p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                }
+                div { class: "mt-1",
+                    input {
+                        r#type: if current_mode() == "Height" { "number" } else { "date" },
+                        min: if current_mode() == "Height" { "1" } else { "1985-11-06" },
+                        step: "1",
+                        name: "timelock-absolute",
+                        id: "timelock-absolute",
+                        class: absolute_input_class,
+                        placeholder: if current_mode() == "Height" { "900000" } else { "YYYY-MM-DD" },
+                        value: update_absolute_var,
+                        oninput: move |event| {
+                            #[cfg(debug_assertions)]
+                            trace!(% update_absolute_var, event_value =% event.value(), "Set absolute timelock");
+                            on_validate_absolute(&event.value());
+                        },
                     }
                 }
+                p { class: "mt-2 text-xs text-gray-500",
+                    "The dispute opens at this block height or date, regardless of when the escrow is funded."
+                }
+                if let Some(error_msg) = absolute_error.read().as_ref() {
+                    p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                }
             }
         }
     }
 }
 
 /// Escrow type input validation component.
+///
+/// With `auto`, the escrow type defaults to "Auto": detected from the signer's key and the transaction.
 #[component]
-pub(crate) fn EscrowTypeInput(mut update_var: Signal<String>) -> Element {
-    // Initialize the signal with "A" when the component is first created
+pub(crate) fn EscrowTypeInput(mut update_var: Signal<String>, auto: Option<bool>) -> Element {
+    let auto = auto.unwrap_or(false);
+
+    // Initialize the signal with "A", or "Auto", when the component is first created
     use_effect(move || {
         // Only set the default value if the current value is empty
         if update_var.read().is_empty() {
-            update_var.set("A".to_string());
+            update_var.set(if auto { "Auto" } else { "A" }.to_string());
         }
     });
 
@@ -543,18 +804,114 @@ pub(crate) fn EscrowTypeInput(mut update_var: Signal<String>) -> Element {
                         update_var.set(event.value());
                     },
                     value: current_value,
+                    if auto {
+                        option { value: "Auto", "Auto - Detect from Your Key and the Transaction" }
+                    }
                     option { value: "A", "A - Collaborative (2-of-2)" }
                     option { value: "B", "B - Dispute: First Party + Arbitrator" }
                     option { value: "C", "C - Dispute: Second Party + Arbitrator" }
+                    option { value: "D", "D - Refund: Refund Party Alone (after timeout)" }
                 }
             }
         }
     }
 }
 
+/// Escrow internal key select component.
+#[component]
+pub(crate) fn InternalKeyInput(mut update_var: Signal<String>) -> Element {
+    // Initialize the signal with "Unspendable" when the component is first created
+    use_effect(move || {
+        // Only set the default value if the current value is empty
+        if update_var.read().is_empty() {
+            update_var.set("Unspendable".to_string());
+        }
+    });
+
+    #[allow(clippy::redundant_closure)]
+    let current_value = use_memo(move || update_var());
+
+    rsx! {
+        div { class: "sm:col-span-3",
+            label {
+                r#for: "internal-key",
+                class: "block text-sm font-medium text-gray-700",
+                "Cooperative Resolution"
+            }
+            div { class: "mt-1",
+                select {
+                    id: "internal-key",
+                    name: "internal-key",
+                    class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set internal key");
+                        update_var.set(event.value());
+                    },
+                    value: current_value,
+                    option { value: "Unspendable", "Script Path (2-of-2 Tapscript)" }
+                    option { value: "MuSig2", "Key Path (MuSig2 aggregate key)" }
+                }
+            }
+            p { class: "mt-2 text-xs text-gray-500",
+                "A MuSig2 key path resolution looks like any single-signature Taproot payment, but needs an extra round of nonce exchange."
+            }
+        }
+    }
+}
+
+/// Dust outputs policy select component.
+#[component]
+pub(crate) fn DustPolicyInput(mut update_var: Signal<String>) -> Element {
+    // Initialize the signal with "Reject" when the component is first created
+    use_effect(move || {
+        // Only set the default value if the current value is empty
+        if update_var.read().is_empty() {
+            update_var.set("Reject".to_string());
+        }
+    });
+
+    #[allow(clippy::redundant_closure)]
+    let current_value = use_memo(move || update_var());
+
+    rsx! {
+        div { class: "sm:col-span-3",
+            label {
+                r#for: "dust-policy",
+                class: "block text-sm font-medium text-gray-700",
+                "Dust Outputs"
+            }
+            div { class: "mt-1",
+                select {
+                    id: "dust-policy",
+                    name: "dust-policy",
+                    class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set dust policy");
+                        update_var.set(event.value());
+                    },
+                    value: current_value,
+                    option { value: "Reject", "Reject the transaction" }
+                    option { value: "MergeIntoFee", "Merge into the fee" }
+                }
+            }
+            p { class: "mt-2 text-xs text-gray-500",
+                "Outputs below the dust limit (330 sats for Taproot) are not relayed by nodes. Merged outputs are lost to the miners."
+            }
+        }
+    }
+}
+
 /// Nostr `nsec` input validation component.
 #[component]
-pub(crate) fn NsecInput(mut update_var: Signal<String>, error: Signal<Option<String>>) -> Element {
+pub(crate) fn NsecInput(
+    mut update_var: Signal<String>,
+    error: Signal<Option<String>>,
+    id: Option<String>,
+) -> Element {
+    let id = id.unwrap_or("nsec".to_string());
+
     let mut on_validate_nsec = move |input: &str| {
         update_var.set(input.to_string());
         let error_msg = validate_input(input, ValidationField::Nsec, true)
@@ -572,15 +929,15 @@ pub(crate) fn NsecInput(mut update_var: Signal<String>, error: Signal<Option<Str
     rsx! {
         div { class: "sm:col-span-3",
             label {
-                r#for: "nsec",
+                r#for: id.as_str(),
                 class: "block text-sm font-medium text-gray-700",
                 "Your Nostr Secret Key (nsec)"
             }
             div { class: "mt-1",
                 input {
                     r#type: "password",
-                    name: "nsec",
-                    id: "nsec",
+                    name: id.as_str(),
+                    id: id.as_str(),
                     class: input_class,
                     placeholder: "nsec...",
                     oninput: move |event| {
@@ -629,12 +986,7 @@ pub(crate) fn TxidInput(
                 {label}
             }
             if !warning.is_empty() {
-                p { class: "mt-2 text-xs text-red-600",
-                    "Deposit a single transaction to the escrow address and inform the transaction ID.
-                    This transaction will be used to fund the escrow address.
-                    Note that it should be a coinjoin transaction between buyer and seller,
-                    i.e. should have only one output: the escrow address with the whole total escrow amount."
-                }
+                p { class: "mt-2 text-xs text-red-600", {warning} }
             }
             div { class: "mt-1",
                 input {
@@ -658,16 +1010,26 @@ pub(crate) fn TxidInput(
 }
 
 /// Transaction input validation component.
+///
+/// With `or_psbt`, a PSBT is accepted too.
 #[component]
 pub(crate) fn TransactionInput(
     mut update_var: Signal<String>,
     label: String,
     id: String,
     error: Signal<Option<String>>,
+    or_psbt: Option<bool>,
 ) -> Element {
+    let or_psbt = or_psbt.unwrap_or(false);
+
     let mut on_validate_transaction = move |input: &str| {
         update_var.set(input.to_string());
-        let error_msg = validate_input(input, ValidationField::Transaction, true)
+        let field = if or_psbt {
+            ValidationField::TransactionOrPsbt
+        } else {
+            ValidationField::Transaction
+        };
+        let error_msg = validate_input(input, field, true)
             .err()
             .map(|e| e.to_string());
         error.set(error_msg);
@@ -692,7 +1054,7 @@ pub(crate) fn TransactionInput(
                     name: id.as_str(),
                     rows: "4",
                     class: input_class,
-                    placeholder: "Paste the transaction here...",
+                    placeholder: if or_psbt { "Paste the transaction or PSBT here..." } else { "Paste the transaction here..." },
                     oninput: move |event| {
                         #[cfg(debug_assertions)]
                         trace!(% update_var, event_value =% event.value(), "Set transaction");
@@ -707,6 +1069,168 @@ pub(crate) fn TransactionInput(
         }
     }
 }
+/// PSBT input validation component.
+#[component]
+pub(crate) fn PsbtInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    error: Signal<Option<String>>,
+    required: Option<bool>,
+) -> Element {
+    let required = required.unwrap_or(true);
+
+    let mut on_validate_psbt = move |input: &str| {
+        update_var.set(input.to_string());
+        let error_msg = validate_input(input, ValidationField::Psbt, required)
+            .err()
+            .map(|e| e.to_string());
+        error.set(error_msg);
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-6",
+            label {
+                r#for: id.as_str(),
+                class: "block text-sm font-medium text-gray-700",
+                {label}
+            }
+            div { class: "mt-1",
+                textarea {
+                    id: id.as_str(),
+                    name: id.as_str(),
+                    rows: "4",
+                    class: input_class,
+                    placeholder: "Paste the PSBT here...",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set PSBT");
+                        on_validate_psbt(&event.value());
+                    },
+                    value: update_var,
+                }
+            }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+        }
+    }
+}
+
+/// Spent outputs input validation component.
+///
+/// Takes a PSBT, or the funding transactions in hex, to read the prevouts of the `unsigned_tx` from.
+/// The funding transactions can be fetched from Esplora, or pasted for offline signing,
+/// the `unsigned_tx` being a transaction in hex or a PSBT.
+/// They are required unless `required` is `false`.
+#[component]
+pub(crate) fn SpentOutputsInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    error: Signal<Option<String>>,
+    unsigned_tx: Signal<String>,
+    unsigned_tx_error: Signal<Option<String>>,
+    required: Option<bool>,
+) -> Element {
+    let required = required.unwrap_or(true);
+    let mut fetching = use_signal(|| false);
+
+    let mut on_validate_spent_outputs = move |input: &str| {
+        update_var.set(input.to_string());
+        let error_msg = validate_input(input, ValidationField::SpentOutputs, required)
+            .err()
+            .map(|e| e.to_string());
+        error.set(error_msg);
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-6",
+            label {
+                r#for: id.as_str(),
+                class: "block text-sm font-medium text-gray-700",
+                {label}
+            }
+            div { class: "mt-1",
+                textarea {
+                    id: id.as_str(),
+                    name: id.as_str(),
+                    rows: "4",
+                    class: input_class,
+                    placeholder: "Paste the PSBT or the funding transactions here...",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set spent outputs");
+                        on_validate_spent_outputs(&event.value());
+                    },
+                    value: update_var,
+                }
+            }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+            p { class: "mt-2 text-xs text-gray-500",
+                "The amounts are read from the funding transactions, fetched from Esplora or pasted for offline use,
+                or from the PSBT of the transaction. Every input must spend an output of the escrow address."
+            }
+            div { class: "mt-3 flex justify-end",
+                SecondaryButton {
+                    onclick: move |_| {
+                        unsigned_tx_error
+                            .set(
+                                validate_input(&unsigned_tx.read(), ValidationField::TransactionOrPsbt, true)
+                                    .err()
+                                    .map(|e| e.to_string()),
+                            );
+                        if unsigned_tx_error.read().is_some() || *fetching.read() {
+                            return;
+                        }
+                        let (unsigned_tx, _) = parse_transaction_or_psbt(&unsigned_tx.read()).unwrap();
+                        #[cfg(debug_assertions)]
+                        trace!(txid = % unsigned_tx.compute_txid(), "Clicked Fetch Funding Transactions");
+                        fetching.set(true);
+                        spawn(async move {
+                            let esplora_client = create_client(&ESPLORA_ENDPOINT.read()).unwrap();
+                            match get_funding_transactions(&esplora_client, &unsigned_tx).await {
+                                Ok(txs) => {
+                                    update_var
+                                        .set(
+                                            txs
+                                                .iter()
+                                                .map(|tx| consensus::serialize(tx).as_hex().to_string())
+                                                .collect::<Vec<_>>()
+                                                .join("\n"),
+                                        );
+                                    error.set(None);
+                                }
+                                Err(e) => {
+                                    #[cfg(debug_assertions)]
+                                    trace!(% e, "Error fetching funding transactions");
+                                    error.set(Some(e.to_string()));
+                                }
+                            }
+                            fetching.set(false);
+                        });
+                    },
+                    text: if *fetching.read() { "Fetching Funding Transactions..." } else { "Fetch Funding Transactions" },
+                }
+            }
+        }
+    }
+}
+
 /// Signature input validation component.
 #[component]
 pub(crate) fn SignatureInput(
@@ -760,12 +1284,125 @@ pub(crate) fn SignatureInput(
         }
     }
 }
+/// MuSig2 public nonce input validation component.
+#[component]
+pub(crate) fn PubNonceInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    error: Signal<Option<String>>,
+    required: Option<bool>,
+) -> Element {
+    let required = required.unwrap_or(true);
+
+    let mut on_validate = move |input: &str| {
+        update_var.set(input.to_string());
+        let error_msg = validate_input(input, ValidationField::PubNonce, required)
+            .err()
+            .map(|e| e.to_string());
+        error.set(error_msg);
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-6",
+            label {
+                r#for: id.as_str(),
+                class: "block text-sm font-medium text-gray-700",
+                {label}
+            }
+            div { class: "mt-1",
+                textarea {
+                    id: id.as_str(),
+                    name: id.as_str(),
+                    rows: "2",
+                    class: input_class,
+                    placeholder: "Paste the public nonces here...",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set public nonce");
+                        on_validate(&event.value());
+                    },
+                    value: update_var,
+                }
+            }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+        }
+    }
+}
+
+/// MuSig2 partial signature input validation component.
+#[component]
+pub(crate) fn PartialSignatureInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    error: Signal<Option<String>>,
+    required: Option<bool>,
+) -> Element {
+    let required = required.unwrap_or(true);
+
+    let mut on_validate = move |input: &str| {
+        update_var.set(input.to_string());
+        let error_msg = validate_input(input, ValidationField::PartialSignature, required)
+            .err()
+            .map(|e| e.to_string());
+        error.set(error_msg);
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-6",
+            label {
+                r#for: id.as_str(),
+                class: "block text-sm font-medium text-gray-700",
+                {label}
+            }
+            div { class: "mt-1",
+                textarea {
+                    id: id.as_str(),
+                    name: id.as_str(),
+                    rows: "2",
+                    class: input_class,
+                    placeholder: "Paste the partial signatures here...",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set partial signature");
+                        on_validate(&event.value());
+                    },
+                    value: update_var,
+                }
+            }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+        }
+    }
+}
+
 /// Address input validation component.
 #[component]
 pub(crate) fn AddressInput(
     mut update_var: Signal<String>,
     error: Signal<Option<String>>,
+    id: Option<String>,
+    label: Option<String>,
 ) -> Element {
+    let id = id.unwrap_or("destination-address".to_string());
+    let label = label.unwrap_or("Your Destination Address".to_string());
+
     let mut on_validate_address = move |input: &str| {
         update_var.set(input.to_string());
         let error_msg = validate_input(input, ValidationField::Address, true)
@@ -782,18 +1419,14 @@ pub(crate) fn AddressInput(
 
     rsx! {
         div { class: "sm:col-span-3",
-            label {
-                r#for: "destination-address",
-                class: "block text-sm font-medium text-gray-700",
-                "Your Destination Address"
-            }
+            label { r#for: "{id}", class: "block text-sm font-medium text-gray-700", "{label}" }
             div { class: "mt-1",
                 input {
                     r#type: "text",
-                    name: "destination-address",
-                    id: "destination-address",
+                    name: "{id}",
+                    id: "{id}",
                     class: input_class,
-                    placeholder: "Enter your destination address...",
+                    placeholder: "Enter an address...",
                     oninput: move |event| {
                         #[cfg(debug_assertions)]
                         trace!(% update_var, event_value =% event.value(), "Set address");
@@ -808,6 +1441,56 @@ pub(crate) fn AddressInput(
     }
 }
 
+/// Optional payout address or single-key descriptor input validation component.
+#[component]
+pub(crate) fn PayoutAddressInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    error: Signal<Option<String>>,
+) -> Element {
+    let mut on_validate_payout_address = move |input: &str| {
+        update_var.set(input.to_string());
+        let error_msg = validate_input(input, ValidationField::PayoutAddress, false)
+            .err()
+            .map(|e| e.to_string());
+        error.set(error_msg);
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-6",
+            label { r#for: "{id}", class: "block text-sm font-medium text-gray-700", "{label}" }
+            div { class: "mt-1",
+                input {
+                    r#type: "text",
+                    name: "{id}",
+                    id: "{id}",
+                    class: input_class,
+                    placeholder: "bc1q... or wpkh([fingerprint/84'/0'/0']02...)#checksum",
+                    value: "{update_var}",
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set payout address");
+                        on_validate_payout_address(&event.value());
+                    },
+                }
+            }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+            p { class: "mt-2 text-xs text-gray-500",
+                "Leave empty to be paid to the resolution address derived from the npub."
+            }
+        }
+    }
+}
+
 /// Vout input validation component (simple 0/1 option).
 #[component]
 pub(crate) fn VoutInput(mut update_var: Signal<String>, label: String, id: String) -> Element {
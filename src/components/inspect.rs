@@ -0,0 +1,334 @@
+//! Inspect escrow transaction component.
+
+use bitcoin::absolute;
+use dioxus::prelude::*;
+
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::{info, trace};
+
+use crate::{
+    NETWORK,
+    error::Error,
+    inspect::{InputSpend, Inspection, inspect_transaction},
+    scripts::{EscrowScript, Timelock, UNSPENDABLE_PUBLIC_KEY},
+    tx::OutputLabel,
+    util::{parse_network, parse_npub, parse_spent_outputs, parse_transaction_or_psbt},
+    validation::{ValidationField, validate_input},
+};
+
+use super::{
+    ArbitratorsInput, Footer, NetworkInput, PartiesInput, PartyFields, PrimaryButton,
+    SpentOutputsInput, TransactionInput, has_parties_errors, use_arbitrators, use_parties,
+    validate_arbitrators,
+};
+
+/// Describes the escrow leaf of a script path spend, see [`EscrowTypeInput`](super::EscrowTypeInput).
+pub(crate) fn escrow_script_description(escrow_script: Option<EscrowScript>) -> &'static str {
+    match escrow_script {
+        Some(EscrowScript::A) => "A - Collaborative",
+        Some(EscrowScript::B) => "B - Dispute: First Party + Arbitrator",
+        Some(EscrowScript::C) => "C - Dispute: Second Party + Arbitrator",
+        Some(EscrowScript::D) => "D - Refund: Refund Party Alone (after timeout)",
+        None => "B or C - Dispute: enter the parties to tell which",
+    }
+}
+
+/// Describes the timelock checked by an escrow leaf.
+fn timelock_description(timelock: Timelock) -> String {
+    match timelock {
+        Timelock::Relative(lock_time) => format!("{lock_time} after the funding confirmation"),
+        Timelock::Absolute(lock_time) => format!("until {lock_time}"),
+    }
+}
+
+/// Parses the filled npubs of the [`PartyFields`], in order.
+fn parse_known_npubs(fields: &[PartyFields]) -> Result<Vec<nostr::PublicKey>, Error> {
+    fields
+        .iter()
+        .filter(|party| !party.npub.read().is_empty())
+        .map(|party| parse_npub(&party.npub.read()))
+        .collect()
+}
+
+/// Inspect escrow transaction component.
+#[component]
+pub(crate) fn Inspect() -> Element {
+    let tx = use_signal(String::new);
+    let spent_outputs = use_signal(String::new);
+    let parties = use_parties();
+    let arbitrators = use_arbitrators();
+    let mut inspection = use_signal(|| Option::<Inspection>::None);
+
+    let mut tx_error = use_signal(|| None);
+    let mut spent_outputs_error = use_signal(|| None);
+    let mut inspect_error = use_signal(|| Option::<String>::None);
+
+    let has_inspect_form_errors = move || {
+        tx_error.read().is_some()
+            || spent_outputs_error.read().is_some()
+            || has_parties_errors(&parties.read())
+            || has_parties_errors(&arbitrators.read())
+    };
+
+    // Every field but the transaction is optional, they only add details.
+    let mut validate_inspect_form = move || {
+        tx_error.set(
+            validate_input(&tx.read(), ValidationField::TransactionOrPsbt, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        spent_outputs_error.set(
+            validate_input(&spent_outputs.read(), ValidationField::SpentOutputs, false)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        validate_arbitrators(&parties.read());
+        validate_arbitrators(&arbitrators.read());
+    };
+
+    rsx! {
+        main { class: "max-w-7xl mx-auto py-6 sm:px-6 lg:px-8",
+            div { class: "px-4 py-6 sm:px-0",
+                h1 { class: "text-2xl font-bold text-gray-900 mb-6", "Inspect Transaction" }
+
+                div { class: "bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            TransactionInput {
+                                update_var: tx,
+                                label: "Transaction or PSBT",
+                                id: "inspect-tx",
+                                error: tx_error,
+                                or_psbt: true,
+                            }
+
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                NetworkInput { id: "network", label: "Bitcoin Network" }
+
+                                SpentOutputsInput {
+                                    update_var: spent_outputs,
+                                    label: "Funding Transactions (optional, to compute the fee)",
+                                    id: "spent-outputs",
+                                    error: spent_outputs_error,
+                                    unsigned_tx: tx,
+                                    unsigned_tx_error: tx_error,
+                                    required: false,
+                                }
+                            }
+
+                            div { class: "border-t border-gray-200 pt-6",
+                                h3 { class: "text-lg font-medium text-gray-900",
+                                    "Known Keys (optional)"
+                                }
+                                p { class: "mt-1 text-xs text-gray-500",
+                                    "Outputs paying the resolution addresses of these npubs are labelled, and the parties tell the dispute leaves apart."
+                                }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                    PartiesInput {
+                                        parties,
+                                        amounts: false,
+                                        fee_shares: false,
+                                        payout_addresses: false,
+                                    }
+
+                                    ArbitratorsInput { arbitrators }
+                                }
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end",
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_inspect_form();
+                                            if has_inspect_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot inspect transaction");
+                                                return;
+                                            }
+                                            let result = parse_transaction_or_psbt(&tx.read())
+                                                .and_then(|(tx, mut psbt_spent_outputs)| {
+                                                    if !spent_outputs.read().trim().is_empty() {
+                                                        psbt_spent_outputs.extend(parse_spent_outputs(&spent_outputs.read())?);
+                                                    }
+                                                    inspect_transaction(
+                                                        &tx,
+                                                        &psbt_spent_outputs,
+                                                        &parse_known_npubs(&parties.read())?,
+                                                        &parse_known_npubs(&arbitrators.read())?,
+                                                        parse_network(&NETWORK.read())?,
+                                                    )
+                                                });
+                                            match result {
+                                                Ok(result) => {
+                                                    #[cfg(debug_assertions)]
+                                                    info!(txid = % result.txid, "Inspected transaction");
+                                                    inspect_error.set(None);
+                                                    inspection.set(Some(result));
+                                                }
+                                                Err(e) => {
+                                                    inspect_error.set(Some(e.to_string()));
+                                                    inspection.set(None);
+                                                }
+                                            }
+                                        },
+                                        text: "Inspect Transaction",
+                                    }
+                                }
+                                if let Some(error_msg) = inspect_error.read().as_ref() {
+                                    p { class: "mt-2 text-xs text-red-600 text-right", "{error_msg}" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if let Some(inspection) = inspection.read().as_ref() {
+                    div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                        div { class: "px-4 py-5 sm:p-6",
+                            h3 { class: "text-lg leading-6 font-medium text-gray-900",
+                                "Transaction"
+                            }
+
+                            dl { class: "mt-5 grid grid-cols-1 gap-x-4 gap-y-6 sm:grid-cols-2",
+                                div {
+                                    dt { class: "text-sm font-medium text-gray-500", "Transaction ID" }
+                                    dd { class: "mt-1 text-sm font-mono text-gray-900 break-all",
+                                        "{inspection.txid}"
+                                    }
+                                }
+                                div {
+                                    dt { class: "text-sm font-medium text-gray-500", "Version and Lock Time" }
+                                    dd { class: "mt-1 text-sm text-gray-900",
+                                        if inspection.lock_time == absolute::LockTime::ZERO {
+                                            "Version {inspection.version}, no lock time"
+                                        } else {
+                                            "Version {inspection.version}, locked until {inspection.lock_time}"
+                                        }
+                                    }
+                                }
+                                div {
+                                    dt { class: "text-sm font-medium text-gray-500", "Size" }
+                                    dd { class: "mt-1 text-sm text-gray-900",
+                                        "{inspection.weight.to_vbytes_ceil()} vB"
+                                    }
+                                }
+                                div {
+                                    dt { class: "text-sm font-medium text-gray-500", "Fee" }
+                                    dd { class: "mt-1 text-sm text-gray-900",
+                                        if let Some(fee) = inspection.fee {
+                                            {
+                                                format!(
+                                                    "{} sats ({:.2} sat/vB at the current size)",
+                                                    fee.to_sat(),
+                                                    fee.to_sat() as f64 / inspection.weight.to_vbytes_ceil() as f64,
+                                                )
+                                            }
+                                        } else {
+                                            "Unknown, add the funding transactions or a PSBT"
+                                        }
+                                    }
+                                }
+                            }
+
+                            h3 { class: "mt-8 text-lg leading-6 font-medium text-gray-900",
+                                "Inputs"
+                            }
+                            for (i , input) in inspection.inputs.iter().enumerate() {
+                                div {
+                                    key: "{i}",
+                                    class: "mt-4 border border-gray-300 rounded-md p-4 space-y-2 text-sm",
+                                    p { class: "font-medium text-gray-900", "Input {i}" }
+                                    p { class: "font-mono text-gray-700 break-all", "{input.previous_output}" }
+                                    if let Some(prevout) = input.prevout.as_ref() {
+                                        p { class: "text-gray-700", "Spends {prevout.value.to_sat()} sats" }
+                                    }
+                                    p { class: "text-gray-700",
+                                        "Sequence {input.sequence.0:#010x}"
+                                        if let Some(lock_time) = input.relative_lock_time {
+                                            ", relative timelock of {lock_time}"
+                                        }
+                                    }
+                                    match &input.spend {
+                                        InputSpend::Unsigned => rsx! {
+                                            p { class: "text-gray-500", "Not signed yet" }
+                                        },
+                                        InputSpend::KeyPath => rsx! {
+                                            p { class: "text-gray-700", "Key path spend, e.g. a MuSig2 cooperative resolution" }
+                                        },
+                                        InputSpend::Other => rsx! {
+                                            p { class: "text-gray-700", "Not a Taproot spend of an escrow" }
+                                        },
+                                        InputSpend::ScriptPath(leaf) => rsx! {
+                                            p { class: "text-gray-700",
+                                                "Script path spend of escrow type {escrow_script_description(leaf.escrow_script)}"
+                                            }
+                                            if let Some(timelock) = leaf.timelock {
+                                                p { class: "text-gray-700", "The leaf is timelocked {timelock_description(timelock)}" }
+                                            }
+                                            p { class: "text-gray-500", "Leaf script" }
+                                            p { class: "font-mono text-gray-700 break-all bg-gray-50 p-2 rounded-md",
+                                                "{leaf.script_asm}"
+                                            }
+                                            p { class: "text-gray-500", "Control block" }
+                                            p { class: "font-mono text-gray-700 break-all bg-gray-50 p-2 rounded-md",
+                                                "Internal key {leaf.control_block.internal_key}"
+                                                if leaf.control_block.internal_key == *UNSPENDABLE_PUBLIC_KEY {
+                                                    " (unspendable)"
+                                                }
+                                                ", leaf version {leaf.control_block.leaf_version}, output key parity {leaf.control_block.output_key_parity:?}, depth {leaf.control_block.merkle_branch.len()}"
+                                            }
+                                        },
+                                    }
+                                    if !input.witness.is_empty() {
+                                        p { class: "text-gray-500", "Witness" }
+                                        ul { class: "font-mono text-xs text-gray-700 break-all bg-gray-50 p-2 rounded-md space-y-1",
+                                            for (j , element) in input.witness.iter().enumerate() {
+                                                li { key: "{j}",
+                                                    if element.is_empty() {
+                                                        "(empty)"
+                                                    } else {
+                                                        "{element}"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+
+                            h3 { class: "mt-8 text-lg leading-6 font-medium text-gray-900",
+                                "Outputs"
+                            }
+                            ul { class: "mt-4 divide-y divide-gray-200 border border-gray-300 rounded-md",
+                                for (i , output) in inspection.outputs.iter().enumerate() {
+                                    li { key: "{i}", class: "flex items-center p-2 text-sm",
+                                        span { class: "text-gray-700 whitespace-nowrap",
+                                            if output.label == OutputLabel::Unknown {
+                                                "Output {i}"
+                                            } else {
+                                                "{output.label}"
+                                            }
+                                        }
+                                        span { class: "ml-3 font-mono text-gray-700 truncate",
+                                            {
+                                                output
+                                                    .address
+                                                    .as_ref()
+                                                    .map_or("Non-standard script".to_string(), ToString::to_string)
+                                            }
+                                        }
+                                        span { class: "ml-auto pl-3 whitespace-nowrap text-gray-900",
+                                            "{output.value.to_sat()} sats"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Footer {}
+    }
+}
@@ -1,31 +1,57 @@
 //! Dioxus Components.
 
+pub(crate) mod arbitrate;
 pub(crate) mod broadcast;
 pub(crate) mod buttons;
 pub(crate) mod combine;
 pub(crate) mod create;
 pub(crate) mod footer;
+pub(crate) mod fund;
+pub(crate) mod funding;
 pub(crate) mod home;
 pub(crate) mod input;
+pub(crate) mod inspect;
 pub(crate) mod navbar;
 pub(crate) mod output;
+pub(crate) mod parties;
+pub(crate) mod payouts;
 pub(crate) mod settings;
 pub(crate) mod sign;
 pub(crate) mod spend;
+pub(crate) mod verify;
 
+pub(crate) use arbitrate::Arbitrate;
 pub(crate) use broadcast::Broadcast;
 pub(crate) use buttons::{ContinueButton, CopyButton, PrimaryButton, SecondaryButton};
 pub(crate) use combine::Combine;
 pub(crate) use create::Create;
 pub(crate) use footer::Footer;
+pub(crate) use fund::Fund;
+pub(crate) use funding::{
+    FundingFields, FundingInput, has_funding_errors, parse_funding, use_funding, validate_funding,
+};
 pub(crate) use home::Home;
 pub(crate) use input::{
-    AddressInput, BitcoinInput, EscrowTypeInput, EsploraInput, FeeRateSelector, NetworkInput,
-    NpubInput, NpubInputDerivedAddress, NsecInput, SignatureInput, TimelockInput, TransactionInput,
-    TxidInput, VoutInput,
+    AddressInput, BitcoinInput, DustPolicyInput, EscrowTypeInput, EsploraInput, FeeRateInput,
+    FeeRateSelector, InternalKeyInput, NetworkInput, NpubInput, NpubInputDerivedAddress, NsecInput,
+    PartialSignatureInput, PayoutAddressInput, PsbtInput, PubNonceInput, SatsInput, SignatureInput,
+    SpentOutputsInput, TimelockInput, TransactionInput, TxidInput, VoutInput,
+    validate_relative_timelock, validate_timelock,
 };
+pub(crate) use inspect::Inspect;
 pub(crate) use navbar::Navbar;
-pub(crate) use output::{DerivedAddressOutput, SignatureOutput, TransactionOutput};
+pub(crate) use output::{DerivedAddressOutput, FeeOutput, SignatureOutput, TransactionOutput};
+pub(crate) use parties::{
+    ArbitratorsInput, FeePolicyInput, PartiesInput, PartyFields, RefundInput, ThresholdInput,
+    has_arbitrators, has_parties_errors, has_refund_errors, parse_escrow_contract,
+    parse_fee_policy, parse_payout_addresses, parse_refund, use_arbitrators, use_parties,
+    use_refund, validate_arbitrators, validate_fee_shares, validate_parties, validate_refund,
+    validate_threshold,
+};
+pub(crate) use payouts::{
+    PayoutsInput, has_payouts_errors, parse_payouts, use_payouts, validate_payouts,
+};
 pub(crate) use settings::Settings;
 pub(crate) use sign::Sign;
 pub(crate) use spend::Spend;
+pub(crate) use verify::Verify;
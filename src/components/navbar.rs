@@ -117,6 +117,20 @@ pub(crate) fn Navbar() -> Element {
                                 to: Route::Create {},
                                 "Create"
                             }
+                            Link {
+                                id: "fund",
+                                class: if is_active(Route::Fund {}) { "border-indigo-500 text-gray-900 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" } else { "border-transparent text-gray-500 hover:border-gray-300 hover:text-gray-700 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" },
+                                aria_current: if is_active(Route::Fund {}) { "page" } else { "" },
+                                to: Route::Fund {},
+                                "Fund"
+                            }
+                            Link {
+                                id: "arbitrate",
+                                class: if is_active(Route::Arbitrate {}) { "border-indigo-500 text-gray-900 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" } else { "border-transparent text-gray-500 hover:border-gray-300 hover:text-gray-700 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" },
+                                aria_current: if is_active(Route::Arbitrate {}) { "page" } else { "" },
+                                to: Route::Arbitrate {},
+                                "Arbitrate"
+                            }
                             Link {
                                 id: "sign",
                                 class: if is_active(Route::Sign {}) { "border-indigo-500 text-gray-900 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" } else { "border-transparent text-gray-500 hover:border-gray-300 hover:text-gray-700 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" },
@@ -145,6 +159,20 @@ pub(crate) fn Navbar() -> Element {
                                 to: Route::Spend {},
                                 "Spend"
                             }
+                            Link {
+                                id: "inspect",
+                                class: if is_active(Route::Inspect {}) { "border-indigo-500 text-gray-900 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" } else { "border-transparent text-gray-500 hover:border-gray-300 hover:text-gray-700 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" },
+                                aria_current: if is_active(Route::Inspect {}) { "page" } else { "" },
+                                to: Route::Inspect {},
+                                "Inspect"
+                            }
+                            Link {
+                                id: "verify",
+                                class: if is_active(Route::Verify {}) { "border-indigo-500 text-gray-900 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" } else { "border-transparent text-gray-500 hover:border-gray-300 hover:text-gray-700 inline-flex items-center px-1 pt-1 border-b-2 text-sm font-medium" },
+                                aria_current: if is_active(Route::Verify {}) { "page" } else { "" },
+                                to: Route::Verify {},
+                                "Verify"
+                            }
                         }
                     }
                     div { class: "flex",
@@ -211,6 +239,26 @@ pub(crate) fn Navbar() -> Element {
                         to: Route::Create {},
                         "Create"
                     }
+                    Link {
+                        id: "fund",
+                        class: if is_active(Route::Fund {}) { "bg-indigo-50 border-indigo-500 text-indigo-700 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" } else { "border-transparent text-gray-600 hover:bg-gray-50 hover:border-gray-300 hover:text-gray-800 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" },
+                        aria_current: if is_active(Route::Fund {}) { "page" } else { "" },
+                        onclick: move |_| {
+                            *is_menu_open.write() = false;
+                        },
+                        to: Route::Fund {},
+                        "Fund"
+                    }
+                    Link {
+                        id: "arbitrate",
+                        class: if is_active(Route::Arbitrate {}) { "bg-indigo-50 border-indigo-500 text-indigo-700 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" } else { "border-transparent text-gray-600 hover:bg-gray-50 hover:border-gray-300 hover:text-gray-800 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" },
+                        aria_current: if is_active(Route::Arbitrate {}) { "page" } else { "" },
+                        onclick: move |_| {
+                            *is_menu_open.write() = false;
+                        },
+                        to: Route::Arbitrate {},
+                        "Arbitrate"
+                    }
                     Link {
                         id: "sign",
                         class: if is_active(Route::Sign {}) { "bg-indigo-50 border-indigo-500 text-indigo-700 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" } else { "border-transparent text-gray-600 hover:bg-gray-50 hover:border-gray-300 hover:text-gray-800 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" },
@@ -251,6 +299,26 @@ pub(crate) fn Navbar() -> Element {
                         to: Route::Spend {},
                         "Spend"
                     }
+                    Link {
+                        id: "inspect",
+                        class: if is_active(Route::Inspect {}) { "bg-indigo-50 border-indigo-500 text-indigo-700 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" } else { "border-transparent text-gray-600 hover:bg-gray-50 hover:border-gray-300 hover:text-gray-800 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" },
+                        aria_current: if is_active(Route::Inspect {}) { "page" } else { "" },
+                        onclick: move |_| {
+                            *is_menu_open.write() = false;
+                        },
+                        to: Route::Inspect {},
+                        "Inspect"
+                    }
+                    Link {
+                        id: "verify",
+                        class: if is_active(Route::Verify {}) { "bg-indigo-50 border-indigo-500 text-indigo-700 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" } else { "border-transparent text-gray-600 hover:bg-gray-50 hover:border-gray-300 hover:text-gray-800 block pl-3 pr-4 py-2 border-l-4 text-base font-medium" },
+                        aria_current: if is_active(Route::Verify {}) { "page" } else { "" },
+                        onclick: move |_| {
+                            *is_menu_open.write() = false;
+                        },
+                        to: Route::Verify {},
+                        "Verify"
+                    }
                 }
             }
         }
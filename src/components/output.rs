@@ -75,3 +75,26 @@ pub(crate) fn DerivedAddressOutput(
         }
     }
 }
+
+/// Fee output component.
+///
+/// Shows the virtual size, the fee and the effective fee rate of a transaction.
+#[component]
+pub(crate) fn FeeOutput(mut update_var: Signal<String>, label: String, id: String) -> Element {
+    rsx! {
+        div { class: "sm:col-span-3",
+            dt { class: "block text-sm font-medium text-gray-500", {label} }
+            dd {
+                id: id.as_str(),
+                class: "mt-1 text-sm text-gray-900 bg-gray-50 p-2 rounded-md border border-gray-300 shadow-sm",
+                {
+                    if update_var.read().is_empty() {
+                        "- vB, - sats (- sat/vB)".to_string()
+                    } else {
+                        update_var.read().clone()
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,777 @@
+//! Escrow Parties Components.
+
+use bitcoin::{Address, Amount, Network};
+use dioxus::prelude::*;
+
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::trace;
+
+use nostr::key::PublicKey as NostrPublicKey;
+
+use crate::{
+    error::{Error, ValidationError},
+    scripts::{EscrowContract, Timelock},
+    tx::FeePolicy,
+    util::{
+        days_hours_to_relative_lock_time, parse_internal_key, parse_npub, parse_payout_address,
+        parse_timelock,
+    },
+    validation::{ValidationField, validate_input},
+};
+
+use super::{
+    BitcoinInput, DerivedAddressOutput, NpubInput, NpubInputDerivedAddress, PayoutAddressInput,
+    SatsInput, SecondaryButton, validate_relative_timelock,
+};
+
+/// The input fields of an escrow party.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PartyFields {
+    pub(crate) npub: Signal<String>,
+    pub(crate) npub_error: Signal<Option<String>>,
+    pub(crate) amount: Signal<String>,
+    pub(crate) amount_error: Signal<Option<String>>,
+    /// The fixed fee share in sats of [`FeePolicy::Fixed`].
+    pub(crate) fee_share: Signal<String>,
+    pub(crate) fee_share_error: Signal<Option<String>>,
+    pub(crate) derived_address: Signal<String>,
+    /// The optional payout address or descriptor that replaces the resolution address.
+    pub(crate) payout_address: Signal<String>,
+    pub(crate) payout_address_error: Signal<Option<String>>,
+}
+
+impl PartyFields {
+    /// Creates empty [`PartyFields`] owned by the current component.
+    pub(crate) fn new() -> Self {
+        Self {
+            npub: Signal::new(String::new()),
+            npub_error: Signal::new(None),
+            amount: Signal::new(String::new()),
+            amount_error: Signal::new(None),
+            fee_share: Signal::new(String::new()),
+            fee_share_error: Signal::new(None),
+            derived_address: Signal::new(String::new()),
+            payout_address: Signal::new(String::new()),
+            payout_address_error: Signal::new(None),
+        }
+    }
+}
+
+/// The input fields of the optional refund leaf of an escrow.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct RefundFields {
+    /// The 1-based index of the refund party, empty without a refund leaf.
+    pub(crate) party: Signal<String>,
+    pub(crate) timelock_days: Signal<String>,
+    pub(crate) timelock_days_error: Signal<Option<String>>,
+    pub(crate) timelock_hours: Signal<String>,
+    pub(crate) timelock_hours_error: Signal<Option<String>>,
+    /// Whether the refund pays the pre-agreed split instead of the refund party alone.
+    pub(crate) split: Signal<bool>,
+}
+
+impl RefundFields {
+    /// Whether a refund party is selected.
+    pub(crate) fn is_enabled(&self) -> bool {
+        !self.party.read().is_empty()
+    }
+}
+
+/// Creates the [`RefundFields`] of an escrow without a refund leaf.
+pub(crate) fn use_refund() -> RefundFields {
+    RefundFields {
+        party: use_signal(String::new),
+        timelock_days: use_signal(String::new),
+        timelock_days_error: use_signal(|| None),
+        timelock_hours: use_signal(String::new),
+        timelock_hours_error: use_signal(|| None),
+        split: use_signal(|| false),
+    }
+}
+
+/// Validates the [`RefundFields`], the timelock is only required with a refund party.
+pub(crate) fn validate_refund(mut refund: RefundFields) {
+    let required = refund.is_enabled();
+    refund.timelock_days_error.set(
+        validate_input(
+            &refund.timelock_days.read(),
+            ValidationField::TimelockDays,
+            required,
+        )
+        .err()
+        .map(|e| e.to_string()),
+    );
+    refund.timelock_hours_error.set(
+        validate_input(
+            &refund.timelock_hours.read(),
+            ValidationField::TimelockHours,
+            required,
+        )
+        .err()
+        .map(|e| e.to_string()),
+    );
+    if required && !has_refund_errors(refund) {
+        refund.timelock_days_error.set(validate_relative_timelock(
+            "Duration",
+            &refund.timelock_days.read(),
+            &refund.timelock_hours.read(),
+        ));
+    }
+}
+
+/// Whether any of the [`RefundFields`] has a validation error.
+pub(crate) fn has_refund_errors(refund: RefundFields) -> bool {
+    refund.timelock_days_error.read().is_some() || refund.timelock_hours_error.read().is_some()
+}
+
+/// Adds the refund leaf of validated [`RefundFields`] to an [`EscrowContract`], if a refund party is selected.
+pub(crate) fn parse_refund(
+    contract: EscrowContract,
+    refund: RefundFields,
+) -> Result<EscrowContract, Error> {
+    if !refund.is_enabled() {
+        return Ok(contract);
+    }
+    let index = refund.party.read().parse::<usize>().unwrap();
+    let Some(refund_party) = contract.parties().get(index.wrapping_sub(1)).copied() else {
+        return Err(Error::WrongInputs(format!(
+            "Refund party {index} is not a party of the escrow"
+        )));
+    };
+    // Counted in the unit of a relative dispute timelock, so that `with_refund` can compare them.
+    let time_based = matches!(
+        contract.timelock(),
+        Some(Timelock::Relative(timelock)) if timelock.is_block_time()
+    );
+    let refund_timelock = days_hours_to_relative_lock_time(
+        refund.timelock_days.read().parse::<u32>().unwrap(),
+        refund.timelock_hours.read().parse::<u32>().unwrap(),
+        time_based,
+    )?;
+    #[cfg(debug_assertions)]
+    trace!(%index, %refund_timelock, "refund leaf");
+
+    contract.with_refund(refund_party, refund_timelock)
+}
+
+/// Creates the [`PartyFields`] of a two-party escrow, more parties can be added with [`PartiesInput`].
+pub(crate) fn use_parties() -> Signal<Vec<PartyFields>> {
+    use_signal(|| vec![PartyFields::new(), PartyFields::new()])
+}
+
+/// Creates the [`PartyFields`] of a single arbitrator, more arbitrators can be added with [`ArbitratorsInput`].
+pub(crate) fn use_arbitrators() -> Signal<Vec<PartyFields>> {
+    use_signal(|| vec![PartyFields::new()])
+}
+
+/// Validates the [`PartyFields`] of every party.
+///
+/// Amounts are only validated if `amounts` is set.
+pub(crate) fn validate_parties(parties: &[PartyFields], amounts: bool) {
+    validate_fields(parties, true, amounts);
+}
+
+/// Validates the [`PartyFields`] of every arbitrator.
+///
+/// Arbitrators are optional, empty `npub`s are ignored.
+pub(crate) fn validate_arbitrators(arbitrators: &[PartyFields]) {
+    validate_fields(arbitrators, false, false);
+}
+
+fn validate_fields(fields: &[PartyFields], npub_required: bool, amounts: bool) {
+    for mut party in fields.iter().copied() {
+        party.npub_error.set(
+            validate_input(&party.npub.read(), ValidationField::Npub, npub_required)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        party.payout_address_error.set(
+            validate_input(
+                &party.payout_address.read(),
+                ValidationField::PayoutAddress,
+                false,
+            )
+            .err()
+            .map(|e| e.to_string()),
+        );
+        if amounts {
+            party.amount_error.set(
+                validate_input(&party.amount.read(), ValidationField::Amount, true)
+                    .err()
+                    .map(|e| e.to_string()),
+            );
+        }
+    }
+}
+
+/// Whether any of the [`PartyFields`] has a validation error.
+pub(crate) fn has_parties_errors(parties: &[PartyFields]) -> bool {
+    parties.iter().any(|party| {
+        party.npub_error.read().is_some()
+            || party.amount_error.read().is_some()
+            || party.fee_share_error.read().is_some()
+            || party.payout_address_error.read().is_some()
+    })
+}
+
+/// Whether any arbitrator `npub` is filled.
+pub(crate) fn has_arbitrators(arbitrators: &[PartyFields]) -> bool {
+    arbitrators
+        .iter()
+        .any(|arbitrator| !arbitrator.npub.read().is_empty())
+}
+
+/// Parses the filled `npub`s of the [`PartyFields`], skipping the empty ones.
+///
+/// The [`PartyFields`] must have been validated with [`validate_parties`] or [`validate_arbitrators`].
+pub(crate) fn parse_parties(parties: &[PartyFields]) -> Vec<NostrPublicKey> {
+    parties
+        .iter()
+        .filter(|party| !party.npub.read().is_empty())
+        .map(|party| parse_npub(&party.npub.read()).unwrap())
+        .collect()
+}
+
+/// Adds the optional payout addresses of the validated [`PartyFields`] to the `contract`,
+/// see [`EscrowContract::with_payout_addresses`].
+///
+/// # Errors
+///
+/// Errors if a payout address is not valid for `network`.
+pub(crate) fn parse_payout_addresses(
+    contract: EscrowContract,
+    parties: &[PartyFields],
+    network: Network,
+) -> Result<EscrowContract, Error> {
+    let payout_addresses = parties
+        .iter()
+        .filter(|party| !party.npub.read().is_empty())
+        .map(|party| {
+            let payout_address = party.payout_address.read();
+            if payout_address.trim().is_empty() {
+                Ok(None)
+            } else {
+                parse_payout_address(&payout_address, network).map(Some)
+            }
+        })
+        .collect::<Result<Vec<Option<Address>>, Error>>()?;
+    #[cfg(debug_assertions)]
+    trace!(?payout_addresses, "payout addresses");
+
+    contract.with_payout_addresses(payout_addresses)
+}
+
+/// Validates an optional threshold of `parties` parties.
+pub(crate) fn validate_threshold(threshold: &str, parties: usize) -> Option<String> {
+    match validate_input(threshold, ValidationField::Threshold, false) {
+        Err(e) => Some(e.to_string()),
+        Ok(()) if parse_threshold(threshold, parties) > parties => {
+            Some(ValidationError::InvalidThreshold.to_string())
+        }
+        Ok(()) => None,
+    }
+}
+
+/// Validates the fee shares of the [`PartyFields`], only required by the fixed `fee_policy`.
+pub(crate) fn validate_fee_shares(fee_policy: &str, parties: &[PartyFields]) {
+    let required = fee_policy == "Fixed";
+    for mut party in parties.iter().copied() {
+        party.fee_share_error.set(if required {
+            validate_input(&party.fee_share.read(), ValidationField::Sats, true)
+                .err()
+                .map(|e| e.to_string())
+        } else {
+            None
+        });
+    }
+}
+
+/// Parses a validated fee policy of [`FeePolicyInput`] into a [`FeePolicy`].
+///
+/// The fee payer is the 1-based index of a party,
+/// and the fixed fee shares are the validated fee shares of the [`PartyFields`].
+pub(crate) fn parse_fee_policy(
+    fee_policy: &str,
+    parties: &[PartyFields],
+) -> Result<FeePolicy, Error> {
+    match fee_policy {
+        "" | "Equal" => Ok(FeePolicy::Equal),
+        "Proportional" => Ok(FeePolicy::Proportional),
+        "Fixed" => Ok(FeePolicy::Fixed(
+            parties
+                .iter()
+                .map(|party| Amount::from_sat(party.fee_share.read().trim().parse().unwrap()))
+                .collect(),
+        )),
+        payer => match payer.parse::<usize>() {
+            Ok(index) if (1..=parties.len()).contains(&index) => Ok(FeePolicy::Payer(index - 1)),
+            _ => Err(Error::FeeSplit(format!("Invalid fee payer: {payer}"))),
+        },
+    }
+}
+
+/// Parses a validated threshold, falling back to `default` if it is empty.
+pub(crate) fn parse_threshold(threshold: &str, default: usize) -> usize {
+    if threshold.trim().is_empty() {
+        default
+    } else {
+        threshold.trim().parse().unwrap()
+    }
+}
+
+/// Parses a validated escrow form into an [`EscrowContract`].
+///
+/// The cooperative and arbitrator thresholds default to all the parties and arbitrators,
+/// and the arbitrated threshold to one party.
+/// The arbitrators and the timelock are only used if any arbitrator is filled.
+#[expect(clippy::too_many_arguments)]
+pub(crate) fn parse_escrow_contract(
+    parties: &[PartyFields],
+    cooperative_threshold: &str,
+    arbitrators: &[PartyFields],
+    arbitrator_threshold: &str,
+    arbitrated_threshold: &str,
+    timelock_mode: &str,
+    timelock_days: &str,
+    timelock_hours: &str,
+    timelock_absolute: &str,
+    internal_key: &str,
+) -> Result<EscrowContract, Error> {
+    let parties = parse_parties(parties);
+    let cooperative_threshold = parse_threshold(cooperative_threshold, parties.len());
+    let arbitrators = parse_parties(arbitrators);
+    let internal_key = parse_internal_key(internal_key)?;
+    if arbitrators.is_empty() {
+        #[cfg(debug_assertions)]
+        trace!("collaborative escrow contract");
+        return EscrowContract::new(
+            parties,
+            cooperative_threshold,
+            arbitrators,
+            1,
+            1,
+            None,
+            internal_key,
+        );
+    }
+
+    #[cfg(debug_assertions)]
+    trace!(arbitrators = %arbitrators.len(), "dispute escrow contract");
+    let arbitrator_threshold = parse_threshold(arbitrator_threshold, arbitrators.len());
+    let arbitrated_threshold = parse_threshold(arbitrated_threshold, 1);
+    let timelock = parse_timelock(
+        timelock_mode,
+        timelock_days,
+        timelock_hours,
+        timelock_absolute,
+    )?;
+    EscrowContract::new(
+        parties,
+        cooperative_threshold,
+        arbitrators,
+        arbitrator_threshold,
+        arbitrated_threshold,
+        Some(timelock),
+        internal_key,
+    )
+}
+
+/// Escrow parties input component.
+///
+/// Renders the `npub` of every party, and optionally their escrow amounts, fixed fee shares
+/// and payout addresses.
+#[component]
+pub(crate) fn PartiesInput(
+    mut parties: Signal<Vec<PartyFields>>,
+    amounts: bool,
+    fee_shares: bool,
+    payout_addresses: bool,
+) -> Element {
+    let party_count = parties.read().len();
+
+    rsx! {
+        div { class: "sm:col-span-6 space-y-6",
+            for (i , party) in parties.read().iter().copied().enumerate() {
+                div {
+                    key: "{i}",
+                    class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                    if amounts {
+                        NpubInputDerivedAddress {
+                            id: format!("npub_{i}"),
+                            label: format!("Party {} Nostr Public Key (npub)", i + 1),
+                            update_var: party.npub,
+                            update_address: party.derived_address,
+                            col_span: 3,
+                            error: party.npub_error,
+                        }
+
+                        BitcoinInput {
+                            id: format!("amount_{i}"),
+                            label: format!("Party {} Escrow Amount (BTC)", i + 1),
+                            update_var: party.amount,
+                            error: party.amount_error,
+                        }
+
+                        DerivedAddressOutput {
+                            update_var: party.derived_address,
+                            label: format!("Party {} Resolution Address", i + 1),
+                            id: format!("address_{i}"),
+                            col_span: 6,
+                        }
+                    } else {
+                        NpubInput {
+                            id: format!("npub_{i}"),
+                            label: format!("Party {} Nostr Public Key (npub)", i + 1),
+                            update_var: party.npub,
+                            error: party.npub_error,
+                            required: true,
+                        }
+                    }
+
+                    if fee_shares {
+                        SatsInput {
+                            id: format!("fee_share_{i}"),
+                            label: format!("Party {} Fee Share (sats)", i + 1),
+                            update_var: party.fee_share,
+                            error: party.fee_share_error,
+                        }
+                    }
+
+                    if payout_addresses {
+                        PayoutAddressInput {
+                            update_var: party.payout_address,
+                            label: format!("Party {} Payout Address or Descriptor (optional)", i + 1),
+                            id: format!("payout_address_{i}"),
+                            error: party.payout_address_error,
+                        }
+                    }
+                }
+            }
+
+            div { class: "flex justify-end",
+                if party_count > 2 {
+                    SecondaryButton {
+                        onclick: move |_| {
+                            #[cfg(debug_assertions)]
+                            trace!(parties = % party_count, "Removed party");
+                            parties.write().pop();
+                        },
+                        text: "Remove Party",
+                    }
+                }
+                SecondaryButton {
+                    onclick: move |_| {
+                        #[cfg(debug_assertions)]
+                        trace!(parties = % party_count, "Added party");
+                        parties.write().push(PartyFields::new());
+                    },
+                    text: "Add Party",
+                }
+            }
+        }
+    }
+}
+
+/// Escrow arbitrators input component.
+///
+/// Renders the optional `npub` of every arbitrator.
+#[component]
+pub(crate) fn ArbitratorsInput(mut arbitrators: Signal<Vec<PartyFields>>) -> Element {
+    let arbitrator_count = arbitrators.read().len();
+
+    rsx! {
+        div { class: "sm:col-span-6 space-y-6",
+            for (i , arbitrator) in arbitrators.read().iter().copied().enumerate() {
+                div {
+                    key: "{i}",
+                    class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                    NpubInput {
+                        id: format!("npub_arbitrator_{i}"),
+                        label: format!("Arbitrator {} Nostr Public Key (npub)", i + 1),
+                        update_var: arbitrator.npub,
+                        error: arbitrator.npub_error,
+                    }
+                }
+            }
+
+            div { class: "flex justify-end",
+                if arbitrator_count > 1 {
+                    SecondaryButton {
+                        onclick: move |_| {
+                            #[cfg(debug_assertions)]
+                            trace!(arbitrators = % arbitrator_count, "Removed arbitrator");
+                            arbitrators.write().pop();
+                        },
+                        text: "Remove Arbitrator",
+                    }
+                }
+                SecondaryButton {
+                    onclick: move |_| {
+                        #[cfg(debug_assertions)]
+                        trace!(arbitrators = % arbitrator_count, "Added arbitrator");
+                        arbitrators.write().push(PartyFields::new());
+                    },
+                    text: "Add Arbitrator",
+                }
+            }
+        }
+    }
+}
+
+/// Escrow threshold input validation component.
+#[component]
+pub(crate) fn ThresholdInput(
+    mut update_var: Signal<String>,
+    label: String,
+    id: String,
+    parties: usize,
+    placeholder: String,
+    error: Signal<Option<String>>,
+) -> Element {
+    let mut on_validate_threshold = move |input: &str| {
+        update_var.set(input.to_string());
+        error.set(validate_threshold(input, parties));
+    };
+
+    let input_class = if error.read().is_some() {
+        "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+    } else {
+        "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+    };
+
+    rsx! {
+        div { class: "sm:col-span-3",
+            label {
+                r#for: id.as_str(),
+                class: "block text-sm font-medium text-gray-700",
+                {label}
+            }
+            div { class: "mt-1",
+                input {
+                    r#type: "number",
+                    min: "1",
+                    max: "{parties}",
+                    step: "1",
+                    name: id.as_str(),
+                    id: id.as_str(),
+                    class: input_class,
+                    placeholder: placeholder.as_str(),
+                    value: update_var,
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set threshold");
+                        on_validate_threshold(&event.value());
+                    },
+                }
+            }
+            p { class: "mt-2 text-xs text-gray-500", "Out of {parties}." }
+            if let Some(error_msg) = error.read().as_ref() {
+                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+            }
+        }
+    }
+}
+
+/// Escrow fee policy input component.
+///
+/// Renders how the fee is split between `parties` parties, see [`parse_fee_policy`],
+/// and if `arbitrators` is set, the option that the arbitrators pay it.
+#[component]
+pub(crate) fn FeePolicyInput(
+    mut update_var: Signal<String>,
+    parties: usize,
+    arbitrators: bool,
+) -> Element {
+    rsx! {
+        div { class: "sm:col-span-3",
+            label {
+                r#for: "fee-policy",
+                class: "block text-sm font-medium text-gray-700",
+                "Fee Split"
+            }
+            div { class: "mt-1",
+                select {
+                    id: "fee-policy",
+                    name: "fee-policy",
+                    class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                    value: update_var,
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% update_var, event_value =% event.value(), "Set fee policy");
+                        update_var.set(event.value());
+                    },
+                    option { value: "Equal", "Equal shares" }
+                    option { value: "Proportional", "Proportional to the escrow amounts" }
+                    for i in 1..=parties {
+                        option { key: "{i}", value: "{i}", "Party {i} pays" }
+                    }
+                    if arbitrators {
+                        option { value: "Arbitrators", "The arbitrators pay" }
+                    }
+                    option { value: "Fixed", "Fixed shares" }
+                }
+            }
+            p { class: "mt-2 text-xs text-gray-500",
+                "The shares always add up to the exact fee. With fixed shares, the fee is their sum."
+            }
+        }
+    }
+}
+
+/// Escrow refund leaf input component.
+///
+/// Renders the refund party of `parties` parties and the refund timelock,
+/// and the refund payout if `payout` is set.
+#[component]
+pub(crate) fn RefundInput(refund: RefundFields, parties: usize, payout: bool) -> Element {
+    let RefundFields {
+        mut party,
+        mut timelock_days,
+        mut timelock_days_error,
+        mut timelock_hours,
+        mut timelock_hours_error,
+        mut split,
+    } = refund;
+
+    let input_class = |error: Signal<Option<String>>| {
+        if error.read().is_some() {
+            "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+        } else {
+            "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+        }
+    };
+    let days_input_class = input_class(timelock_days_error);
+    let hours_input_class = input_class(timelock_hours_error);
+
+    rsx! {
+        div { class: "sm:col-span-3",
+            label {
+                r#for: "refund-party",
+                class: "block text-sm font-medium text-gray-700",
+                "Refund Party"
+            }
+            div { class: "mt-1",
+                select {
+                    id: "refund-party",
+                    name: "refund-party",
+                    class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                    value: party,
+                    oninput: move |event| {
+                        #[cfg(debug_assertions)]
+                        trace!(% party, event_value =% event.value(), "Set refund party");
+                        party.set(event.value());
+                    },
+                    option { value: "", "None" }
+                    for i in 1..=parties {
+                        option { key: "{i}", value: "{i}", "Party {i}" }
+                    }
+                }
+            }
+            p { class: "mt-2 text-xs text-gray-500",
+                "Can spend alone after the refund timelock, if the other parties and the arbitrators vanish."
+            }
+        }
+
+        if refund.is_enabled() {
+            div { class: "sm:col-span-3",
+                div { class: "grid grid-cols-2 gap-4",
+                    div {
+                        label {
+                            r#for: "refund-timelock-days",
+                            class: "block text-sm font-medium text-gray-700",
+                            "Refund Timelock (Days)"
+                        }
+                        div { class: "mt-1",
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "1",
+                                name: "refund-timelock-days",
+                                id: "refund-timelock-days",
+                                class: days_input_class,
+                                placeholder: "0",
+                                value: timelock_days,
+                                oninput: move |event| {
+                                    #[cfg(debug_assertions)]
+                                    trace!(% timelock_days, event_value =% event.value(), "Set refund timelock days");
+                                    timelock_days.set(event.value());
+                                    timelock_days_error
+                                        .set(
+                                            validate_input(&event.value(), ValidationField::TimelockDays, true)
+                                                .err()
+                                                .map(|e| e.to_string()),
+                                        );
+                                },
+                            }
+                        }
+                        if let Some(error_msg) = timelock_days_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                        }
+                    }
+                    div {
+                        label {
+                            r#for: "refund-timelock-hours",
+                            class: "block text-sm font-medium text-gray-700",
+                            "Refund Timelock (Hours)"
+                        }
+                        div { class: "mt-1",
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "1",
+                                max: "23",
+                                name: "refund-timelock-hours",
+                                id: "refund-timelock-hours",
+                                class: hours_input_class,
+                                placeholder: "0",
+                                value: timelock_hours,
+                                oninput: move |event| {
+                                    #[cfg(debug_assertions)]
+                                    trace!(% timelock_hours, event_value =% event.value(), "Set refund timelock hours");
+                                    timelock_hours.set(event.value());
+                                    timelock_hours_error
+                                        .set(
+                                            validate_input(&event.value(), ValidationField::TimelockHours, true)
+                                                .err()
+                                                .map(|e| e.to_string()),
+                                        );
+                                },
+                            }
+                        }
+                        if let Some(error_msg) = timelock_hours_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                        }
+                    }
+                }
+                p { class: "mt-2 text-xs text-gray-500",
+                    "Should be much longer than the dispute timelock, which must then be a duration and not a height or date."
+                }
+            }
+
+            if payout {
+                div { class: "sm:col-span-3",
+                    label {
+                        r#for: "refund-payout",
+                        class: "block text-sm font-medium text-gray-700",
+                        "Refund Payout"
+                    }
+                    div { class: "mt-1",
+                        select {
+                            id: "refund-payout",
+                            name: "refund-payout",
+                            class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border",
+                            value: if split() { "split" } else { "party" },
+                            oninput: move |event| {
+                                #[cfg(debug_assertions)]
+                                trace!(% split, event_value =% event.value(), "Set refund payout");
+                                split.set(event.value() == "split");
+                            },
+                            option { value: "party", "Refund Party" }
+                            option { value: "split", "Pre-agreed Split" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
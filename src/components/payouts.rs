@@ -0,0 +1,293 @@
+//! Escrow Payouts Components.
+
+use bitcoin::{Address, Amount, Network};
+use dioxus::prelude::*;
+
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::trace;
+
+use crate::{
+    error::Error,
+    scripts::EscrowContract,
+    tx::{Payout, PayoutAmount},
+    util::parse_percent,
+    validation::{ValidationField, validate_input},
+};
+
+use super::SecondaryButton;
+
+/// The input fields of a payout of a resolution transaction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PayoutFields {
+    /// The 1-based index of the party to pay, or `Address` for any other address.
+    pub(crate) destination: Signal<String>,
+    pub(crate) address: Signal<String>,
+    pub(crate) address_error: Signal<Option<String>>,
+    pub(crate) amount: Signal<String>,
+    pub(crate) amount_error: Signal<Option<String>>,
+    /// Whether the amount is a `%` of the escrow or `BTC`.
+    pub(crate) unit: Signal<String>,
+}
+
+impl PayoutFields {
+    /// Creates [`PayoutFields`] of a percentage for the first party, owned by the current component.
+    pub(crate) fn new() -> Self {
+        Self {
+            destination: Signal::new("1".to_string()),
+            address: Signal::new(String::new()),
+            address_error: Signal::new(None),
+            amount: Signal::new(String::new()),
+            amount_error: Signal::new(None),
+            unit: Signal::new("%".to_string()),
+        }
+    }
+}
+
+/// Creates no [`PayoutFields`], i.e. every party gets its own escrow amount back.
+///
+/// Payouts can be added with [`PayoutsInput`].
+pub(crate) fn use_payouts() -> Signal<Vec<PayoutFields>> {
+    use_signal(Vec::new)
+}
+
+/// Validates the [`PayoutFields`] of every payout.
+pub(crate) fn validate_payouts(payouts: &[PayoutFields]) {
+    for mut payout in payouts.iter().copied() {
+        payout
+            .address_error
+            .set(if *payout.destination.read() == "Address" {
+                validate_input(&payout.address.read(), ValidationField::Address, true)
+                    .err()
+                    .map(|e| e.to_string())
+            } else {
+                None
+            });
+        payout.amount_error.set(validate_payout_amount(
+            &payout.amount.read(),
+            &payout.unit.read(),
+        ));
+    }
+}
+
+/// Validates a payout amount in `unit`.
+fn validate_payout_amount(amount: &str, unit: &str) -> Option<String> {
+    let field = if unit == "%" {
+        ValidationField::Percent
+    } else {
+        ValidationField::Amount
+    };
+    validate_input(amount, field, true)
+        .err()
+        .map(|e| e.to_string())
+}
+
+/// Whether any of the [`PayoutFields`] has a validation error.
+pub(crate) fn has_payouts_errors(payouts: &[PayoutFields]) -> bool {
+    payouts
+        .iter()
+        .any(|payout| payout.address_error.read().is_some() || payout.amount_error.read().is_some())
+}
+
+/// Parses validated [`PayoutFields`] into [`Payout`]s to any address
+/// or to the [`EscrowContract::resolution_address`] of a party of the `contract`.
+///
+/// # Errors
+///
+/// Errors if a payout is to a party that is not one of the parties,
+/// or if the payout address of the party is not valid for `network`.
+pub(crate) fn parse_payouts(
+    payouts: &[PayoutFields],
+    contract: &EscrowContract,
+    network: Network,
+) -> Result<Vec<Payout>, Error> {
+    payouts
+        .iter()
+        .map(|payout| {
+            let destination = match payout.destination.read().as_str() {
+                "Address" => payout
+                    .address
+                    .read()
+                    .parse::<Address<_>>()
+                    .unwrap()
+                    .require_network(network)
+                    .unwrap(),
+                party => {
+                    let index = party.parse::<usize>().unwrap();
+                    if !(1..=contract.parties().len()).contains(&index) {
+                        return Err(Error::Payout(format!(
+                            "Party {index} is not a party of the escrow"
+                        )));
+                    }
+                    contract.resolution_address(index - 1, network)?
+                }
+            };
+            let amount = if *payout.unit.read() == "%" {
+                PayoutAmount::Percent(parse_percent(&payout.amount.read())?)
+            } else {
+                PayoutAmount::Fixed(
+                    Amount::from_btc(payout.amount.read().parse::<f64>().unwrap()).unwrap(),
+                )
+            };
+
+            Ok(Payout {
+                destination,
+                amount,
+            })
+        })
+        .collect()
+}
+
+/// Escrow payouts input component.
+///
+/// Renders the destination, either one of `parties` parties or any address,
+/// and the amount of every payout.
+#[component]
+pub(crate) fn PayoutsInput(mut payouts: Signal<Vec<PayoutFields>>, parties: usize) -> Element {
+    let payout_count = payouts.read().len();
+
+    let input_class = |error: Signal<Option<String>>| {
+        if error.read().is_some() {
+            "shadow-sm focus:ring-red-500 focus:border-red-500 block w-full sm:text-sm border-red-300 rounded-md p-2 border bg-red-50"
+        } else {
+            "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border"
+        }
+    };
+    let select_class = "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block w-full sm:text-sm border-gray-300 rounded-md p-2 border";
+
+    rsx! {
+        div { class: "sm:col-span-6 space-y-6",
+            p { class: "text-xs text-gray-500",
+                "Without payouts every party gets its own escrow amount back. With payouts, fixed amounts are paid as is,
+                and the percentages, which must add up to 100%, split what is left after the fee.
+                The payouts plus the fee must add up to the escrow exactly."
+            }
+
+            for (i , payout) in payouts.read().iter().copied().enumerate() {
+                div {
+                    key: "{i}",
+                    class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                    div { class: "sm:col-span-2",
+                        label {
+                            r#for: "payout_destination_{i}",
+                            class: "block text-sm font-medium text-gray-700",
+                            "Payout {i + 1} Destination"
+                        }
+                        div { class: "mt-1",
+                            select {
+                                id: "payout_destination_{i}",
+                                class: select_class,
+                                value: payout.destination,
+                                oninput: move |event| {
+                                    let mut destination = payout.destination;
+                                    #[cfg(debug_assertions)]
+                                    trace!(% destination, event_value =% event.value(), "Set payout destination");
+                                    destination.set(event.value());
+                                },
+                                for party in 1..=parties {
+                                    option { key: "{party}", value: "{party}", "Party {party}" }
+                                }
+                                option { value: "Address", "Other address" }
+                            }
+                        }
+                    }
+
+                    div { class: "sm:col-span-2",
+                        label {
+                            r#for: "payout_amount_{i}",
+                            class: "block text-sm font-medium text-gray-700",
+                            "Payout {i + 1} Amount"
+                        }
+                        div { class: "mt-1 flex gap-2",
+                            input {
+                                r#type: "number",
+                                min: "0",
+                                step: "any",
+                                id: "payout_amount_{i}",
+                                class: input_class(payout.amount_error),
+                                placeholder: "0",
+                                value: payout.amount,
+                                oninput: move |event| {
+                                    let (mut amount, mut amount_error) = (payout.amount, payout.amount_error);
+                                    #[cfg(debug_assertions)]
+                                    trace!(% amount, event_value =% event.value(), "Set payout amount");
+                                    amount.set(event.value());
+                                    amount_error.set(validate_payout_amount(&event.value(), &payout.unit.read()));
+                                },
+                            }
+                            select {
+                                id: "payout_unit_{i}",
+                                class: "shadow-sm focus:ring-indigo-500 focus:border-indigo-500 block sm:text-sm border-gray-300 rounded-md p-2 border",
+                                value: payout.unit,
+                                oninput: move |event| {
+                                    let (mut unit, mut amount_error) = (payout.unit, payout.amount_error);
+                                    unit.set(event.value());
+                                    amount_error.set(validate_payout_amount(&payout.amount.read(), &event.value()));
+                                },
+                                option { value: "%", "%" }
+                                option { value: "BTC", "BTC" }
+                            }
+                        }
+                        if let Some(error_msg) = payout.amount_error.read().as_ref() {
+                            p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                        }
+                    }
+
+                    if *payout.destination.read() == "Address" {
+                        div { class: "sm:col-span-2",
+                            label {
+                                r#for: "payout_address_{i}",
+                                class: "block text-sm font-medium text-gray-700",
+                                "Payout {i + 1} Address"
+                            }
+                            div { class: "mt-1",
+                                input {
+                                    r#type: "text",
+                                    id: "payout_address_{i}",
+                                    class: input_class(payout.address_error),
+                                    placeholder: "bc1...",
+                                    value: payout.address,
+                                    oninput: move |event| {
+                                        let (mut address, mut address_error) = (payout.address, payout.address_error);
+                                        #[cfg(debug_assertions)]
+                                        trace!(% address, event_value =% event.value(), "Set payout address");
+                                        address.set(event.value());
+                                        address_error
+                                            .set(
+                                                validate_input(&event.value(), ValidationField::Address, true)
+                                                    .err()
+                                                    .map(|e| e.to_string()),
+                                            );
+                                    },
+                                }
+                            }
+                            if let Some(error_msg) = payout.address_error.read().as_ref() {
+                                p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                            }
+                        }
+                    }
+                }
+            }
+
+            div { class: "flex justify-end",
+                if payout_count > 0 {
+                    SecondaryButton {
+                        onclick: move |_| {
+                            #[cfg(debug_assertions)]
+                            trace!(payouts = % payout_count, "Removed payout");
+                            payouts.write().pop();
+                        },
+                        text: "Remove Payout",
+                    }
+                }
+                SecondaryButton {
+                    onclick: move |_| {
+                        #[cfg(debug_assertions)]
+                        trace!(payouts = % payout_count, "Added payout");
+                        payouts.write().push(PayoutFields::new());
+                    },
+                    text: "Add Payout",
+                }
+            }
+        }
+    }
+}
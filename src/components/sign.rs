@@ -1,116 +1,357 @@
 //! Sign escrow transaction component.
 
-use bitcoin::{Amount, Transaction, TxOut, consensus};
+use bitcoin::{Amount, Psbt, Transaction, consensus, hex::DisplayHex};
 use dioxus::prelude::*;
+use musig2::SecNonce;
+use nostr::key::PublicKey as NostrPublicKey;
 
 #[cfg(debug_assertions)]
 use dioxus::logger::tracing::{info, trace};
 
 use crate::{
     NETWORK, Route,
-    scripts::escrow_address,
-    sign::sign_escrow_tx,
+    error::Error,
+    scripts::{EscrowScript, contract_address},
+    sign::{
+        check_psbt_prevouts, escrow_prevouts, escrow_psbt_prevouts, musig2_psbt_aggregate,
+        musig2_psbt_nonces, musig2_psbt_partial_sign, sign_contract_tx, sign_escrow_psbt,
+        signable_leaves,
+    },
+    tx::{AgreedPayouts, FeePolicy, OutputLabel, SigningPolicy, signing_policy},
     util::{
-        days_to_blocks, hours_to_blocks, parse_escrow_type, parse_network, parse_npub, parse_nsec,
+        format_partial_signatures, format_pub_nonces, format_signatures, npub_to_x_only_public_key,
+        nsec_to_x_only_public_key, parse_escrow_type, parse_fee_rate, parse_network, parse_npub,
+        parse_nsec, parse_partial_signatures, parse_pub_nonces, parse_spent_outputs,
     },
     validation::{ValidationField, validate_input},
 };
 
 use super::{
-    BitcoinInput, ContinueButton, CopyButton, EscrowTypeInput, Footer, NetworkInput, NpubInput,
-    NsecInput, PrimaryButton, SignatureOutput, TimelockInput, TransactionInput, TxidInput,
+    ArbitratorsInput, ContinueButton, CopyButton, EscrowTypeInput, FeePolicyInput, FeeRateInput,
+    Footer, InternalKeyInput, NetworkInput, NsecInput, PartialSignatureInput, PartiesInput,
+    PartyFields, PrimaryButton, PsbtInput, PubNonceInput, RefundInput, SatsInput, SignatureOutput,
+    SpentOutputsInput, ThresholdInput, TimelockInput, TransactionInput, TransactionOutput,
+    has_arbitrators, has_parties_errors, has_refund_errors, parse_escrow_contract,
+    parse_fee_policy, parse_payout_addresses, parse_refund, use_arbitrators, use_parties,
+    use_refund, validate_arbitrators, validate_fee_shares, validate_parties, validate_refund,
+    validate_threshold, validate_timelock,
 };
 
 /// Sign escrow transaction component.
 #[component]
 pub(crate) fn Sign() -> Element {
-    let unsigned_tx = use_signal(String::new);
-    let mut signature = use_signal(String::new);
-    let escrow_type = use_signal(String::new);
-    let npub_buyer = use_signal(String::new);
-    let npub_seller = use_signal(String::new);
+    let parties = use_parties();
+    let cooperative_threshold = use_signal(String::new);
+    let arbitrated_threshold = use_signal(String::new);
     let nsec = use_signal(String::new);
-    let npub_arbitrator = use_signal(String::new);
-    let amount_total = use_signal(String::new);
+    let arbitrators = use_arbitrators();
+    let arbitrator_threshold = use_signal(String::new);
+    let refund = use_refund();
     let timelock_days = use_signal(String::new);
     let timelock_hours = use_signal(String::new);
-    let funding_txid = use_signal(String::new);
-
-    let mut npub_buyer_error = use_signal(|| None);
-    let mut npub_seller_error = use_signal(|| None);
-    let mut npub_arbitrator_error = use_signal(|| None);
-    let mut amount_total_error = use_signal(|| None);
-    let mut timelock_days_error = use_signal(|| None);
-    let mut timelock_hours_error = use_signal(|| None);
-    let mut funding_txid_error = use_signal(|| None);
-    let mut unsigned_tx_error = use_signal(|| None);
+    let timelock_mode = use_signal(String::new);
+    let timelock_absolute = use_signal(String::new);
+    let internal_key = use_signal(String::new);
+    // What the arbitrators were agreed to get, the parties' are their `amount` in sats.
+    let agreed_arbitrator_fee = use_signal(String::new);
+    let fee_policy = use_signal(|| "Equal".to_string());
+    let max_fee_rate = use_signal(String::new);
+
+    let mut cooperative_threshold_error = use_signal(|| None);
+    let mut arbitrated_threshold_error = use_signal(|| None);
+    let mut arbitrator_threshold_error = use_signal(|| None);
+    let timelock_days_error = use_signal(|| None);
+    let timelock_hours_error = use_signal(|| None);
+    let timelock_absolute_error = use_signal(|| Option::<String>::None);
     let mut nsec_error = use_signal(|| None);
+    let mut agreed_arbitrator_fee_error = use_signal(|| None);
+    let mut max_fee_rate_error = use_signal(|| None);
 
-    let has_sign_form_errors = move || {
-        npub_buyer_error.read().is_some()
-            || npub_seller_error.read().is_some()
-            || npub_arbitrator_error.read().is_some()
-            || amount_total_error.read().is_some()
+    // The role of the nsec in the escrow, matched against the npubs of the parties and arbitrators.
+    let signer_role = move || {
+        let x_only_pk = nsec_to_x_only_public_key(&parse_nsec(&nsec.read()).ok()?);
+        let is_signer = |party: &PartyFields| {
+            parse_npub(&party.npub.read())
+                .and_then(|npub| npub_to_x_only_public_key(&npub))
+                .is_ok_and(|party_pk| party_pk == x_only_pk)
+        };
+        if let Some(i) = parties.read().iter().position(is_signer) {
+            return Some(format!("Your key is Party {} of the escrow.", i + 1));
+        }
+        if let Some(i) = arbitrators.read().iter().position(is_signer) {
+            return Some(format!("Your key is Arbitrator {} of the escrow.", i + 1));
+        }
+        Some("Your key is none of the parties or arbitrators of the escrow.".to_string())
+    };
+
+    // The escrow and the nsec are shared by the PSBT and the raw transaction.
+    let has_escrow_form_errors = move || {
+        has_parties_errors(&parties.read())
+            || cooperative_threshold_error.read().is_some()
+            || arbitrated_threshold_error.read().is_some()
+            || has_parties_errors(&arbitrators.read())
+            || arbitrator_threshold_error.read().is_some()
             || timelock_days_error.read().is_some()
             || timelock_hours_error.read().is_some()
-            || funding_txid_error.read().is_some()
-            || unsigned_tx_error.read().is_some()
+            || timelock_absolute_error.read().is_some()
             || nsec_error.read().is_some()
+            || agreed_arbitrator_fee_error.read().is_some()
+            || max_fee_rate_error.read().is_some()
+            || has_refund_errors(refund)
     };
 
-    let mut validate_sign_form = move || {
-        npub_buyer_error.set(
-            validate_input(&npub_buyer.read(), ValidationField::Npub, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-        npub_seller_error.set(
-            validate_input(&npub_seller.read(), ValidationField::Npub, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
-        amount_total_error.set(
-            validate_input(&amount_total.read(), ValidationField::Amount, true)
+    let mut validate_escrow_form = move || {
+        validate_parties(&parties.read(), false);
+        for mut party in parties.read().iter().copied() {
+            party.amount_error.set(
+                validate_input(&party.amount.read(), ValidationField::Sats, true)
+                    .err()
+                    .map(|e| e.to_string()),
+            );
+        }
+        agreed_arbitrator_fee_error.set(
+            validate_input(&agreed_arbitrator_fee.read(), ValidationField::Sats, false)
                 .err()
                 .map(|e| e.to_string()),
         );
-        funding_txid_error.set(
-            validate_input(&funding_txid.read(), ValidationField::Txid, true)
+        validate_fee_shares(&fee_policy.read(), &parties.read());
+        max_fee_rate_error.set(
+            validate_input(&max_fee_rate.read(), ValidationField::FeeRate, true)
                 .err()
                 .map(|e| e.to_string()),
         );
+        let party_count = parties.read().len();
+        cooperative_threshold_error.set(validate_threshold(
+            &cooperative_threshold.read(),
+            party_count,
+        ));
         nsec_error.set(
             validate_input(&nsec.read(), ValidationField::Nsec, true)
                 .err()
                 .map(|e| e.to_string()),
         );
-        unsigned_tx_error.set(
-            validate_input(&unsigned_tx.read(), ValidationField::Transaction, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
 
-        let arbitrator_filled = !npub_arbitrator.read().is_empty();
-        npub_arbitrator_error.set(
-            validate_input(&npub_arbitrator.read(), ValidationField::Npub, false)
+        validate_arbitrators(&arbitrators.read());
+        let arbitrator_filled = has_arbitrators(&arbitrators.read());
+
+        if arbitrator_filled {
+            arbitrator_threshold_error.set(validate_threshold(
+                &arbitrator_threshold.read(),
+                arbitrators.read().len(),
+            ));
+            arbitrated_threshold_error.set(validate_threshold(
+                &arbitrated_threshold.read(),
+                party_count,
+            ));
+            validate_timelock(
+                &timelock_mode.read(),
+                &timelock_days.read(),
+                &timelock_hours.read(),
+                &timelock_absolute.read(),
+                timelock_days_error,
+                timelock_hours_error,
+                timelock_absolute_error,
+            );
+        }
+        validate_refund(refund);
+    };
+
+    let parse_contract = move || {
+        parse_escrow_contract(
+            &parties.read(),
+            &cooperative_threshold.read(),
+            &arbitrators.read(),
+            &arbitrator_threshold.read(),
+            &arbitrated_threshold.read(),
+            &timelock_mode.read(),
+            &timelock_days.read(),
+            &timelock_hours.read(),
+            &timelock_absolute.read(),
+            &internal_key.read(),
+        )
+        .and_then(|contract| parse_refund(contract, refund))
+        .and_then(|contract| {
+            parse_payout_addresses(
+                contract,
+                &parties.read(),
+                parse_network(&NETWORK.read()).unwrap(),
+            )
+        })
+    };
+
+    let agreed_payouts = move || {
+        parse_agreed_payouts(
+            &parties.read(),
+            &agreed_arbitrator_fee.read(),
+            &fee_policy.read(),
+            &max_fee_rate.read(),
+        )
+    };
+
+    let unsigned_psbt = use_signal(String::new);
+    // The funding transactions to check the amounts of the PSBT against, optional.
+    let psbt_spent_outputs = use_signal(String::new);
+    let mut signed_psbt = use_signal(String::new);
+    let mut psbt_policy_report = use_signal(|| Option::<(SigningPolicy, Option<String>)>::None);
+    let mut psbt_sign_anyway = use_signal(|| false);
+
+    let mut unsigned_psbt_error = use_signal(|| None);
+    let mut psbt_spent_outputs_error = use_signal(|| None);
+
+    let has_sign_psbt_form_errors = move || {
+        has_escrow_form_errors()
+            || unsigned_psbt_error.read().is_some()
+            || psbt_spent_outputs_error.read().is_some()
+    };
+
+    let mut validate_sign_psbt_form = move || {
+        validate_escrow_form();
+        unsigned_psbt_error.set(
+            validate_input(&unsigned_psbt.read(), ValidationField::Psbt, true)
                 .err()
                 .map(|e| e.to_string()),
         );
+        psbt_spent_outputs_error.set(
+            validate_input(
+                &psbt_spent_outputs.read(),
+                ValidationField::SpentOutputs,
+                false,
+            )
+            .err()
+            .map(|e| e.to_string()),
+        );
+    };
 
-        if arbitrator_filled {
-            timelock_days_error.set(
-                validate_input(&timelock_days.read(), ValidationField::TimelockDays, true)
+    let musig2_psbt = use_signal(String::new);
+    let their_pub_nonce = use_signal(String::new);
+    let their_partial_signature = use_signal(String::new);
+    // The secret nonce never leaves this page, it is lost on navigation and is used only once.
+    let mut sec_nonces = use_signal(|| Option::<Vec<SecNonce>>::None);
+    let mut our_pub_nonce = use_signal(String::new);
+    let mut our_partial_signature = use_signal(String::new);
+    let mut musig2_signed_tx = use_signal(String::new);
+    let mut musig2_policy_report = use_signal(|| Option::<(SigningPolicy, Option<String>)>::None);
+    let mut musig2_sign_anyway = use_signal(|| false);
+
+    let mut musig2_psbt_error = use_signal(|| None);
+    let mut their_pub_nonce_error = use_signal(|| None);
+    let mut their_partial_signature_error = use_signal(|| None);
+
+    let has_musig2_form_errors = move || {
+        has_escrow_form_errors()
+            || musig2_psbt_error.read().is_some()
+            || their_pub_nonce_error.read().is_some()
+            || their_partial_signature_error.read().is_some()
+    };
+
+    // Each MuSig2 step requires the counterparty's nonce and partial signature or not.
+    let mut validate_musig2_form =
+        move |pub_nonce_required: bool, partial_signature_required: bool| {
+            validate_escrow_form();
+            musig2_psbt_error.set(
+                validate_input(&musig2_psbt.read(), ValidationField::Psbt, true)
                     .err()
                     .map(|e| e.to_string()),
             );
-            timelock_hours_error.set(
-                validate_input(&timelock_hours.read(), ValidationField::TimelockHours, true)
-                    .err()
-                    .map(|e| e.to_string()),
+            their_pub_nonce_error.set(
+                validate_input(
+                    &their_pub_nonce.read(),
+                    ValidationField::PubNonce,
+                    pub_nonce_required,
+                )
+                .err()
+                .map(|e| e.to_string()),
             );
-        }
+            their_partial_signature_error.set(
+                validate_input(
+                    &their_partial_signature.read(),
+                    ValidationField::PartialSignature,
+                    partial_signature_required,
+                )
+                .err()
+                .map(|e| e.to_string()),
+            );
+        };
+
+    // The MuSig2 key path of the escrow of the form, checked like the leaves before signing it.
+    let mut checked_musig2_psbt =
+        move || -> Result<(Psbt, NostrPublicKey, NostrPublicKey), Error> {
+            let network = parse_network(&NETWORK.read()).unwrap();
+            let psbt = musig2_psbt.read().trim().parse::<Psbt>().unwrap();
+            musig2_policy_report.set(None);
+            let contract = parse_contract()?;
+            let &[npub_1, npub_2] = contract.parties() else {
+                return Err(Error::WrongInputs(
+                    "MuSig2 signing needs an escrow of two parties".to_string(),
+                ));
+            };
+            let policy = signing_policy(
+                &psbt.unsigned_tx,
+                &escrow_psbt_prevouts(&psbt, &contract, network)?,
+                &contract,
+                EscrowScript::A,
+                network,
+            )?;
+            check_signing_policy(
+                policy,
+                agreed_payouts(),
+                musig2_policy_report,
+                *musig2_sign_anyway.read(),
+            )?;
+            Ok((psbt, npub_1, npub_2))
+        };
+
+    let unsigned_tx = use_signal(String::new);
+    let mut signature = use_signal(String::new);
+    // The signatures of every leaf signed, when the escrow type is detected.
+    let mut leaf_signatures = use_signal(Vec::<(EscrowScript, String)>::new);
+    // What the transaction pays and why it was refused, checked before signing it.
+    let mut signing_policy_report = use_signal(|| Option::<(SigningPolicy, Option<String>)>::None);
+    let mut sign_anyway = use_signal(|| false);
+    let escrow_type = use_signal(String::new);
+    let spent_outputs = use_signal(String::new);
+
+    let mut unsigned_tx_error = use_signal(|| None);
+    let mut spent_outputs_error = use_signal(|| None);
+    let mut contract_error = use_signal(|| Option::<String>::None);
+
+    let has_sign_form_errors = move || {
+        has_escrow_form_errors()
+            || spent_outputs_error.read().is_some()
+            || unsigned_tx_error.read().is_some()
+    };
+
+    let mut validate_sign_form = move || {
+        validate_escrow_form();
+        spent_outputs_error.set(
+            validate_input(&spent_outputs.read(), ValidationField::SpentOutputs, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        unsigned_tx_error.set(
+            validate_input(&unsigned_tx.read(), ValidationField::Transaction, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
     };
 
+    // The override is for the transaction that it was checked on, a new one is checked again.
+    use_effect(move || {
+        unsigned_psbt.read();
+        psbt_policy_report.set(None);
+        psbt_sign_anyway.set(false);
+    });
+    use_effect(move || {
+        musig2_psbt.read();
+        musig2_policy_report.set(None);
+        musig2_sign_anyway.set(false);
+    });
+    use_effect(move || {
+        unsigned_tx.read();
+        signing_policy_report.set(None);
+        sign_anyway.set(false);
+    });
+
     let var_name = rsx! {
         main { class: "max-w-7xl mx-auto py-6 sm:px-6 lg:px-8",
             div { class: "px-4 py-6 sm:px-0",
@@ -119,49 +360,39 @@ pub(crate) fn Sign() -> Element {
                 div { class: "bg-white shadow overflow-hidden sm:rounded-lg",
                     div { class: "px-4 py-5 sm:p-6",
                         div { class: "space-y-6",
-
-                            TransactionInput {
-                                update_var: unsigned_tx,
-                                label: "Unsigned Transaction",
-                                id: "unsigned-tx",
-                                error: unsigned_tx_error,
+                            h3 { class: "text-lg font-medium text-gray-900", "Escrow Details" }
+                            p { class: "text-sm text-gray-500",
+                                "The escrow that you sign for, recomputed to check the PSBT or the transaction before signing it."
                             }
 
                             div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
                                 NetworkInput { id: "network", label: "Bitcoin Network" }
 
-                                EscrowTypeInput { update_var: escrow_type }
+                                InternalKeyInput { update_var: internal_key }
 
-                                NpubInput {
-                                    id: "npub_buyer",
-                                    label: "Buyer Nostr Public Key (npub)",
-                                    update_var: npub_buyer,
-                                    error: npub_buyer_error,
+                                PartiesInput {
+                                    parties,
+                                    amounts: false,
+                                    fee_shares: *fee_policy.read() == "Fixed",
+                                    payout_addresses: true,
                                 }
 
-                                NpubInput {
-                                    id: "npub_seller",
-                                    label: "Seller Nostr Public Key (npub)",
-                                    update_var: npub_seller,
-                                    error: npub_seller_error,
-                                }
-
-                                TxidInput {
-                                    label: "Escrow funding Transaction ID",
-                                    update_var: funding_txid,
-                                    warning: "",
-                                    error: funding_txid_error,
+                                ThresholdInput {
+                                    update_var: cooperative_threshold,
+                                    label: "Cooperative Threshold",
+                                    id: "cooperative_threshold",
+                                    parties: parties.read().len(),
+                                    placeholder: "All parties",
+                                    error: cooperative_threshold_error,
                                 }
 
+                                NsecInput { update_var: nsec, error: nsec_error }
 
-                                BitcoinInput {
-                                    id: "amount_total",
-                                    label: "Total Locked Escrow Amount (BTC)",
-                                    update_var: amount_total,
-                                    error: amount_total_error,
+                                if let Some(role) = signer_role() {
+                                    div { class: "sm:col-span-6",
+                                        p { class: "text-xs text-gray-500", "{role}" }
+                                    }
                                 }
-
-                                NsecInput { update_var: nsec, error: nsec_error }
                             }
 
                             div {
@@ -173,23 +404,451 @@ pub(crate) fn Sign() -> Element {
 
                                 div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
 
-                                    NpubInput {
-                                        id: "npub_arbitrator",
-                                        label: "Arbitrator Nostr Public Key (npub)",
-                                        update_var: npub_arbitrator,
-                                        error: npub_arbitrator_error,
+                                    ArbitratorsInput { arbitrators }
+
+                                    ThresholdInput {
+                                        update_var: arbitrator_threshold,
+                                        label: "Arbitrator Threshold",
+                                        id: "arbitrator_threshold",
+                                        parties: arbitrators.read().len(),
+                                        placeholder: "All arbitrators",
+                                        error: arbitrator_threshold_error,
+                                    }
+
+                                    ThresholdInput {
+                                        update_var: arbitrated_threshold,
+                                        label: "Arbitrated Threshold",
+                                        id: "arbitrated_threshold",
+                                        parties: parties.read().len(),
+                                        placeholder: "1",
+                                        error: arbitrated_threshold_error,
                                     }
 
                                     TimelockInput {
+                                        update_mode_var: timelock_mode,
                                         update_day_var: timelock_days,
                                         update_hour_var: timelock_hours,
+                                        update_absolute_var: timelock_absolute,
                                         day_error: timelock_days_error,
                                         hour_error: timelock_hours_error,
-                                        required: !npub_arbitrator.read().is_empty(),
+                                        absolute_error: timelock_absolute_error,
+                                        required: has_arbitrators(&arbitrators.read()),
+                                    }
+                                }
+                            }
+
+                            div { class: "border-t border-gray-200 pt-6",
+                                h3 { class: "text-lg font-medium text-gray-900", "Refund Details" }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                    RefundInput {
+                                        refund,
+                                        parties: parties.read().len(),
+                                        payout: false,
                                     }
                                 }
                             }
 
+                            div { class: "border-t border-gray-200 pt-6",
+                                h3 { class: "text-lg font-medium text-gray-900", "Agreed Payouts" }
+                                p { class: "mt-1 text-sm text-gray-500",
+                                    "What each party and the arbitrators were agreed to get before the transaction fee, adding up to the escrow value.
+                                    Signing is refused if the fee rate exceeds the maximum, or if one of them is paid more, or less by more than their share of the fee."
+                                }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                    for (i , party) in parties.read().iter().enumerate() {
+                                        SatsInput {
+                                            key: "{i}",
+                                            id: format!("agreed_payout_{i}"),
+                                            label: format!("Party {} Agreed Payout (sats)", i + 1),
+                                            update_var: party.amount,
+                                            error: party.amount_error,
+                                        }
+                                    }
+
+                                    if has_arbitrators(&arbitrators.read()) {
+                                        SatsInput {
+                                            id: "agreed_arbitrator_fee",
+                                            label: "Arbitrators Agreed Fee (sats)",
+                                            update_var: agreed_arbitrator_fee,
+                                            error: agreed_arbitrator_fee_error,
+                                        }
+                                    }
+
+                                    FeePolicyInput {
+                                        update_var: fee_policy,
+                                        parties: parties.read().len(),
+                                        arbitrators: has_arbitrators(&arbitrators.read()),
+                                    }
+
+                                    FeeRateInput {
+                                        id: "max_fee_rate",
+                                        label: "Maximum Fee Rate (sats/vByte)",
+                                        update_var: max_fee_rate,
+                                        error: max_fee_rate_error,
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            h3 { class: "text-lg font-medium text-gray-900", "Sign PSBT" }
+
+                            PsbtInput {
+                                update_var: unsigned_psbt,
+                                label: "Unsigned PSBT",
+                                id: "unsigned-psbt",
+                                error: unsigned_psbt_error,
+                            }
+
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                SpentOutputsInput {
+                                    update_var: psbt_spent_outputs,
+                                    label: "Funding Transactions (optional, to check the PSBT amounts)",
+                                    id: "psbt-spent-outputs",
+                                    error: psbt_spent_outputs_error,
+                                    unsigned_tx: unsigned_psbt,
+                                    unsigned_tx_error: unsigned_psbt_error,
+                                    required: false,
+                                }
+                            }
+
+                            SigningPolicyReport {
+                                report: psbt_policy_report,
+                                sign_anyway: psbt_sign_anyway,
+                                id: "psbt-sign-anyway",
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end",
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_sign_psbt_form();
+                                            if has_sign_psbt_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot sign PSBT");
+                                                return;
+                                            }
+                                            let nsec = parse_nsec(&nsec.read()).unwrap();
+                                            let network = parse_network(&NETWORK.read()).unwrap();
+                                            let mut psbt = unsigned_psbt.read().trim().parse::<Psbt>().unwrap();
+                                            psbt_policy_report.set(None);
+                                            let signed = parse_contract()
+                                                .and_then(|contract| {
+                                                    // The amounts of the PSBT are checked against real data when given.
+                                                    if !psbt_spent_outputs.read().trim().is_empty() {
+                                                        check_psbt_prevouts(
+                                                            &psbt,
+                                                            &parse_spent_outputs(&psbt_spent_outputs.read())?,
+                                                        )?;
+                                                    }
+                                                    let leaves = signable_leaves(
+                                                        &contract,
+                                                        &nsec_to_x_only_public_key(&nsec),
+                                                        &psbt.unsigned_tx,
+                                                    )?;
+                                                    // Show what is signed, and refuse to pay outside of the agreement unless overridden.
+                                                    // Without a signable leaf, the policy of the key path is checked.
+                                                    let policy = signing_policy(
+                                                        &psbt.unsigned_tx,
+                                                        &escrow_psbt_prevouts(&psbt, &contract, network)?,
+                                                        &contract,
+                                                        leaves.first().copied().unwrap_or(EscrowScript::A),
+                                                        network,
+                                                    )?;
+                                                    check_signing_policy(
+                                                        policy,
+                                                        agreed_payouts(),
+                                                        psbt_policy_report,
+                                                        *psbt_sign_anyway.read(),
+                                                    )?;
+                                                    sign_escrow_psbt(&mut psbt, &nsec, &contract, network)
+                                                });
+                                            match signed {
+                                                Ok(0) => {
+                                                    unsigned_psbt_error
+                                                        .set(
+                                                            Some(
+                                                                "Your key is not part of any spending condition of this PSBT."
+                                                                    .to_string(),
+                                                            ),
+                                                        );
+                                                }
+                                                Ok(_signatures) => {
+                                                    #[cfg(debug_assertions)]
+                                                    info!(% _signatures, % psbt, "Signed PSBT");
+                                                    signed_psbt.set(psbt.to_string());
+                                                }
+                                                Err(e) => {
+                                                    unsigned_psbt_error.set(Some(e.to_string()));
+                                                }
+                                            }
+                                        },
+                                        text: "Sign PSBT",
+                                    }
+                                }
+                            }
+
+                            TransactionOutput {
+                                update_var: signed_psbt,
+                                label: "Signed PSBT",
+                                id: "signed-psbt",
+                                placeholder: "Signed PSBT will appear here...",
+                            }
+
+                            div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
+                                CopyButton { text: "PSBT", clipboard_text: signed_psbt }
+                                ContinueButton {
+                                    to: Route::Combine {},
+                                    text: "Continue to Combine",
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            h3 { class: "text-lg font-medium text-gray-900",
+                                "Cooperative MuSig2 Signing"
+                            }
+                            p { class: "text-sm text-gray-500",
+                                "For escrows of two parties with a MuSig2 key path, signed with the escrow details and agreed payouts above. One party starts the session and sends their public nonce,
+                                the other party signs with it and sends back their public nonce and partial signature,
+                                and the first party aggregates both into the signed transaction.
+                                Every input has its own session: the nonces and partial signatures are one per input, in input order, separated by commas.
+                                Keep this page open between starting the session and aggregating the signatures."
+                            }
+
+                            PsbtInput {
+                                update_var: musig2_psbt,
+                                label: "Unsigned PSBT",
+                                id: "musig2-psbt",
+                                error: musig2_psbt_error,
+                            }
+
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                PubNonceInput {
+                                    update_var: their_pub_nonce,
+                                    label: "Counterparty Public Nonces",
+                                    id: "their-pub-nonce",
+                                    error: their_pub_nonce_error,
+                                    required: false,
+                                }
+
+                                PartialSignatureInput {
+                                    update_var: their_partial_signature,
+                                    label: "Counterparty Partial Signatures",
+                                    id: "their-partial-signature",
+                                    error: their_partial_signature_error,
+                                    required: false,
+                                }
+                            }
+
+                            SigningPolicyReport {
+                                report: musig2_policy_report,
+                                sign_anyway: musig2_sign_anyway,
+                                id: "musig2-sign-anyway",
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end space-x-3",
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_musig2_form(false, false);
+                                            if has_musig2_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot start MuSig2 session");
+                                                return;
+                                            }
+                                            let nsec = parse_nsec(&nsec.read()).unwrap();
+                                            let nonces = checked_musig2_psbt()
+                                                .and_then(|(psbt, npub_1, npub_2)| {
+                                                    musig2_psbt_nonces(&psbt, &nsec, &npub_1, &npub_2)
+                                                });
+                                            match nonces {
+                                                Ok(nonces) => {
+                                                    let pub_nonces = format_pub_nonces(
+                                                        &nonces.iter().map(SecNonce::public_nonce).collect::<Vec<_>>(),
+                                                    );
+                                                    #[cfg(debug_assertions)]
+                                                    info!(% pub_nonces, "Started MuSig2 session");
+                                                    our_pub_nonce.set(pub_nonces);
+                                                    our_partial_signature.set(String::new());
+                                                    sec_nonces.set(Some(nonces));
+                                                }
+                                                Err(e) => {
+                                                    musig2_psbt_error.set(Some(e.to_string()));
+                                                }
+                                            }
+                                        },
+                                        text: "Start Session",
+                                    }
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_musig2_form(true, false);
+                                            if has_musig2_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot sign MuSig2 session");
+                                                return;
+                                            }
+                                            let nsec = parse_nsec(&nsec.read()).unwrap();
+                                            let their_pub_nonces = parse_pub_nonces(&their_pub_nonce.read()).unwrap();
+                                            let signed = checked_musig2_psbt()
+                                                .and_then(|(psbt, npub_1, npub_2)| {
+                                                    musig2_psbt_partial_sign(&psbt, &nsec, &npub_1, &npub_2, &their_pub_nonces)
+                                                });
+                                            match signed {
+                                                Ok((pub_nonces, partial_signatures)) => {
+                                                    let pub_nonces = format_pub_nonces(&pub_nonces);
+                                                    #[cfg(debug_assertions)]
+                                                    info!(% pub_nonces, "Signed MuSig2 session");
+                                                    our_pub_nonce.set(pub_nonces);
+                                                    our_partial_signature.set(format_partial_signatures(&partial_signatures));
+                                                }
+                                                Err(e) => {
+                                                    musig2_psbt_error.set(Some(e.to_string()));
+                                                }
+                                            }
+                                        },
+                                        text: "Sign with Counterparty Nonce",
+                                    }
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_musig2_form(true, true);
+                                            if has_musig2_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot aggregate MuSig2 signatures");
+                                                return;
+                                            }
+                                            let (psbt, npub_1, npub_2) = match checked_musig2_psbt() {
+                                                Ok(checked) => checked,
+                                                Err(e) => {
+                                                    musig2_psbt_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            };
+                                            // Take the secret nonces so that they can never be reused.
+                                            let Some(nonces) = sec_nonces.take() else {
+                                                musig2_psbt_error
+                                                    .set(
+                                                        Some(
+                                                            "Start a session before aggregating the signatures.".to_string(),
+                                                        ),
+                                                    );
+                                                return;
+                                            };
+                                            let nsec = parse_nsec(&nsec.read()).unwrap();
+                                            let their_pub_nonces = parse_pub_nonces(&their_pub_nonce.read()).unwrap();
+                                            let their_partial_signatures = parse_partial_signatures(
+                                                    &their_partial_signature.read(),
+                                                )
+                                                .unwrap();
+                                            match musig2_psbt_aggregate(
+                                                &psbt,
+                                                &nsec,
+                                                &npub_1,
+                                                &npub_2,
+                                                nonces,
+                                                &their_pub_nonces,
+                                                &their_partial_signatures,
+                                            ) {
+                                                Ok(signed_tx) => {
+                                                    let signed_tx = consensus::serialize(&signed_tx).as_hex().to_string();
+                                                    #[cfg(debug_assertions)]
+                                                    info!(% signed_tx, "Aggregated MuSig2 signatures");
+                                                    musig2_signed_tx.set(signed_tx);
+                                                }
+                                                Err(e) => {
+                                                    their_partial_signature_error.set(Some(e.to_string()));
+                                                }
+                                            }
+                                        },
+                                        text: "Aggregate Signatures",
+                                    }
+                                }
+                            }
+
+                            dl { class: "grid grid-cols-1 gap-x-4 gap-y-6 sm:grid-cols-2",
+                                TransactionOutput {
+                                    update_var: our_pub_nonce,
+                                    label: "Your Public Nonces",
+                                    id: "our-pub-nonce",
+                                    placeholder: "Your public nonces will appear here...",
+                                }
+
+                                TransactionOutput {
+                                    update_var: our_partial_signature,
+                                    label: "Your Partial Signatures",
+                                    id: "our-partial-signature",
+                                    placeholder: "Your partial signatures will appear here...",
+                                }
+                            }
+
+                            TransactionOutput {
+                                update_var: musig2_signed_tx,
+                                label: "Signed Transaction",
+                                id: "musig2-signed-tx",
+                                placeholder: "Signed transaction will appear here...",
+                            }
+
+                            div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
+                                CopyButton { text: "Nonces", clipboard_text: our_pub_nonce }
+                                CopyButton {
+                                    text: "Partial Signatures",
+                                    clipboard_text: our_partial_signature,
+                                }
+                                CopyButton {
+                                    text: "Transaction",
+                                    clipboard_text: musig2_signed_tx,
+                                }
+                                ContinueButton {
+                                    to: Route::Broadcast {},
+                                    text: "Continue to Broadcast",
+                                }
+                            }
+                        }
+                    }
+                }
+
+                div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            h3 { class: "text-lg font-medium text-gray-900", "Sign Raw Transaction" }
+
+                            TransactionInput {
+                                update_var: unsigned_tx,
+                                label: "Unsigned Transaction",
+                                id: "unsigned-tx",
+                                error: unsigned_tx_error,
+                            }
+
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                EscrowTypeInput { update_var: escrow_type, auto: true }
+
+                                SpentOutputsInput {
+                                    update_var: spent_outputs,
+                                    label: "Funding Transactions or PSBT",
+                                    id: "spent-outputs",
+                                    error: spent_outputs_error,
+                                    unsigned_tx,
+                                    unsigned_tx_error,
+                                }
+                            }
+
+                            SigningPolicyReport {
+                                report: signing_policy_report,
+                                sign_anyway,
+                                id: "sign-anyway",
+                            }
+
                             div { class: "pt-5",
                                 div { class: "flex justify-end",
                                     PrimaryButton {
@@ -202,93 +861,97 @@ pub(crate) fn Sign() -> Element {
                                             }
                                             #[cfg(debug_assertions)]
                                             trace!(
-                                                % npub_buyer, % npub_seller, % amount_total, % NETWORK, % npub_arbitrator, %
-                                                timelock_days, % timelock_hours, % escrow_type,
+                                                parties = % parties.read().len(), % cooperative_threshold, % NETWORK,
+                                                arbitrators = % arbitrators.read().len(), % arbitrator_threshold, % arbitrated_threshold, % timelock_days, % timelock_hours, %
+                                                escrow_type,
                                                 "Clicked Generate Transaction"
                                             );
-                                            let npub_buyer = parse_npub(&npub_buyer.read()).unwrap();
-                                            let npub_seller = parse_npub(&npub_seller.read()).unwrap();
+                                            signing_policy_report.set(None);
                                             let nsec = parse_nsec(&nsec.read()).unwrap();
-                                            let escrow_type = parse_escrow_type(&escrow_type.read()).unwrap();
-                                            let btc_amount_total = Amount::from_btc(
-                                                    amount_total.read().parse::<f64>().unwrap(),
-                                                )
-                                                .unwrap();
                                             let network = parse_network(&NETWORK.read()).unwrap();
                                             let unsigned_tx: Transaction = consensus::encode::deserialize_hex(
                                                     &unsigned_tx.read(),
                                                 )
                                                 .unwrap();
-                                            let signature_str = if !npub_arbitrator.read().is_empty() {
-                                                #[cfg(debug_assertions)]
-                                                trace!("dispute escrow sign");
-                                                let npub_arbitrator = parse_npub(&npub_arbitrator.read()).unwrap();
-                                                let timelock_hours = hours_to_blocks(
-                                                    timelock_hours.read().parse::<u32>().unwrap(),
-                                                );
-                                                let timelock_days = days_to_blocks(
-                                                    timelock_days.read().parse::<u32>().unwrap(),
-                                                );
-                                                let timelock_duration = timelock_days + timelock_hours;
-                                                let escrow_address = escrow_address(
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        Some(&npub_arbitrator),
-                                                        Some(timelock_duration),
-                                                        network,
-                                                    )
-                                                    .unwrap();
-                                                let prevout = TxOut {
-                                                    value: btc_amount_total,
-                                                    script_pubkey: escrow_address.script_pubkey(),
-                                                };
-                                                sign_escrow_tx(
+                                            let signed_leaves = match parse_contract()
+                                                .and_then(|contract| {
+                                                    let escrow_address = contract_address(&contract, network)?;
+                                                    // The prevout of every input, in input order, from real data.
+                                                    let prevouts = escrow_prevouts(
                                                         &unsigned_tx,
-                                                        0,
-                                                        &nsec,
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        Some(&npub_arbitrator),
-                                                        Some(timelock_days + timelock_hours),
-                                                        vec![prevout],
-                                                        escrow_type,
-                                                    )
-                                                    .unwrap()
-                                            } else {
-                                                #[cfg(debug_assertions)]
-                                                trace!("collaborative escrow sign");
-                                                let escrow_address = escrow_address(
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        None,
-                                                        None,
-                                                        network,
-                                                    )
-                                                    .unwrap();
-                                                let prevout = TxOut {
-                                                    value: btc_amount_total,
-                                                    script_pubkey: escrow_address.script_pubkey(),
-                                                };
-                                                sign_escrow_tx(
+                                                        &parse_spent_outputs(&spent_outputs.read())?,
+                                                        &escrow_address.script_pubkey(),
+                                                    )?;
+                                                    // Detect the leaves that the key can sign from the transaction.
+                                                    let escrow_scripts = if *escrow_type.read() == "Auto" {
+                                                        let leaves = signable_leaves(
+                                                            &contract,
+                                                            &nsec_to_x_only_public_key(&nsec),
+                                                            &unsigned_tx,
+                                                        )?;
+                                                        if leaves.is_empty() {
+                                                            return Err(
+                                                                Error::WrongInputs(
+                                                                    "Your key cannot sign any spending condition of the escrow for this transaction"
+                                                                        .to_string(),
+                                                                ),
+                                                            );
+                                                        }
+                                                        leaves
+                                                    } else {
+                                                        vec![parse_escrow_type(&escrow_type.read())?]
+                                                    };
+                                                    // Show what is signed, and refuse to pay outside of the contract unless overridden.
+                                                    let policy = signing_policy(
                                                         &unsigned_tx,
-                                                        0,
-                                                        &nsec,
-                                                        &npub_buyer,
-                                                        &npub_seller,
-                                                        None,
-                                                        None,
-                                                        vec![prevout],
-                                                        escrow_type,
-                                                    )
-                                                    .unwrap()
+                                                        &prevouts,
+                                                        &contract,
+                                                        escrow_scripts[0],
+                                                        network,
+                                                    )?;
+                                                    check_signing_policy(
+                                                        policy,
+                                                        agreed_payouts(),
+                                                        signing_policy_report,
+                                                        *sign_anyway.read(),
+                                                    )?;
+                                                    escrow_scripts
+                                                        .into_iter()
+                                                        .map(|escrow_script| {
+                                                            let signatures = sign_contract_tx(
+                                                                &unsigned_tx,
+                                                                &nsec,
+                                                                &contract,
+                                                                &prevouts,
+                                                                escrow_script,
+                                                            )?;
+                                                            Ok((escrow_script, format_signatures(&signatures)))
+                                                        })
+                                                        .collect::<Result<Vec<_>, Error>>()
+                                                })
+                                            {
+                                                Ok(signed_leaves) => signed_leaves,
+                                                Err(e) => {
+                                                    contract_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
                                             };
+                                            contract_error.set(None);
                                             #[cfg(debug_assertions)]
-                                            info!(% signature_str, "Generated signature");
-                                            signature.set(signature_str.to_string());
+                                            info!(? signed_leaves, "Generated signatures");
+                                            // A single leaf is the signature, several are left to the signer to pick.
+                                            match signed_leaves.as_slice() {
+                                                [(_, signature_str)] => signature.set(signature_str.clone()),
+                                                _ => signature.set(String::new()),
+                                            }
+                                            leaf_signatures.set(signed_leaves);
                                         },
                                         text: "Sign Transaction",
                                     }
                                 }
+                                if let Some(error_msg) = contract_error.read().as_ref() {
+                                    p { class: "mt-2 text-xs text-red-600 text-right", "{error_msg}" }
+                                }
                             }
                         }
                     }
@@ -303,6 +966,40 @@ pub(crate) fn Sign() -> Element {
 
                         SignatureOutput { update_var: signature }
 
+                        if let [(escrow_script, _)] = leaf_signatures.read().as_slice() {
+                            p { class: "mt-2 text-xs text-gray-500",
+                                "Signed the {escrow_script:?} spending condition."
+                            }
+                        }
+
+                        if leaf_signatures.read().len() > 1 {
+                            p { class: "mt-2 text-xs text-gray-500",
+                                "Your key can sign several spending conditions of this transaction, send the signatures of the agreed one."
+                            }
+                            for (escrow_script , signatures) in leaf_signatures.read().iter().cloned() {
+                                div { key: "{escrow_script:?}", class: "mt-4",
+                                    label {
+                                        r#for: "signature-{escrow_script:?}",
+                                        class: "block text-sm font-medium text-gray-700",
+                                        "Escrow Type {escrow_script:?}"
+                                    }
+                                    textarea {
+                                        id: "signature-{escrow_script:?}",
+                                        readonly: "true",
+                                        rows: "2",
+                                        class: "mt-1 shadow-sm block w-full sm:text-sm border-gray-300 rounded-md p-2 border bg-gray-50",
+                                        value: "{signatures}",
+                                    }
+                                    div { class: "mt-2",
+                                        CopyButton {
+                                            text: format!("Signatures {escrow_script:?}"),
+                                            clipboard_text: signatures.clone(),
+                                        }
+                                    }
+                                }
+                            }
+                        }
+
                         div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
                             CopyButton { text: "Signature", clipboard_text: signature }
                             ContinueButton {
@@ -318,3 +1015,144 @@ pub(crate) fn Sign() -> Element {
     };
     var_name
 }
+
+/// Parses the validated "Agreed Payouts" form into [`AgreedPayouts`].
+///
+/// The fee payer and the fixed fee shares of the form are of the parties, or the arbitrators pay,
+/// they are mapped to the paid recipients that the [`AgreedPayouts::fee_policy`] splits the fee between.
+///
+/// # Errors
+///
+/// Errors if the fee payer is not agreed to be paid.
+fn parse_agreed_payouts(
+    parties: &[PartyFields],
+    arbitrator_fee: &str,
+    fee_policy: &str,
+    max_fee_rate: &str,
+) -> Result<AgreedPayouts, Error> {
+    let parse_sats = |sats: &str| Amount::from_sat(sats.trim().parse().unwrap_or_default());
+    let party_payouts = parties
+        .iter()
+        .map(|party| parse_sats(&party.amount.read()))
+        .collect::<Vec<_>>();
+    let arbitrators = parse_sats(arbitrator_fee);
+    let paid = |payouts: &[Amount]| {
+        payouts
+            .iter()
+            .filter(|amount| **amount > Amount::ZERO)
+            .count()
+    };
+    let fee_policy = match fee_policy {
+        "Arbitrators" if arbitrators > Amount::ZERO => FeePolicy::Payer(paid(&party_payouts)),
+        "Arbitrators" => {
+            return Err(Error::FeeSplit(
+                "The arbitrators pay the fee but are not agreed to be paid".to_string(),
+            ));
+        }
+        fee_policy => match parse_fee_policy(fee_policy, parties)? {
+            FeePolicy::Payer(payer) if party_payouts[payer] > Amount::ZERO => {
+                FeePolicy::Payer(paid(&party_payouts[..payer]))
+            }
+            FeePolicy::Payer(payer) => {
+                return Err(Error::FeeSplit(format!(
+                    "Party {} pays the fee but is not agreed to be paid",
+                    payer + 1
+                )));
+            }
+            FeePolicy::Fixed(shares) => FeePolicy::Fixed(
+                shares
+                    .into_iter()
+                    .zip(&party_payouts)
+                    .filter(|(_, amount)| **amount > Amount::ZERO)
+                    .map(|(share, _)| share)
+                    .chain((arbitrators > Amount::ZERO).then_some(Amount::ZERO))
+                    .collect(),
+            ),
+            fee_policy => fee_policy,
+        },
+    };
+    Ok(AgreedPayouts {
+        parties: party_payouts,
+        arbitrators,
+        fee_policy,
+        max_fee_rate: parse_fee_rate(max_fee_rate)?,
+    })
+}
+
+/// Checks a [`SigningPolicy`] against the `agreed` payouts and shows it in the `report`.
+///
+/// # Errors
+///
+/// Errors with why the policy was refused, unless `sign_anyway` overrides it.
+fn check_signing_policy(
+    policy: SigningPolicy,
+    agreed: Result<AgreedPayouts, Error>,
+    mut report: Signal<Option<(SigningPolicy, Option<String>)>>,
+    sign_anyway: bool,
+) -> Result<(), Error> {
+    let checked = agreed.and_then(|agreed| policy.check(&agreed));
+    report.set(Some((
+        policy,
+        checked.as_ref().err().map(ToString::to_string),
+    )));
+    if sign_anyway { Ok(()) } else { checked }
+}
+
+/// What a transaction pays before signing it, with the override of a refused [`SigningPolicy`].
+#[component]
+fn SigningPolicyReport(
+    report: Signal<Option<(SigningPolicy, Option<String>)>>,
+    mut sign_anyway: Signal<bool>,
+    id: String,
+) -> Element {
+    rsx! {
+        if let Some((policy, refusal)) = report.read().as_ref() {
+            div { class: "border-t border-gray-200 pt-6",
+                h3 { class: "text-lg font-medium text-gray-900", "What You Are Signing" }
+                ul { class: "mt-4 divide-y divide-gray-200 border border-gray-300 rounded-md",
+                    for (i , output) in policy.outputs.iter().enumerate() {
+                        li { key: "{i}", class: "flex items-center p-2 text-sm",
+                            span {
+                                class: if output.label == OutputLabel::Unknown { "font-bold text-red-600 whitespace-nowrap" } else { "text-gray-700 whitespace-nowrap" },
+                                "{output.label}"
+                            }
+                            span { class: "ml-3 font-mono text-gray-700 truncate",
+                                {
+                                    output
+                                        .address
+                                        .as_ref()
+                                        .map_or("Non-standard script".to_string(), ToString::to_string)
+                                }
+                            }
+                            span { class: "ml-auto pl-3 whitespace-nowrap text-gray-900",
+                                "{output.value.to_sat()} sats"
+                            }
+                        }
+                    }
+                }
+                p { class: "mt-2 text-xs text-gray-500", "Fee: {policy.fee}" }
+                if let Some(refusal) = refusal {
+                    p { class: "mt-2 text-sm text-red-600", "{refusal}" }
+                    div { class: "mt-4 flex items-center",
+                        input {
+                            r#type: "checkbox",
+                            id: id.as_str(),
+                            class: "h-4 w-4 text-indigo-600 border-gray-300 rounded",
+                            checked: *sign_anyway.read(),
+                            onchange: move |event| {
+                                #[cfg(debug_assertions)]
+                                trace!(checked = % event.checked(), "Toggled sign anyway");
+                                sign_anyway.set(event.checked());
+                            },
+                        }
+                        label {
+                            r#for: id.as_str(),
+                            class: "ml-3 text-sm text-red-600",
+                            "Sign anyway: I checked that these outputs and amounts were agreed on"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,6 @@
 //! Spend from resolution address component.
 
-use bitcoin::{Address, Amount, TxOut, Txid, consensus, hex::DisplayHex};
+use bitcoin::{Address, Amount, FeeRate, OutPoint, TxOut, Txid, consensus, hex::DisplayHex};
 use dioxus::prelude::*;
 
 #[cfg(debug_assertions)]
@@ -8,47 +8,56 @@ use dioxus::logger::tracing::trace;
 
 use crate::{
     ESPLORA_ENDPOINT, NETWORK, Route,
-    esplora::{FeeEstimate, create_client, get_fee_estimates},
+    esplora::{FeeEstimate, create_client, get_fee_estimates, get_utxos},
     sign::sign_resolution_tx,
-    tx::resolution_tx,
-    util::{P2TR_TX_VBYTE_KEY_PATH, parse_network, parse_nsec},
+    tx::{Utxo, select_utxos, spendable_utxos, sweep_fee, sweep_tx},
+    util::{parse_fee_rate, parse_network, parse_nsec},
     validation::{ValidationField, validate_input},
 };
 
 use super::{
-    AddressInput, BitcoinInput, ContinueButton, CopyButton, DerivedAddressOutput, FeeRateSelector,
-    Footer, NetworkInput, NpubInputDerivedAddress, NsecInput, PrimaryButton, TransactionOutput,
-    TxidInput, VoutInput,
+    AddressInput, BitcoinInput, ContinueButton, CopyButton, DerivedAddressOutput, FeeOutput,
+    FeeRateSelector, Footer, NetworkInput, NpubInputDerivedAddress, NsecInput, PrimaryButton,
+    SecondaryButton, TransactionOutput, TxidInput, VoutInput,
 };
 
 /// Spend from resolution address component.
+///
+/// Sweeps the selected UTXOs of the resolution address, fetched from Esplora or added manually,
+/// into a single output.
 #[component]
 pub(crate) fn Spend() -> Element {
     let npub = use_signal(String::new);
     let escrow_txid = use_signal(String::new);
     let destination_address = use_signal(String::new);
     let amount = use_signal(String::new);
+    let target_amount = use_signal(String::new);
     let mut fee_rate = use_signal(String::new);
     let fee_estimates = use_signal(|| Option::<FeeEstimate>::None);
     let vout = use_signal(|| "0".to_string());
     let derived_address = use_signal(String::new);
     let nsec = use_signal(String::new);
+    let mut utxos = use_signal(Vec::<Utxo>::new);
+    let mut selected = use_signal(Vec::<OutPoint>::new);
+    let mut fetching_utxos = use_signal(|| false);
     let mut signed_tx_str = use_signal(String::new);
+    let mut fee_str = use_signal(String::new);
 
     let mut npub_error = use_signal(|| None);
     let mut escrow_txid_error = use_signal(|| None);
     let mut destination_address_error = use_signal(|| None);
     let mut amount_error = use_signal(|| None);
+    let mut target_amount_error = use_signal(|| None);
     let mut fee_rate_error = use_signal(|| None);
     let mut nsec_error = use_signal(|| None);
+    let mut utxos_error = use_signal(|| None);
 
     let has_spend_form_errors = move || {
         npub_error.read().is_some()
-            || escrow_txid_error.read().is_some()
             || destination_address_error.read().is_some()
-            || amount_error.read().is_some()
             || fee_rate_error.read().is_some()
             || nsec_error.read().is_some()
+            || utxos_error.read().is_some()
     };
 
     let mut validate_spend_form = move || {
@@ -57,21 +66,11 @@ pub(crate) fn Spend() -> Element {
                 .err()
                 .map(|e| e.to_string()),
         );
-        escrow_txid_error.set(
-            validate_input(&escrow_txid.read(), ValidationField::Txid, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
         destination_address_error.set(
             validate_input(&destination_address.read(), ValidationField::Address, true)
                 .err()
                 .map(|e| e.to_string()),
         );
-        amount_error.set(
-            validate_input(&amount.read(), ValidationField::Amount, true)
-                .err()
-                .map(|e| e.to_string()),
-        );
         fee_rate_error.set(
             validate_input(&fee_rate.read(), ValidationField::FeeRate, true)
                 .err()
@@ -82,8 +81,32 @@ pub(crate) fn Spend() -> Element {
                 .err()
                 .map(|e| e.to_string()),
         );
+        utxos_error.set(if selected.read().is_empty() {
+            Some("Select at least one UTXO to spend.".to_string())
+        } else {
+            None
+        });
+    };
+
+    let has_utxo_form_errors =
+        move || escrow_txid_error.read().is_some() || amount_error.read().is_some();
+
+    let mut validate_utxo_form = move || {
+        escrow_txid_error.set(
+            validate_input(&escrow_txid.read(), ValidationField::Txid, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
+        amount_error.set(
+            validate_input(&amount.read(), ValidationField::Amount, true)
+                .err()
+                .map(|e| e.to_string()),
+        );
     };
 
+    // The fee rate to select the UTXOs worth spending at, if valid.
+    let selection_fee_rate = move || -> Option<FeeRate> { parse_fee_rate(&fee_rate.read()).ok() };
+
     use_effect(move || {
         to_owned![fee_estimates];
 
@@ -125,31 +148,11 @@ pub(crate) fn Spend() -> Element {
                                     error: npub_error,
                                 }
 
-                                TxidInput {
-                                    label: "Escrow Resolution Transaction ID",
-                                    update_var: escrow_txid,
-                                    warning: "",
-                                    error: escrow_txid_error,
-                                }
-
-                                VoutInput {
-                                    id: "escrow_vout",
-                                    label: "Escrow Resolution Transaction Output Index",
-                                    update_var: vout,
-                                }
-
                                 AddressInput {
                                     update_var: destination_address,
                                     error: destination_address_error,
                                 }
 
-                                BitcoinInput {
-                                    id: "amount",
-                                    label: "Total Locked Amount (BTC)",
-                                    update_var: amount,
-                                    error: amount_error,
-                                }
-
                                 FeeRateSelector {
                                     id: "fee",
                                     label_input: "Fee rate (sats/vByte)",
@@ -167,6 +170,258 @@ pub(crate) fn Spend() -> Element {
                                 }
 
                                 NsecInput { update_var: nsec, error: nsec_error }
+
+                                BitcoinInput {
+                                    id: "target_amount",
+                                    label: "Amount to Receive (BTC), to select only the UTXOs that cover it",
+                                    update_var: target_amount,
+                                    error: target_amount_error,
+                                }
+
+                                div { class: "sm:col-span-6",
+                                    label { class: "block text-sm font-medium text-gray-700",
+                                        "UTXOs of Your Resolution Address"
+                                    }
+                                    if utxos.read().is_empty() {
+                                        p { class: "mt-2 text-xs text-gray-500",
+                                            "Fetch the UTXOs of your resolution address from Esplora, or add them manually below."
+                                        }
+                                    } else {
+                                        ul { class: "mt-1 divide-y divide-gray-200 border border-gray-300 rounded-md",
+                                            for utxo in utxos.read().iter().copied() {
+                                                li {
+                                                    key: "{utxo.outpoint}",
+                                                    class: "flex items-center p-2 text-sm",
+                                                    input {
+                                                        r#type: "checkbox",
+                                                        id: "utxo-{utxo.outpoint}",
+                                                        class: "h-4 w-4 text-indigo-600 border-gray-300 rounded",
+                                                        checked: selected.read().contains(&utxo.outpoint),
+                                                        onchange: move |event| {
+                                                            #[cfg(debug_assertions)]
+                                                            trace!(outpoint = % utxo.outpoint, checked = % event.checked(), "Toggled UTXO");
+                                                            if event.checked() {
+                                                                selected.write().push(utxo.outpoint);
+                                                            } else {
+                                                                selected.write().retain(|outpoint| *outpoint != utxo.outpoint);
+                                                            }
+                                                            utxos_error.set(None);
+                                                        },
+                                                    }
+                                                    label {
+                                                        r#for: "utxo-{utxo.outpoint}",
+                                                        class: "ml-3 font-mono text-gray-700 truncate",
+                                                        "{utxo.outpoint}"
+                                                    }
+                                                    span { class: "ml-auto pl-3 whitespace-nowrap text-gray-900",
+                                                        "{utxo.value.to_sat()} sats"
+                                                    }
+                                                    if !utxo.confirmed {
+                                                        span { class: "ml-2 text-xs text-gray-500", "unconfirmed" }
+                                                    }
+                                                    if selection_fee_rate()
+                                                        .is_some_and(|fee_rate| utxo.effective_value(fee_rate).is_none())
+                                                    {
+                                                        span { class: "ml-2 text-xs text-red-600", "not worth its fee" }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        p { class: "mt-2 text-xs text-gray-500",
+                                            {
+                                                let selected_utxos = selected.read();
+                                                let total: u64 = utxos
+                                                    .read()
+                                                    .iter()
+                                                    .filter(|utxo| selected_utxos.contains(&utxo.outpoint))
+                                                    .map(|utxo| utxo.value.to_sat())
+                                                    .sum();
+                                                format!(
+                                                    "Selected {} of {} UTXOs: {total} sats.",
+                                                    selected_utxos.len(),
+                                                    utxos.read().len(),
+                                                )
+                                            }
+                                        }
+                                    }
+                                    if let Some(error_msg) = utxos_error.read().as_ref() {
+                                        p { class: "mt-2 text-xs text-red-600", "{error_msg}" }
+                                    }
+                                    div { class: "mt-3 flex justify-end",
+                                        SecondaryButton {
+                                            onclick: move |_| {
+                                                npub_error
+                                                    .set(
+                                                        validate_input(&npub.read(), ValidationField::Npub, true)
+                                                            .err()
+                                                            .map(|e| e.to_string()),
+                                                    );
+                                                if npub_error.read().is_some() || *fetching_utxos.read() {
+                                                    return;
+                                                }
+                                                let network = parse_network(&NETWORK.read()).unwrap();
+                                                let derived_address = derived_address
+                                                    .read()
+                                                    .parse::<Address<_>>()
+                                                    .unwrap()
+                                                    .require_network(network)
+                                                    .unwrap();
+                                                #[cfg(debug_assertions)]
+                                                trace!(% derived_address, "Clicked Fetch UTXOs");
+                                                fetching_utxos.set(true);
+                                                spawn(async move {
+                                                    let esplora_client = create_client(&ESPLORA_ENDPOINT.read()).unwrap();
+                                                    match get_utxos(&esplora_client, &derived_address).await {
+                                                        Ok(fetched) if fetched.is_empty() => {
+                                                            utxos_error
+                                                                .set(Some("No UTXOs found for your resolution address.".to_string()));
+                                                        }
+                                                        Ok(fetched) => {
+                                                            #[cfg(debug_assertions)]
+                                                            trace!(? fetched, "UTXOs fetched successfully");
+                                                            // Select the UTXOs worth spending, or all of them without a fee rate yet.
+                                                            let worth_spending = match selection_fee_rate() {
+                                                                Some(fee_rate) => spendable_utxos(&fetched, fee_rate),
+                                                                None => fetched.clone(),
+                                                            };
+                                                            selected
+                                                                .set(worth_spending.iter().map(|utxo| utxo.outpoint).collect());
+                                                            utxos.set(fetched);
+                                                            utxos_error.set(None);
+                                                        }
+                                                        Err(e) => {
+                                                            #[cfg(debug_assertions)]
+                                                            trace!(% e, "Error fetching UTXOs");
+                                                            utxos_error.set(Some(e.to_string()));
+                                                        }
+                                                    }
+                                                    fetching_utxos.set(false);
+                                                });
+                                            },
+                                            text: if *fetching_utxos.read() { "Fetching UTXOs..." } else { "Fetch UTXOs" },
+                                        }
+                                        SecondaryButton {
+                                            onclick: move |_| {
+                                                fee_rate_error
+                                                    .set(
+                                                        validate_input(&fee_rate.read(), ValidationField::FeeRate, true)
+                                                            .err()
+                                                            .map(|e| e.to_string()),
+                                                    );
+                                                let Some(fee_rate) = selection_fee_rate() else {
+                                                    return;
+                                                };
+                                                let worth_spending = spendable_utxos(&utxos.read(), fee_rate);
+                                                #[cfg(debug_assertions)]
+                                                trace!(? worth_spending, "Clicked Select Worth Spending");
+                                                selected.set(worth_spending.iter().map(|utxo| utxo.outpoint).collect());
+                                                utxos_error.set(None);
+                                            },
+                                            text: "Select Worth Spending",
+                                        }
+                                        SecondaryButton {
+                                            onclick: move |_| {
+                                                destination_address_error
+                                                    .set(
+                                                        validate_input(&destination_address.read(), ValidationField::Address, true)
+                                                            .err()
+                                                            .map(|e| e.to_string()),
+                                                    );
+                                                fee_rate_error
+                                                    .set(
+                                                        validate_input(&fee_rate.read(), ValidationField::FeeRate, true)
+                                                            .err()
+                                                            .map(|e| e.to_string()),
+                                                    );
+                                                target_amount_error
+                                                    .set(
+                                                        validate_input(&target_amount.read(), ValidationField::Amount, true)
+                                                            .err()
+                                                            .map(|e| e.to_string()),
+                                                    );
+                                                if destination_address_error.read().is_some()
+                                                    || fee_rate_error.read().is_some()
+                                                    || target_amount_error.read().is_some()
+                                                {
+                                                    return;
+                                                }
+                                                let network = parse_network(&NETWORK.read()).unwrap();
+                                                let destination_address = destination_address
+                                                    .read()
+                                                    .parse::<Address<_>>()
+                                                    .unwrap()
+                                                    .require_network(network)
+                                                    .unwrap();
+                                                let fee_rate = parse_fee_rate(&fee_rate.read()).unwrap();
+                                                let target = Amount::from_btc(target_amount.read().parse::<f64>().unwrap())
+                                                    .unwrap();
+                                                #[cfg(debug_assertions)]
+                                                trace!(% target, % fee_rate, "Clicked Select for Amount");
+                                                match select_utxos(&utxos.read(), target, &destination_address, fee_rate) {
+                                                    Ok(covering) => {
+                                                        selected.set(covering.iter().map(|utxo| utxo.outpoint).collect());
+                                                        utxos_error.set(None);
+                                                    }
+                                                    Err(e) => utxos_error.set(Some(e.to_string())),
+                                                }
+                                            },
+                                            text: "Select for Amount",
+                                        }
+                                    }
+                                }
+
+                                TxidInput {
+                                    label: "UTXO Transaction ID",
+                                    update_var: escrow_txid,
+                                    warning: "",
+                                    error: escrow_txid_error,
+                                }
+
+                                VoutInput {
+                                    id: "escrow_vout",
+                                    label: "UTXO Output Index",
+                                    update_var: vout,
+                                }
+
+                                BitcoinInput {
+                                    id: "amount",
+                                    label: "UTXO Amount (BTC)",
+                                    update_var: amount,
+                                    error: amount_error,
+                                }
+
+                                div { class: "sm:col-span-3 flex items-end justify-end",
+                                    SecondaryButton {
+                                        onclick: move |_| {
+                                            validate_utxo_form();
+                                            if has_utxo_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("UTXO has validation errors, cannot add it");
+                                                return;
+                                            }
+                                            let outpoint = OutPoint {
+                                                txid: escrow_txid.read().parse::<Txid>().unwrap(),
+                                                vout: vout.read().parse::<u32>().unwrap(),
+                                            };
+                                            if utxos.read().iter().any(|utxo| utxo.outpoint == outpoint) {
+                                                utxos_error.set(Some(format!("UTXO {outpoint} is already listed.")));
+                                                return;
+                                            }
+                                            // Manually added outputs are assumed confirmed.
+                                            let utxo = Utxo {
+                                                outpoint,
+                                                value: Amount::from_btc(amount.read().parse::<f64>().unwrap()).unwrap(),
+                                                confirmed: true,
+                                            };
+                                            #[cfg(debug_assertions)]
+                                            trace!(? utxo, "Clicked Add UTXO");
+                                            utxos.write().push(utxo);
+                                            selected.write().push(outpoint);
+                                            utxos_error.set(None);
+                                        },
+                                        text: "Add UTXO",
+                                    }
+                                }
                             }
 
                             div { class: "pt-5",
@@ -181,15 +436,17 @@ pub(crate) fn Spend() -> Element {
                                             }
                                             #[cfg(debug_assertions)]
                                             trace!(
-                                                % npub, % amount, % NETWORK, % escrow_txid, % derived_address,
+                                                % npub, % NETWORK, ? selected, % derived_address,
                                                 "Clicked Sign Transaction"
                                             );
                                             let nsec = parse_nsec(&nsec.read()).unwrap();
-                                            let btc_amount = Amount::from_btc(amount.read().parse::<f64>().unwrap())
-                                                .unwrap();
                                             let network = parse_network(&NETWORK.read()).unwrap();
-                                            let escrow_txid = escrow_txid.read().parse::<Txid>().unwrap();
-                                            let vout = vout.read().parse::<u32>().unwrap();
+                                            let selected_utxos = utxos
+                                                .read()
+                                                .iter()
+                                                .filter(|utxo| selected.read().contains(&utxo.outpoint))
+                                                .copied()
+                                                .collect::<Vec<_>>();
                                             let derived_address = derived_address
                                                 .read()
                                                 .parse::<Address<_>>()
@@ -202,29 +459,54 @@ pub(crate) fn Spend() -> Element {
                                                 .unwrap()
                                                 .require_network(network)
                                                 .unwrap();
-                                            let fee_rate = fee_rate.read().parse::<u64>().unwrap();
-                                            let fee = Amount::from_sat(fee_rate * P2TR_TX_VBYTE_KEY_PATH);
-                                            let unsigned_tx = resolution_tx(
-                                                btc_amount,
-                                                escrow_txid,
-                                                vout,
+                                            let fee_rate = parse_fee_rate(&fee_rate.read()).unwrap();
+                                            let sweep_fee = match sweep_fee(
+                                                &selected_utxos,
                                                 &destination_address,
-                                                fee,
-                                            );
+                                                fee_rate,
+                                            ) {
+                                                Ok(sweep_fee) => sweep_fee,
+                                                Err(e) => {
+                                                    fee_rate_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            };
+                                            // The output after the fee should not be dust.
+                                            let unsigned_tx = match sweep_tx(
+                                                &selected_utxos,
+                                                &destination_address,
+                                                sweep_fee.fee,
+                                            ) {
+                                                Ok(unsigned_tx) => unsigned_tx,
+                                                Err(e) => {
+                                                    utxos_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            };
                                             #[cfg(debug_assertions)]
                                             trace!(
                                                 unsigned_tx = % consensus::serialize(& unsigned_tx).as_hex(),
                                                 "Created unsigned resolution transaction"
                                             );
-                                            let prevout = TxOut {
-                                                value: btc_amount,
-                                                script_pubkey: derived_address.script_pubkey(),
+                                            let prevouts = selected_utxos
+                                                .iter()
+                                                .map(|utxo| TxOut {
+                                                    value: utxo.value,
+                                                    script_pubkey: derived_address.script_pubkey(),
+                                                })
+                                                .collect::<Vec<_>>();
+                                            let signed_tx = match sign_resolution_tx(&unsigned_tx, &nsec, &prevouts) {
+                                                Ok(signed_tx) => signed_tx,
+                                                Err(e) => {
+                                                    utxos_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
                                             };
-                                            let signed_tx = sign_resolution_tx(&unsigned_tx, &nsec, prevout);
                                             let signed_tx = consensus::serialize(&signed_tx).as_hex().to_string();
                                             #[cfg(debug_assertions)]
                                             trace!(% signed_tx, "Signed resolution transaction");
                                             signed_tx_str.set(signed_tx);
+                                            fee_str.set(sweep_fee.to_string());
                                         },
                                         text: "Sign Transaction",
                                     }
@@ -248,6 +530,12 @@ pub(crate) fn Spend() -> Element {
                             placeholder: "Signed transaction will appear here...",
                         }
 
+                        FeeOutput {
+                            update_var: fee_str,
+                            label: "Size and Fee",
+                            id: "resolution-fee",
+                        }
+
                         div { class: "mt-5 flex flex-col space-y-3 sm:flex-row sm:space-y-0 sm:space-x-3",
                             CopyButton {
                                 text: "Transaction",
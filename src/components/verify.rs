@@ -0,0 +1,318 @@
+//! Verify escrow address component.
+
+use bitcoin::Address;
+use dioxus::prelude::*;
+
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::{info, trace};
+
+use crate::{
+    NETWORK,
+    scripts::{Taptree, UNSPENDABLE_PUBLIC_KEY, contract_taptree},
+    util::parse_network,
+};
+
+use super::{
+    AddressInput, ArbitratorsInput, Footer, InternalKeyInput, NetworkInput, PartiesInput,
+    PrimaryButton, RefundInput, ThresholdInput, TimelockInput, has_arbitrators, has_parties_errors,
+    has_refund_errors, inspect::escrow_script_description, parse_escrow_contract, parse_refund,
+    use_arbitrators, use_parties, use_refund, validate_arbitrators, validate_parties,
+    validate_refund, validate_threshold, validate_timelock,
+};
+
+/// Verify escrow address component.
+#[component]
+pub(crate) fn Verify() -> Element {
+    let parties = use_parties();
+    let cooperative_threshold = use_signal(String::new);
+    let arbitrated_threshold = use_signal(String::new);
+    let arbitrators = use_arbitrators();
+    let arbitrator_threshold = use_signal(String::new);
+    let refund = use_refund();
+    let timelock_days = use_signal(String::new);
+    let timelock_hours = use_signal(String::new);
+    let timelock_mode = use_signal(String::new);
+    let timelock_absolute = use_signal(String::new);
+    let internal_key = use_signal(String::new);
+    let claimed_address = use_signal(String::new);
+    let mut taptree = use_signal(|| Option::<Taptree>::None);
+    let mut verified = use_signal(|| false);
+
+    let mut cooperative_threshold_error = use_signal(|| None);
+    let mut arbitrated_threshold_error = use_signal(|| None);
+    let mut arbitrator_threshold_error = use_signal(|| None);
+    let timelock_days_error = use_signal(|| Option::<String>::None);
+    let timelock_hours_error = use_signal(|| Option::<String>::None);
+    let timelock_absolute_error = use_signal(|| Option::<String>::None);
+    let mut claimed_address_error = use_signal(|| Option::<String>::None);
+    let mut contract_error = use_signal(|| Option::<String>::None);
+
+    let has_verify_form_errors = move || {
+        has_parties_errors(&parties.read())
+            || cooperative_threshold_error.read().is_some()
+            || arbitrated_threshold_error.read().is_some()
+            || has_parties_errors(&arbitrators.read())
+            || arbitrator_threshold_error.read().is_some()
+            || timelock_days_error.read().is_some()
+            || timelock_hours_error.read().is_some()
+            || timelock_absolute_error.read().is_some()
+            || claimed_address_error.read().is_some()
+            || claimed_address.read().is_empty()
+            || has_refund_errors(refund)
+    };
+
+    let mut validate_verify_form = move || {
+        validate_parties(&parties.read(), false);
+        let party_count = parties.read().len();
+        cooperative_threshold_error.set(validate_threshold(
+            &cooperative_threshold.read(),
+            party_count,
+        ));
+
+        validate_arbitrators(&arbitrators.read());
+        if has_arbitrators(&arbitrators.read()) {
+            arbitrator_threshold_error.set(validate_threshold(
+                &arbitrator_threshold.read(),
+                arbitrators.read().len(),
+            ));
+            arbitrated_threshold_error.set(validate_threshold(
+                &arbitrated_threshold.read(),
+                party_count,
+            ));
+            validate_timelock(
+                &timelock_mode.read(),
+                &timelock_days.read(),
+                &timelock_hours.read(),
+                &timelock_absolute.read(),
+                timelock_days_error,
+                timelock_hours_error,
+                timelock_absolute_error,
+            );
+        }
+        validate_refund(refund);
+    };
+
+    rsx! {
+        main { class: "max-w-7xl mx-auto py-6 sm:px-6 lg:px-8",
+            div { class: "px-4 py-6 sm:px-0",
+                h1 { class: "text-2xl font-bold text-gray-900 mb-6", "Verify Escrow Address" }
+
+                div { class: "bg-white shadow overflow-hidden sm:rounded-lg",
+                    div { class: "px-4 py-5 sm:p-6",
+                        div { class: "space-y-6",
+                            p { class: "text-sm text-gray-500",
+                                "Before funding, recompute the deposit address from the agreed contract instead of trusting the one you were sent."
+                            }
+
+                            div { class: "grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                AddressInput {
+                                    update_var: claimed_address,
+                                    error: claimed_address_error,
+                                    id: "claimed-address",
+                                    label: "Deposit Address to Verify",
+                                }
+
+                                NetworkInput { id: "network", label: "Bitcoin Network" }
+
+                                PartiesInput {
+                                    parties,
+                                    amounts: false,
+                                    fee_shares: false,
+                                    payout_addresses: false,
+                                }
+
+                                ThresholdInput {
+                                    update_var: cooperative_threshold,
+                                    label: "Cooperative Threshold",
+                                    id: "cooperative_threshold",
+                                    parties: parties.read().len(),
+                                    placeholder: "All parties",
+                                    error: cooperative_threshold_error,
+                                }
+
+                                InternalKeyInput { update_var: internal_key }
+                            }
+
+                            div { class: "border-t border-gray-200 pt-6",
+                                h3 { class: "text-lg font-medium text-gray-900",
+                                    "Optional Dispute Resolution"
+                                }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+
+                                    ArbitratorsInput { arbitrators }
+
+                                    ThresholdInput {
+                                        update_var: arbitrator_threshold,
+                                        label: "Arbitrator Threshold",
+                                        id: "arbitrator_threshold",
+                                        parties: arbitrators.read().len(),
+                                        placeholder: "All arbitrators",
+                                        error: arbitrator_threshold_error,
+                                    }
+
+                                    ThresholdInput {
+                                        update_var: arbitrated_threshold,
+                                        label: "Arbitrated Threshold",
+                                        id: "arbitrated_threshold",
+                                        parties: parties.read().len(),
+                                        placeholder: "1",
+                                        error: arbitrated_threshold_error,
+                                    }
+
+                                    TimelockInput {
+                                        update_mode_var: timelock_mode,
+                                        update_day_var: timelock_days,
+                                        update_hour_var: timelock_hours,
+                                        update_absolute_var: timelock_absolute,
+                                        day_error: timelock_days_error,
+                                        hour_error: timelock_hours_error,
+                                        absolute_error: timelock_absolute_error,
+                                        required: has_arbitrators(&arbitrators.read()),
+                                    }
+                                }
+                            }
+
+                            div { class: "border-t border-gray-200 pt-6",
+                                h3 { class: "text-lg font-medium text-gray-900", "Optional Refund" }
+
+                                div { class: "mt-4 grid grid-cols-1 gap-y-6 gap-x-4 sm:grid-cols-6",
+                                    RefundInput {
+                                        refund,
+                                        parties: parties.read().len(),
+                                        payout: false,
+                                    }
+                                }
+                            }
+
+                            div { class: "pt-5",
+                                div { class: "flex justify-end",
+                                    PrimaryButton {
+                                        onclick: move |_| {
+                                            validate_verify_form();
+                                            if has_verify_form_errors() {
+                                                #[cfg(debug_assertions)]
+                                                trace!("Form has validation errors, cannot verify address");
+                                                return;
+                                            }
+                                            let network = parse_network(&NETWORK.read()).unwrap();
+                                            let address = claimed_address
+                                                .read()
+                                                .parse::<Address<_>>()
+                                                .unwrap()
+                                                .require_network(network);
+                                            let address = match address {
+                                                Ok(address) => address,
+                                                Err(e) => {
+                                                    claimed_address_error.set(Some(e.to_string()));
+                                                    return;
+                                                }
+                                            };
+                                            let result = parse_escrow_contract(
+                                                    &parties.read(),
+                                                    &cooperative_threshold.read(),
+                                                    &arbitrators.read(),
+                                                    &arbitrator_threshold.read(),
+                                                    &arbitrated_threshold.read(),
+                                                    &timelock_mode.read(),
+                                                    &timelock_days.read(),
+                                                    &timelock_hours.read(),
+                                                    &timelock_absolute.read(),
+                                                    &internal_key.read(),
+                                                )
+                                                .and_then(|contract| parse_refund(contract, refund))
+                                                .and_then(|contract| contract_taptree(&contract, network));
+                                            match result {
+                                                Ok(result) => {
+                                                    let matches = result.commits_to(&address);
+                                                    #[cfg(debug_assertions)]
+                                                    info!(% result.address, matches, "Verified escrow address");
+                                                    contract_error.set(None);
+                                                    verified.set(matches);
+                                                    taptree.set(Some(result));
+                                                }
+                                                Err(e) => {
+                                                    contract_error.set(Some(e.to_string()));
+                                                    taptree.set(None);
+                                                }
+                                            }
+                                        },
+                                        text: "Verify Address",
+                                    }
+                                }
+                                if let Some(error_msg) = contract_error.read().as_ref() {
+                                    p { class: "mt-2 text-xs text-red-600 text-right", "{error_msg}" }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                if let Some(taptree) = taptree.read().as_ref() {
+                    div { class: "mt-8 bg-white shadow overflow-hidden sm:rounded-lg",
+                        div { class: "px-4 py-5 sm:p-6",
+                            if *verified.read() {
+                                div { class: "rounded-md bg-green-50 border border-green-300 p-4",
+                                    p { class: "text-sm font-medium text-green-800",
+                                        "PASS: the deposit address commits to these keys, thresholds and timelocks."
+                                    }
+                                }
+                            } else {
+                                div { class: "rounded-md bg-red-50 border border-red-300 p-4",
+                                    p { class: "text-sm font-medium text-red-800",
+                                        "FAIL: the deposit address does not commit to this contract. Do not fund it."
+                                    }
+                                    p { class: "mt-1 text-sm text-red-700 break-all",
+                                        "The contract you entered has the address {taptree.address}."
+                                    }
+                                }
+                            }
+
+                            h3 { class: "mt-8 text-lg leading-6 font-medium text-gray-900",
+                                "Taproot Tree"
+                            }
+                            dl { class: "mt-5 grid grid-cols-1 gap-x-4 gap-y-6 sm:grid-cols-2",
+                                div {
+                                    dt { class: "text-sm font-medium text-gray-500", "Internal Key" }
+                                    dd { class: "mt-1 text-sm font-mono text-gray-900 break-all",
+                                        "{taptree.internal_key}"
+                                        if taptree.internal_key == *UNSPENDABLE_PUBLIC_KEY {
+                                            " (unspendable)"
+                                        } else {
+                                            " (MuSig2 aggregate of the parties)"
+                                        }
+                                    }
+                                }
+                                div {
+                                    dt { class: "text-sm font-medium text-gray-500", "Merkle Root" }
+                                    dd { class: "mt-1 text-sm font-mono text-gray-900 break-all",
+                                        if let Some(merkle_root) = taptree.merkle_root {
+                                            "{merkle_root}"
+                                        } else {
+                                            "None, key path only"
+                                        }
+                                    }
+                                }
+                            }
+
+                            for (i , leaf) in taptree.leaves.iter().enumerate() {
+                                div {
+                                    key: "{i}",
+                                    class: "mt-4 border border-gray-300 rounded-md p-4 space-y-2 text-sm",
+                                    p { class: "font-medium text-gray-900",
+                                        "{escrow_script_description(Some(leaf.escrow_script))}"
+                                    }
+                                    p { class: "text-gray-700", "Depth {leaf.depth}" }
+                                    p { class: "font-mono text-gray-700 break-all bg-gray-50 p-2 rounded-md",
+                                        "{leaf.script.to_asm_string()}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Footer {}
+    }
+}
@@ -21,17 +21,125 @@ pub(crate) enum Error {
     #[error("Rounding error")]
     Rounding,
 
+    #[error("Fee split error: {0}")]
+    FeeSplit(String),
+
+    #[error("Payout error: {0}")]
+    Payout(String),
+
+    #[error(
+        "Output {index} of {} sats is below the dust limit of {} sats",
+        value.to_sat(),
+        limit.to_sat()
+    )]
+    Dust {
+        index: usize,
+        value: bitcoin::Amount,
+        limit: bitcoin::Amount,
+    },
+
     #[error("Invalid escrow type: {0}")]
     InvalidEscrowType(String),
 
+    #[error("Invalid internal key: {0}")]
+    InvalidInternalKey(String),
+
+    #[error("Invalid timelock: {0}")]
+    InvalidTimelock(String),
+
+    #[error("Transaction can never satisfy the timelock of the escrow leaf: {0}")]
+    TimelockMismatch(String),
+
+    #[error("Invalid fee rate: {0}")]
+    InvalidFeeRate(String),
+
+    #[error("Invalid payout address: {0}")]
+    InvalidPayoutAddress(String),
+
     #[error("Invalid network: {0}")]
     InvalidNetwork(String),
 
     #[error("Esplora error: {0}")]
     Esplora(#[from] esplora_client::Error),
 
-    #[error("Expected exactly one funding transaction")]
-    ExpectedOneFundingTransaction,
+    #[error("PSBT error: {0}")]
+    Psbt(#[from] bitcoin::psbt::Error),
+
+    #[error("PSBT extraction error: {0}")]
+    ExtractTx(#[from] Box<bitcoin::psbt::ExtractTxError>),
+
+    #[error("Sighash error: {0}")]
+    Sighash(#[from] bitcoin::sighash::TaprootError),
+
+    #[error("Missing witness UTXO for input {0}")]
+    MissingWitnessUtxo(usize),
+
+    #[error("Missing the prevout of input {0}")]
+    MissingPrevout(usize),
+
+    #[error("Input {0} does not spend an output of the escrow address")]
+    PrevoutNotEscrow(usize),
+
+    #[error("The witness UTXO of input {0} does not match the output that it spends")]
+    PrevoutMismatch(usize),
+
+    #[error("Not enough signatures to finalize input {0}")]
+    MissingSignatures(usize),
+
+    #[error("{0} signatures do not verify")]
+    InvalidSignatures(usize),
+
+    #[error("Signatures {0} were not made by any key of the escrow for this transaction")]
+    UnknownSignatures(usize),
+
+    #[error("Signatures {0} and {1} were made by the same key")]
+    DuplicateSignatures(usize, usize),
+
+    #[error("{0} outputs do not pay a party or an arbitrator of the escrow")]
+    UnknownOutputs(usize),
+
+    #[error(
+        "{recipient} is paid {} sats instead of the agreed {} sats",
+        paid.to_sat(),
+        agreed.to_sat()
+    )]
+    UnexpectedAmount {
+        recipient: String,
+        paid: bitcoin::Amount,
+        agreed: bitcoin::Amount,
+    },
+
+    #[error(
+        "The agreed payouts of {} sats do not add up to the escrow value of {} sats",
+        agreed.to_sat(),
+        escrow.to_sat()
+    )]
+    UnbalancedPayouts {
+        agreed: bitcoin::Amount,
+        escrow: bitcoin::Amount,
+    },
+
+    #[error(
+        "The fee of {} sats exceeds the {} sats of the maximum fee rate",
+        fee.to_sat(),
+        max.to_sat()
+    )]
+    FeeTooHigh {
+        fee: bitcoin::Amount,
+        max: bitcoin::Amount,
+    },
+
+    #[error("MuSig2 key aggregation error: {0}")]
+    MuSig2KeyAgg(#[from] musig2::errors::KeyAggError),
+
+    #[error("MuSig2 tweak error: {0}")]
+    MuSig2Tweak(#[from] musig2::errors::TweakError),
+
+    #[error("MuSig2 signing error: {0}")]
+    MuSig2Signing(#[from] musig2::errors::SigningError),
+
+    #[error("MuSig2 partial signature verification error: {0}")]
+    MuSig2Verify(#[from] musig2::errors::VerifyError),
 }
 
 /// Represents validation errors for form fields and user input.
@@ -49,7 +157,13 @@ pub(crate) enum ValidationError {
     #[error("Amount must be between 0.00000001 and 100 BTC.")]
     InvalidAmount,
 
-    #[error("Fee rate must be a positive integer greater than zero.")]
+    #[error("Amount must be a whole number of sats.")]
+    InvalidSats,
+
+    #[error("Percentage must be between 0.01 and 100, with up to two decimals.")]
+    InvalidPercent,
+
+    #[error("Fee rate must be a positive number of sats per vByte.")]
     InvalidFeeRate,
 
     #[error("Invalid nsec format. Please enter a valid Nostr secret key.")]
@@ -58,21 +172,64 @@ pub(crate) enum ValidationError {
     #[error("Invalid transaction ID. Please enter a valid transaction ID.")]
     InvalidTxid,
 
+    #[error("Invalid output index. Please enter a non-negative whole number.")]
+    InvalidVout,
+
     #[error("Invalid transaction format. The transaction should be a hexadecimal string.")]
     InvalidTransaction,
 
-    #[error("Invalid signature format.")]
+    #[error("Invalid PSBT format. The PSBT should be a base64 string.")]
+    InvalidPsbt,
+
+    #[error("Invalid transaction or PSBT. Please enter a transaction in hex or a PSBT in base64.")]
+    InvalidTransactionOrPsbt,
+
+    #[error(
+        "Invalid funding transactions. Please enter a PSBT, or the funding transactions in hex separated by whitespace."
+    )]
+    InvalidSpentOutputs,
+
+    #[error("Invalid signature format. Please enter one signature per input, separated by commas.")]
     InvalidSignature,
 
+    #[error(
+        "Invalid MuSig2 public nonce format. Please enter one hexadecimal nonce per input, separated by commas."
+    )]
+    InvalidPubNonce,
+
+    #[error(
+        "Invalid MuSig2 partial signature format. Please enter one hexadecimal signature per input, separated by commas."
+    )]
+    InvalidPartialSignature,
+
     #[error("Invalid Bitcoin address format. Please check and try again.")]
     InvalidAddress,
 
+    #[error(
+        "Invalid payout address. Please enter an address or a single-key descriptor for the selected network."
+    )]
+    InvalidPayoutAddress,
+
     #[error("Invalid URL format. Should start with http:// or https://.")]
     InvalidUrl,
 
-    #[error("Days should be between 0 and 1,000.")]
+    #[error("Days should be between 0 and 455.")]
     InvalidTimelockDays,
 
     #[error("Hours should be between 0 and 23.")]
     InvalidTimelockHours,
+
+    #[error("Threshold should be between 1 and the number of parties.")]
+    InvalidThreshold,
+
+    #[error(
+        "Relative timelock should be at most 65,535 blocks (about 455 days), or 388 days if time-based."
+    )]
+    InvalidRelativeTimelock,
+
+    #[error("Block height should be between 1 and 499,999,999.")]
+    InvalidTimelockHeight,
+
+    #[error("Invalid date. Please pick a date after 1985-11-05.")]
+    InvalidTimelockDate,
 }
@@ -1,16 +1,22 @@
 //! Interactions with Esplora backends.
 #![allow(dead_code)]
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
 
-use bitcoin::{Address, Amount, Transaction, Txid};
-use esplora_client::{AsyncClient, Builder, r#async::DefaultSleeper};
+use bitcoin::{Address, Amount, OutPoint, Script, Transaction};
+use esplora_client::{AsyncClient, Builder, Tx, r#async::DefaultSleeper};
 
-use crate::error::Error;
+use crate::{error::Error, tx::Utxo};
 
 /// How Esplora returns fee estimates.
 pub(crate) type FeeEstimate = HashMap<u16, f64>;
 
+/// How many confirmed transactions Esplora returns per page of an address history.
+const CONFIRMED_TXS_PER_PAGE: usize = 25;
+
 /// Creates a new `async` Esplora client.
 pub(crate) fn create_client(url: &str) -> Result<AsyncClient<DefaultSleeper>, Error> {
     Ok(Builder::new(url).build_async()?)
@@ -34,23 +40,188 @@ pub(crate) async fn get_balance(
     Ok(Amount::from_sat(balance))
 }
 
-/// Gets funding [`Txid`] from Esplora.
+/// The outputs funding an escrow address, see [`get_funding_outputs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FundingOutputs {
+    /// The unspent outputs paying to the escrow address, including the unconfirmed ones.
+    pub(crate) utxos: Vec<Utxo>,
+    /// What to check before spending the `utxos` as the funding of the escrow.
+    pub(crate) warnings: Vec<FundingWarning>,
+}
+
+/// Something unusual about the history of an escrow address that does not prevent spending it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FundingWarning {
+    /// No transaction pays to the escrow address yet.
+    NotFunded,
+    /// Outputs of the escrow address were already spent, so the address is reused.
+    Reused {
+        /// How many outputs of the address were spent.
+        spent: usize,
+    },
+    /// More than one transaction pays to the escrow address.
+    SeveralTransactions {
+        /// How many transactions have unspent outputs paying to the address.
+        transactions: usize,
+    },
+    /// Some funding outputs are only in the mempool.
+    Unconfirmed {
+        /// How many funding outputs are unconfirmed.
+        outputs: usize,
+    },
+}
+
+impl fmt::Display for FundingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFunded => write!(f, "The escrow address has not been funded yet."),
+            Self::Reused { spent } => write!(
+                f,
+                "The escrow address is reused: {spent} of its outputs were already spent. \
+                 Check that the remaining outputs belong to this escrow."
+            ),
+            Self::SeveralTransactions { transactions } => write!(
+                f,
+                "The escrow address is funded by {transactions} transactions. \
+                 Check that every one of them belongs to this escrow."
+            ),
+            Self::Unconfirmed { outputs } => write!(
+                f,
+                "{outputs} funding outputs are not confirmed yet and could still be replaced."
+            ),
+        }
+    }
+}
+
+/// Gets the [`FundingOutputs`] of an escrow [`Address`] from Esplora.
 ///
-/// This assumes a virgin address with just one funding transaction.
-pub(crate) async fn get_funding_txid(
+/// Unlike a virgin address with a single funding transaction, any history is accepted:
+/// reused addresses, several deposits and mempool-only funding are reported as [`FundingWarning`]s.
+pub(crate) async fn get_funding_outputs(
+    client: &AsyncClient<DefaultSleeper>,
+    address: &Address,
+) -> Result<FundingOutputs, Error> {
+    let txs = get_address_history(client, address).await?;
+
+    Ok(funding_outputs(&txs, &address.script_pubkey()))
+}
+
+/// Finds the [`FundingOutputs`] of the `txs` paying to `script_pubkey`, see [`address_utxos`].
+pub(crate) fn funding_outputs(txs: &[Tx], script_pubkey: &Script) -> FundingOutputs {
+    let utxos = address_utxos(txs, script_pubkey);
+    let mut warnings = Vec::new();
+
+    let received = txs
+        .iter()
+        .flat_map(|tx| &tx.vout)
+        .filter(|vout| vout.scriptpubkey.as_script() == script_pubkey)
+        .count();
+    if received == 0 {
+        warnings.push(FundingWarning::NotFunded);
+    }
+    let spent = received - utxos.len();
+    if spent > 0 {
+        warnings.push(FundingWarning::Reused { spent });
+    }
+    let transactions = utxos
+        .iter()
+        .map(|utxo| utxo.outpoint.txid)
+        .collect::<HashSet<_>>()
+        .len();
+    if transactions > 1 {
+        warnings.push(FundingWarning::SeveralTransactions { transactions });
+    }
+    let outputs = utxos.iter().filter(|utxo| !utxo.confirmed).count();
+    if outputs > 0 {
+        warnings.push(FundingWarning::Unconfirmed { outputs });
+    }
+
+    FundingOutputs { utxos, warnings }
+}
+
+/// Gets the whole transaction history of an [`Address`] from Esplora,
+/// the mempool transactions first, then the confirmed ones from the newest.
+pub(crate) async fn get_address_history(
+    client: &AsyncClient<DefaultSleeper>,
+    address: &Address,
+) -> Result<Vec<Tx>, Error> {
+    let mut txs = client.get_address_txs(address, None).await?;
+    let mut confirmed = txs.iter().filter(|tx| tx.status.confirmed).count();
+    while confirmed == CONFIRMED_TXS_PER_PAGE {
+        let last_seen = txs.last().map(|tx| tx.txid);
+        let page = client.get_address_txs(address, last_seen).await?;
+        confirmed = page.len();
+        txs.extend(page);
+    }
+
+    Ok(txs)
+}
+
+/// Gets the [`Utxo`]s of an [`Address`] from Esplora, including the unconfirmed ones.
+pub(crate) async fn get_utxos(
     client: &AsyncClient<DefaultSleeper>,
     address: &Address,
-) -> Result<Txid, Error> {
-    let txs = client.get_address_txs(address, None).await?;
-    if txs.len() > 1 {
-        return Err(Error::ExpectedOneFundingTransaction);
+) -> Result<Vec<Utxo>, Error> {
+    let txs = get_address_history(client, address).await?;
+
+    Ok(address_utxos(&txs, &address.script_pubkey()))
+}
+
+/// Finds the outputs of the `txs` paying to `script_pubkey` that no input of the `txs` spends.
+///
+/// Any transaction spending an output of an address is in the history of that address,
+/// hence the history of the address is enough to tell the unspent outputs.
+pub(crate) fn address_utxos(txs: &[Tx], script_pubkey: &Script) -> Vec<Utxo> {
+    let spent = txs
+        .iter()
+        .flat_map(|tx| &tx.vin)
+        .map(|vin| OutPoint {
+            txid: vin.txid,
+            vout: vin.vout,
+        })
+        .collect::<HashSet<_>>();
+
+    txs.iter()
+        .flat_map(|tx| {
+            tx.vout
+                .iter()
+                .enumerate()
+                .filter(|(_, vout)| vout.scriptpubkey.as_script() == script_pubkey)
+                .map(|(vout, output)| Utxo {
+                    outpoint: OutPoint {
+                        txid: tx.txid,
+                        vout: vout as u32,
+                    },
+                    value: Amount::from_sat(output.value),
+                    confirmed: tx.status.confirmed,
+                })
+        })
+        .filter(|utxo| !spent.contains(&utxo.outpoint))
+        .collect()
+}
+
+/// Gets the transactions whose outputs a [`Transaction`] spends from Esplora, including the unconfirmed ones.
+pub(crate) async fn get_funding_transactions(
+    client: &AsyncClient<DefaultSleeper>,
+    transaction: &Transaction,
+) -> Result<Vec<Transaction>, Error> {
+    let mut seen = HashSet::new();
+    let txids = transaction
+        .input
+        .iter()
+        .map(|input| input.previous_output.txid)
+        .filter(|txid| seen.insert(*txid))
+        .collect::<Vec<_>>();
+    let mut txs = Vec::with_capacity(txids.len());
+    for txid in txids {
+        let tx = client
+            .get_tx(&txid)
+            .await?
+            .ok_or_else(|| Error::WrongInputs(format!("Funding transaction {txid} not found")))?;
+        txs.push(tx);
     }
-    let funding_txid = txs
-        .first()
-        .expect("safe to unwrap since we've checked the length")
-        .txid;
 
-    Ok(funding_txid)
+    Ok(txs)
 }
 
 /// Broadcast [`Transaction`].
@@ -66,7 +237,8 @@ pub(crate) async fn broadcast_transaction(
 mod tests {
     use std::sync::LazyLock;
 
-    use bitcoin::Network;
+    use bitcoin::{Network, ScriptBuf, Txid, hashes::Hash};
+    use esplora_client::{PrevOut, TxStatus, Vin, Vout};
 
     use super::*;
 
@@ -79,6 +251,53 @@ mod tests {
             .unwrap()
     });
 
+    /// A transaction `id` spending the `vin` of the address, with the `vout` values to the scripts.
+    fn tx(id: u8, vin: Vec<OutPoint>, vout: Vec<(&ScriptBuf, u64)>, confirmed: bool) -> Tx {
+        Tx {
+            txid: Txid::from_byte_array([id; 32]),
+            version: 2,
+            locktime: 0,
+            vin: vin
+                .into_iter()
+                .map(|outpoint| Vin {
+                    txid: outpoint.txid,
+                    vout: outpoint.vout,
+                    prevout: Some(PrevOut {
+                        value: 0,
+                        scriptpubkey: TESTNET4_ADDRESS.script_pubkey(),
+                    }),
+                    scriptsig: ScriptBuf::new(),
+                    witness: vec![],
+                    sequence: u32::MAX,
+                    is_coinbase: false,
+                })
+                .collect(),
+            vout: vout
+                .into_iter()
+                .map(|(scriptpubkey, value)| Vout {
+                    value,
+                    scriptpubkey: scriptpubkey.clone(),
+                })
+                .collect(),
+            size: 0,
+            weight: 0,
+            status: TxStatus {
+                confirmed,
+                block_height: None,
+                block_hash: None,
+                block_time: None,
+            },
+            fee: 0,
+        }
+    }
+
+    fn outpoint(id: u8, vout: u32) -> OutPoint {
+        OutPoint {
+            txid: Txid::from_byte_array([id; 32]),
+            vout,
+        }
+    }
+
     #[tokio::test]
     async fn get_fee_works() {
         let client = create_client(TESTNET4_URL).unwrap();
@@ -94,12 +313,102 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn get_funding_txid_works() {
+    async fn get_funding_outputs_works() {
         let client = create_client(TESTNET4_URL).unwrap();
-        let txid = get_funding_txid(&client, &TESTNET4_ADDRESS).await.unwrap();
+        let funding = get_funding_outputs(&client, &TESTNET4_ADDRESS)
+            .await
+            .unwrap();
         let expected = "bf8053a5db5b9d64b9ae49569ddd84c476f711e2971ed519eea777525acc8f09"
             .parse::<Txid>()
             .unwrap();
-        assert_eq!(txid, expected);
+        assert!(
+            funding
+                .utxos
+                .iter()
+                .any(|utxo| utxo.outpoint.txid == expected)
+        );
+    }
+
+    #[test]
+    fn address_utxos_works() {
+        let script_pubkey = TESTNET4_ADDRESS.script_pubkey();
+        let other = ScriptBuf::new_op_return([]);
+
+        // Two payments, the first one spent, and an unconfirmed change back to the address.
+        let txs = [
+            tx(
+                3,
+                vec![outpoint(1, 0)],
+                vec![(&other, 1_000), (&script_pubkey, 500)],
+                false,
+            ),
+            tx(2, vec![], vec![(&script_pubkey, 20_000)], true),
+            tx(1, vec![], vec![(&script_pubkey, 10_000), (&other, 1)], true),
+        ];
+        let utxos = address_utxos(&txs, &script_pubkey);
+        assert_eq!(
+            utxos,
+            vec![
+                Utxo {
+                    outpoint: outpoint(3, 1),
+                    value: Amount::from_sat(500),
+                    confirmed: false,
+                },
+                Utxo {
+                    outpoint: outpoint(2, 0),
+                    value: Amount::from_sat(20_000),
+                    confirmed: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn funding_outputs_works() {
+        let script_pubkey = TESTNET4_ADDRESS.script_pubkey();
+        let other = ScriptBuf::new_op_return([]);
+
+        // A virgin address.
+        let funding = funding_outputs(&[], &script_pubkey);
+        assert!(funding.utxos.is_empty());
+        assert_eq!(funding.warnings, vec![FundingWarning::NotFunded]);
+
+        // A single confirmed deposit at any output index.
+        let deposit = tx(1, vec![], vec![(&other, 1), (&script_pubkey, 10_000)], true);
+        let funding = funding_outputs(std::slice::from_ref(&deposit), &script_pubkey);
+        assert_eq!(
+            funding.utxos,
+            vec![Utxo {
+                outpoint: outpoint(1, 1),
+                value: Amount::from_sat(10_000),
+                confirmed: true,
+            }]
+        );
+        assert!(funding.warnings.is_empty());
+
+        // A second deposit only in the mempool.
+        let txs = [
+            tx(2, vec![], vec![(&script_pubkey, 20_000)], false),
+            deposit.clone(),
+        ];
+        let funding = funding_outputs(&txs, &script_pubkey);
+        assert_eq!(funding.utxos.len(), 2);
+        assert_eq!(
+            funding.warnings,
+            vec![
+                FundingWarning::SeveralTransactions { transactions: 2 },
+                FundingWarning::Unconfirmed { outputs: 1 },
+            ]
+        );
+
+        // A reused address, the first deposit already spent.
+        let txs = [
+            tx(3, vec![], vec![(&script_pubkey, 30_000)], true),
+            tx(2, vec![outpoint(1, 1)], vec![(&other, 9_000)], true),
+            deposit,
+        ];
+        let funding = funding_outputs(&txs, &script_pubkey);
+        assert_eq!(funding.utxos.len(), 1);
+        assert_eq!(funding.warnings, vec![FundingWarning::Reused { spent: 1 }]);
     }
 }
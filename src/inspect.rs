@@ -0,0 +1,343 @@
+//! Decodes escrow transactions and PSBTs.
+
+use std::collections::BTreeMap;
+
+use bitcoin::{
+    Address, Amount, Network, OutPoint, Sequence, Transaction, TxOut, Txid, Weight, absolute,
+    hex::DisplayHex, relative, taproot::ControlBlock, transaction,
+};
+#[cfg(debug_assertions)]
+use dioxus::logger::tracing::trace;
+use nostr::key::PublicKey as NostrPublicKey;
+
+use crate::{
+    error::Error,
+    scripts::{EscrowScript, Timelock, leaf_escrow_script, leaf_timelock},
+    tx::OutputLabel,
+    util::{npub_to_address, npub_to_x_only_public_key},
+};
+
+/// A Tapscript leaf spent by a script path input, decoded from its witness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LeafSpend {
+    /// The leaf script in ASM.
+    pub(crate) script_asm: String,
+    /// The control block proving that the leaf is in the Taproot tree.
+    pub(crate) control_block: ControlBlock,
+    /// The timelock that the leaf checks, if any.
+    pub(crate) timelock: Option<Timelock>,
+    /// The escrow leaf, if it can be told, see [`leaf_escrow_script`].
+    pub(crate) escrow_script: Option<EscrowScript>,
+}
+
+/// How an input spends its prevout, decoded from its witness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InputSpend {
+    /// The input is not signed yet.
+    Unsigned,
+    /// A Taproot key path spend, e.g. a MuSig2 cooperative resolution.
+    KeyPath,
+    /// A Taproot script path spend of an escrow leaf.
+    ScriptPath(LeafSpend),
+    /// Any other witness.
+    Other,
+}
+
+/// An input of an [`Inspection`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InspectedInput {
+    /// The output that the input spends.
+    pub(crate) previous_output: OutPoint,
+    /// The `nSequence` of the input.
+    pub(crate) sequence: Sequence,
+    /// The BIP68 relative timelock that the `nSequence` enables, if any,
+    /// `None` if the transaction version is below 2.
+    pub(crate) relative_lock_time: Option<relative::LockTime>,
+    /// The output that the input spends, if known.
+    pub(crate) prevout: Option<TxOut>,
+    /// The elements of the witness in hex.
+    pub(crate) witness: Vec<String>,
+    /// How the input spends its prevout.
+    pub(crate) spend: InputSpend,
+}
+
+/// An output of an [`Inspection`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InspectedOutput {
+    /// The address of the output, if its script is a standard one.
+    pub(crate) address: Option<Address>,
+    /// The value of the output.
+    pub(crate) value: Amount,
+    /// Whose npub-derived address the output pays, if any.
+    pub(crate) label: OutputLabel,
+}
+
+/// A decoded [`Transaction`], see [`inspect_transaction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Inspection {
+    /// The txid of the transaction.
+    pub(crate) txid: Txid,
+    /// The version of the transaction, BIP68 relative timelocks need version 2.
+    pub(crate) version: transaction::Version,
+    /// The absolute lock time of the transaction.
+    pub(crate) lock_time: absolute::LockTime,
+    /// The weight of the transaction with its current witnesses.
+    pub(crate) weight: Weight,
+    /// Every input, in order.
+    pub(crate) inputs: Vec<InspectedInput>,
+    /// Every output, in order.
+    pub(crate) outputs: Vec<InspectedOutput>,
+    /// The fee, if the prevout of every input is known.
+    pub(crate) fee: Option<Amount>,
+}
+
+/// Decodes a [`Transaction`] spending an escrow.
+///
+/// The prevouts of the inputs are looked up in the `spent_outputs`, and the outputs are matched
+/// to the key path addresses of the `parties` and `arbitrators` npubs.
+/// The leaf of every script path input is decoded from its witness,
+/// and the [`EscrowScript`] is told from the keys of the `parties`.
+///
+/// # Errors
+///
+/// Errors if an npub is not a valid key.
+pub(crate) fn inspect_transaction(
+    tx: &Transaction,
+    spent_outputs: &BTreeMap<OutPoint, TxOut>,
+    parties: &[NostrPublicKey],
+    arbitrators: &[NostrPublicKey],
+    network: Network,
+) -> Result<Inspection, Error> {
+    let party_keys = parties
+        .iter()
+        .map(npub_to_x_only_public_key)
+        .collect::<Result<Vec<_>, _>>()?;
+    let party_addresses = parties
+        .iter()
+        .map(|npub| npub_to_address(npub, network))
+        .collect::<Result<Vec<_>, _>>()?;
+    let arbitrator_addresses = arbitrators
+        .iter()
+        .map(|npub| npub_to_address(npub, network))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let inputs = tx
+        .input
+        .iter()
+        .map(|input| {
+            let spend = if input.witness.is_empty() {
+                InputSpend::Unsigned
+            } else if let (Some(leaf_script), Some(control_block)) = (
+                input.witness.taproot_leaf_script(),
+                input
+                    .witness
+                    .taproot_control_block()
+                    .and_then(|control_block| ControlBlock::decode(control_block).ok()),
+            ) {
+                let script = leaf_script.script;
+                InputSpend::ScriptPath(LeafSpend {
+                    script_asm: script.to_asm_string(),
+                    control_block,
+                    timelock: leaf_timelock(script),
+                    escrow_script: leaf_escrow_script(script, &party_keys),
+                })
+            } else if input.witness.len() == 1 {
+                InputSpend::KeyPath
+            } else {
+                InputSpend::Other
+            };
+            InspectedInput {
+                previous_output: input.previous_output,
+                sequence: input.sequence,
+                // BIP68 only applies from version 2.
+                relative_lock_time: if tx.version >= transaction::Version::TWO {
+                    input.sequence.to_relative_lock_time()
+                } else {
+                    None
+                },
+                prevout: spent_outputs.get(&input.previous_output).cloned(),
+                witness: input
+                    .witness
+                    .iter()
+                    .map(|element| element.to_lower_hex_string())
+                    .collect(),
+                spend,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let outputs = tx
+        .output
+        .iter()
+        .map(|output| {
+            let is_paid = |address: &Address| address.script_pubkey() == output.script_pubkey;
+            let label = if let Some(index) = party_addresses.iter().position(is_paid) {
+                OutputLabel::Party(index)
+            } else if let Some(index) = arbitrator_addresses.iter().position(is_paid) {
+                OutputLabel::Arbitrator(index)
+            } else {
+                OutputLabel::Unknown
+            };
+            InspectedOutput {
+                address: Address::from_script(&output.script_pubkey, network).ok(),
+                value: output.value,
+                label,
+            }
+        })
+        .collect();
+
+    let input_value = inputs
+        .iter()
+        .map(|input| input.prevout.as_ref().map(|prevout| prevout.value))
+        .sum::<Option<Amount>>();
+    let output_value = tx.output.iter().map(|output| output.value).sum::<Amount>();
+    let fee = input_value.and_then(|input_value| input_value.checked_sub(output_value));
+    #[cfg(debug_assertions)]
+    trace!(txid = %tx.compute_txid(), ?fee, "inspected transaction");
+
+    Ok(Inspection {
+        txid: tx.compute_txid(),
+        version: tx.version,
+        lock_time: tx.lock_time,
+        weight: tx.weight(),
+        inputs,
+        outputs,
+        fee,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{Txid, consensus};
+    use nostr::key::SecretKey as NostrSecretKey;
+    use secp256k1::SECP256K1;
+
+    use crate::{
+        scripts::{EscrowContract, InternalKey, contract_scripts},
+        sign::{finalize_escrow_psbt, sign_escrow_psbt},
+        tx::{DustPolicy, FeePolicy, Utxo, contract_psbt},
+        util::parse_transaction_or_psbt,
+    };
+
+    use super::*;
+
+    fn generate_nostr_keys() -> (NostrSecretKey, NostrPublicKey) {
+        let nsec = NostrSecretKey::generate();
+        let npub = nsec.public_key(SECP256K1).x_only_public_key().0.into();
+        (nsec, npub)
+    }
+
+    #[test]
+    fn inspect_dispute_transaction() {
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (_, npub_2) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let lock_time = relative::LockTime::from_height(6);
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(lock_time)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let funding_txid: Txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+        let fee = Amount::from_sat(1_000);
+        let psbt = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[Amount::from_sat(50_000); 2],
+            &[Utxo {
+                outpoint: OutPoint {
+                    txid: funding_txid,
+                    vout: 0,
+                },
+                value: Amount::from_sat(100_000),
+                confirmed: true,
+            }],
+            fee,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+
+        // Unsigned, the PSBT still knows its prevouts.
+        let (unsigned, spent_outputs) = parse_transaction_or_psbt(&psbt.to_string()).unwrap();
+        let inspection = inspect_transaction(&unsigned, &spent_outputs, &[], &[], network).unwrap();
+        assert_eq!(inspection.fee, Some(fee));
+        assert_eq!(inspection.inputs[0].spend, InputSpend::Unsigned);
+        assert_eq!(inspection.inputs[0].relative_lock_time, Some(lock_time));
+        assert!(
+            inspection
+                .outputs
+                .iter()
+                .all(|output| output.label == OutputLabel::Unknown)
+        );
+        // A version 1 transaction does not enable the relative timelock of its sequence.
+        let mut version_1 = unsigned.clone();
+        version_1.version = transaction::Version::ONE;
+        let inspection =
+            inspect_transaction(&version_1, &spent_outputs, &[], &[], network).unwrap();
+        assert_eq!(inspection.inputs[0].sequence, unsigned.input[0].sequence);
+        assert_eq!(inspection.inputs[0].relative_lock_time, None);
+
+        let mut psbt_1 = psbt.clone();
+        sign_escrow_psbt(&mut psbt_1, &nsec_1, &contract, network).unwrap();
+        let mut psbt_arb = psbt.clone();
+        sign_escrow_psbt(&mut psbt_arb, &nsec_arb, &contract, network).unwrap();
+        psbt_1.combine(psbt_arb).unwrap();
+        let signed = finalize_escrow_psbt(psbt_1).unwrap();
+
+        // The raw transaction does not know its prevouts.
+        let (signed, no_spent_outputs) =
+            parse_transaction_or_psbt(&consensus::encode::serialize_hex(&signed)).unwrap();
+        assert!(no_spent_outputs.is_empty());
+        let inspection = inspect_transaction(
+            &signed,
+            &no_spent_outputs,
+            &[npub_1, npub_2],
+            &[npub_arb],
+            network,
+        )
+        .unwrap();
+        assert_eq!(inspection.fee, None);
+        let InputSpend::ScriptPath(leaf) = &inspection.inputs[0].spend else {
+            panic!("not a script path spend: {:?}", inspection.inputs[0].spend);
+        };
+        assert_eq!(leaf.escrow_script, Some(EscrowScript::B));
+        assert_eq!(leaf.timelock, Some(Timelock::Relative(lock_time)));
+        assert_eq!(
+            leaf.script_asm,
+            contract_scripts(&contract, EscrowScript::B)
+                .unwrap()
+                .to_asm_string()
+        );
+        assert_eq!(inspection.inputs[0].witness.len(), 4);
+        let mut labels = inspection
+            .outputs
+            .iter()
+            .map(|output| output.label)
+            .collect::<Vec<_>>();
+        labels.sort_by_key(ToString::to_string);
+        assert_eq!(labels, [OutputLabel::Party(0), OutputLabel::Party(1)]);
+
+        // The funding transaction gives the fee of a raw transaction.
+        let spent_outputs = BTreeMap::from([(
+            signed.input[0].previous_output,
+            psbt.inputs[0].witness_utxo.clone().unwrap(),
+        )]);
+        let inspection = inspect_transaction(&signed, &spent_outputs, &[], &[], network).unwrap();
+        assert_eq!(inspection.fee, Some(fee));
+        let InputSpend::ScriptPath(leaf) = &inspection.inputs[0].spend else {
+            panic!("not a script path spend");
+        };
+        // Without the parties, leaves B and C look alike.
+        assert_eq!(leaf.escrow_script, None);
+    }
+}
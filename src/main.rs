@@ -13,13 +13,17 @@ use dioxus::logger::{
 pub(crate) mod components;
 pub(crate) mod error;
 pub(crate) mod esplora;
+pub(crate) mod inspect;
 pub(crate) mod scripts;
 pub(crate) mod sign;
 pub(crate) mod tx;
 pub(crate) mod util;
 pub(crate) mod validation;
 
-use components::{Broadcast, Combine, Create, Home, Navbar, Settings, Sign, Spend};
+use components::{
+    Arbitrate, Broadcast, Combine, Create, Fund, Home, Inspect, Navbar, Settings, Sign, Spend,
+    Verify,
+};
 
 #[derive(Debug, Clone, Routable, PartialEq)]
 #[rustfmt::skip]
@@ -29,14 +33,22 @@ enum Route {
         Home {},
         #[route("/create")]
         Create {},
+        #[route("/fund")]
+        Fund {},
         #[route("/sign")]
         Sign {},
         #[route("/combine")]
         Combine {},
+        #[route("/arbitrate")]
+        Arbitrate {},
         #[route("/broadcast")]
         Broadcast {},
         #[route("/spend")]
         Spend {},
+        #[route("/inspect")]
+        Inspect {},
+        #[route("/verify")]
+        Verify {},
         #[route("/settings")]
         Settings {},
 }
@@ -1,19 +1,26 @@
 //! Creates Tapscripts using Nostr keys.
 
-use std::sync::LazyLock;
+use std::{collections::BTreeSet, sync::LazyLock};
 
 use bitcoin::{
-    Address, Network, ScriptBuf, Sequence, XOnlyPublicKey,
+    Address, Network, Script, ScriptBuf, XOnlyPublicKey, absolute,
     hashes::{Hash, sha256},
-    opcodes::all::*,
-    taproot::{LeafVersion, TaprootBuilder, TaprootBuilderError, TaprootSpendInfo},
+    key::Parity,
+    opcodes::{Opcode, all::*},
+    relative,
+    script::{Builder, Instruction},
+    taproot::{LeafVersion, TapNodeHash, TaprootBuilder, TaprootBuilderError, TaprootSpendInfo},
 };
 #[cfg(debug_assertions)]
 use dioxus::logger::tracing::trace;
+use musig2::KeyAggContext;
 use nostr::key::PublicKey as NostrPublicKey;
-use secp256k1::SECP256K1;
+use secp256k1::{PublicKey, SECP256K1};
 
-use crate::{error::Error, util::npub_to_x_only_public_key};
+use crate::{
+    error::Error,
+    util::{npub_to_x_only_public_key, resolution_address},
+};
 
 /// A verifiably unspendable public key, produced by hashing a fixed string to a curve group
 /// generator.
@@ -31,138 +38,12 @@ pub(crate) static UNSPENDABLE_PUBLIC_KEY: LazyLock<XOnlyPublicKey> = LazyLock::n
         .expect("valid xonly public key")
 });
 
-/// Creates an escrow-resolution 2-of-3 multisig P2TR [`TaprootSpendInfo`] from 2 [`NostrPublicKey`]s,
-/// an optional arbitrator [`NostrPublicKey`] and an optional timelock duration in blocks.
-///
-/// # Spending Conditions
-///
-/// - 2-of-2 multisig between the two parties without timelocks.
-/// - 2-of-3 multisig between the one of the parties and the arbitrator with a timelock
-///   (if using an arbitrator).
-///
-/// # Merkle Tree Layout
-///
-/// 1. `A`: 2-of-2 multisig between the two parties without timelocks.
-/// 2. `B`: 2-of-3 multisig between the first of the parties and the arbitrator with a timelock
-///    (if using an arbitrator).
-/// 3. `C`: 2-of-3 multisig between the second of the parties and the arbitrator with a timelock
-///    (if using an arbitrator).
-///
-/// `A` is at depth 1, and `B` and `C` are at depth 2.
-///
-/// ```text
-///     root
-///        \
-///        /\
-///       /  \
-///      A    *
-///          / \
-///         /   \
-///        B     C
-/// ```
-pub(crate) fn escrow_spend_info(
-    npub_1: &NostrPublicKey,
-    npub_2: &NostrPublicKey,
-    npub_arbitrator: Option<&NostrPublicKey>,
-    timelock_duration: Option<u32>,
-) -> Result<TaprootSpendInfo, Error> {
-    // Collaborative Path
-    if npub_arbitrator.is_none() && timelock_duration.is_none() {
-        #[cfg(debug_assertions)]
-        trace!("Collaborative path");
-
-        let script_1 = escrow_scripts(
-            npub_1,
-            npub_2,
-            npub_arbitrator,
-            timelock_duration,
-            EscrowScript::A,
-        )?;
-
-        TaprootBuilder::new()
-            .add_leaf_with_ver(0, script_1, LeafVersion::TapScript)?
-            .finalize(SECP256K1, *UNSPENDABLE_PUBLIC_KEY)
-            // FIXME(@storopoli): better error here.
-            .map_err(|_| Error::TaprootBuilder(TaprootBuilderError::EmptyTree))
-    }
-    // Arbitrator path.
-    else if npub_arbitrator.is_some() && timelock_duration.is_some() {
-        #[cfg(debug_assertions)]
-        trace!(npub_arbitrator = %npub_arbitrator.unwrap().to_hex(), timelock_duration = %timelock_duration.unwrap(), "Arbitrator path");
-
-        let script_1 = escrow_scripts(
-            npub_1,
-            npub_2,
-            npub_arbitrator,
-            timelock_duration,
-            EscrowScript::A,
-        )?;
-
-        let script_2 = escrow_scripts(
-            npub_1,
-            npub_2,
-            npub_arbitrator,
-            timelock_duration,
-            EscrowScript::B,
-        )?;
-        let script_3 = escrow_scripts(
-            npub_1,
-            npub_2,
-            npub_arbitrator,
-            timelock_duration,
-            EscrowScript::C,
-        )?;
-
-        TaprootBuilder::new()
-            .add_leaf_with_ver(1, script_1, LeafVersion::TapScript)?
-            .add_leaf_with_ver(2, script_2, LeafVersion::TapScript)?
-            .add_leaf_with_ver(2, script_3, LeafVersion::TapScript)?
-            .finalize(SECP256K1, *UNSPENDABLE_PUBLIC_KEY)
-            // FIXME(@storopoli): better error here.
-            .map_err(|_| Error::TaprootBuilder(TaprootBuilderError::EmptyTree))
-    }
-    // If the match arm failed, means that the inputs to this functions are wrong.
-    else {
-        Err(Error::WrongInputs(format!(
-            "Wrong inputs. Either pass npub_arbitrator and timelock_duration as Some or None. Got npub_arbitrator: {npub_arbitrator:?}. Got timelock_duration: {timelock_duration:?}"
-        )))
-    }
-}
-
-/// Creates an escrow-resolution 2-of-3 multisig P2TR [`TaprootSpendInfo`] from 2 [`NostrPublicKey`]s,
-/// an optional arbitrator [`NostrPublicKey`] and an optional timelock duration in blocks.
-///
-/// # Spending Conditions
-///
-/// - 2-of-2 multisig between the two parties without timelocks.
-/// - 2-of-3 multisig between the one of the parties and the arbitrator with a timelock
-///   (if using an arbitrator).
-///
-/// # Merkle Tree Layout
-///
-/// 1. `A`: 2-of-2 multisig between the two parties without timelocks.
-/// 2. `B`: 2-of-3 multisig between the first of the parties and the arbitrator with a timelock
-///    (if using an arbitrator).
-/// 3. `C`: 2-of-3 multisig between the second of the parties and the arbitrator with a timelock
-///    (if using an arbitrator).
-///
-/// `A` is at depth 1, and `B` and `C` are at depth 2.
-///
-/// ```text
-///     root
-///        \
-///        /\
-///       /  \
-///      A    *
-///          / \
-///         /   \
-///        B     C
-/// ```
-pub(crate) fn escrow_scripts(
+/// The `A`, `B` and `C` leaves of a two-party [`EscrowContract`] with any dispute [`Timelock`].
+fn two_party_scripts(
     npub_1: &NostrPublicKey,
     npub_2: &NostrPublicKey,
     npub_arbitrator: Option<&NostrPublicKey>,
-    timelock_duration: Option<u32>,
+    timelock: Option<Timelock>,
     escrow_script: EscrowScript,
 ) -> Result<ScriptBuf, Error> {
     // Parse npubs to bitcoin public keys.
@@ -178,36 +59,26 @@ pub(crate) fn escrow_scripts(
             .push_x_only_key(&pk_1)
             .push_opcode(OP_CHECKSIG)
             .into_script()),
-        EscrowScript::B => {
-            let npub_arbitrator = npub_arbitrator.unwrap();
+        EscrowScript::B | EscrowScript::C => {
+            // The dispute leaves only exist with an arbitrator and a timelock.
+            let (Some(npub_arbitrator), Some(timelock)) = (npub_arbitrator, timelock) else {
+                return Err(Error::InvalidEscrowType(format!("{escrow_script:?}")));
+            };
             let pk_arbitrator = npub_to_x_only_public_key(npub_arbitrator)?;
-            // Timelock.
-            let sequence = Sequence::from_consensus(timelock_duration.unwrap());
-            Ok(ScriptBuf::builder()
-                .push_sequence(sequence)
-                .push_opcode(OP_CSV)
-                .push_opcode(OP_DROP)
+            let pk_party = if escrow_script == EscrowScript::B {
+                pk_1
+            } else {
+                pk_2
+            };
+            Ok(push_timelock(ScriptBuf::builder(), timelock)
                 .push_x_only_key(&pk_arbitrator)
                 .push_opcode(OP_CHECKSIGVERIFY)
-                .push_x_only_key(&pk_1)
-                .push_opcode(OP_CHECKSIG)
-                .into_script())
-        }
-        EscrowScript::C => {
-            let npub_arbitrator = npub_arbitrator.unwrap();
-            let pk_arbitrator = npub_to_x_only_public_key(npub_arbitrator)?;
-            // Timelock.
-            let sequence = Sequence::from_consensus(timelock_duration.unwrap());
-            Ok(ScriptBuf::builder()
-                .push_sequence(sequence)
-                .push_opcode(OP_CSV)
-                .push_opcode(OP_DROP)
-                .push_x_only_key(&pk_arbitrator)
-                .push_opcode(OP_CHECKSIGVERIFY)
-                .push_x_only_key(&pk_2)
+                .push_x_only_key(&pk_party)
                 .push_opcode(OP_CHECKSIG)
                 .into_script())
         }
+        // The refund leaf is only part of an `EscrowContract`.
+        EscrowScript::D => Err(Error::InvalidEscrowType(format!("{escrow_script:?}"))),
     }
 }
 
@@ -231,23 +102,87 @@ pub(crate) fn escrow_scripts(
 ///         /   \
 ///        B     C
 /// ```
+///
+/// 4. `D`: the refund party alone with a longer timelock (if using a refund).
+///
+/// See [`EscrowContract`] for the layout with the refund leaf.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum EscrowScript {
     A,
     B,
     C,
+    D,
+}
+
+/// The internal key of the escrow Taproot output.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+pub(crate) enum InternalKey {
+    /// The [`UNSPENDABLE_PUBLIC_KEY`], every resolution is a script path spend.
+    #[default]
+    Unspendable,
+
+    /// The BIP327 MuSig2 aggregate of the two parties' keys,
+    /// the cooperative resolution is a key path spend.
+    MuSig2,
+}
+
+/// The timelock of the dispute leaves.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Timelock {
+    /// `OP_CHECKSEQUENCEVERIFY` BIP68 duration in blocks or 512-second intervals,
+    /// relative to the funding confirmation.
+    Relative(relative::LockTime),
+
+    /// `OP_CHECKLOCKTIMEVERIFY` block height or unix timestamp,
+    /// so that the dispute window does not slide if the funding confirms late.
+    Absolute(absolute::LockTime),
 }
 
-/// Creates an escrow-resolution 2-of-3 multisig P2TR [`Address`] from 2 [`NostrPublicKey`]s,
-/// an optional arbitrator [`NostrPublicKey`] and an optional timelock duration in blocks.
+/// Pushes the `timelock` check of a dispute leaf, leaving the stack untouched.
+fn push_timelock(builder: Builder, timelock: Timelock) -> Builder {
+    match timelock {
+        Timelock::Relative(lock_time) => builder
+            .push_sequence(lock_time.to_sequence())
+            .push_opcode(OP_CSV),
+        Timelock::Absolute(lock_time) => builder.push_lock_time(lock_time).push_opcode(OP_CLTV),
+    }
+    .push_opcode(OP_DROP)
+}
+
+/// Creates the BIP327 MuSig2 [`KeyAggContext`] of the two parties' [`NostrPublicKey`]s.
+///
+/// Nostr keys are x-only, hence they are lifted to the point with an even Y coordinate
+/// (BIP340) and aggregated in the order `npub_1`, `npub_2`.
+/// The context is untweaked, its aggregated public key is the escrow internal key.
+pub(crate) fn musig2_key_agg_ctx(
+    npub_1: &NostrPublicKey,
+    npub_2: &NostrPublicKey,
+) -> Result<KeyAggContext, Error> {
+    let pk_1 = npub_to_x_only_public_key(npub_1)?.public_key(Parity::Even);
+    let pk_2 = npub_to_x_only_public_key(npub_2)?.public_key(Parity::Even);
+
+    Ok(KeyAggContext::new([pk_1, pk_2])?)
+}
+
+/// A generalized N-party escrow contract.
 ///
 /// # Spending Conditions
 ///
-/// - 2-of-2 multisig between the two parties without timelocks.
-/// - 2-of-3 multisig between the one of the parties and the arbitrator with a timelock
-///   (if using an arbitrator).
+/// - `A`: `cooperative_threshold`-of-`parties` multisig without timelocks.
+/// - `B`: `arbitrator_threshold`-of-`arbitrators` multisig and `arbitrated_threshold`-of-`parties` multisig
+///   with a timelock (if using arbitrators).
+/// - `D`: the refund party alone with a longer timelock (if using a refund, see [`EscrowContract::with_refund`]).
+///
+/// The multisigs are BIP342 `multi_a` (`OP_CHECKSIGADD`) scripts.
+/// The dispute [`Timelock`] is either relative to the funding (`OP_CSV`) or absolute (`OP_CLTV`),
+/// the refund timelock is always relative.
+/// `A` and `B` are both at depth 1, or `A` is the root without arbitrators.
+/// `D` is the least likely resolution, hence it is the sibling of the deepest leaf.
 ///
-/// # Merkle Tree Layout
+/// # Two-Party Escrow
+///
+/// A two-party contract with a cooperative threshold of 2, a single arbitrator and an arbitrated threshold of 1
+/// is the 2-of-3 escrow:
 ///
 /// 1. `A`: 2-of-2 multisig between the two parties without timelocks.
 /// 2. `B`: 2-of-3 multisig between the first of the parties and the arbitrator with a timelock
@@ -267,14 +202,446 @@ pub(crate) enum EscrowScript {
 ///         /   \
 ///        B     C
 /// ```
-pub(crate) fn escrow_address(
-    npub_1: &NostrPublicKey,
-    npub_2: &NostrPublicKey,
-    npub_arbitrator: Option<&NostrPublicKey>,
-    timelock_duration: Option<u32>,
+///
+/// With [`InternalKey::MuSig2`] the internal key is the BIP327 MuSig2 aggregate of the two parties,
+/// the cooperative resolution is a key path spend and `A` is left out of the tree:
+/// the collaborative escrow has no leaves, and `B` and `C` are both at depth 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EscrowContract {
+    parties: Vec<NostrPublicKey>,
+    cooperative_threshold: usize,
+    arbitrators: Vec<NostrPublicKey>,
+    arbitrator_threshold: usize,
+    arbitrated_threshold: usize,
+    timelock: Option<Timelock>,
+    internal_key: InternalKey,
+    refund: Option<(NostrPublicKey, relative::LockTime)>,
+    payout_addresses: Vec<Option<Address>>,
+}
+
+impl EscrowContract {
+    /// Creates a new [`EscrowContract`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if there are less than two `parties`, if a key is repeated among the `parties` and `arbitrators`,
+    /// if a threshold is not between 1 and the number of keys it applies to,
+    /// if only one of `arbitrators` and `timelock` is passed,
+    /// or if a [`InternalKey::MuSig2`] internal key is used with anything but the two-party escrow.
+    pub(crate) fn new(
+        parties: Vec<NostrPublicKey>,
+        cooperative_threshold: usize,
+        arbitrators: Vec<NostrPublicKey>,
+        arbitrator_threshold: usize,
+        arbitrated_threshold: usize,
+        timelock: Option<Timelock>,
+        internal_key: InternalKey,
+    ) -> Result<Self, Error> {
+        let contract = Self {
+            parties,
+            cooperative_threshold,
+            arbitrators,
+            arbitrator_threshold,
+            arbitrated_threshold,
+            timelock,
+            internal_key,
+            refund: None,
+            payout_addresses: Vec::new(),
+        };
+
+        let parties = contract.parties.len();
+        if parties < 2 {
+            return Err(Error::WrongInputs(format!(
+                "An escrow needs at least two parties. Got {parties}"
+            )));
+        }
+        // A repeated key would let one signer satisfy several keys of a `multi_a` threshold.
+        let mut keys = BTreeSet::new();
+        for npub in contract.parties.iter().chain(&contract.arbitrators) {
+            if !keys.insert(npub_to_x_only_public_key(npub)?) {
+                return Err(Error::WrongInputs(format!(
+                    "Every party and arbitrator should have its own key. Got {npub} twice"
+                )));
+            }
+        }
+        if !(1..=parties).contains(&contract.cooperative_threshold) {
+            return Err(Error::WrongInputs(format!(
+                "Cooperative threshold should be between 1 and {parties}. Got {}",
+                contract.cooperative_threshold
+            )));
+        }
+        if contract.arbitrators.is_empty() == contract.timelock.is_some() {
+            return Err(Error::WrongInputs(format!(
+                "Wrong inputs. Either pass arbitrators and timelock or none of them. Got arbitrators: {:?}. Got timelock: {:?}",
+                contract.arbitrators, contract.timelock
+            )));
+        }
+        let arbitrators = contract.arbitrators.len();
+        if arbitrators > 0 && !(1..=arbitrators).contains(&contract.arbitrator_threshold) {
+            return Err(Error::WrongInputs(format!(
+                "Arbitrator threshold should be between 1 and {arbitrators}. Got {}",
+                contract.arbitrator_threshold
+            )));
+        }
+        if arbitrators > 0 && !(1..=parties).contains(&contract.arbitrated_threshold) {
+            return Err(Error::WrongInputs(format!(
+                "Arbitrated threshold should be between 1 and {parties}. Got {}",
+                contract.arbitrated_threshold
+            )));
+        }
+        if contract.internal_key == InternalKey::MuSig2 && !contract.is_two_party() {
+            return Err(Error::WrongInputs(
+                "A MuSig2 internal key is only supported by the two-party escrow".to_string(),
+            ));
+        }
+
+        Ok(contract)
+    }
+
+    /// Adds a unilateral refund leaf `D` to the contract, spendable by `refund_party` alone
+    /// after the relative `refund_timelock`.
+    ///
+    /// It is the way out if the other parties never sign and the arbitrators vanish.
+    /// Like every leaf, it does not constrain the outputs: the pre-agreed split
+    /// is the refund transaction of [`refund_psbt`](crate::tx::refund_psbt) that the refund party signs.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `refund_party` is not a party of the contract,
+    /// if the arbitrated leaves timelock is absolute, or if the refund timelock is not longer than
+    /// the relative arbitrated leaves timelock or counts in another unit.
+    pub(crate) fn with_refund(
+        mut self,
+        refund_party: NostrPublicKey,
+        refund_timelock: relative::LockTime,
+    ) -> Result<Self, Error> {
+        if !self.parties.contains(&refund_party) {
+            return Err(Error::WrongInputs(format!(
+                "The refund party should be a party of the escrow. Got {refund_party:?}"
+            )));
+        }
+        // Both timelocks must start at the funding confirmation and count in the same unit to be compared.
+        match self.timelock {
+            None => {}
+            Some(Timelock::Absolute(timelock)) => {
+                return Err(Error::WrongInputs(format!(
+                    "The refund timelock is relative to the funding and cannot be compared to the absolute dispute timelock {timelock}"
+                )));
+            }
+            Some(Timelock::Relative(timelock)) if !timelock.is_same_unit(refund_timelock) => {
+                return Err(Error::WrongInputs(format!(
+                    "The refund timelock should count in the same unit as the dispute timelock {timelock}. Got {refund_timelock}"
+                )));
+            }
+            Some(Timelock::Relative(timelock)) if refund_timelock.is_implied_by(timelock) => {
+                return Err(Error::WrongInputs(format!(
+                    "The refund timelock should be longer than the dispute timelock {timelock}. Got {refund_timelock}"
+                )));
+            }
+            Some(Timelock::Relative(_)) => {}
+        }
+        self.refund = Some((refund_party, refund_timelock));
+
+        Ok(self)
+    }
+
+    /// Pays the parties to their optional `payout_addresses`, e.g. a cold wallet,
+    /// instead of the resolution addresses derived from their `npub`s.
+    ///
+    /// The payout addresses do not change the escrow address, only the outputs of its transactions.
+    ///
+    /// # Errors
+    ///
+    /// Errors if there is not one optional payout address per party.
+    pub(crate) fn with_payout_addresses(
+        mut self,
+        payout_addresses: Vec<Option<Address>>,
+    ) -> Result<Self, Error> {
+        if payout_addresses.len() != self.parties.len() {
+            return Err(Error::WrongInputs(format!(
+                "Expected one optional payout address per party. Got {} parties and {} payout addresses",
+                self.parties.len(),
+                payout_addresses.len()
+            )));
+        }
+        self.payout_addresses = payout_addresses;
+
+        Ok(self)
+    }
+
+    /// The [`resolution_address`] of the party at `index` on `network`.
+    ///
+    /// # Errors
+    ///
+    /// Errors if `index` is not a party, or if the payout address is not valid for `network`.
+    pub(crate) fn resolution_address(
+        &self,
+        index: usize,
+        network: Network,
+    ) -> Result<Address, Error> {
+        let Some(npub) = self.parties.get(index) else {
+            return Err(Error::WrongInputs(format!(
+                "Party {index} is not one of the {} parties",
+                self.parties.len()
+            )));
+        };
+        let payout_address = self.payout_addresses.get(index).and_then(Option::as_ref);
+
+        resolution_address(npub, payout_address, network)
+    }
+
+    /// The [`EscrowContract::resolution_address`] of every party, in order.
+    ///
+    /// # Errors
+    ///
+    /// Errors if a payout address is not valid for `network`.
+    pub(crate) fn resolution_addresses(&self, network: Network) -> Result<Vec<Address>, Error> {
+        (0..self.parties.len())
+            .map(|index| self.resolution_address(index, network))
+            .collect()
+    }
+
+    /// The parties of the contract.
+    pub(crate) fn parties(&self) -> &[NostrPublicKey] {
+        &self.parties
+    }
+
+    /// The arbitrators of the contract, empty without dispute resolution.
+    pub(crate) fn arbitrators(&self) -> &[NostrPublicKey] {
+        &self.arbitrators
+    }
+
+    /// The timelock of the arbitrated leaves, if any.
+    pub(crate) fn timelock(&self) -> Option<Timelock> {
+        self.timelock
+    }
+
+    /// The refund party and the relative timelock of the refund leaf, if any.
+    pub(crate) fn refund(&self) -> Option<(&NostrPublicKey, relative::LockTime)> {
+        self.refund
+            .as_ref()
+            .map(|(refund_party, refund_timelock)| (refund_party, *refund_timelock))
+    }
+
+    /// The internal key of the contract Taproot output.
+    pub(crate) fn internal_key(&self) -> InternalKey {
+        self.internal_key
+    }
+
+    /// Whether the contract is the two-party escrow, see [`EscrowContract`].
+    pub(crate) fn is_two_party(&self) -> bool {
+        self.parties.len() == 2
+            && self.cooperative_threshold == 2
+            && (self.arbitrators.is_empty()
+                || (self.arbitrators.len() == 1 && self.arbitrated_threshold == 1))
+    }
+}
+
+#[cfg(test)]
+impl EscrowContract {
+    /// Creates the two-party escrow of `npub_1` and `npub_2`,
+    /// with an optional arbitrator and an optional relative dispute timelock.
+    pub(crate) fn two_party(
+        npub_1: &NostrPublicKey,
+        npub_2: &NostrPublicKey,
+        npub_arbitrator: Option<&NostrPublicKey>,
+        timelock_duration: Option<relative::LockTime>,
+        internal_key: InternalKey,
+    ) -> Result<Self, Error> {
+        Self::new(
+            vec![*npub_1, *npub_2],
+            2,
+            npub_arbitrator.into_iter().copied().collect(),
+            1,
+            1,
+            timelock_duration.map(Timelock::Relative),
+            internal_key,
+        )
+    }
+}
+
+/// Creates the Tapscript of an [`EscrowContract`] leaf.
+///
+/// Two-party contracts have the `A`, `B` and `C` leaves of the 2-of-3 escrow,
+/// N-party contracts have only `A` and `B`, and both can have the refund leaf `D`.
+///
+/// # Errors
+///
+/// Errors if the contract does not have the `escrow_script` leaf.
+pub(crate) fn contract_scripts(
+    contract: &EscrowContract,
+    escrow_script: EscrowScript,
+) -> Result<ScriptBuf, Error> {
+    if escrow_script == EscrowScript::D {
+        let Some((refund_party, refund_timelock)) = contract.refund() else {
+            return Err(Error::InvalidEscrowType(format!("{escrow_script:?}")));
+        };
+        let pk_refund = npub_to_x_only_public_key(refund_party)?;
+        return Ok(
+            push_timelock(ScriptBuf::builder(), Timelock::Relative(refund_timelock))
+                .push_x_only_key(&pk_refund)
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        );
+    }
+
+    if contract.is_two_party() {
+        return two_party_scripts(
+            &contract.parties[0],
+            &contract.parties[1],
+            contract.arbitrators.first(),
+            contract.timelock,
+            escrow_script,
+        );
+    }
+
+    let parties = contract
+        .parties
+        .iter()
+        .map(npub_to_x_only_public_key)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match escrow_script {
+        EscrowScript::A => Ok(push_multi_a(
+            Builder::new(),
+            contract.cooperative_threshold,
+            &parties,
+            OP_NUMEQUAL,
+        )
+        .into_script()),
+        EscrowScript::B if !contract.arbitrators.is_empty() => {
+            let arbitrators = contract
+                .arbitrators
+                .iter()
+                .map(npub_to_x_only_public_key)
+                .collect::<Result<Vec<_>, _>>()?;
+            let builder = push_timelock(Builder::new(), contract.timelock.unwrap());
+            // A single arbitrator is a plain signature check, as in the two-party escrow.
+            let builder = match arbitrators.as_slice() {
+                [pk_arbitrator] => builder
+                    .push_x_only_key(pk_arbitrator)
+                    .push_opcode(OP_CHECKSIGVERIFY),
+                arbitrators => push_multi_a(
+                    builder,
+                    contract.arbitrator_threshold,
+                    arbitrators,
+                    OP_NUMEQUALVERIFY,
+                ),
+            };
+            Ok(push_multi_a(
+                builder,
+                contract.arbitrated_threshold,
+                &parties,
+                OP_NUMEQUAL,
+            )
+            .into_script())
+        }
+        escrow_script => Err(Error::InvalidEscrowType(format!("{escrow_script:?}"))),
+    }
+}
+
+/// Pushes a BIP342 `multi_a` `threshold`-of-`keys` multisig to a script [`Builder`],
+/// ending with the `numequal` opcode, either `OP_NUMEQUAL` or `OP_NUMEQUALVERIFY`.
+fn push_multi_a(
+    builder: Builder,
+    threshold: usize,
+    keys: &[XOnlyPublicKey],
+    numequal: Opcode,
+) -> Builder {
+    let mut builder = builder;
+    for (i, key) in keys.iter().enumerate() {
+        builder = builder.push_x_only_key(key);
+        builder = if i == 0 {
+            builder.push_opcode(OP_CHECKSIG)
+        } else {
+            builder.push_opcode(OP_CHECKSIGADD)
+        };
+    }
+    builder.push_int(threshold as i64).push_opcode(numequal)
+}
+
+/// Creates the P2TR [`TaprootSpendInfo`] of an [`EscrowContract`].
+///
+/// See [`EscrowContract`] for the Merkle tree layout.
+pub(crate) fn contract_spend_info(contract: &EscrowContract) -> Result<TaprootSpendInfo, Error> {
+    let internal_key = match contract.internal_key {
+        InternalKey::Unspendable => *UNSPENDABLE_PUBLIC_KEY,
+        InternalKey::MuSig2 => {
+            let key_agg_ctx = musig2_key_agg_ctx(&contract.parties[0], &contract.parties[1])?;
+            let aggregated_pubkey: PublicKey = key_agg_ctx.aggregated_pubkey_untweaked();
+            aggregated_pubkey.x_only_public_key().0
+        }
+    };
+
+    let mut leaves = contract_leaves(contract)?;
+    if contract.refund.is_some() {
+        let script_refund = contract_scripts(contract, EscrowScript::D)?;
+        match leaves.pop() {
+            Some((depth, script)) => {
+                leaves.push((depth + 1, script));
+                leaves.push((depth + 1, script_refund));
+            }
+            None => leaves.push((0, script_refund)),
+        }
+    }
+    #[cfg(debug_assertions)]
+    trace!(parties = %contract.parties.len(), leaves = %leaves.len(), "contract leaves");
+
+    // MuSig2 collaborative escrow is key path only.
+    if leaves.is_empty() {
+        return Ok(TaprootSpendInfo::new_key_spend(
+            SECP256K1,
+            internal_key,
+            None,
+        ));
+    }
+
+    let mut builder = TaprootBuilder::new();
+    for (depth, script) in leaves {
+        builder = builder.add_leaf_with_ver(depth, script, LeafVersion::TapScript)?;
+    }
+    builder
+        .finalize(SECP256K1, internal_key)
+        // FIXME(@storopoli): better error here.
+        .map_err(|_| Error::TaprootBuilder(TaprootBuilderError::EmptyTree))
+}
+
+/// The leaves of an [`EscrowContract`] but the refund leaf, with their depths in depth-first order.
+///
+/// Two-party contracts have the layout of the two-party escrow, see [`EscrowContract`].
+fn contract_leaves(contract: &EscrowContract) -> Result<Vec<(u8, ScriptBuf)>, Error> {
+    let arbitrated = !contract.arbitrators.is_empty();
+    let leaves = if contract.is_two_party() {
+        match (contract.internal_key, arbitrated) {
+            (InternalKey::MuSig2, false) => vec![],
+            (InternalKey::MuSig2, true) => vec![(1, EscrowScript::B), (1, EscrowScript::C)],
+            (InternalKey::Unspendable, false) => vec![(0, EscrowScript::A)],
+            (InternalKey::Unspendable, true) => vec![
+                (1, EscrowScript::A),
+                (2, EscrowScript::B),
+                (2, EscrowScript::C),
+            ],
+        }
+    } else if arbitrated {
+        vec![(1, EscrowScript::A), (1, EscrowScript::B)]
+    } else {
+        vec![(0, EscrowScript::A)]
+    };
+
+    leaves
+        .into_iter()
+        .map(|(depth, escrow_script)| Ok((depth, contract_scripts(contract, escrow_script)?)))
+        .collect()
+}
+
+/// Creates the P2TR [`Address`] of an [`EscrowContract`].
+///
+/// See [`EscrowContract`] for the Merkle tree layout.
+pub(crate) fn contract_address(
+    contract: &EscrowContract,
     network: Network,
 ) -> Result<Address, Error> {
-    let taproot_spend_info = escrow_spend_info(npub_1, npub_2, npub_arbitrator, timelock_duration)?;
+    let taproot_spend_info = contract_spend_info(contract)?;
 
     let internal_key = taproot_spend_info.internal_key();
     let merkle_root = taproot_spend_info.merkle_root();
@@ -282,12 +649,226 @@ pub(crate) fn escrow_address(
     Ok(Address::p2tr(SECP256K1, internal_key, merkle_root, network))
 }
 
+/// A leaf of the Taproot tree of an escrow, see [`Taptree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TaptreeLeaf {
+    /// The spending condition of the leaf.
+    pub(crate) escrow_script: EscrowScript,
+    /// The depth of the leaf, i.e. the length of its Merkle branch.
+    pub(crate) depth: usize,
+    /// The leaf script.
+    pub(crate) script: ScriptBuf,
+}
+
+/// The Taproot tree committed to by the address of an [`EscrowContract`], see [`contract_taptree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Taptree {
+    /// The P2TR address of the escrow.
+    pub(crate) address: Address,
+    /// The internal key, either [`UNSPENDABLE_PUBLIC_KEY`] or the MuSig2 aggregate of the parties.
+    pub(crate) internal_key: XOnlyPublicKey,
+    /// The Merkle root of the leaves, none if the escrow is key path only.
+    pub(crate) merkle_root: Option<TapNodeHash>,
+    /// Every leaf, in [`EscrowScript`] order.
+    pub(crate) leaves: Vec<TaptreeLeaf>,
+}
+
+impl Taptree {
+    /// Whether `address` is the address of the escrow, i.e. it commits to the same
+    /// internal key and leaves.
+    pub(crate) fn commits_to(&self, address: &Address) -> bool {
+        self.address.script_pubkey() == address.script_pubkey()
+    }
+}
+
+/// Recomputes the [`Taptree`] of an [`EscrowContract`] from its parameters,
+/// so that a party can check a claimed escrow address before funding it.
+///
+/// See [`EscrowContract`] for the Merkle tree layout.
+pub(crate) fn contract_taptree(
+    contract: &EscrowContract,
+    network: Network,
+) -> Result<Taptree, Error> {
+    let taproot_spend_info = contract_spend_info(contract)?;
+    let script_map = taproot_spend_info.script_map();
+    let leaves = [
+        EscrowScript::A,
+        EscrowScript::B,
+        EscrowScript::C,
+        EscrowScript::D,
+    ]
+    .into_iter()
+    .filter_map(|escrow_script| {
+        let script = contract_scripts(contract, escrow_script).ok()?;
+        let depth = script_map
+            .get(&(script.clone(), LeafVersion::TapScript))?
+            .iter()
+            .map(|merkle_branch| merkle_branch.len())
+            .min()?;
+        Some(TaptreeLeaf {
+            escrow_script,
+            depth,
+            script,
+        })
+    })
+    .collect();
+
+    Ok(Taptree {
+        address: Address::p2tr_tweaked(taproot_spend_info.output_key(), network),
+        internal_key: taproot_spend_info.internal_key(),
+        merkle_root: taproot_spend_info.merkle_root(),
+        leaves,
+    })
+}
+
+/// A group of keys of a Tapscript leaf, `threshold` of which have to sign.
+///
+/// A key checked with `OP_CHECKSIGVERIFY` or a lone `OP_CHECKSIG` is a group of one key with a threshold of one,
+/// and a `multi_a` multisig is a group of its keys with its threshold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SignerGroup {
+    /// Keys of the group, in the order that they appear in the script.
+    pub(crate) keys: Vec<XOnlyPublicKey>,
+
+    /// Number of `keys` that have to sign.
+    pub(crate) threshold: usize,
+}
+
+/// Returns the [`SignerGroup`]s of a Tapscript leaf built by [`contract_scripts`].
+///
+/// Every group has to be satisfied to spend the leaf.
+pub(crate) fn leaf_signers(script: &Script) -> Vec<SignerGroup> {
+    let mut groups = Vec::new();
+    let mut group: Option<SignerGroup> = None;
+    let mut key = None;
+    let mut number = None;
+
+    for instruction in script.instructions().flatten() {
+        match instruction {
+            Instruction::PushBytes(bytes) if bytes.len() == 32 => {
+                key = XOnlyPublicKey::from_slice(bytes.as_bytes()).ok();
+            }
+            Instruction::Op(OP_CHECKSIGVERIFY) => groups.push(SignerGroup {
+                keys: key.take().into_iter().collect(),
+                threshold: 1,
+            }),
+            Instruction::Op(OP_CHECKSIG) => {
+                groups.extend(group.take());
+                group = Some(SignerGroup {
+                    keys: key.take().into_iter().collect(),
+                    threshold: 1,
+                });
+            }
+            Instruction::Op(OP_CHECKSIGADD) => {
+                if let Some(group) = group.as_mut() {
+                    group.keys.extend(key.take());
+                }
+            }
+            Instruction::Op(OP_NUMEQUAL | OP_NUMEQUALVERIFY) => {
+                if let Some(mut group) = group.take() {
+                    group.threshold = number.unwrap_or_default();
+                    groups.push(group);
+                }
+            }
+            instruction => {
+                number = instruction
+                    .script_num()
+                    .and_then(|number| usize::try_from(number).ok());
+            }
+        }
+    }
+    groups.extend(group);
+
+    groups
+}
+
+/// Returns the [`XOnlyPublicKey`]s that a Tapscript leaf checks signatures against,
+/// in the order that they appear in the `script`.
+pub(crate) fn leaf_x_only_keys(script: &Script) -> Vec<XOnlyPublicKey> {
+    script
+        .instructions()
+        .filter_map(|instruction| match instruction {
+            Ok(Instruction::PushBytes(bytes)) if bytes.len() == 32 => {
+                XOnlyPublicKey::from_slice(bytes.as_bytes()).ok()
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the [`Timelock`] that a Tapscript leaf checks before its signatures, if any.
+///
+/// Escrow leaves start with `<n> OP_CSV OP_DROP` or `<n> OP_CLTV OP_DROP`, see [`push_timelock`].
+pub(crate) fn leaf_timelock(script: &Script) -> Option<Timelock> {
+    let mut instructions = script.instructions();
+    let n = timelock_num(instructions.next()?.ok()?)?;
+    let n = u32::try_from(n).ok()?;
+    match instructions.next()?.ok()?.opcode()? {
+        OP_CSV => relative::LockTime::from_consensus(n)
+            .ok()
+            .map(Timelock::Relative),
+        OP_CLTV => Some(Timelock::Absolute(absolute::LockTime::from_consensus(n))),
+        _ => None,
+    }
+}
+
+/// Decodes the script number of a timelock, of up to 5 bytes as `OP_CLTV` and `OP_CSV` read it
+/// (BIP65, BIP112), since timestamps from 2038 do not fit in the 4 bytes of other numbers.
+fn timelock_num(instruction: Instruction<'_>) -> Option<i64> {
+    match instruction {
+        Instruction::PushBytes(bytes) if (1..=5).contains(&bytes.len()) => {
+            let (last, rest) = bytes.as_bytes().split_last()?;
+            // Little-endian, with the sign in the top bit of the last byte.
+            let magnitude = rest
+                .iter()
+                .rev()
+                .fold(i64::from(last & 0x7f), |n, byte| n << 8 | i64::from(*byte));
+            Some(if last & 0x80 == 0 {
+                magnitude
+            } else {
+                -magnitude
+            })
+        }
+        instruction => instruction.script_num(),
+    }
+}
+
+/// Tells which [`EscrowScript`] a Tapscript leaf built by [`contract_scripts`] is,
+/// given the keys of the `parties` of the escrow in order.
+///
+/// The cooperative leaf has no timelock, and the refund leaf is the only timelocked leaf with a single key.
+/// The dispute leaves of a two-party escrow differ only in their party, hence without the `parties`
+/// they cannot be told apart and `None` is returned.
+pub(crate) fn leaf_escrow_script(
+    script: &Script,
+    parties: &[XOnlyPublicKey],
+) -> Option<EscrowScript> {
+    if leaf_timelock(script).is_none() {
+        return Some(EscrowScript::A);
+    }
+    let keys = leaf_x_only_keys(script);
+    if keys.len() == 1 {
+        return Some(EscrowScript::D);
+    }
+    let party_keys = keys
+        .iter()
+        .filter(|key| parties.contains(key))
+        .collect::<Vec<_>>();
+    match party_keys.as_slice() {
+        [] => None,
+        [key] if parties.len() == 2 && parties[1] == **key => Some(EscrowScript::C),
+        _ => Some(EscrowScript::B),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
     use bitcoin::AddressType;
 
+    use crate::util::{npub_to_address, parse_date};
+
     use super::*;
 
     // Taken from https://docs.rs/bitcoin/latest/bitcoin/struct.PublicKey.html
@@ -307,7 +888,10 @@ mod tests {
         let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
         let network = Network::Testnet;
 
-        let address = escrow_address(&npub_1, &npub_2, None, None, network).unwrap();
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let address = contract_address(&contract, network).unwrap();
 
         assert_eq!(address.address_type().unwrap(), AddressType::P2tr);
         assert_eq!(
@@ -321,21 +905,706 @@ mod tests {
         let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
         let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
         let npub_arb = NostrPublicKey::from_str(KEY_C).unwrap();
-        let timelock_duration = 100;
+        let timelock_duration = relative::LockTime::from_height(100);
         let network = Network::Testnet;
 
-        let address = escrow_address(
+        let contract = EscrowContract::two_party(
             &npub_1,
             &npub_2,
             Some(&npub_arb),
             Some(timelock_duration),
-            network,
+            InternalKey::Unspendable,
         )
         .unwrap();
+        let address = contract_address(&contract, network).unwrap();
         assert_eq!(address.address_type().unwrap(), AddressType::P2tr);
         assert_eq!(
             address.to_string(),
             "tb1paxkfvp7rra9707t8l2mk5mwuljrq6dgs0w6yey56q3d5gynp7u7s838an7".to_string()
         );
     }
+
+    #[test]
+    fn musig2_spend_info() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_arb = NostrPublicKey::from_str(KEY_C).unwrap();
+        let aggregated_pubkey: PublicKey = musig2_key_agg_ctx(&npub_1, &npub_2)
+            .unwrap()
+            .aggregated_pubkey_untweaked();
+        let (aggregated_pubkey, _) = aggregated_pubkey.x_only_public_key();
+
+        // Collaborative escrow is key path only.
+        let collaborative =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::MuSig2).unwrap();
+        let spend_info = contract_spend_info(&collaborative).unwrap();
+        assert_eq!(spend_info.internal_key(), aggregated_pubkey);
+        assert!(spend_info.merkle_root().is_none());
+
+        // Dispute escrow keeps the B and C leaves, at depth 1.
+        let dispute = EscrowContract::two_party(
+            &npub_1,
+            &npub_2,
+            Some(&npub_arb),
+            Some(relative::LockTime::from_height(100)),
+            InternalKey::MuSig2,
+        )
+        .unwrap();
+        let spend_info = contract_spend_info(&dispute).unwrap();
+        assert_eq!(spend_info.internal_key(), aggregated_pubkey);
+        assert_eq!(spend_info.script_map().len(), 2);
+        for escrow_script in [EscrowScript::B, EscrowScript::C] {
+            let script = contract_scripts(&dispute, escrow_script).unwrap();
+            let control_block = spend_info
+                .control_block(&(script, LeafVersion::TapScript))
+                .unwrap();
+            assert_eq!(control_block.merkle_branch.len(), 1);
+        }
+
+        // The MuSig2 escrow address differs from the unspendable one.
+        let unspendable =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        assert_ne!(
+            contract_address(&unspendable, Network::Testnet).unwrap(),
+            contract_address(&collaborative, Network::Testnet).unwrap()
+        );
+    }
+
+    #[test]
+    fn two_party_contract() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_arb = NostrPublicKey::from_str(KEY_C).unwrap();
+        let pk_2 = npub_to_x_only_public_key(&npub_2).unwrap();
+        let pk_arb = npub_to_x_only_public_key(&npub_arb).unwrap();
+        let timelock_duration = relative::LockTime::from_height(100);
+        let network = Network::Testnet;
+
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(timelock_duration)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        assert!(contract.is_two_party());
+
+        // The two-party contract is the 2-of-3 escrow of `dispute_address`.
+        assert_eq!(
+            contract_address(&contract, network).unwrap().to_string(),
+            "tb1paxkfvp7rra9707t8l2mk5mwuljrq6dgs0w6yey56q3d5gynp7u7s838an7"
+        );
+        let expected = ScriptBuf::builder()
+            .push_sequence(timelock_duration.to_sequence())
+            .push_opcode(OP_CSV)
+            .push_opcode(OP_DROP)
+            .push_x_only_key(&pk_arb)
+            .push_opcode(OP_CHECKSIGVERIFY)
+            .push_x_only_key(&pk_2)
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+        assert_eq!(
+            contract_scripts(&contract, EscrowScript::C).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn n_party_contract() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_3 = NostrPublicKey::from_str(KEY_C).unwrap();
+        let pk_1 = npub_to_x_only_public_key(&npub_1).unwrap();
+        let pk_2 = npub_to_x_only_public_key(&npub_2).unwrap();
+        let pk_3 = npub_to_x_only_public_key(&npub_3).unwrap();
+        let (pk_arb, _) = secp256k1::SecretKey::from_slice(&[1; 32])
+            .unwrap()
+            .x_only_public_key(SECP256K1);
+        let npub_arb = NostrPublicKey::from(pk_arb);
+
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2, npub_3],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(100))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+
+        let script_a = contract_scripts(&contract, EscrowScript::A).unwrap();
+        assert_eq!(
+            leaf_signers(&script_a),
+            vec![SignerGroup {
+                keys: vec![pk_1, pk_2, pk_3],
+                threshold: 2
+            }]
+        );
+
+        let script_b = contract_scripts(&contract, EscrowScript::B).unwrap();
+        assert_eq!(
+            leaf_signers(&script_b),
+            vec![
+                SignerGroup {
+                    keys: vec![pk_arb],
+                    threshold: 1
+                },
+                SignerGroup {
+                    keys: vec![pk_1, pk_2, pk_3],
+                    threshold: 1
+                }
+            ]
+        );
+
+        // There is no leaf C with more than two parties.
+        assert!(matches!(
+            contract_scripts(&contract, EscrowScript::C),
+            Err(Error::InvalidEscrowType(_))
+        ));
+
+        let spend_info = contract_spend_info(&contract).unwrap();
+        assert_eq!(spend_info.internal_key(), *UNSPENDABLE_PUBLIC_KEY);
+        for script in [script_a, script_b] {
+            let control_block = spend_info
+                .control_block(&(script, LeafVersion::TapScript))
+                .unwrap();
+            assert_eq!(control_block.merkle_branch.len(), 1);
+        }
+
+        let address = contract_address(&contract, Network::Testnet).unwrap();
+        assert_eq!(address.address_type().unwrap(), AddressType::P2tr);
+    }
+
+    #[test]
+    fn arbitrator_panel_contract() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let pk_1 = npub_to_x_only_public_key(&npub_1).unwrap();
+        let pk_2 = npub_to_x_only_public_key(&npub_2).unwrap();
+        let pk_arbs = [[1; 32], [2; 32], [3; 32]].map(|secret| {
+            secp256k1::SecretKey::from_slice(&secret)
+                .unwrap()
+                .x_only_public_key(SECP256K1)
+                .0
+        });
+        let npub_arbs = pk_arbs.map(NostrPublicKey::from).to_vec();
+
+        // 2-of-3 arbitrators and one of the parties.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            npub_arbs.clone(),
+            2,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(100))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+
+        let script_b = contract_scripts(&contract, EscrowScript::B).unwrap();
+        assert_eq!(
+            leaf_signers(&script_b),
+            vec![
+                SignerGroup {
+                    keys: pk_arbs.to_vec(),
+                    threshold: 2
+                },
+                SignerGroup {
+                    keys: vec![pk_1, pk_2],
+                    threshold: 1
+                }
+            ]
+        );
+        // A single party leaf covers both parties, hence there is no leaf C.
+        assert!(contract_scripts(&contract, EscrowScript::C).is_err());
+        assert_eq!(
+            contract_spend_info(&contract).unwrap().script_map().len(),
+            2
+        );
+
+        // A panel is not the two-party escrow, hence no MuSig2.
+        assert!(
+            EscrowContract::new(
+                vec![npub_1, npub_2],
+                2,
+                npub_arbs.clone(),
+                2,
+                1,
+                Some(Timelock::Relative(relative::LockTime::from_height(100))),
+                InternalKey::MuSig2,
+            )
+            .is_err()
+        );
+        // The arbitrator threshold is bounded by the number of arbitrators.
+        assert!(
+            EscrowContract::new(
+                vec![npub_1, npub_2],
+                2,
+                npub_arbs,
+                4,
+                1,
+                Some(Timelock::Relative(relative::LockTime::from_height(100))),
+                InternalKey::Unspendable,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn invalid_contracts() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_3 = NostrPublicKey::from_str(KEY_C).unwrap();
+        let parties = vec![npub_1, npub_2, npub_3];
+        let unspendable = InternalKey::Unspendable;
+
+        assert!(EscrowContract::new(vec![npub_1], 1, vec![], 1, 1, None, unspendable).is_err());
+        assert!(EscrowContract::new(parties.clone(), 0, vec![], 1, 1, None, unspendable).is_err());
+        assert!(EscrowContract::new(parties.clone(), 4, vec![], 1, 1, None, unspendable).is_err());
+        // Arbitrators distinct from the parties, so that only the checked input is wrong.
+        let two_parties = vec![npub_1, npub_2];
+        assert!(
+            EscrowContract::new(
+                two_parties.clone(),
+                2,
+                vec![npub_3],
+                1,
+                1,
+                None,
+                unspendable
+            )
+            .is_err()
+        );
+        assert!(
+            EscrowContract::new(
+                two_parties,
+                2,
+                vec![npub_3],
+                1,
+                3,
+                Some(Timelock::Relative(relative::LockTime::from_height(100))),
+                unspendable
+            )
+            .is_err()
+        );
+        assert!(EscrowContract::new(parties, 2, vec![], 1, 1, None, InternalKey::MuSig2).is_err());
+
+        // A repeated party would sign twice for a 2-of-3 alone, and so would a party that is also an arbitrator.
+        assert!(matches!(
+            EscrowContract::new(
+                vec![npub_1, npub_2, npub_1],
+                2,
+                vec![],
+                1,
+                1,
+                None,
+                unspendable
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+        assert!(matches!(
+            EscrowContract::new(
+                vec![npub_1, npub_2],
+                2,
+                vec![npub_2],
+                1,
+                1,
+                Some(Timelock::Relative(relative::LockTime::from_height(100))),
+                unspendable
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+    }
+
+    #[test]
+    fn refund_contract() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_arb = NostrPublicKey::from_str(KEY_C).unwrap();
+        let pk_1 = npub_to_x_only_public_key(&npub_1).unwrap();
+        let merkle_branch_len = |spend_info: &TaprootSpendInfo, script: ScriptBuf| {
+            spend_info
+                .control_block(&(script, LeafVersion::TapScript))
+                .unwrap()
+                .merkle_branch
+                .len()
+        };
+
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(100))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        // No refund leaf without a refund party.
+        assert!(matches!(
+            contract_scripts(&contract, EscrowScript::D),
+            Err(Error::InvalidEscrowType(_))
+        ));
+        // The refund party must be a party and wait longer than the dispute.
+        assert!(
+            contract
+                .clone()
+                .with_refund(npub_arb, relative::LockTime::from_height(1_000))
+                .is_err()
+        );
+        assert!(
+            contract
+                .clone()
+                .with_refund(npub_1, relative::LockTime::from_height(100))
+                .is_err()
+        );
+        // Nor can blocks be compared to 512-second intervals.
+        assert!(
+            contract
+                .clone()
+                .with_refund(npub_1, relative::LockTime::from_512_second_intervals(1_000))
+                .is_err()
+        );
+
+        let contract = contract
+            .with_refund(npub_1, relative::LockTime::from_height(1_000))
+            .unwrap();
+        let script_d = contract_scripts(&contract, EscrowScript::D).unwrap();
+        let expected = ScriptBuf::builder()
+            .push_sequence(relative::LockTime::from_height(1_000).to_sequence())
+            .push_opcode(OP_CSV)
+            .push_opcode(OP_DROP)
+            .push_x_only_key(&pk_1)
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+        assert_eq!(script_d, expected);
+        assert_eq!(
+            leaf_signers(&script_d),
+            vec![SignerGroup {
+                keys: vec![pk_1],
+                threshold: 1
+            }]
+        );
+
+        // The refund leaf is the sibling of the deepest leaf.
+        let spend_info = contract_spend_info(&contract).unwrap();
+        for (escrow_script, depth) in [
+            (EscrowScript::A, 1),
+            (EscrowScript::B, 2),
+            (EscrowScript::C, 3),
+            (EscrowScript::D, 3),
+        ] {
+            let script = contract_scripts(&contract, escrow_script).unwrap();
+            assert_eq!(merkle_branch_len(&spend_info, script), depth);
+        }
+
+        // A MuSig2 collaborative escrow gets the refund leaf as its only leaf.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::MuSig2,
+        )
+        .unwrap()
+        .with_refund(npub_2, relative::LockTime::from_height(1_000))
+        .unwrap();
+        let spend_info = contract_spend_info(&contract).unwrap();
+        let script_d = contract_scripts(&contract, EscrowScript::D).unwrap();
+        assert_eq!(merkle_branch_len(&spend_info, script_d), 0);
+        assert_ne!(spend_info.internal_key(), *UNSPENDABLE_PUBLIC_KEY);
+    }
+
+    #[test]
+    fn absolute_timelock_contract() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_3 = NostrPublicKey::from_str(KEY_C).unwrap();
+        let pk_3 = npub_to_x_only_public_key(&npub_3).unwrap();
+        let lock_time = absolute::LockTime::from_height(900_000).unwrap();
+
+        // Two-party dispute leaves.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_3],
+            1,
+            1,
+            Some(Timelock::Absolute(lock_time)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let pk_2 = npub_to_x_only_public_key(&npub_2).unwrap();
+        let expected = ScriptBuf::builder()
+            .push_lock_time(lock_time)
+            .push_opcode(OP_CLTV)
+            .push_opcode(OP_DROP)
+            .push_x_only_key(&pk_3)
+            .push_opcode(OP_CHECKSIGVERIFY)
+            .push_x_only_key(&pk_2)
+            .push_opcode(OP_CHECKSIG)
+            .into_script();
+        assert_eq!(
+            contract_scripts(&contract, EscrowScript::C).unwrap(),
+            expected
+        );
+        // Not the relative escrow of the same keys.
+        assert_ne!(
+            contract_address(&contract, Network::Testnet).unwrap(),
+            contract_address(
+                &EscrowContract::two_party(
+                    &npub_1,
+                    &npub_2,
+                    Some(&npub_3),
+                    Some(relative::LockTime::from_height(65_535)),
+                    InternalKey::Unspendable
+                )
+                .unwrap(),
+                Network::Testnet
+            )
+            .unwrap()
+        );
+
+        // The refund timelock is relative, hence cannot be compared to an absolute dispute timelock.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            1,
+            vec![npub_3],
+            1,
+            1,
+            Some(Timelock::Absolute(lock_time)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        assert!(matches!(
+            contract
+                .clone()
+                .with_refund(npub_1, relative::LockTime::from_height(144)),
+            Err(Error::WrongInputs(_))
+        ));
+        let script_b = contract_scripts(&contract, EscrowScript::B).unwrap();
+        assert!(
+            script_b
+                .instructions()
+                .any(|instruction| instruction == Ok(Instruction::Op(OP_CLTV)))
+        );
+        assert_eq!(
+            leaf_timelock(&script_b),
+            Some(Timelock::Absolute(lock_time))
+        );
+
+        // Timestamps from 2038 are pushed in 5 bytes.
+        let lock_time = parse_date("2040-01-01").unwrap();
+        assert!(lock_time.to_consensus_u32() > i32::MAX as u32);
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_3],
+            1,
+            1,
+            Some(Timelock::Absolute(lock_time)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let script_c = contract_scripts(&contract, EscrowScript::C).unwrap();
+        assert_eq!(
+            leaf_timelock(&script_c),
+            Some(Timelock::Absolute(lock_time))
+        );
+        assert_eq!(
+            leaf_escrow_script(
+                &script_c,
+                &[npub_to_x_only_public_key(&npub_1).unwrap(), pk_2]
+            ),
+            Some(EscrowScript::C)
+        );
+        assert_eq!(
+            leaf_signers(&script_b)[0],
+            SignerGroup {
+                keys: vec![pk_3],
+                threshold: 1
+            }
+        );
+    }
+
+    #[test]
+    fn leaf_escrow_script_from_keys() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_arb = NostrPublicKey::from_str(KEY_C).unwrap();
+        let parties = [
+            npub_to_x_only_public_key(&npub_1).unwrap(),
+            npub_to_x_only_public_key(&npub_2).unwrap(),
+        ];
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(100))),
+            InternalKey::Unspendable,
+        )
+        .unwrap()
+        .with_refund(npub_2, relative::LockTime::from_height(1_000))
+        .unwrap();
+
+        for escrow_script in [
+            EscrowScript::A,
+            EscrowScript::B,
+            EscrowScript::C,
+            EscrowScript::D,
+        ] {
+            let script = contract_scripts(&contract, escrow_script).unwrap();
+            assert_eq!(leaf_escrow_script(&script, &parties), Some(escrow_script));
+        }
+        // The dispute leaves cannot be told apart without the parties.
+        let script_c = contract_scripts(&contract, EscrowScript::C).unwrap();
+        assert_eq!(leaf_escrow_script(&script_c, &[]), None);
+    }
+
+    #[test]
+    fn contract_taptree_commits_to_address() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_arb = NostrPublicKey::from_str(KEY_C).unwrap();
+        let timelock_duration = relative::LockTime::from_height(100);
+        let network = Network::Testnet;
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(timelock_duration)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+
+        // Same tree as `contract_spend_info`, with `A` at depth 1, and `B` and `C` at depth 2.
+        let taptree = contract_taptree(&contract, network).unwrap();
+        let spend_info = contract_spend_info(&contract).unwrap();
+        assert_eq!(taptree.merkle_root, spend_info.merkle_root());
+        assert_eq!(taptree.internal_key, *UNSPENDABLE_PUBLIC_KEY);
+        assert_eq!(
+            taptree
+                .leaves
+                .iter()
+                .map(|leaf| (leaf.escrow_script, leaf.depth))
+                .collect::<Vec<_>>(),
+            [
+                (EscrowScript::A, 1),
+                (EscrowScript::B, 2),
+                (EscrowScript::C, 2)
+            ]
+        );
+        let address = contract_address(&contract, network).unwrap();
+        assert!(taptree.commits_to(&address));
+        assert_eq!(taptree.address, address);
+
+        // Another timelock or arbitrator is another address.
+        let other_timelock = contract_address(
+            &EscrowContract::two_party(
+                &npub_1,
+                &npub_2,
+                Some(&npub_arb),
+                Some(relative::LockTime::from_height(99)),
+                InternalKey::Unspendable,
+            )
+            .unwrap(),
+            network,
+        )
+        .unwrap();
+        assert!(!taptree.commits_to(&other_timelock));
+        let other_arbitrator = contract_address(
+            &EscrowContract::two_party(
+                &npub_1,
+                &npub_arb,
+                Some(&npub_2),
+                Some(timelock_duration),
+                InternalKey::Unspendable,
+            )
+            .unwrap(),
+            network,
+        )
+        .unwrap();
+        assert!(!taptree.commits_to(&other_arbitrator));
+
+        // The refund leaf is a sibling of the last leaf, and MuSig2 drops `A` from the tree.
+        let contract = contract
+            .with_refund(npub_1, relative::LockTime::from_height(1_000))
+            .unwrap();
+        let taptree = contract_taptree(&contract, network).unwrap();
+        assert_eq!(
+            taptree
+                .leaves
+                .iter()
+                .map(|leaf| leaf.depth)
+                .collect::<Vec<_>>(),
+            [1, 2, 3, 3]
+        );
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::MuSig2,
+        )
+        .unwrap();
+        let taptree = contract_taptree(&contract, network).unwrap();
+        assert!(taptree.leaves.is_empty());
+        assert_eq!(taptree.merkle_root, None);
+    }
+
+    #[test]
+    fn contract_taptree_rejects_other_escrows() {
+        let npub_1 = NostrPublicKey::from_str(KEY_A).unwrap();
+        let npub_2 = NostrPublicKey::from_str(KEY_B).unwrap();
+        let npub_arb = NostrPublicKey::from_str(KEY_C).unwrap();
+        let network = Network::Testnet;
+        let timelock = Some(Timelock::Relative(relative::LockTime::from_height(100)));
+        let contract_with = |parties: Vec<NostrPublicKey>, arbitrated_threshold, internal_key| {
+            EscrowContract::new(
+                parties,
+                2,
+                vec![npub_arb],
+                1,
+                arbitrated_threshold,
+                timelock,
+                internal_key,
+            )
+            .unwrap()
+        };
+        let taptree = contract_taptree(
+            &contract_with(vec![npub_1, npub_2], 1, InternalKey::Unspendable),
+            network,
+        )
+        .unwrap();
+
+        // The parties in another order, another threshold or another internal key.
+        for other in [
+            contract_with(vec![npub_2, npub_1], 1, InternalKey::Unspendable),
+            contract_with(vec![npub_1, npub_2], 2, InternalKey::Unspendable),
+            contract_with(vec![npub_1, npub_2], 1, InternalKey::MuSig2),
+        ] {
+            let address = contract_address(&other, network).unwrap();
+            assert!(!taptree.commits_to(&address), "{other:?}");
+        }
+
+        // The internal key alone, without the leaves.
+        let key_path_only = Address::p2tr(SECP256K1, taptree.internal_key, None, network);
+        assert!(!taptree.commits_to(&key_path_only));
+        // Nor is a party's own address the escrow.
+        let party_address = npub_to_address(&npub_1, network).unwrap();
+        assert!(!taptree.commits_to(&party_address));
+    }
 }
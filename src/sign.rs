@@ -1,95 +1,201 @@
 //! Signs Taproot Transactions using Nostr keys.
 
+use std::{collections::BTreeMap, fmt};
+
 use bitcoin::{
-    Script, ScriptBuf, TapLeafHash, TapSighashType, Transaction, TxOut, Witness,
+    Amount, Network, OutPoint, Psbt, Script, ScriptBuf, TapLeafHash, TapNodeHash, TapSighashType,
+    Transaction, TxOut, Witness, XOnlyPublicKey,
     hashes::Hash,
-    key::TapTweak,
+    key::{Parity, TapTweak},
+    psbt,
     sighash::{Prevouts, SighashCache},
-    taproot::{LeafVersion, TaprootSpendInfo},
+    taproot::{self, ControlBlock, LeafVersion, TaprootSpendInfo},
 };
 #[cfg(debug_assertions)]
 use dioxus::logger::tracing::{error, trace};
+use musig2::{
+    AggNonce, KeyAggContext, PartialSignature, PubNonce, SecNonce, SecNonceBuilder,
+    aggregate_partial_signatures, sign_partial, verify_partial,
+};
 use nostr::key::{PublicKey as NostrPublicKey, SecretKey as NostrSecretKey};
-use secp256k1::{Message, SECP256K1, schnorr};
+use secp256k1::{Message, PublicKey, SECP256K1, SecretKey, rand, schnorr};
 
 use crate::{
     error::Error,
-    scripts::{EscrowScript, escrow_scripts},
+    scripts::{
+        EscrowContract, EscrowScript, InternalKey, contract_address, contract_scripts,
+        contract_spend_info, leaf_signers, leaf_timelock, leaf_x_only_keys, musig2_key_agg_ctx,
+    },
+    tx::check_timelock,
 };
 
 /// Signs a [`Transaction`] with the given [`NostrSecretKey`].
 ///
-/// It must be a P2TR key path spend transaction with a single input as the 0th vout.
+/// It must be a P2TR key path spend transaction of the resolution address,
+/// every input is signed with the `prevouts` of all the inputs, in order.
+///
+/// # Errors
+///
+/// Errors if there is not one prevout per input.
 pub(crate) fn sign_resolution_tx(
     transaction: &Transaction,
     nsec: &NostrSecretKey,
-    prevout: TxOut,
-) -> Transaction {
+    prevouts: &[TxOut],
+) -> Result<Transaction, Error> {
+    if transaction.input.len() != prevouts.len() {
+        return Err(Error::WrongInputs(format!(
+            "Expected one prevout per input. Got {} inputs and {} prevouts",
+            transaction.input.len(),
+            prevouts.len()
+        )));
+    }
     // Parse nsec to a bitcoin secret key.
     let keypair = nsec.keypair(SECP256K1);
+    // For key path spend, we need to apply taproot tweak.
+    let tweaked = keypair.tap_tweak(SECP256K1, None);
 
     let mut sighasher = SighashCache::new(transaction);
     let sighash_type = TapSighashType::Default;
-    let sighash = sighasher
-        .taproot_key_spend_signature_hash(0, &Prevouts::All(&[prevout]), sighash_type)
-        .expect("must create sighash");
-    let message = Message::from_digest(*sighash.as_byte_array());
+    let mut witnesses = Vec::with_capacity(prevouts.len());
+    for index in 0..prevouts.len() {
+        let sighash = sighasher.taproot_key_spend_signature_hash(
+            index,
+            &Prevouts::All(prevouts),
+            sighash_type,
+        )?;
+        let message = Message::from_digest(*sighash.as_byte_array());
+        let signature = SECP256K1.sign_schnorr_no_aux_rand(&message, &tweaked.to_keypair());
+        #[cfg(debug_assertions)]
+        trace!(%index, signature = %signature, txid = %transaction.compute_txid(), "Signature resolution transaction");
+
+        // Construct the witness stack
+        let mut witness = Witness::new();
+        witness.push(signature.as_ref());
+        witnesses.push(witness);
+    }
 
-    // For key path spend, we need to apply taproot tweak.
-    let tweaked = keypair.tap_tweak(SECP256K1, None);
-    let signature = SECP256K1.sign_schnorr_no_aux_rand(&message, &tweaked.to_keypair());
-    #[cfg(debug_assertions)]
-    trace!(signature = %signature, txid = %transaction.compute_txid(), "Signature resolution transaction");
     let mut transaction = transaction.clone();
+    for (input, witness) in transaction.input.iter_mut().zip(witnesses) {
+        input.witness = witness;
+    }
+    Ok(transaction)
+}
 
-    // Construct the witness stack
-    let mut witness = Witness::new();
-    witness.push(signature.as_ref());
+/// Signs every input of an [`EscrowContract`] P2TR [`Transaction`] using a [`NostrSecretKey`].
+///
+/// The inputs are signed using the provided [`NostrSecretKey`], the `prevouts` of all the inputs,
+/// and the `escrow_script` leaf of the `contract`, one signature per input in order.
+pub(crate) fn sign_contract_tx(
+    tx: &Transaction,
+    nsec: &NostrSecretKey,
+    contract: &EscrowContract,
+    prevouts: &[TxOut],
+    escrow_script: EscrowScript,
+) -> Result<Vec<schnorr::Signature>, Error> {
+    let locking_script = contract_scripts(contract, escrow_script)?;
+    #[cfg(debug_assertions)]
+    trace!(locking_script = %locking_script.to_asm_string(), "contract locking script");
 
-    transaction.input[0].witness = witness;
-    transaction
+    sign_leaves(tx, nsec, &locking_script, prevouts)
 }
 
-/// Signs an escrow P2TR [`Transaction`], given an input `index` using a [`NostrSecretKey`].
+/// The leaves of an [`EscrowContract`] that the `x_only_pk` can sign to spend a [`Transaction`],
+/// i.e. the leaves of the escrow with the key whose timelock every input can satisfy.
 ///
-/// The input is signed using the provided [`NostrSecretKey`], `prevouts`, and [`ScriptBuf`] locking script.
-#[expect(clippy::too_many_arguments)]
-pub(crate) fn sign_escrow_tx(
+/// Leaves without a timelock are left out if a timelocked leaf is satisfied, since the `nSequence`
+/// and lock time of a transaction spending the cooperative leaf never wait for a timelock.
+/// Several leaves are left when only the signer can tell them apart.
+///
+/// # Errors
+///
+/// Errors if the Taproot tree of the contract could not be built.
+pub(crate) fn signable_leaves(
+    contract: &EscrowContract,
+    x_only_pk: &XOnlyPublicKey,
+    tx: &Transaction,
+) -> Result<Vec<EscrowScript>, Error> {
+    let taproot_spend_info = contract_spend_info(contract)?;
+    let mut leaves = Vec::new();
+    for escrow_script in [
+        EscrowScript::A,
+        EscrowScript::B,
+        EscrowScript::C,
+        EscrowScript::D,
+    ] {
+        let Ok(locking_script) = contract_scripts(contract, escrow_script) else {
+            continue;
+        };
+        let leaf = (locking_script, LeafVersion::TapScript);
+        if !taproot_spend_info.script_map().contains_key(&leaf)
+            || !leaf_x_only_keys(&leaf.0).contains(x_only_pk)
+        {
+            continue;
+        }
+        let timelock = leaf_timelock(&leaf.0);
+        if (0..tx.input.len()).all(|index| check_timelock(tx, index, timelock).is_ok()) {
+            leaves.push((escrow_script, timelock));
+        }
+    }
+    if leaves.iter().any(|(_, timelock)| timelock.is_some()) {
+        leaves.retain(|(_, timelock)| timelock.is_some());
+    }
+    #[cfg(debug_assertions)]
+    trace!(%x_only_pk, ?leaves, "signable leaves");
+
+    Ok(leaves
+        .into_iter()
+        .map(|(escrow_script, _)| escrow_script)
+        .collect())
+}
+
+/// Signs every input of a [`Transaction`] spending the Tapscript `locking_script` leaf, see [`sign_leaf`].
+///
+/// # Errors
+///
+/// Errors if there is not one prevout per input.
+fn sign_leaves(
+    tx: &Transaction,
+    nsec: &NostrSecretKey,
+    locking_script: &Script,
+    prevouts: &[TxOut],
+) -> Result<Vec<schnorr::Signature>, Error> {
+    if tx.input.len() != prevouts.len() {
+        return Err(Error::WrongInputs(format!(
+            "Expected one prevout per input. Got {} inputs and {} prevouts",
+            tx.input.len(),
+            prevouts.len()
+        )));
+    }
+
+    (0..tx.input.len())
+        .map(|index| sign_leaf(tx, index, nsec, locking_script, prevouts))
+        .collect()
+}
+
+/// Signs the input `index` of a [`Transaction`] spending the Tapscript `locking_script` leaf.
+///
+/// Refuses to sign if the transaction can never satisfy the timelock of the leaf.
+fn sign_leaf(
     tx: &Transaction,
     index: usize,
     nsec: &NostrSecretKey,
-    npub_1: &NostrPublicKey,
-    npub_2: &NostrPublicKey,
-    npub_arbitrator: Option<&NostrPublicKey>,
-    timelock_duration: Option<u32>,
-    prevouts: Vec<TxOut>,
-    escrow_script: EscrowScript,
+    locking_script: &Script,
+    prevouts: &[TxOut],
 ) -> Result<schnorr::Signature, Error> {
+    check_timelock(tx, index, leaf_timelock(locking_script))?;
+
     // Parse nsec to a bitcoin secret key.
     let keypair = nsec.keypair(SECP256K1);
-
-    // get which escrow type.
-    let locking_script = escrow_scripts(
-        npub_1,
-        npub_2,
-        npub_arbitrator,
-        timelock_duration,
-        escrow_script,
-    )?;
-    #[cfg(debug_assertions)]
-    trace!(%index, locking_script = %locking_script.to_asm_string(), "escrow locking script");
-    let leaf_hash = TapLeafHash::from_script(&locking_script, LeafVersion::TapScript);
+    let leaf_hash = TapLeafHash::from_script(locking_script, LeafVersion::TapScript);
 
     let sighash_type = TapSighashType::Default;
     let mut sighash_cache = SighashCache::new(tx);
-    let sighash = sighash_cache
-        .taproot_script_spend_signature_hash(
-            index,
-            &Prevouts::All(&prevouts),
-            leaf_hash,
-            sighash_type,
-        )
-        .expect("fail to create sighash");
+    let sighash = sighash_cache.taproot_script_spend_signature_hash(
+        index,
+        &Prevouts::All(prevouts),
+        leaf_hash,
+        sighash_type,
+    )?;
     let message = Message::from_digest_slice(sighash.as_byte_array())?;
 
     // For script path, we use the UNTWEAKED keypair.
@@ -110,41 +216,398 @@ pub(crate) fn sign_escrow_tx(
     Ok(signature)
 }
 
-/// Types of escrow transactions.
-#[allow(dead_code)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) enum EscrowType<'a> {
-    /// Collaborative escrow transaction.
-    ///
-    /// No timelocks and no arbitrator.
-    Collaborative {
-        participant_1: &'a NostrPublicKey,
-        participant_2: &'a NostrPublicKey,
-    },
+/// Combine the [`schnorr::Signature`]s of the `signatures` keys into every [`Transaction`] input
+/// spending the `locking_script` leaf, given the signatures of each input in order.
+///
+/// It supports `multi_a` leaves where not every key signs.
+///
+/// # Errors
+///
+/// Errors if there are not enough `signatures` of an input for the `locking_script`,
+/// if the `locking_script` is not a leaf of the `taproot_spend_info`,
+/// or if the transaction can never satisfy the timelock of the `locking_script`.
+pub(crate) fn combine_contract_signatures(
+    mut transaction: Transaction,
+    signatures: &[BTreeMap<XOnlyPublicKey, schnorr::Signature>],
+    locking_script: &Script,
+    taproot_spend_info: &TaprootSpendInfo,
+) -> Result<Transaction, Error> {
+    if signatures.len() < transaction.input.len() {
+        return Err(Error::MissingSignatures(signatures.len()));
+    }
+    let control_block = leaf_control_block(locking_script, taproot_spend_info)?;
+
+    for index in 0..transaction.input.len() {
+        check_timelock(&transaction, index, leaf_timelock(locking_script))?;
+    }
+    for (index, (input, signatures)) in transaction.input.iter_mut().zip(signatures).enumerate() {
+        input.witness = leaf_witness(locking_script, &control_block, |x_only_pk| {
+            signatures
+                .get(x_only_pk)
+                .map(|signature| signature.serialize().to_vec())
+        })
+        .ok_or(Error::MissingSignatures(index))?;
+    }
+
+    Ok(transaction)
+}
+
+/// Why a [`schnorr::Signature`] of an escrow input does not verify, see [`verify_contract_signatures`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SignatureFault {
+    /// The signature is for the right transaction and leaf, but for other prevout amounts.
+    Amount,
+    /// The signature is for another leaf of the escrow, i.e. another spending condition.
+    Leaf,
+    /// The signature is for another transaction, or for amounts that could not be guessed.
+    Transaction,
+}
+
+impl fmt::Display for SignatureFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Amount => write!(f, "it was made for a wrong funding amount"),
+            Self::Leaf => write!(f, "it was made for another spending condition"),
+            Self::Transaction => {
+                write!(f, "it was made for another transaction or other amounts")
+            }
+        }
+    }
+}
+
+/// A [`schnorr::Signature`] of an escrow input that does not verify.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InvalidSignature {
+    /// The input of the signature.
+    pub(crate) index: usize,
+    /// The key of the signer.
+    pub(crate) x_only_pk: XOnlyPublicKey,
+    /// The most likely cause.
+    pub(crate) fault: SignatureFault,
+}
+
+/// Diagnoses [`schnorr::Signature`]s of the inputs of a [`Transaction`] spending a leaf of an escrow.
+struct SignatureChecker<'a> {
+    transaction: &'a Transaction,
+    prevouts: &'a [TxOut],
+    locking_script: &'a Script,
+    /// The leaves of the escrow other than the `locking_script`.
+    other_leaves: Vec<&'a ScriptBuf>,
+    /// The amounts of the prevouts and their total, sorted.
+    amounts: Vec<Amount>,
+    /// The sighash type that the signatures commit to.
+    sighash_type: TapSighashType,
+}
 
-    /// Dispute escrow transaction.
+impl<'a> SignatureChecker<'a> {
+    /// Creates a [`SignatureChecker`] of the `locking_script` leaf among the escrow `leaves`.
     ///
-    /// Timelocked and with an arbitrator.
-    Dispute {
-        participant_1: &'a NostrPublicKey,
-        participant_2: &'a NostrPublicKey,
-        arbitrator: &'a NostrPublicKey,
-    },
+    /// # Errors
+    ///
+    /// Errors if there is not one prevout per input.
+    fn new(
+        transaction: &'a Transaction,
+        prevouts: &'a [TxOut],
+        locking_script: &'a Script,
+        leaves: impl IntoIterator<Item = &'a ScriptBuf>,
+    ) -> Result<Self, Error> {
+        if transaction.input.len() != prevouts.len() {
+            return Err(Error::WrongInputs(format!(
+                "Expected one prevout per input. Got {} inputs and {} prevouts",
+                transaction.input.len(),
+                prevouts.len()
+            )));
+        }
+        let other_leaves = leaves
+            .into_iter()
+            .filter(|leaf| leaf.as_script() != locking_script)
+            .collect();
+        let mut amounts = prevouts
+            .iter()
+            .map(|prevout| prevout.value)
+            .collect::<Vec<_>>();
+        amounts.push(prevouts.iter().map(|prevout| prevout.value).sum());
+        amounts.sort();
+        amounts.dedup();
+
+        Ok(Self {
+            transaction,
+            prevouts,
+            locking_script,
+            other_leaves,
+            amounts,
+            sighash_type: TapSighashType::Default,
+        })
+    }
+
+    /// Checks signatures that commit to the `sighash_type` rather than the default one.
+    fn with_sighash_type(self, sighash_type: TapSighashType) -> Self {
+        Self {
+            sighash_type,
+            ..self
+        }
+    }
+
+    /// Whether the `signature` of the input `index` verifies for the `x_only_pk`
+    /// against the sighash of the `leaf` with the `prevouts`.
+    fn verifies(
+        &self,
+        index: usize,
+        prevouts: &[TxOut],
+        leaf: &Script,
+        x_only_pk: &XOnlyPublicKey,
+        signature: &schnorr::Signature,
+    ) -> Result<bool, Error> {
+        let leaf_hash = TapLeafHash::from_script(leaf, LeafVersion::TapScript);
+        let sighash = SighashCache::new(self.transaction).taproot_script_spend_signature_hash(
+            index,
+            &Prevouts::All(prevouts),
+            leaf_hash,
+            self.sighash_type,
+        )?;
+        let message = Message::from_digest(sighash.to_byte_array());
+        Ok(SECP256K1
+            .verify_schnorr(signature, &message, x_only_pk)
+            .is_ok())
+    }
+
+    /// The [`SignatureFault`] of the `signature` of the input `index` for the `x_only_pk`,
+    /// or `None` if it is valid.
+    fn fault(
+        &self,
+        index: usize,
+        x_only_pk: &XOnlyPublicKey,
+        signature: &schnorr::Signature,
+    ) -> Result<Option<SignatureFault>, Error> {
+        if self.verifies(
+            index,
+            self.prevouts,
+            self.locking_script,
+            x_only_pk,
+            signature,
+        )? {
+            return Ok(None);
+        }
+        for leaf in &self.other_leaves {
+            if self.verifies(index, self.prevouts, leaf, x_only_pk, signature)? {
+                return Ok(Some(SignatureFault::Leaf));
+            }
+        }
+        for wrong_index in 0..self.prevouts.len() {
+            for amount in &self.amounts {
+                let mut wrong_prevouts = self.prevouts.to_vec();
+                wrong_prevouts[wrong_index].value = *amount;
+                if self.verifies(
+                    index,
+                    &wrong_prevouts,
+                    self.locking_script,
+                    x_only_pk,
+                    signature,
+                )? {
+                    return Ok(Some(SignatureFault::Amount));
+                }
+            }
+        }
+
+        Ok(Some(SignatureFault::Transaction))
+    }
 }
 
-/// Combine one multiple [`schnorr::Signature`]s into a single [`Transaction`] input.
-pub(crate) fn combine_signatures(
-    mut transaction: Transaction,
-    index: usize,
-    signatures: Vec<&schnorr::Signature>,
+/// Verifies the [`schnorr::Signature`]s of every input spending the `locking_script` leaf,
+/// as [`combine_contract_signatures`] takes them, against the sighash of the `prevouts`.
+///
+/// Every invalid signature is diagnosed: whether it verifies for another leaf of the
+/// `taproot_spend_info`, or for other plausible funding amounts, i.e. the amount of another
+/// prevout or the total of the prevouts, as typed by hand in place of the amount of one input.
+///
+/// # Errors
+///
+/// Errors if there is not one prevout per input, or if the sighash could not be computed.
+pub(crate) fn verify_contract_signatures(
+    transaction: &Transaction,
+    signatures: &[BTreeMap<XOnlyPublicKey, schnorr::Signature>],
+    prevouts: &[TxOut],
+    locking_script: &Script,
+    taproot_spend_info: &TaprootSpendInfo,
+) -> Result<Vec<InvalidSignature>, Error> {
+    let checker = SignatureChecker::new(
+        transaction,
+        prevouts,
+        locking_script,
+        taproot_spend_info.script_map().keys().map(|(leaf, _)| leaf),
+    )?;
+
+    let mut invalid = Vec::new();
+    for (index, signatures) in signatures.iter().enumerate().take(transaction.input.len()) {
+        for (x_only_pk, signature) in signatures {
+            let Some(fault) = checker.fault(index, x_only_pk, signature)? else {
+                continue;
+            };
+            #[cfg(debug_assertions)]
+            trace!(%index, %x_only_pk, ?fault, "Invalid signature");
+            invalid.push(InvalidSignature {
+                index,
+                x_only_pk: *x_only_pk,
+                fault,
+            });
+        }
+    }
+
+    Ok(invalid)
+}
+
+/// Maps unordered sets of [`schnorr::Signature`]s, each made by one signer for every input
+/// in input order, to the key of their signer, as [`combine_contract_signatures`] takes them.
+///
+/// The signer of a set is the key of any leaf of the `taproot_spend_info` whose signature of
+/// every input verifies against the sighash of that input for the `locking_script` leaf with the `prevouts`.
+/// Failing that, the key whose signatures of every input only verify for another leaf or other amounts,
+/// so that [`verify_contract_signatures`] can diagnose them.
+///
+/// # Errors
+///
+/// Errors if a set does not have one signature per input, if there is not one prevout per input,
+/// if a set was not made by any key of the escrow for this transaction,
+/// or if two sets were made by the same key.
+pub(crate) fn map_contract_signatures(
+    transaction: &Transaction,
+    signatures: &[Vec<schnorr::Signature>],
+    prevouts: &[TxOut],
+    locking_script: &Script,
+    taproot_spend_info: &TaprootSpendInfo,
+) -> Result<Vec<BTreeMap<XOnlyPublicKey, schnorr::Signature>>, Error> {
+    let checker = SignatureChecker::new(
+        transaction,
+        prevouts,
+        locking_script,
+        taproot_spend_info.script_map().keys().map(|(leaf, _)| leaf),
+    )?;
+    let mut keys = taproot_spend_info
+        .script_map()
+        .keys()
+        .flat_map(|(leaf, _)| leaf_x_only_keys(leaf))
+        .collect::<Vec<_>>();
+    keys.sort();
+    keys.dedup();
+
+    let mut mapped = vec![BTreeMap::new(); transaction.input.len()];
+    // The set that each signer made.
+    let mut signers = BTreeMap::new();
+    for (set, signer_signatures) in signatures.iter().enumerate() {
+        if signer_signatures.len() != transaction.input.len() {
+            return Err(Error::WrongInputs(format!(
+                "Signatures {} has {} signatures for a transaction of {} inputs",
+                set + 1,
+                signer_signatures.len(),
+                transaction.input.len()
+            )));
+        }
+        let mut signer = None;
+        for x_only_pk in &keys {
+            match signer_fault(&checker, x_only_pk, signer_signatures)? {
+                None => {
+                    signer = Some(*x_only_pk);
+                    break;
+                }
+                Some(SignatureFault::Transaction) => {}
+                Some(_) => {
+                    signer = signer.or(Some(*x_only_pk));
+                }
+            }
+        }
+        let x_only_pk = signer.ok_or(Error::UnknownSignatures(set + 1))?;
+        #[cfg(debug_assertions)]
+        trace!(set, %x_only_pk, "Mapped signatures");
+        if let Some(first) = signers.insert(x_only_pk, set) {
+            return Err(Error::DuplicateSignatures(first + 1, set + 1));
+        }
+        for (input_signatures, signature) in mapped.iter_mut().zip(signer_signatures) {
+            input_signatures.insert(x_only_pk, *signature);
+        }
+    }
+
+    Ok(mapped)
+}
+
+/// The worst [`SignatureFault`] of the `signatures` of every input for the `x_only_pk`,
+/// each against the sighash of its own input, or `None` if they are all valid.
+fn signer_fault(
+    checker: &SignatureChecker<'_>,
+    x_only_pk: &XOnlyPublicKey,
+    signatures: &[schnorr::Signature],
+) -> Result<Option<SignatureFault>, Error> {
+    let mut worst = None;
+    for (index, signature) in signatures.iter().enumerate() {
+        match checker.fault(index, x_only_pk, signature)? {
+            None => {}
+            Some(SignatureFault::Transaction) => return Ok(Some(SignatureFault::Transaction)),
+            Some(fault) => worst = Some(fault),
+        }
+    }
+
+    Ok(worst)
+}
+
+/// The [`ControlBlock`] of the `locking_script` leaf of the `taproot_spend_info`.
+///
+/// # Errors
+///
+/// Errors if the `locking_script` is not a leaf of the `taproot_spend_info`.
+fn leaf_control_block(
     locking_script: &Script,
     taproot_spend_info: &TaprootSpendInfo,
-) -> Transaction {
+) -> Result<ControlBlock, Error> {
     let prevout_leaf = (ScriptBuf::from(locking_script), LeafVersion::TapScript);
-    let control_block = taproot_spend_info
-        .control_block(&prevout_leaf)
-        .expect("Unable to create Control block");
+    match taproot_spend_info.control_block(&prevout_leaf) {
+        Some(control_block) => Ok(control_block),
+        None => Err(Error::WrongInputs(
+            "The locking script is not a leaf of the escrow".to_string(),
+        )),
+    }
+}
+
+/// Constructs a script path spend [`Witness`] of a `locking_script` leaf
+/// with the `signature` of the keys of each [`SignerGroup`](crate::scripts::SignerGroup) of [`leaf_signers`].
+///
+/// Only the threshold number of signatures of each group are used, the others are left empty.
+/// Returns `None` if there are not enough signatures.
+fn leaf_witness(
+    locking_script: &Script,
+    control_block: &ControlBlock,
+    signature: impl Fn(&XOnlyPublicKey) -> Option<Vec<u8>>,
+) -> Option<Witness> {
+    let mut signatures = BTreeMap::new();
+    for group in leaf_signers(locking_script) {
+        let group_signatures = group
+            .keys
+            .iter()
+            .filter_map(|x_only_pk| Some((*x_only_pk, signature(x_only_pk)?)))
+            .take(group.threshold)
+            .collect::<Vec<_>>();
+        if group_signatures.len() < group.threshold {
+            return None;
+        }
+        signatures.extend(group_signatures);
+    }
+
+    // The last key in the script is checked last, hence its signature goes first.
+    Some(script_path_witness(
+        leaf_x_only_keys(locking_script)
+            .iter()
+            .rev()
+            .map(|x_only_pk| signatures.get(x_only_pk).cloned().unwrap_or_default()),
+        locking_script,
+        control_block,
+    ))
+}
 
+/// Constructs a script path spend [`Witness`] from the `signatures`,
+/// the `locking_script` and its [`ControlBlock`].
+fn script_path_witness<S: AsRef<[u8]>>(
+    signatures: impl IntoIterator<Item = S>,
+    locking_script: &Script,
+    control_block: &ControlBlock,
+) -> Witness {
     // Construct the witness stack
     let mut witness = Witness::new();
 
@@ -154,88 +617,813 @@ pub(crate) fn combine_signatures(
     }
 
     // Push locking script
-    witness.push(prevout_leaf.0.as_bytes());
+    witness.push(locking_script.as_bytes());
 
     // Push control block
     witness.push(control_block.serialize());
 
-    transaction.input[index].witness = witness;
-
-    transaction
+    witness
 }
 
-#[cfg(test)]
-mod tests {
-    use std::sync::{LazyLock, Once};
-
-    use bitcoin::{
-        Amount, BlockHash, Network, OutPoint, TxIn, absolute, consensus, hex::DisplayHex,
-        transaction,
-    };
-
-    use corepc_node::Node;
-    use dioxus::logger::tracing::{debug, info};
-    use nostr::nips::nip21::ToNostrUri;
-    use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+/// Size of a BIP340 Schnorr signature with the default sighash type.
+const SCHNORR_SIGNATURE_SIZE: usize = 64;
 
-    use crate::{
-        scripts::{escrow_address, escrow_spend_info},
-        tx::escrow_tx,
-        util::{npub_to_address, npub_to_x_only_public_key},
-    };
+/// Constructs a [`Witness`] spending the `escrow_script` of an [`EscrowContract`] with placeholder signatures.
+///
+/// It has the exact size of the final witness, including the control block of the leaf,
+/// hence the weight of the transaction is known before it is signed.
+/// The cooperative [`EscrowScript::A`] of a [`InternalKey::MuSig2`] contract is spent through the key path.
+///
+/// # Errors
+///
+/// Errors if the `escrow_script` is not a leaf of the `contract`.
+pub(crate) fn dummy_contract_witness(
+    contract: &EscrowContract,
+    escrow_script: EscrowScript,
+) -> Result<Witness, Error> {
+    if escrow_script == EscrowScript::A && contract.internal_key() == InternalKey::MuSig2 {
+        return Ok(dummy_key_path_witness());
+    }
+    let locking_script = contract_scripts(contract, escrow_script)?;
+    let control_block = contract_spend_info(contract)?
+        .control_block(&(locking_script.clone(), LeafVersion::TapScript))
+        .ok_or_else(|| Error::InvalidEscrowType(format!("{escrow_script:?}")))?;
+
+    Ok(leaf_witness(&locking_script, &control_block, |_| {
+        Some(vec![0; SCHNORR_SIGNATURE_SIZE])
+    })
+    .expect("every key has a signature"))
+}
 
-    use super::*;
+/// Constructs a key path spend [`Witness`] with a placeholder signature.
+pub(crate) fn dummy_key_path_witness() -> Witness {
+    Witness::from_slice(&[[0; SCHNORR_SIGNATURE_SIZE]])
+}
 
-    static COINBASE_AMOUNT: LazyLock<Amount> = LazyLock::new(|| Amount::from_btc(50.0).unwrap());
-    const FEE: Amount = Amount::from_sat(1_000);
-    static MULTISIG_AMOUNT: LazyLock<Amount> = LazyLock::new(|| *COINBASE_AMOUNT - FEE);
-    // static ESCROW_AMOUNT: LazyLock<Amount> = LazyLock::new(|| *MULTISIG_AMOUNT - FEE);
-    const COINBASE_MATURITY: usize = 101;
+/// Gets the `witness_utxo` of every input of a [`Psbt`] that spends an [`EscrowContract`].
+///
+/// Each must pay to the address of the [`EscrowContract`], recomputed rather than trusted from the [`Psbt`].
+///
+/// # Errors
+///
+/// Errors if an input does not have a `witness_utxo` or if it does not pay to the escrow address.
+pub(crate) fn escrow_psbt_prevouts(
+    psbt: &Psbt,
+    contract: &EscrowContract,
+    network: Network,
+) -> Result<Vec<TxOut>, Error> {
+    let prevouts = psbt_prevouts(psbt)?;
+    let escrow_script = contract_address(contract, network)?.script_pubkey();
+    if let Some(index) = prevouts
+        .iter()
+        .position(|prevout| prevout.script_pubkey != escrow_script)
+    {
+        return Err(Error::PrevoutNotEscrow(index));
+    }
+    Ok(prevouts)
+}
 
-    // Generated by https://nostrtool.com
-    // const NSEC_1: &str = "nsec1hufm8kzq0c4l9zsja7daynm47mfq2fkn38cm38yrpjmv6zctz2ysjmqw36";
-    // const NPUB_1: &str = "npub1nckhhhcxm8usszvxt6yku6efp4fpay3saglx6yhtu8pfv3kdqhqsfn0vd7";
-    // const NSEC_2: &str = "nsec1svda3gyta75ny0t7aqqv9ldh0hazt89qc48jjgw8wkv5wy9w6fgq34wv4z";
-    // const NPUB_2: &str = "npub1xy4xk87gglf4psv3lr7aymvs09e44fq0zxcf6kc43lawusvz3cts270an7";
+/// Signs every escrow leaf of a [`Psbt`] that the [`NostrSecretKey`] is a key of.
+///
+/// The `witness_utxo` of every input must pay to the address of the [`EscrowContract`],
+/// recomputed rather than trusted from the [`Psbt`], see [`check_psbt_prevouts`] for their amounts.
+///
+/// The signatures are added to the `tap_script_sigs` of each input,
+/// so that the [`Psbt`] can be combined with the other parties' [`Psbt`]s
+/// and finalized with [`finalize_escrow_psbt`].
+///
+/// Returns the number of signatures added.
+///
+/// # Errors
+///
+/// Errors if an input does not have a `witness_utxo`, if it does not pay to the escrow address,
+/// or if the sighash could not be computed.
+pub(crate) fn sign_escrow_psbt(
+    psbt: &mut Psbt,
+    nsec: &NostrSecretKey,
+    contract: &EscrowContract,
+    network: Network,
+) -> Result<usize, Error> {
+    // Parse nsec to a bitcoin secret key.
+    let keypair = nsec.keypair(SECP256K1);
+    let (x_only_pk, _) = keypair.x_only_public_key();
+
+    let prevouts = escrow_psbt_prevouts(psbt, contract, network)?;
+
+    let mut sighash_cache = SighashCache::new(&psbt.unsigned_tx);
+    let mut signatures = 0;
+    for (index, input) in psbt.inputs.iter_mut().enumerate() {
+        let sighash_type = match input.sighash_type {
+            Some(sighash_type) => sighash_type
+                .taproot_hash_ty()
+                .map_err(|e| Error::WrongInputs(e.to_string()))?,
+            None => TapSighashType::Default,
+        };
 
-    fn generate_nostr_keys() -> (NostrSecretKey, NostrPublicKey) {
-        let nsec = NostrSecretKey::generate();
-        let npub: NostrPublicKey = nsec.public_key(SECP256K1).x_only_public_key().0.into();
-        trace!(derived_npub = %npub.to_nostr_uri().unwrap());
-        (nsec, npub)
+        for (locking_script, leaf_version) in input.tap_scripts.values() {
+            if !leaf_x_only_keys(locking_script).contains(&x_only_pk) {
+                continue;
+            }
+            // The leaves that the transaction can never spend are not signed.
+            if check_timelock(&psbt.unsigned_tx, index, leaf_timelock(locking_script)).is_err() {
+                continue;
+            }
+            let leaf_hash = TapLeafHash::from_script(locking_script, *leaf_version);
+            let sighash = sighash_cache.taproot_script_spend_signature_hash(
+                index,
+                &Prevouts::All(&prevouts),
+                leaf_hash,
+                sighash_type,
+            )?;
+            let message = Message::from_digest(*sighash.as_byte_array());
+
+            // For script path, we use the UNTWEAKED keypair.
+            let signature = SECP256K1.sign_schnorr_no_aux_rand(&message, &keypair);
+            #[cfg(debug_assertions)]
+            trace!(%index, %leaf_hash, %signature, "Signature escrow PSBT");
+
+            input.tap_script_sigs.insert(
+                (x_only_pk, leaf_hash),
+                taproot::Signature {
+                    signature,
+                    sighash_type,
+                },
+            );
+            signatures += 1;
+        }
     }
 
-    // Only attempt to start tracing once
-    fn init_tracing() {
-        static INIT: Once = Once::new();
+    Ok(signatures)
+}
 
-        INIT.call_once(|| {
-            tracing_subscriber::registry()
-                .with(fmt::layer())
-                .with(EnvFilter::from_default_env())
-                .try_init()
-                .ok();
+/// Signs every input of a collaborative funding [`Psbt`] that spends the resolution address
+/// of the [`NostrSecretKey`] by its key path, see [`funding_psbt`](crate::tx::funding_psbt).
+///
+/// The signatures are added to the `tap_key_sig` of each input,
+/// so that the [`Psbt`] can be combined with the other parties' [`Psbt`]s
+/// and finalized with [`finalize_escrow_psbt`].
+///
+/// Returns the number of signatures added.
+///
+/// # Errors
+///
+/// Errors if an input does not have a `witness_utxo` or if the sighash could not be computed.
+pub(crate) fn sign_funding_psbt(psbt: &mut Psbt, nsec: &NostrSecretKey) -> Result<usize, Error> {
+    let keypair = nsec.keypair(SECP256K1);
+    let (x_only_pk, _) = keypair.x_only_public_key();
+    // The resolution address has no script path, see `npub_to_address`.
+    let script_pubkey = ScriptBuf::new_p2tr(SECP256K1, x_only_pk, None);
+    let tweaked = keypair.tap_tweak(SECP256K1, None).to_keypair();
+
+    let prevouts = psbt_prevouts(psbt)?;
+
+    let mut sighash_cache = SighashCache::new(&psbt.unsigned_tx);
+    let mut signatures = 0;
+    for (index, (input, prevout)) in psbt.inputs.iter_mut().zip(&prevouts).enumerate() {
+        if prevout.script_pubkey != script_pubkey {
+            continue;
+        }
+        let sighash = sighash_cache.taproot_key_spend_signature_hash(
+            index,
+            &Prevouts::All(&prevouts),
+            TapSighashType::Default,
+        )?;
+        let message = Message::from_digest(*sighash.as_byte_array());
+        let signature = SECP256K1.sign_schnorr_no_aux_rand(&message, &tweaked);
+        #[cfg(debug_assertions)]
+        trace!(%index, %signature, "Signature funding PSBT");
+
+        input.tap_key_sig = Some(taproot::Signature {
+            signature,
+            sighash_type: TapSighashType::Default,
         });
+        signatures += 1;
     }
 
-    #[test]
-    fn sign_collaborative_tx_flow() {
-        init_tracing();
+    Ok(signatures)
+}
 
-        // Setup regtest node and clients.
-        let bitcoind = Node::from_downloaded().unwrap();
-        let btc_client = &bitcoind.client;
+/// Looks up the prevout of every input of a [`Transaction`], in input order,
+/// among the `spent_outputs`, see [`parse_spent_outputs`](crate::util::parse_spent_outputs).
+///
+/// Every prevout must pay to the `escrow_script`, so that a wrong amount or a wrong escrow
+/// is caught before signing rather than at broadcast.
+///
+/// # Errors
+///
+/// Errors if the prevout of an input is missing or does not pay to the `escrow_script`.
+pub(crate) fn escrow_prevouts(
+    tx: &Transaction,
+    spent_outputs: &BTreeMap<OutPoint, TxOut>,
+    escrow_script: &Script,
+) -> Result<Vec<TxOut>, Error> {
+    tx.input
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            let prevout = spent_outputs
+                .get(&input.previous_output)
+                .ok_or(Error::MissingPrevout(index))?;
+            if prevout.script_pubkey.as_script() != escrow_script {
+                return Err(Error::PrevoutNotEscrow(index));
+            }
+            Ok(prevout.clone())
+        })
+        .collect()
+}
 
-        // Get network.
-        let network = btc_client
-            .get_blockchain_info()
-            .expect("must get blockchain info")
-            .chain;
-        let network = network.parse::<Network>().expect("network must be valid");
+/// Checks the `witness_utxo` of every input of a [`Psbt`] against the output that it spends
+/// among the `spent_outputs`, e.g. of the funding transactions fetched from Esplora,
+/// so that a [`Psbt`] cannot misstate the amounts that its signatures commit to.
+///
+/// # Errors
+///
+/// Errors if an input does not have a `witness_utxo`, if the output that it spends is missing,
+/// or if they differ.
+pub(crate) fn check_psbt_prevouts(
+    psbt: &Psbt,
+    spent_outputs: &BTreeMap<OutPoint, TxOut>,
+) -> Result<(), Error> {
+    for (index, (input, prevout)) in psbt
+        .unsigned_tx
+        .input
+        .iter()
+        .zip(psbt_prevouts(psbt)?)
+        .enumerate()
+    {
+        let spent_output = spent_outputs
+            .get(&input.previous_output)
+            .ok_or(Error::MissingPrevout(index))?;
+        if *spent_output != prevout {
+            return Err(Error::PrevoutMismatch(index));
+        }
+    }
 
-        // Generate nsec and npub.
-        let (nsec_1, npub_1) = generate_nostr_keys();
-        let (nsec_2, npub_2) = generate_nostr_keys();
+    Ok(())
+}
+
+/// Collects the `witness_utxo` of every input of a [`Psbt`].
+///
+/// # Errors
+///
+/// Errors if an input has no `witness_utxo`.
+pub(crate) fn psbt_prevouts(psbt: &Psbt) -> Result<Vec<TxOut>, Error> {
+    psbt.inputs
+        .iter()
+        .enumerate()
+        .map(|(index, input)| {
+            input
+                .witness_utxo
+                .clone()
+                .ok_or(Error::MissingWitnessUtxo(index))
+        })
+        .collect()
+}
+
+/// Verifies the `tap_script_sigs` of every input of an escrow [`Psbt`] against the sighash
+/// of their leaf with the prevouts of the [`Psbt`], see [`verify_contract_signatures`].
+///
+/// A signature of a leaf that the input does not have is for another spending condition.
+///
+/// # Errors
+///
+/// Errors if an input with signatures does not have a `witness_utxo`, or if the sighash could not be computed.
+pub(crate) fn verify_escrow_psbt(psbt: &Psbt) -> Result<Vec<InvalidSignature>, Error> {
+    Ok(escrow_psbt_faults(psbt)?
+        .into_iter()
+        .map(|(invalid, _)| invalid)
+        .collect())
+}
+
+/// The [`InvalidSignature`]s of the `tap_script_sigs` of an escrow [`Psbt`], with their leaf.
+fn escrow_psbt_faults(psbt: &Psbt) -> Result<Vec<(InvalidSignature, TapLeafHash)>, Error> {
+    // Key path spends, e.g. of a collaborative funding PSBT, need no prevouts here.
+    if psbt
+        .inputs
+        .iter()
+        .all(|input| input.tap_script_sigs.is_empty())
+    {
+        return Ok(Vec::new());
+    }
+    let prevouts = psbt_prevouts(psbt)?;
+
+    let mut invalid = Vec::new();
+    for (index, input) in psbt.inputs.iter().enumerate() {
+        for (&(x_only_pk, leaf_hash), signature) in &input.tap_script_sigs {
+            let leaves = input.tap_scripts.values().map(|(leaf, _)| leaf);
+            let locking_script = input.tap_scripts.values().find(|(leaf, leaf_version)| {
+                TapLeafHash::from_script(leaf, *leaf_version) == leaf_hash
+            });
+            let fault = match locking_script {
+                Some((locking_script, _)) => {
+                    SignatureChecker::new(&psbt.unsigned_tx, &prevouts, locking_script, leaves)?
+                        .with_sighash_type(signature.sighash_type)
+                        .fault(index, &x_only_pk, &signature.signature)?
+                }
+                None => Some(SignatureFault::Leaf),
+            };
+            let Some(fault) = fault else {
+                continue;
+            };
+            #[cfg(debug_assertions)]
+            trace!(%index, %x_only_pk, ?fault, "Invalid PSBT signature");
+            invalid.push((
+                InvalidSignature {
+                    index,
+                    x_only_pk,
+                    fault,
+                },
+                leaf_hash,
+            ));
+        }
+    }
+
+    Ok(invalid)
+}
+
+/// Finalizes a combined escrow [`Psbt`] into a signed [`Transaction`].
+///
+/// For every input, the shallowest leaf in the Taproot tree that has enough valid signatures
+/// in `tap_script_sigs` is used to construct the witness,
+/// unless it is a key path spend with a `tap_key_sig`, e.g. of a collaborative funding [`Psbt`].
+/// Invalid signatures are skipped, see [`verify_escrow_psbt`].
+///
+/// # Errors
+///
+/// Errors if an input with signatures does not have a `witness_utxo`,
+/// or if an input does not have enough valid signatures for any of its leaves.
+pub(crate) fn finalize_escrow_psbt(mut psbt: Psbt) -> Result<Transaction, Error> {
+    for (invalid, leaf_hash) in escrow_psbt_faults(&psbt)? {
+        psbt.inputs[invalid.index]
+            .tap_script_sigs
+            .remove(&(invalid.x_only_pk, leaf_hash));
+    }
+
+    for (index, input) in psbt.inputs.iter_mut().enumerate() {
+        if input.final_script_witness.is_some() {
+            continue;
+        }
+        if let Some(signature) = input.tap_key_sig {
+            *input = psbt::Input {
+                witness_utxo: input.witness_utxo.take(),
+                non_witness_utxo: input.non_witness_utxo.take(),
+                final_script_witness: Some(Witness::from_slice(&[signature.to_vec()])),
+                ..Default::default()
+            };
+            continue;
+        }
+
+        // Shallower leaves have smaller control blocks.
+        let mut leaves = input
+            .tap_scripts
+            .iter()
+            .filter(|(_, (locking_script, _))| {
+                check_timelock(&psbt.unsigned_tx, index, leaf_timelock(locking_script)).is_ok()
+            })
+            .collect::<Vec<_>>();
+        leaves.sort_by_key(|(control_block, _)| control_block.size());
+
+        let witness = leaves
+            .into_iter()
+            .find_map(|(control_block, (locking_script, leaf_version))| {
+                let leaf_hash = TapLeafHash::from_script(locking_script, *leaf_version);
+                leaf_witness(locking_script, control_block, |x_only_pk| {
+                    input
+                        .tap_script_sigs
+                        .get(&(*x_only_pk, leaf_hash))
+                        .map(|signature| signature.to_vec())
+                })
+            })
+            .ok_or(Error::MissingSignatures(index))?;
+        #[cfg(debug_assertions)]
+        trace!(%index, witness_len = %witness.len(), "Finalized escrow PSBT input");
+
+        // Clear everything but the UTXOs and the final witness (BIP174 Input Finalizer).
+        *input = psbt::Input {
+            witness_utxo: input.witness_utxo.take(),
+            non_witness_utxo: input.non_witness_utxo.take(),
+            final_script_witness: Some(witness),
+            ..Default::default()
+        };
+    }
+
+    Ok(psbt.extract_tx().map_err(Box::new)?)
+}
+
+/// Creates the BIP327 MuSig2 [`KeyAggContext`] that signs the key path of an escrow
+/// with an [`InternalKey::MuSig2`](crate::scripts::InternalKey::MuSig2) internal key.
+///
+/// The aggregate of `npub_1` and `npub_2` is tweaked with the escrow `merkle_root` (BIP341),
+/// hence it signs for the escrow output key.
+///
+/// # Errors
+///
+/// Errors if `internal_key` is not the MuSig2 aggregate of the parties.
+pub(crate) fn musig2_signing_ctx(
+    npub_1: &NostrPublicKey,
+    npub_2: &NostrPublicKey,
+    internal_key: XOnlyPublicKey,
+    merkle_root: Option<TapNodeHash>,
+) -> Result<KeyAggContext, Error> {
+    let key_agg_ctx = musig2_key_agg_ctx(npub_1, npub_2)?;
+    let aggregated_pubkey: PublicKey = key_agg_ctx.aggregated_pubkey_untweaked();
+    if aggregated_pubkey.x_only_public_key().0 != internal_key {
+        return Err(Error::WrongInputs(
+            "The escrow internal key is not the MuSig2 aggregate of the parties' keys".to_string(),
+        ));
+    }
+
+    let key_agg_ctx = match merkle_root {
+        Some(merkle_root) => key_agg_ctx.with_taproot_tweak(merkle_root.as_byte_array())?,
+        None => key_agg_ctx.with_unspendable_taproot_tweak()?,
+    };
+    Ok(key_agg_ctx)
+}
+
+/// Creates the [`musig2_signing_ctx`] of the escrow input `index` of a [`Psbt`],
+/// from its `tap_internal_key` and `tap_merkle_root`, along with the prevouts of the [`Psbt`].
+///
+/// # Errors
+///
+/// Errors if the input does not have a `tap_internal_key` or any input does not have a `witness_utxo`,
+/// or if the internal key is not the MuSig2 aggregate of the parties.
+pub(crate) fn musig2_psbt_ctx(
+    psbt: &Psbt,
+    index: usize,
+    npub_1: &NostrPublicKey,
+    npub_2: &NostrPublicKey,
+) -> Result<(KeyAggContext, Vec<TxOut>), Error> {
+    let input = match psbt.inputs.get(index) {
+        Some(input) => input,
+        None => return Err(Error::WrongInputs(format!("PSBT has no input {index}"))),
+    };
+    let internal_key = match input.tap_internal_key {
+        Some(internal_key) => internal_key,
+        None => {
+            return Err(Error::WrongInputs(format!(
+                "PSBT input {index} has no Taproot internal key"
+            )));
+        }
+    };
+    let key_agg_ctx = musig2_signing_ctx(npub_1, npub_2, internal_key, input.tap_merkle_root)?;
+
+    Ok((key_agg_ctx, psbt_prevouts(psbt)?))
+}
+
+/// Starts a MuSig2 signing session of the escrow key path, given an input `index`.
+///
+/// Returns a fresh random [`SecNonce`]: its [`PubNonce`] must be sent to the counterparty,
+/// which answers with [`musig2_partial_sign`].
+/// The [`SecNonce`] must never leave this device and must be used once, in [`musig2_aggregate`].
+///
+/// # Errors
+///
+/// Errors if the sighash could not be computed.
+pub(crate) fn musig2_nonce(
+    tx: &Transaction,
+    index: usize,
+    nsec: &NostrSecretKey,
+    key_agg_ctx: &KeyAggContext,
+    prevouts: &[TxOut],
+) -> Result<SecNonce, Error> {
+    let seckey = musig2_seckey(nsec);
+    let message = key_path_sighash(tx, index, prevouts)?;
+    let aggregated_pubkey: PublicKey = key_agg_ctx.aggregated_pubkey();
+
+    let sec_nonce = SecNonceBuilder::from_seckey(rand::random::<[u8; 32]>(), seckey)
+        .with_aggregated_pubkey(aggregated_pubkey)
+        .with_message(&message)
+        .build();
+    #[cfg(debug_assertions)]
+    trace!(%index, pub_nonce = %sec_nonce.public_nonce(), "MuSig2 nonce");
+
+    Ok(sec_nonce)
+}
+
+/// Answers a MuSig2 signing session started by the counterparty with [`musig2_nonce`],
+/// given an input `index` and their [`PubNonce`].
+///
+/// Our nonce is derived from the secret key, the sighash and `their_pub_nonce`
+/// (as BIP327 `DeterministicSign`), hence no state has to be kept and it is never reused
+/// with a different counterparty nonce.
+///
+/// Returns our [`PubNonce`] and [`PartialSignature`], that must be sent back to the counterparty.
+///
+/// # Errors
+///
+/// Errors if the sighash could not be computed or if `nsec` is not a key of `key_agg_ctx`.
+pub(crate) fn musig2_partial_sign(
+    tx: &Transaction,
+    index: usize,
+    nsec: &NostrSecretKey,
+    key_agg_ctx: &KeyAggContext,
+    prevouts: &[TxOut],
+    their_pub_nonce: &PubNonce,
+) -> Result<(PubNonce, PartialSignature), Error> {
+    let seckey = musig2_seckey(nsec);
+    let message = key_path_sighash(tx, index, prevouts)?;
+    let aggregated_pubkey: PublicKey = key_agg_ctx.aggregated_pubkey();
+    let their_pub_nonce_bytes = their_pub_nonce.serialize();
+
+    let sec_nonce = SecNonceBuilder::from_seckey(seckey.secret_bytes(), seckey)
+        .with_aggregated_pubkey(aggregated_pubkey)
+        .with_message(&message)
+        .with_extra_input(&their_pub_nonce_bytes)
+        .build();
+    let pub_nonce = sec_nonce.public_nonce();
+    let aggregated_nonce = AggNonce::sum([&pub_nonce, their_pub_nonce]);
+
+    let partial_signature: PartialSignature =
+        sign_partial(key_agg_ctx, seckey, sec_nonce, &aggregated_nonce, message)?;
+    #[cfg(debug_assertions)]
+    trace!(%index, %pub_nonce, "MuSig2 partial signature");
+
+    Ok((pub_nonce, partial_signature))
+}
+
+/// Finishes a MuSig2 signing session started with [`musig2_nonce`], given an input `index`.
+///
+/// Signs with our `sec_nonce`, verifies the counterparty [`PartialSignature`] and aggregates both
+/// into the key path spend [`Witness`] of the input.
+///
+/// # Errors
+///
+/// Errors if the sighash could not be computed, if `nsec` is not a key of `key_agg_ctx`
+/// or if the counterparty [`PartialSignature`] is not valid.
+#[expect(clippy::too_many_arguments)]
+pub(crate) fn musig2_aggregate(
+    mut tx: Transaction,
+    index: usize,
+    nsec: &NostrSecretKey,
+    key_agg_ctx: &KeyAggContext,
+    prevouts: &[TxOut],
+    sec_nonce: SecNonce,
+    their_pub_nonce: &PubNonce,
+    their_partial_signature: PartialSignature,
+) -> Result<Transaction, Error> {
+    let seckey = musig2_seckey(nsec);
+    let message = key_path_sighash(&tx, index, prevouts)?;
+    let pubkey = seckey.public_key(SECP256K1);
+    let their_pubkey = match key_agg_ctx
+        .pubkeys()
+        .iter()
+        .map(|pubkey| PublicKey::from(*pubkey))
+        .find(|their_pubkey| *their_pubkey != pubkey)
+    {
+        Some(pubkey) => pubkey,
+        None => {
+            return Err(Error::WrongInputs(
+                "The MuSig2 session has no counterparty".to_string(),
+            ));
+        }
+    };
+
+    let pub_nonce = sec_nonce.public_nonce();
+    let aggregated_nonce = AggNonce::sum([&pub_nonce, their_pub_nonce]);
+    let partial_signature: PartialSignature =
+        sign_partial(key_agg_ctx, seckey, sec_nonce, &aggregated_nonce, message)?;
+    verify_partial(
+        key_agg_ctx,
+        their_partial_signature,
+        &aggregated_nonce,
+        their_pubkey,
+        their_pub_nonce,
+        message,
+    )?;
+
+    let signature: schnorr::Signature = aggregate_partial_signatures(
+        key_agg_ctx,
+        &aggregated_nonce,
+        [partial_signature, their_partial_signature],
+        message,
+    )?;
+    #[cfg(debug_assertions)]
+    trace!(%index, %signature, txid = %tx.compute_txid(), "MuSig2 aggregated signature");
+
+    tx.input[index].witness = Witness::p2tr_key_spend(&taproot::Signature {
+        signature,
+        sighash_type: TapSighashType::Default,
+    });
+    Ok(tx)
+}
+
+/// Starts the MuSig2 signing sessions of the escrow key path of every input of a [`Psbt`],
+/// one [`musig2_nonce`] per input in input order.
+///
+/// # Errors
+///
+/// Errors if the [`musig2_psbt_ctx`] or the [`musig2_nonce`] of an input could not be created.
+pub(crate) fn musig2_psbt_nonces(
+    psbt: &Psbt,
+    nsec: &NostrSecretKey,
+    npub_1: &NostrPublicKey,
+    npub_2: &NostrPublicKey,
+) -> Result<Vec<SecNonce>, Error> {
+    (0..psbt.inputs.len())
+        .map(|index| {
+            let (key_agg_ctx, prevouts) = musig2_psbt_ctx(psbt, index, npub_1, npub_2)?;
+            musig2_nonce(&psbt.unsigned_tx, index, nsec, &key_agg_ctx, &prevouts)
+        })
+        .collect()
+}
+
+/// Answers the MuSig2 signing sessions of every input of a [`Psbt`] started with [`musig2_psbt_nonces`],
+/// one [`musig2_partial_sign`] per input with the counterparty [`PubNonce`] of that input.
+///
+/// Returns our [`PubNonce`]s and [`PartialSignature`]s in input order.
+///
+/// # Errors
+///
+/// Errors if there is not one counterparty [`PubNonce`] per input,
+/// or if the [`musig2_partial_sign`] of an input fails.
+pub(crate) fn musig2_psbt_partial_sign(
+    psbt: &Psbt,
+    nsec: &NostrSecretKey,
+    npub_1: &NostrPublicKey,
+    npub_2: &NostrPublicKey,
+    their_pub_nonces: &[PubNonce],
+) -> Result<(Vec<PubNonce>, Vec<PartialSignature>), Error> {
+    check_session_len(psbt, their_pub_nonces.len(), "public nonce")?;
+
+    their_pub_nonces
+        .iter()
+        .enumerate()
+        .map(|(index, their_pub_nonce)| {
+            let (key_agg_ctx, prevouts) = musig2_psbt_ctx(psbt, index, npub_1, npub_2)?;
+            musig2_partial_sign(
+                &psbt.unsigned_tx,
+                index,
+                nsec,
+                &key_agg_ctx,
+                &prevouts,
+                their_pub_nonce,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(|signed| signed.into_iter().unzip())
+}
+
+/// Finishes the MuSig2 signing sessions of every input of a [`Psbt`] started with [`musig2_psbt_nonces`],
+/// one [`musig2_aggregate`] per input, into the transaction with the key path spend of every input.
+///
+/// # Errors
+///
+/// Errors if there is not one [`SecNonce`], counterparty [`PubNonce`] and [`PartialSignature`] per input,
+/// or if the [`musig2_aggregate`] of an input fails.
+pub(crate) fn musig2_psbt_aggregate(
+    psbt: &Psbt,
+    nsec: &NostrSecretKey,
+    npub_1: &NostrPublicKey,
+    npub_2: &NostrPublicKey,
+    sec_nonces: Vec<SecNonce>,
+    their_pub_nonces: &[PubNonce],
+    their_partial_signatures: &[PartialSignature],
+) -> Result<Transaction, Error> {
+    check_session_len(psbt, sec_nonces.len(), "secret nonce")?;
+    check_session_len(psbt, their_pub_nonces.len(), "public nonce")?;
+    check_session_len(psbt, their_partial_signatures.len(), "partial signature")?;
+
+    // The key path sighashes do not commit to the witnesses of the other inputs.
+    sec_nonces
+        .into_iter()
+        .zip(their_pub_nonces)
+        .zip(their_partial_signatures)
+        .enumerate()
+        .try_fold(
+            psbt.unsigned_tx.clone(),
+            |tx, (index, ((sec_nonce, their_pub_nonce), their_partial_signature))| {
+                let (key_agg_ctx, prevouts) = musig2_psbt_ctx(psbt, index, npub_1, npub_2)?;
+                musig2_aggregate(
+                    tx,
+                    index,
+                    nsec,
+                    &key_agg_ctx,
+                    &prevouts,
+                    sec_nonce,
+                    their_pub_nonce,
+                    *their_partial_signature,
+                )
+            },
+        )
+}
+
+/// Checks that a MuSig2 session of a [`Psbt`] has one `item` per input, given the `count` of items.
+fn check_session_len(psbt: &Psbt, count: usize, item: &str) -> Result<(), Error> {
+    if count != psbt.inputs.len() {
+        return Err(Error::WrongInputs(format!(
+            "Expected one MuSig2 {item} per input. Got {count} for {} inputs",
+            psbt.inputs.len()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the secret key of `nsec` that matches its even Y public key,
+/// which is the one aggregated in [`musig2_key_agg_ctx`].
+fn musig2_seckey(nsec: &NostrSecretKey) -> SecretKey {
+    let keypair = nsec.keypair(SECP256K1);
+    match keypair.x_only_public_key().1 {
+        Parity::Even => keypair.secret_key(),
+        Parity::Odd => keypair.secret_key().negate(),
+    }
+}
+
+/// Computes the BIP341 key path sighash of the input `index` of `tx`.
+fn key_path_sighash(tx: &Transaction, index: usize, prevouts: &[TxOut]) -> Result<[u8; 32], Error> {
+    let mut sighash_cache = SighashCache::new(tx);
+    let sighash = sighash_cache.taproot_key_spend_signature_hash(
+        index,
+        &Prevouts::All(prevouts),
+        TapSighashType::Default,
+    )?;
+    Ok(sighash.to_byte_array())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{LazyLock, Once};
+
+    use bitcoin::{
+        Amount, BlockHash, FeeRate, Network, OutPoint, Sequence, TxIn, Txid, absolute, consensus,
+        hex::DisplayHex, relative, transaction,
+    };
+
+    use corepc_node::Node;
+    use dioxus::logger::tracing::{debug, info};
+    use nostr::nips::nip21::ToNostrUri;
+    use tracing_subscriber::{EnvFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+    use crate::{
+        scripts::{EscrowContract, Timelock, contract_address},
+        tx::{
+            DustPolicy, FeePolicy, FundingContribution, TxFee, Utxo, contract_psbt, funding_psbt,
+            refund_psbt, sweep_tx,
+        },
+        util::{npub_to_address, npub_to_x_only_public_key, parse_spent_outputs},
+    };
+
+    use super::*;
+
+    static COINBASE_AMOUNT: LazyLock<Amount> = LazyLock::new(|| Amount::from_btc(50.0).unwrap());
+    const FEE: Amount = Amount::from_sat(1_000);
+    static MULTISIG_AMOUNT: LazyLock<Amount> = LazyLock::new(|| *COINBASE_AMOUNT - FEE);
+    // static ESCROW_AMOUNT: LazyLock<Amount> = LazyLock::new(|| *MULTISIG_AMOUNT - FEE);
+    const COINBASE_MATURITY: usize = 101;
+
+    // Generated by https://nostrtool.com
+    // const NSEC_1: &str = "nsec1hufm8kzq0c4l9zsja7daynm47mfq2fkn38cm38yrpjmv6zctz2ysjmqw36";
+    // const NPUB_1: &str = "npub1nckhhhcxm8usszvxt6yku6efp4fpay3saglx6yhtu8pfv3kdqhqsfn0vd7";
+    // const NSEC_2: &str = "nsec1svda3gyta75ny0t7aqqv9ldh0hazt89qc48jjgw8wkv5wy9w6fgq34wv4z";
+    // const NPUB_2: &str = "npub1xy4xk87gglf4psv3lr7aymvs09e44fq0zxcf6kc43lawusvz3cts270an7";
+
+    /// The single output of `value` at index 0 of `txid` that funds an escrow.
+    fn single_funding(txid: Txid, value: Amount) -> [Utxo; 1] {
+        [Utxo {
+            outpoint: OutPoint { txid, vout: 0 },
+            value,
+            confirmed: true,
+        }]
+    }
+
+    fn generate_nostr_keys() -> (NostrSecretKey, NostrPublicKey) {
+        let nsec = NostrSecretKey::generate();
+        let npub: NostrPublicKey = nsec.public_key(SECP256K1).x_only_public_key().0.into();
+        trace!(derived_npub = %npub.to_nostr_uri().unwrap());
+        (nsec, npub)
+    }
+
+    // Only attempt to start tracing once
+    fn init_tracing() {
+        static INIT: Once = Once::new();
+
+        INIT.call_once(|| {
+            tracing_subscriber::registry()
+                .with(fmt::layer())
+                .with(EnvFilter::from_default_env())
+                .try_init()
+                .ok();
+        });
+    }
+
+    #[test]
+    fn sign_collaborative_tx_flow() {
+        init_tracing();
+
+        // Setup regtest node and clients.
+        let bitcoind = Node::from_downloaded().unwrap();
+        let btc_client = &bitcoind.client;
+
+        // Get network.
+        let network = btc_client
+            .get_blockchain_info()
+            .expect("must get blockchain info")
+            .chain;
+        let network = network.parse::<Network>().expect("network must be valid");
+
+        // Generate nsec and npub.
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
         // Get the xonly pks.
         let xonly_1 = nsec_1.x_only_public_key(SECP256K1).0;
         let xonly_2 = nsec_2.x_only_public_key(SECP256K1).0;
@@ -262,7 +1450,10 @@ mod tests {
             .compute_txid();
 
         // Send to the 2-of-2 multisig address.
-        let escrow_address = escrow_address(&npub_1, &npub_2, None, None, network).unwrap();
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let escrow_address = contract_address(&contract, network).unwrap();
         #[cfg(debug_assertions)]
         trace!(%escrow_address, "Escrow address");
 
@@ -292,7 +1483,7 @@ mod tests {
             value: *COINBASE_AMOUNT,
             script_pubkey: funded_address.script_pubkey(),
         };
-        let signed = sign_resolution_tx(&unsigned, &nsec_1, prevouts);
+        let signed = sign_resolution_tx(&unsigned, &nsec_1, &[prevouts]).unwrap();
         trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed funding");
 
         // Test if the transaction is valid.
@@ -306,17 +1497,18 @@ mod tests {
 
         // Spend from the escrow address.
         let escrow_type = EscrowScript::A;
-        let unsigned = escrow_tx(
-            &npub_1,
-            &npub_2,
-            None,
-            *MULTISIG_AMOUNT / 2,
-            *MULTISIG_AMOUNT / 2,
-            txid,
+        let unsigned = contract_psbt(
+            &contract,
+            escrow_type,
+            &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+            &single_funding(txid, *MULTISIG_AMOUNT),
             FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
             network,
         )
-        .unwrap();
+        .unwrap()
+        .unsigned_tx;
         trace!(transaction=%consensus::serialize(&unsigned).as_hex(), "Unsigned escrow transaction");
 
         let script_pubkey = escrow_address.script_pubkey();
@@ -324,33 +1516,25 @@ mod tests {
             value: *MULTISIG_AMOUNT,
             script_pubkey,
         };
-        let sig_1 = sign_escrow_tx(
+        let sig_1 = sign_contract_tx(
             &unsigned,
-            0,
             &nsec_1,
-            &npub_1,
-            &npub_2,
-            None,
-            None,
-            vec![prevouts.clone()],
+            &contract,
+            std::slice::from_ref(&prevouts),
             escrow_type,
         )
-        .unwrap();
-        let sig_2 = sign_escrow_tx(
+        .unwrap()[0];
+        let sig_2 = sign_contract_tx(
             &unsigned,
-            0,
             &nsec_2,
-            &npub_1,
-            &npub_2,
-            None,
-            None,
-            vec![prevouts.clone()],
+            &contract,
+            std::slice::from_ref(&prevouts),
             escrow_type,
         )
-        .unwrap();
+        .unwrap()[0];
 
         // Manually verify each signature
-        let locking_script = escrow_scripts(&npub_1, &npub_2, None, None, EscrowScript::A).unwrap();
+        let locking_script = contract_scripts(&contract, EscrowScript::A).unwrap();
         trace!(locking_script=%locking_script.to_asm_string(), "Locking script");
         let tap_leaf_hash = TapLeafHash::from_script(&locking_script, LeafVersion::TapScript);
         let sighash = SighashCache::new(&unsigned)
@@ -372,14 +1556,17 @@ mod tests {
 
         let script_ver = &(locking_script.clone(), LeafVersion::TapScript);
         trace!(locking_script=%script_ver.0.to_asm_string(), leaf_version=%script_ver.1, "Script version");
-        let taproot_spend_info = escrow_spend_info(&npub_1, &npub_2, None, None).unwrap();
-        let signed = combine_signatures(
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+        let signed = combine_contract_signatures(
             unsigned,
-            0,
-            vec![&sig_1, &sig_2],
+            &[BTreeMap::from([
+                (npub_to_x_only_public_key(&npub_1).unwrap(), sig_1),
+                (npub_to_x_only_public_key(&npub_2).unwrap(), sig_2),
+            ])],
             &locking_script,
             &taproot_spend_info,
-        );
+        )
+        .unwrap();
         trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed escrow");
         info!(total_size=%signed.total_size(), "Signed Script A resolution transaction");
         let result = btc_client.send_raw_transaction(&signed);
@@ -431,15 +1618,16 @@ mod tests {
             .compute_txid();
 
         // Send to the 2-of-2 multisig address.
-        let timelock_duration = 6;
-        let escrow_address = escrow_address(
+        let timelock_duration = relative::LockTime::from_height(6);
+        let contract = EscrowContract::two_party(
             &npub_1,
             &npub_2,
             Some(&npub_arb),
             Some(timelock_duration),
-            network,
+            InternalKey::Unspendable,
         )
         .unwrap();
+        let escrow_address = contract_address(&contract, network).unwrap();
         trace!(%escrow_address, "Escrow address");
 
         // Create the transaction.
@@ -468,7 +1656,7 @@ mod tests {
             value: *COINBASE_AMOUNT,
             script_pubkey: funded_address.script_pubkey(),
         };
-        let signed = sign_resolution_tx(&unsigned, &nsec_1, prevouts);
+        let signed = sign_resolution_tx(&unsigned, &nsec_1, &[prevouts]).unwrap();
         info!(total_size=%signed.total_size(), "Signed Script B resolution transaction");
         trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed funding");
 
@@ -483,17 +1671,18 @@ mod tests {
 
         // Spend from the escrow address.
         let escrow_type = EscrowScript::B;
-        let unsigned = escrow_tx(
-            &npub_1,
-            &npub_2,
-            Some(timelock_duration),
-            *MULTISIG_AMOUNT / 2,
-            *MULTISIG_AMOUNT / 2,
-            txid,
+        let unsigned = contract_psbt(
+            &contract,
+            escrow_type,
+            &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+            &single_funding(txid, *MULTISIG_AMOUNT),
             FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
             network,
         )
-        .unwrap();
+        .unwrap()
+        .unsigned_tx;
         trace!(transaction=%consensus::serialize(&unsigned).as_hex(), "Unsigned escrow transaction");
 
         let script_pubkey = escrow_address.script_pubkey();
@@ -501,40 +1690,25 @@ mod tests {
             value: *MULTISIG_AMOUNT,
             script_pubkey,
         };
-        let sig_1 = sign_escrow_tx(
+        let sig_1 = sign_contract_tx(
             &unsigned,
-            0,
             &nsec_1, // First participant
-            &npub_1,
-            &npub_2,
-            Some(&npub_arb),
-            Some(timelock_duration),
-            vec![prevouts.clone()],
+            &contract,
+            std::slice::from_ref(&prevouts),
             escrow_type,
         )
-        .unwrap();
-        let sig_2 = sign_escrow_tx(
+        .unwrap()[0];
+        let sig_2 = sign_contract_tx(
             &unsigned,
-            0,
             &nsec_arb, // Arbitrator
-            &npub_1,
-            &npub_2,
-            Some(&npub_arb),
-            Some(timelock_duration),
-            vec![prevouts.clone()],
+            &contract,
+            std::slice::from_ref(&prevouts),
             escrow_type,
         )
-        .unwrap();
+        .unwrap()[0];
 
         // Manually verify each signature
-        let locking_script = escrow_scripts(
-            &npub_1,
-            &npub_2,
-            Some(&npub_arb),
-            Some(timelock_duration),
-            escrow_type,
-        )
-        .unwrap();
+        let locking_script = contract_scripts(&contract, escrow_type).unwrap();
         trace!(locking_script=%locking_script.to_asm_string(), "Locking script");
         let tap_leaf_hash = TapLeafHash::from_script(&locking_script, LeafVersion::TapScript);
         let sighash = SighashCache::new(&unsigned)
@@ -556,15 +1730,17 @@ mod tests {
 
         let script_ver = &(locking_script.clone(), LeafVersion::TapScript);
         trace!(locking_script=%script_ver.0.to_asm_string(), leaf_version=%script_ver.1, "Script version");
-        let taproot_spend_info =
-            escrow_spend_info(&npub_1, &npub_2, Some(&npub_arb), Some(timelock_duration)).unwrap();
-        let signed = combine_signatures(
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+        let signed = combine_contract_signatures(
             unsigned,
-            0,
-            vec![&sig_1, &sig_2],
+            &[BTreeMap::from([
+                (npub_to_x_only_public_key(&npub_1).unwrap(), sig_1),
+                (npub_to_x_only_public_key(&npub_arb).unwrap(), sig_2),
+            ])],
             &locking_script,
             &taproot_spend_info,
-        );
+        )
+        .unwrap();
         trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed escrow");
 
         // First try to broadcast the transaction without the timelock has reached
@@ -573,7 +1749,10 @@ mod tests {
 
         // Now let's move timelock_duration - 1 blocks and should pass
         btc_client
-            .generate_to_address(timelock_duration as usize - 1, &funded_address)
+            .generate_to_address(
+                timelock_duration.to_consensus_u32() as usize - 1,
+                &funded_address,
+            )
             .unwrap();
         let result = btc_client.send_raw_transaction(&signed);
         assert!(result.is_ok());
@@ -624,15 +1803,16 @@ mod tests {
             .compute_txid();
 
         // Send to the 2-of-2 multisig address.
-        let timelock_duration = 6;
-        let escrow_address = escrow_address(
+        let timelock_duration = relative::LockTime::from_height(6);
+        let contract = EscrowContract::two_party(
             &npub_1,
             &npub_2,
             Some(&npub_arb),
             Some(timelock_duration),
-            network,
+            InternalKey::Unspendable,
         )
         .unwrap();
+        let escrow_address = contract_address(&contract, network).unwrap();
         trace!(%escrow_address, "Escrow address");
 
         // Create the transaction.
@@ -661,7 +1841,7 @@ mod tests {
             value: *COINBASE_AMOUNT,
             script_pubkey: funded_address.script_pubkey(),
         };
-        let signed = sign_resolution_tx(&unsigned, &nsec_1, prevouts);
+        let signed = sign_resolution_tx(&unsigned, &nsec_1, &[prevouts]).unwrap();
         trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed funding");
         info!(total_size=%signed.total_size(), "Signed Script C resolution transaction");
 
@@ -676,17 +1856,18 @@ mod tests {
 
         // Spend from the escrow address.
         let escrow_type = EscrowScript::C;
-        let unsigned = escrow_tx(
-            &npub_1,
-            &npub_2,
-            Some(timelock_duration),
-            *MULTISIG_AMOUNT / 2,
-            *MULTISIG_AMOUNT / 2,
-            txid,
+        let unsigned = contract_psbt(
+            &contract,
+            escrow_type,
+            &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+            &single_funding(txid, *MULTISIG_AMOUNT),
             FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
             network,
         )
-        .unwrap();
+        .unwrap()
+        .unsigned_tx;
         trace!(transaction=%consensus::serialize(&unsigned).as_hex(), "Unsigned escrow transaction");
 
         let script_pubkey = escrow_address.script_pubkey();
@@ -694,40 +1875,25 @@ mod tests {
             value: *MULTISIG_AMOUNT,
             script_pubkey,
         };
-        let sig_1 = sign_escrow_tx(
+        let sig_1 = sign_contract_tx(
             &unsigned,
-            0,
             &nsec_2, // Second participant
-            &npub_1,
-            &npub_2,
-            Some(&npub_arb),
-            Some(timelock_duration),
-            vec![prevouts.clone()],
+            &contract,
+            std::slice::from_ref(&prevouts),
             escrow_type,
         )
-        .unwrap();
-        let sig_2 = sign_escrow_tx(
+        .unwrap()[0];
+        let sig_2 = sign_contract_tx(
             &unsigned,
-            0,
             &nsec_arb, // Arbitrator
-            &npub_1,
-            &npub_2,
-            Some(&npub_arb),
-            Some(timelock_duration),
-            vec![prevouts.clone()],
+            &contract,
+            std::slice::from_ref(&prevouts),
             escrow_type,
         )
-        .unwrap();
+        .unwrap()[0];
 
         // Manually verify each signature
-        let locking_script = escrow_scripts(
-            &npub_1,
-            &npub_2,
-            Some(&npub_arb),
-            Some(timelock_duration),
-            escrow_type,
-        )
-        .unwrap();
+        let locking_script = contract_scripts(&contract, escrow_type).unwrap();
         trace!(locking_script=%locking_script.to_asm_string(), "Locking script");
         let tap_leaf_hash = TapLeafHash::from_script(&locking_script, LeafVersion::TapScript);
         let sighash = SighashCache::new(&unsigned)
@@ -749,15 +1915,17 @@ mod tests {
 
         let script_ver = &(locking_script.clone(), LeafVersion::TapScript);
         trace!(locking_script=%script_ver.0.to_asm_string(), leaf_version=%script_ver.1, "Script version");
-        let taproot_spend_info =
-            escrow_spend_info(&npub_1, &npub_2, Some(&npub_arb), Some(timelock_duration)).unwrap();
-        let signed = combine_signatures(
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+        let signed = combine_contract_signatures(
             unsigned,
-            0,
-            vec![&sig_1, &sig_2],
+            &[BTreeMap::from([
+                (npub_to_x_only_public_key(&npub_2).unwrap(), sig_1),
+                (npub_to_x_only_public_key(&npub_arb).unwrap(), sig_2),
+            ])],
             &locking_script,
             &taproot_spend_info,
-        );
+        )
+        .unwrap();
         trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed escrow");
 
         // First try to broadcast the transaction without the timelock has reached
@@ -766,9 +1934,1755 @@ mod tests {
 
         // Now let's move timelock_duration - 1 blocks and should pass
         btc_client
-            .generate_to_address(timelock_duration as usize - 1, &funded_address)
+            .generate_to_address(
+                timelock_duration.to_consensus_u32() as usize - 1,
+                &funded_address,
+            )
             .unwrap();
         let result = btc_client.send_raw_transaction(&signed);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn sign_sweep_tx() {
+        let (nsec, npub) = generate_nostr_keys();
+        let address = npub_to_address(&npub, Network::Regtest).unwrap();
+        let utxos = [1, 2].map(|vout| Utxo {
+            outpoint: OutPoint {
+                txid: Txid::from_byte_array([vout as u8; 32]),
+                vout,
+            },
+            value: Amount::from_sat(10_000 * vout as u64),
+            confirmed: true,
+        });
+        let unsigned = sweep_tx(&utxos, &address, Amount::from_sat(500)).unwrap();
+        let prevouts = utxos.map(|utxo| TxOut {
+            value: utxo.value,
+            script_pubkey: address.script_pubkey(),
+        });
+
+        // Every input is signed by the tweaked key of the resolution address.
+        let signed = sign_resolution_tx(&unsigned, &nsec, &prevouts).unwrap();
+        let (output_key, _) = npub_to_x_only_public_key(&npub)
+            .unwrap()
+            .tap_tweak(SECP256K1, None);
+        let mut sighasher = SighashCache::new(&unsigned);
+        for (index, input) in signed.input.iter().enumerate() {
+            let sighash = sighasher
+                .taproot_key_spend_signature_hash(
+                    index,
+                    &Prevouts::All(&prevouts),
+                    TapSighashType::Default,
+                )
+                .unwrap();
+            let signature = schnorr::Signature::from_slice(input.witness.nth(0).unwrap()).unwrap();
+            SECP256K1
+                .verify_schnorr(
+                    &signature,
+                    &Message::from_digest(*sighash.as_byte_array()),
+                    &output_key.to_x_only_public_key(),
+                )
+                .unwrap();
+        }
+
+        // Every input needs its prevout.
+        assert!(matches!(
+            sign_resolution_tx(&unsigned, &nsec, &prevouts[..1]),
+            Err(Error::WrongInputs(_))
+        ));
+    }
+
+    #[test]
+    fn sign_collaborative_psbt_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb, _) = generate_nostr_keys();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+            &single_funding(funding_txid, *MULTISIG_AMOUNT),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+
+        // Each party signs its own copy of the PSBT.
+        let mut psbt_1 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_1, &nsec_1, &contract, network).unwrap(),
+            1
+        );
+        let mut psbt_2 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_2, &nsec_2, &contract, network).unwrap(),
+            1
+        );
+        // The arbitrator is not part of the collaborative escrow.
+        let mut psbt_arb = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_arb, &nsec_arb, &contract, network).unwrap(),
+            0
+        );
+
+        // A single signature is not enough.
+        assert!(matches!(
+            finalize_escrow_psbt(psbt_1.clone()),
+            Err(Error::MissingSignatures(0))
+        ));
+
+        psbt_1.combine(psbt_2).unwrap();
+        let signed = finalize_escrow_psbt(psbt_1).unwrap();
+        trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed escrow PSBT");
+
+        // Must be the same as signing and combining the raw transaction.
+        let prevouts = unsigned.inputs[0].witness_utxo.clone().unwrap();
+        let sig_1 = sign_contract_tx(
+            &unsigned.unsigned_tx,
+            &nsec_1,
+            &contract,
+            std::slice::from_ref(&prevouts),
+            EscrowScript::A,
+        )
+        .unwrap()[0];
+        let sig_2 = sign_contract_tx(
+            &unsigned.unsigned_tx,
+            &nsec_2,
+            &contract,
+            std::slice::from_ref(&prevouts),
+            EscrowScript::A,
+        )
+        .unwrap()[0];
+        let locking_script = contract_scripts(&contract, EscrowScript::A).unwrap();
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+        let expected = combine_contract_signatures(
+            unsigned.unsigned_tx,
+            &[BTreeMap::from([
+                (npub_to_x_only_public_key(&npub_1).unwrap(), sig_1),
+                (npub_to_x_only_public_key(&npub_2).unwrap(), sig_2),
+            ])],
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        assert_eq!(signed, expected);
+    }
+
+    #[test]
+    fn sign_dispute_psbt_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (_, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let timelock_duration = relative::LockTime::from_height(6);
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        let contract = EscrowContract::two_party(
+            &npub_1,
+            &npub_2,
+            Some(&npub_arb),
+            Some(timelock_duration),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::C,
+            &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+            &single_funding(funding_txid, *MULTISIG_AMOUNT),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+
+        // The second party is in leaves A and C, the arbitrator in leaves B and C.
+        let mut psbt_2 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_2, &nsec_2, &contract, network).unwrap(),
+            2
+        );
+        let mut psbt_arb = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_arb, &nsec_arb, &contract, network).unwrap(),
+            2
+        );
+
+        psbt_2.combine(psbt_arb).unwrap();
+        let signed = finalize_escrow_psbt(psbt_2).unwrap();
+
+        // Finalized through leaf C.
+        let locking_script = contract_scripts(&contract, EscrowScript::C).unwrap();
+        let witness = &signed.input[0].witness;
+        assert_eq!(witness.len(), 4);
+        assert_eq!(
+            witness.taproot_leaf_script().unwrap().script,
+            locking_script.as_script()
+        );
+    }
+
+    #[test]
+    fn finalize_escrow_psbt_skips_invalid_signatures() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (_, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        let contract = EscrowContract::two_party(
+            &npub_1,
+            &npub_2,
+            Some(&npub_arb),
+            Some(relative::LockTime::from_height(6)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::C,
+            &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+            &single_funding(funding_txid, *MULTISIG_AMOUNT),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        let leaf_hash = |escrow_script| {
+            TapLeafHash::from_script(
+                &contract_scripts(&contract, escrow_script).unwrap(),
+                LeafVersion::TapScript,
+            )
+        };
+        let arb_pk = npub_to_x_only_public_key(&npub_arb).unwrap();
+
+        let mut psbt_2 = unsigned.clone();
+        sign_escrow_psbt(&mut psbt_2, &nsec_2, &contract, network).unwrap();
+        let mut psbt_arb = unsigned.clone();
+        sign_escrow_psbt(&mut psbt_arb, &nsec_arb, &contract, network).unwrap();
+        let arb_signature =
+            |escrow_script| psbt_arb.inputs[0].tap_script_sigs[&(arb_pk, leaf_hash(escrow_script))];
+        assert!(verify_escrow_psbt(&psbt_2).unwrap().is_empty());
+        let invalid = InvalidSignature {
+            index: 0,
+            x_only_pk: arb_pk,
+            fault: SignatureFault::Leaf,
+        };
+
+        // The arbitrator's signature of leaf C pasted for leaf B is skipped, leaf C still finalizes.
+        let mut psbt = psbt_2.clone();
+        let mut pasted = psbt_arb.clone();
+        pasted.inputs[0].tap_script_sigs.insert(
+            (arb_pk, leaf_hash(EscrowScript::B)),
+            arb_signature(EscrowScript::C),
+        );
+        psbt.combine(pasted).unwrap();
+        assert_eq!(verify_escrow_psbt(&psbt).unwrap(), [invalid]);
+        let signed = finalize_escrow_psbt(psbt).unwrap();
+        assert_eq!(
+            signed.input[0]
+                .witness
+                .taproot_leaf_script()
+                .unwrap()
+                .script,
+            contract_scripts(&contract, EscrowScript::C)
+                .unwrap()
+                .as_script()
+        );
+
+        // The arbitrator's signature of leaf B pasted for leaf C leaves no leaf with enough signatures.
+        let mut psbt = psbt_2.clone();
+        let mut pasted = psbt_arb.clone();
+        pasted.inputs[0].tap_script_sigs.insert(
+            (arb_pk, leaf_hash(EscrowScript::C)),
+            arb_signature(EscrowScript::B),
+        );
+        psbt.combine(pasted).unwrap();
+        assert_eq!(verify_escrow_psbt(&psbt).unwrap(), [invalid]);
+        assert!(matches!(
+            finalize_escrow_psbt(psbt),
+            Err(Error::MissingSignatures(0))
+        ));
+    }
+
+    #[test]
+    fn sign_contract_psbt_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (_, npub_2) = generate_nostr_keys();
+        let (nsec_3, npub_3) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let timelock_duration = relative::LockTime::from_height(6);
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        // 2-of-3 cooperative, arbitrator and 1-of-3 arbitrated.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2, npub_3],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(timelock_duration)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let amount = *MULTISIG_AMOUNT / 3;
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[amount; 3],
+            &single_funding(funding_txid, amount * 3),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        assert_eq!(unsigned.unsigned_tx.output.len(), 3);
+
+        // Each party is in leaves A and B, but the cooperative transaction cannot spend leaf B.
+        let mut psbt_1 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_1, &nsec_1, &contract, network).unwrap(),
+            1
+        );
+        let mut psbt_3 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_3, &nsec_3, &contract, network).unwrap(),
+            1
+        );
+        let mut psbt_arb = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_arb, &nsec_arb, &contract, network).unwrap(),
+            0
+        );
+
+        // A single party signature is not enough for leaf A.
+        assert!(matches!(
+            finalize_escrow_psbt(psbt_1.clone()),
+            Err(Error::MissingSignatures(0))
+        ));
+
+        // Any two of the three parties finalize through leaf A.
+        let mut cooperative = psbt_1.clone();
+        cooperative.combine(psbt_3.clone()).unwrap();
+        let signed = finalize_escrow_psbt(cooperative).unwrap();
+        assert_dummy_weight(&signed, &contract, EscrowScript::A);
+        let locking_script = contract_scripts(&contract, EscrowScript::A).unwrap();
+        let witness = &signed.input[0].witness;
+        assert_eq!(
+            witness.taproot_leaf_script().unwrap().script,
+            locking_script.as_script()
+        );
+        // Signatures in reverse key order, the second party's is empty.
+        assert_eq!(witness.len(), 5);
+        assert_eq!(witness[0].len(), 64);
+        assert!(witness[1].is_empty());
+        assert_eq!(witness[2].len(), 64);
+
+        // Must be the same as signing and combining the raw transaction.
+        let prevouts = [unsigned.inputs[0].witness_utxo.clone().unwrap()];
+        let mut signatures = BTreeMap::new();
+        for (nsec, npub) in [(&nsec_1, &npub_1), (&nsec_3, &npub_3)] {
+            let signature = sign_contract_tx(
+                &unsigned.unsigned_tx,
+                nsec,
+                &contract,
+                &prevouts,
+                EscrowScript::A,
+            )
+            .unwrap()[0];
+            signatures.insert(npub_to_x_only_public_key(npub).unwrap(), signature);
+        }
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+        let expected = combine_contract_signatures(
+            unsigned.unsigned_tx.clone(),
+            &[signatures],
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        assert_eq!(signed, expected);
+
+        // Nor can the arbitrator sign leaf B on it.
+        assert!(matches!(
+            sign_contract_tx(
+                &unsigned.unsigned_tx,
+                &nsec_arb,
+                &contract,
+                &prevouts,
+                EscrowScript::B,
+            ),
+            Err(Error::TimelockMismatch(_))
+        ));
+
+        // The arbitrator and any one party finalize the dispute transaction through leaf B.
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[amount; 3],
+            &single_funding(funding_txid, amount * 3),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        let mut psbt_3 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_3, &nsec_3, &contract, network).unwrap(),
+            2
+        );
+        let mut psbt_arb = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_arb, &nsec_arb, &contract, network).unwrap(),
+            1
+        );
+        psbt_arb.combine(psbt_3).unwrap();
+        let signed = finalize_escrow_psbt(psbt_arb).unwrap();
+        assert_dummy_weight(&signed, &contract, EscrowScript::B);
+        let locking_script = contract_scripts(&contract, EscrowScript::B).unwrap();
+        let witness = &signed.input[0].witness;
+        assert_eq!(
+            witness.taproot_leaf_script().unwrap().script,
+            locking_script.as_script()
+        );
+        assert_eq!(witness.len(), 6);
+    }
+
+    #[test]
+    fn sign_refund_psbt_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (_, npub_2) = generate_nostr_keys();
+        let (_, npub_3) = generate_nostr_keys();
+        let (_, npub_arb) = generate_nostr_keys();
+        let refund_timelock_duration = relative::LockTime::from_height(144);
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        // 2-of-3 cooperative, arbitrator and a refund to the first party.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2, npub_3],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(6))),
+            InternalKey::Unspendable,
+        )
+        .unwrap()
+        .with_refund(npub_1, refund_timelock_duration)
+        .unwrap();
+        let amount = *MULTISIG_AMOUNT / 3;
+        let unsigned = refund_psbt(
+            &contract,
+            &[amount; 3],
+            false,
+            &single_funding(funding_txid, amount * 3),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        assert_eq!(
+            unsigned.unsigned_tx.input[0].sequence,
+            refund_timelock_duration.to_sequence()
+        );
+        assert_eq!(unsigned.unsigned_tx.output.len(), 1);
+        assert_eq!(unsigned.unsigned_tx.output[0].value, amount * 3 - FEE);
+        let split = refund_psbt(
+            &contract,
+            &[amount; 3],
+            true,
+            &single_funding(funding_txid, amount * 3),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        assert_eq!(split.unsigned_tx.output.len(), 3);
+
+        // The refund party is in leaves A, B and D, and finalizes alone through leaf D.
+        let mut psbt = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt, &nsec_1, &contract, network).unwrap(),
+            3
+        );
+        let signed = finalize_escrow_psbt(psbt).unwrap();
+        assert_dummy_weight(&signed, &contract, EscrowScript::D);
+        let locking_script = contract_scripts(&contract, EscrowScript::D).unwrap();
+        let witness = &signed.input[0].witness;
+        assert_eq!(
+            witness.taproot_leaf_script().unwrap().script,
+            locking_script.as_script()
+        );
+        assert_eq!(witness.len(), 3);
+
+        // Must be the same as signing and combining the raw transaction.
+        let prevouts = [unsigned.inputs[0].witness_utxo.clone().unwrap()];
+        let signature = sign_contract_tx(
+            &unsigned.unsigned_tx,
+            &nsec_1,
+            &contract,
+            &prevouts,
+            EscrowScript::D,
+        )
+        .unwrap()[0];
+        let signatures = BTreeMap::from([(npub_to_x_only_public_key(&npub_1).unwrap(), signature)]);
+        let expected = combine_contract_signatures(
+            unsigned.unsigned_tx.clone(),
+            &[signatures],
+            &locking_script,
+            &contract_spend_info(&contract).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(signed, expected);
+    }
+
+    #[test]
+    fn sign_arbitrator_panel_psbt_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (_, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb_1, npub_arb_1) = generate_nostr_keys();
+        let (_, npub_arb_2) = generate_nostr_keys();
+        let (nsec_arb_3, npub_arb_3) = generate_nostr_keys();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        // 2-of-3 arbitrators and one of the parties.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb_1, npub_arb_2, npub_arb_3],
+            2,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(6))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let amount = *MULTISIG_AMOUNT / 2;
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[amount; 2],
+            &single_funding(funding_txid, amount * 2),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+
+        let mut psbt_2 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_2, &nsec_2, &contract, network).unwrap(),
+            2
+        );
+        let mut psbt_arb_1 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_arb_1, &nsec_arb_1, &contract, network).unwrap(),
+            1
+        );
+        let mut psbt_arb_3 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_arb_3, &nsec_arb_3, &contract, network).unwrap(),
+            1
+        );
+
+        // A single arbitrator is not enough.
+        let mut psbt = psbt_2.clone();
+        psbt.combine(psbt_arb_1.clone()).unwrap();
+        assert!(matches!(
+            finalize_escrow_psbt(psbt.clone()),
+            Err(Error::MissingSignatures(0))
+        ));
+
+        // Any two arbitrators are.
+        psbt.combine(psbt_arb_3).unwrap();
+        let signed = finalize_escrow_psbt(psbt).unwrap();
+        assert_dummy_weight(&signed, &contract, EscrowScript::B);
+        let locking_script = contract_scripts(&contract, EscrowScript::B).unwrap();
+        let witness = &signed.input[0].witness;
+        assert_eq!(
+            witness.taproot_leaf_script().unwrap().script,
+            locking_script.as_script()
+        );
+        // Party signatures then arbitrator signatures, each in reverse key order.
+        assert_eq!(witness.len(), 7);
+        assert_eq!(witness[0].len(), 64);
+        assert!(witness[1].is_empty());
+        assert_eq!(witness[2].len(), 64);
+        assert!(witness[3].is_empty());
+        assert_eq!(witness[4].len(), 64);
+    }
+
+    /// Checks that the [`dummy_contract_witness`] of the `escrow_script` has the weight of the `signed` witness.
+    #[test]
+    fn sign_multi_deposit_psbt_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let amount = *MULTISIG_AMOUNT / 2;
+
+        // Each party deposits its own escrow amount from a different wallet.
+        let funding = [
+            (
+                "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799",
+                1,
+            ),
+            (
+                "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+                0,
+            ),
+        ]
+        .map(|(txid, vout)| Utxo {
+            outpoint: OutPoint {
+                txid: txid.parse().unwrap(),
+                vout,
+            },
+            value: amount,
+            confirmed: true,
+        });
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[amount; 2],
+            &funding,
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        assert_eq!(unsigned.unsigned_tx.input.len(), 2);
+
+        // Both parties sign both inputs.
+        let mut psbt_1 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_1, &nsec_1, &contract, network).unwrap(),
+            2
+        );
+        let mut psbt_2 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_2, &nsec_2, &contract, network).unwrap(),
+            2
+        );
+        psbt_1.combine(psbt_2).unwrap();
+        let signed = finalize_escrow_psbt(psbt_1).unwrap();
+        assert!(signed.input.iter().all(|input| input.witness.len() == 4));
+
+        // Each signature commits to its own input and to the prevouts of all the inputs.
+        let prevouts = unsigned
+            .inputs
+            .iter()
+            .map(|input| input.witness_utxo.clone().unwrap())
+            .collect::<Vec<_>>();
+        let locking_script = contract_scripts(&contract, EscrowScript::A).unwrap();
+        let leaf_hash = TapLeafHash::from_script(&locking_script, LeafVersion::TapScript);
+        let mut signatures = vec![BTreeMap::new(); 2];
+        for (nsec, npub) in [(&nsec_1, &npub_1), (&nsec_2, &npub_2)] {
+            let x_only_pk = npub_to_x_only_public_key(npub).unwrap();
+            let signer_signatures = sign_contract_tx(
+                &unsigned.unsigned_tx,
+                nsec,
+                &contract,
+                &prevouts,
+                EscrowScript::A,
+            )
+            .unwrap();
+            assert_eq!(signer_signatures.len(), 2);
+            for (index, signature) in signer_signatures.into_iter().enumerate() {
+                let sighash = SighashCache::new(&unsigned.unsigned_tx)
+                    .taproot_script_spend_signature_hash(
+                        index,
+                        &Prevouts::All(&prevouts),
+                        leaf_hash,
+                        TapSighashType::Default,
+                    )
+                    .unwrap();
+                let message = Message::from_digest(*sighash.as_byte_array());
+                assert!(
+                    SECP256K1
+                        .verify_schnorr(&signature, &message, &x_only_pk)
+                        .is_ok()
+                );
+                signatures[index].insert(x_only_pk, signature);
+            }
+        }
+
+        // Must be the same as signing and combining the raw transaction.
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+        let expected = combine_contract_signatures(
+            unsigned.unsigned_tx.clone(),
+            &signatures,
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        assert_eq!(signed, expected);
+
+        // Every input needs its prevout and its signatures.
+        assert!(matches!(
+            sign_contract_tx(
+                &unsigned.unsigned_tx,
+                &nsec_1,
+                &contract,
+                &prevouts[..1],
+                EscrowScript::A,
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+        assert!(matches!(
+            combine_contract_signatures(
+                unsigned.unsigned_tx,
+                &signatures[..1],
+                &locking_script,
+                &taproot_spend_info,
+            ),
+            Err(Error::MissingSignatures(1))
+        ));
+    }
+
+    #[test]
+    fn sign_funding_psbt_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let escrow_address = npub_to_address(&npub_arb, network).unwrap();
+        let utxo = |txid: &str, vout| Utxo {
+            outpoint: OutPoint {
+                txid: txid.parse().unwrap(),
+                vout,
+            },
+            value: Amount::from_sat(100_000),
+            confirmed: true,
+        };
+        let contributions = [
+            FundingContribution {
+                npub: npub_1,
+                utxos: vec![
+                    utxo(
+                        "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799",
+                        0,
+                    ),
+                    utxo(
+                        "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799",
+                        1,
+                    ),
+                ],
+                amount: Amount::from_sat(150_000),
+                change_address: None,
+            },
+            FundingContribution {
+                npub: npub_2,
+                utxos: vec![utxo(
+                    "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+                    0,
+                )],
+                amount: Amount::from_sat(50_000),
+                change_address: None,
+            },
+        ];
+        let unsigned = funding_psbt(
+            &escrow_address,
+            &contributions,
+            FeeRate::from_sat_per_vb_unchecked(2),
+            network,
+        )
+        .unwrap();
+
+        // Each party signs its own inputs only.
+        let mut psbt_1 = unsigned.clone();
+        assert_eq!(sign_funding_psbt(&mut psbt_1, &nsec_1).unwrap(), 2);
+        let mut psbt_2 = unsigned.clone();
+        assert_eq!(sign_funding_psbt(&mut psbt_2, &nsec_2).unwrap(), 1);
+        let mut psbt_arb = unsigned.clone();
+        assert_eq!(sign_funding_psbt(&mut psbt_arb, &nsec_arb).unwrap(), 0);
+
+        // The input of party 2 is not signed yet.
+        assert!(matches!(
+            finalize_escrow_psbt(psbt_1.clone()),
+            Err(Error::MissingSignatures(2))
+        ));
+
+        psbt_1.combine(psbt_2).unwrap();
+        let signed = finalize_escrow_psbt(psbt_1).unwrap();
+        trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed funding PSBT");
+
+        let prevouts = psbt_prevouts(&unsigned).unwrap();
+        verify_key_path_spends(&signed, &prevouts);
+        let tx_fee = TxFee::new(
+            &signed,
+            &vec![dummy_key_path_witness(); signed.input.len()],
+            FeeRate::from_sat_per_vb_unchecked(2),
+        )
+        .unwrap();
+        assert_eq!(tx_fee.weight, signed.weight());
+    }
+
+    #[test]
+    fn escrow_prevouts_from_spent_outputs() {
+        let network = Network::Regtest;
+        let (_, npub_1) = generate_nostr_keys();
+        let (_, npub_2) = generate_nostr_keys();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let escrow_script = contract_address(&contract, network)
+            .unwrap()
+            .script_pubkey();
+
+        // A deposit to the escrow address with a change output before it.
+        let funding_tx = Transaction {
+            version: transaction::Version(2),
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![
+                TxOut {
+                    value: Amount::from_sat(5_000),
+                    script_pubkey: npub_to_address(&npub_1, network).unwrap().script_pubkey(),
+                },
+                TxOut {
+                    value: *MULTISIG_AMOUNT,
+                    script_pubkey: escrow_script.clone(),
+                },
+            ],
+        };
+        let funding = [Utxo {
+            outpoint: OutPoint {
+                txid: funding_tx.compute_txid(),
+                vout: 1,
+            },
+            value: *MULTISIG_AMOUNT,
+            confirmed: true,
+        }];
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[*MULTISIG_AMOUNT / 2; 2],
+            &funding,
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        let expected = psbt_prevouts(&unsigned).unwrap();
+
+        // From the funding transaction in hex, or from the PSBT.
+        let funding_hex = consensus::serialize(&funding_tx).as_hex().to_string();
+        for input in [format!("\n{funding_hex}\n"), unsigned.to_string()] {
+            let spent_outputs = parse_spent_outputs(&input).unwrap();
+            assert_eq!(
+                escrow_prevouts(&unsigned.unsigned_tx, &spent_outputs, &escrow_script).unwrap(),
+                expected
+            );
+        }
+
+        // The prevouts must be known and pay to the escrow address.
+        assert!(matches!(
+            escrow_prevouts(&unsigned.unsigned_tx, &BTreeMap::new(), &escrow_script),
+            Err(Error::MissingPrevout(0))
+        ));
+        let mut wrong = funding_tx.clone();
+        wrong.output.swap(0, 1);
+        let spent_outputs =
+            parse_spent_outputs(&consensus::serialize(&wrong).as_hex().to_string()).unwrap();
+        let mut unsigned_tx = unsigned.unsigned_tx.clone();
+        unsigned_tx.input[0].previous_output.txid = wrong.compute_txid();
+        assert!(matches!(
+            escrow_prevouts(&unsigned_tx, &spent_outputs, &escrow_script),
+            Err(Error::PrevoutNotEscrow(0))
+        ));
+        assert!(parse_spent_outputs("abc").is_err());
+        assert!(parse_spent_outputs(" ").is_err());
+    }
+
+    #[test]
+    fn sign_escrow_psbt_checks_prevouts() {
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (_, npub_2) = generate_nostr_keys();
+        let (_, npub_other) = generate_nostr_keys();
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let funding_tx = Transaction {
+            version: transaction::Version(2),
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: vec![TxOut {
+                value: *MULTISIG_AMOUNT,
+                script_pubkey: contract_address(&contract, network)
+                    .unwrap()
+                    .script_pubkey(),
+            }],
+        };
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[*MULTISIG_AMOUNT / 2; 2],
+            &single_funding(funding_tx.compute_txid(), *MULTISIG_AMOUNT),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        let spent_outputs =
+            parse_spent_outputs(&consensus::serialize(&funding_tx).as_hex().to_string()).unwrap();
+        check_psbt_prevouts(&unsigned, &spent_outputs).unwrap();
+        let mut psbt = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt, &nsec_1, &contract, network).unwrap(),
+            1
+        );
+
+        // A PSBT of another escrow is refused, even though the key signs it.
+        let other =
+            EscrowContract::two_party(&npub_1, &npub_other, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let mut psbt = unsigned.clone();
+        assert!(matches!(
+            sign_escrow_psbt(&mut psbt, &nsec_1, &other, network),
+            Err(Error::PrevoutNotEscrow(0))
+        ));
+        assert!(psbt.inputs[0].tap_script_sigs.is_empty());
+
+        // A PSBT that misstates the amount of the escrow differs from the funding transaction.
+        let mut inflated = unsigned.clone();
+        inflated.inputs[0].witness_utxo.as_mut().unwrap().value = *MULTISIG_AMOUNT * 2;
+        assert!(matches!(
+            check_psbt_prevouts(&inflated, &spent_outputs),
+            Err(Error::PrevoutMismatch(0))
+        ));
+        assert!(matches!(
+            check_psbt_prevouts(&unsigned, &BTreeMap::new()),
+            Err(Error::MissingPrevout(0))
+        ));
+        let mut missing = unsigned.clone();
+        missing.inputs[0].witness_utxo = None;
+        assert!(matches!(
+            sign_escrow_psbt(&mut missing, &nsec_1, &contract, network),
+            Err(Error::MissingWitnessUtxo(0))
+        ));
+    }
+
+    fn assert_dummy_weight(
+        signed: &Transaction,
+        contract: &EscrowContract,
+        escrow_script: EscrowScript,
+    ) {
+        let witness = dummy_contract_witness(contract, escrow_script).unwrap();
+        let tx_fee = TxFee::new(signed, &[witness], FeeRate::from_sat_per_vb_unchecked(1)).unwrap();
+        assert_eq!(tx_fee.weight, signed.weight());
+    }
+
+    /// Checks that the key path spends of every input of `tx` are valid for their `prevouts`.
+    fn verify_key_path_spends(tx: &Transaction, prevouts: &[TxOut]) {
+        for (index, (input, prevout)) in tx.input.iter().zip(prevouts).enumerate() {
+            assert_eq!(input.witness.len(), 1);
+            let signature = schnorr::Signature::from_slice(&input.witness[0]).unwrap();
+            let message = Message::from_digest(key_path_sighash(tx, index, prevouts).unwrap());
+            let output_key =
+                XOnlyPublicKey::from_slice(&prevout.script_pubkey.as_bytes()[2..]).unwrap();
+            SECP256K1
+                .verify_schnorr(&signature, &message, &output_key)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn verify_contract_signatures_diagnoses() {
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(6))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let amount = *MULTISIG_AMOUNT / 2;
+        let funding = [0, 1].map(|vout| Utxo {
+            outpoint: OutPoint {
+                txid: "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+                    .parse()
+                    .unwrap(),
+                vout,
+            },
+            value: amount,
+            confirmed: true,
+        });
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[amount; 2],
+            &funding,
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        let tx = &unsigned.unsigned_tx;
+        let prevouts = psbt_prevouts(&unsigned).unwrap();
+        let locking_script = contract_scripts(&contract, EscrowScript::B).unwrap();
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+
+        // Party 2 signs right, party 1 signs the cooperative leaf,
+        // and the arbitrator types the total amount as the amount of the first deposit.
+        let mut wrong_amounts = prevouts.clone();
+        wrong_amounts[0].value = amount * 2;
+        let mut signatures = vec![BTreeMap::new(); 2];
+        for (nsec, npub, prevouts, escrow_script) in [
+            (&nsec_2, &npub_2, &prevouts, EscrowScript::B),
+            (&nsec_1, &npub_1, &prevouts, EscrowScript::A),
+            (&nsec_arb, &npub_arb, &wrong_amounts, EscrowScript::B),
+        ] {
+            let x_only_pk = npub_to_x_only_public_key(npub).unwrap();
+            let signer_signatures =
+                sign_contract_tx(tx, nsec, &contract, prevouts, escrow_script).unwrap();
+            for (input_signatures, signature) in signatures.iter_mut().zip(signer_signatures) {
+                input_signatures.insert(x_only_pk, signature);
+            }
+        }
+        let invalid = verify_contract_signatures(
+            tx,
+            &signatures,
+            &prevouts,
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        let fault_of = |npub: &NostrPublicKey, index: usize| {
+            let x_only_pk = npub_to_x_only_public_key(npub).unwrap();
+            invalid
+                .iter()
+                .find(|invalid| invalid.x_only_pk == x_only_pk && invalid.index == index)
+                .map(|invalid| invalid.fault)
+        };
+        assert_eq!(invalid.len(), 4);
+        for index in 0..2 {
+            assert_eq!(fault_of(&npub_2, index), None);
+            assert_eq!(fault_of(&npub_1, index), Some(SignatureFault::Leaf));
+            assert_eq!(fault_of(&npub_arb, index), Some(SignatureFault::Amount));
+        }
+
+        // Signatures of another transaction.
+        let mut other_tx = tx.clone();
+        other_tx.output[0].value -= Amount::from_sat(1);
+        let other_signatures =
+            sign_contract_tx(&other_tx, &nsec_2, &contract, &prevouts, EscrowScript::B)
+                .unwrap()
+                .into_iter()
+                .map(|signature| {
+                    BTreeMap::from([(npub_to_x_only_public_key(&npub_2).unwrap(), signature)])
+                })
+                .collect::<Vec<_>>();
+        let invalid = verify_contract_signatures(
+            tx,
+            &other_signatures,
+            &prevouts,
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        assert_eq!(invalid.len(), 2);
+        assert!(
+            invalid
+                .iter()
+                .all(|invalid| invalid.fault == SignatureFault::Transaction)
+        );
+        assert!(matches!(
+            verify_contract_signatures(
+                tx,
+                &signatures,
+                &prevouts[..1],
+                &locking_script,
+                &taproot_spend_info,
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+
+        // Valid signatures pasted for the wrong input, or under the wrong key.
+        let x_only_pk_1 = npub_to_x_only_public_key(&npub_1).unwrap();
+        let x_only_pk_2 = npub_to_x_only_public_key(&npub_2).unwrap();
+        let swapped =
+            [1, 0].map(|index| BTreeMap::from([(x_only_pk_2, signatures[index][&x_only_pk_2])]));
+        let misattributed =
+            [0, 1].map(|index| BTreeMap::from([(x_only_pk_1, signatures[index][&x_only_pk_2])]));
+        for wrong_signatures in [swapped, misattributed] {
+            let invalid = verify_contract_signatures(
+                tx,
+                &wrong_signatures,
+                &prevouts,
+                &locking_script,
+                &taproot_spend_info,
+            )
+            .unwrap();
+            assert_eq!(invalid.len(), 2);
+            assert!(
+                invalid
+                    .iter()
+                    .all(|invalid| invalid.fault == SignatureFault::Transaction)
+            );
+        }
+    }
+
+    #[test]
+    fn map_contract_signatures_in_any_order() {
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let (nsec_other, _) = generate_nostr_keys();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(6))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let amount = *MULTISIG_AMOUNT / 2;
+        let funding = [0, 1].map(|vout| Utxo {
+            outpoint: OutPoint {
+                txid: "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+                    .parse()
+                    .unwrap(),
+                vout,
+            },
+            value: amount,
+            confirmed: true,
+        });
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[amount; 2],
+            &funding,
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        let tx = &unsigned.unsigned_tx;
+        let prevouts = psbt_prevouts(&unsigned).unwrap();
+        let locking_script = contract_scripts(&contract, EscrowScript::B).unwrap();
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+        let sign = |nsec: &NostrSecretKey, escrow_script: EscrowScript| {
+            sign_contract_tx(tx, nsec, &contract, &prevouts, escrow_script).unwrap()
+        };
+
+        // The arbitrator pasted in the middle, and party 2 signed the cooperative leaf.
+        let sets = vec![
+            sign(&nsec_1, EscrowScript::B),
+            sign(&nsec_arb, EscrowScript::B),
+            sign(&nsec_2, EscrowScript::A),
+        ];
+        let signatures =
+            map_contract_signatures(tx, &sets, &prevouts, &locking_script, &taproot_spend_info)
+                .unwrap();
+        assert_eq!(signatures.len(), 2);
+        for (index, input_signatures) in signatures.iter().enumerate() {
+            for (npub, set) in [
+                (&npub_1, &sets[0]),
+                (&npub_arb, &sets[1]),
+                (&npub_2, &sets[2]),
+            ] {
+                assert_eq!(
+                    input_signatures[&npub_to_x_only_public_key(npub).unwrap()],
+                    set[index]
+                );
+            }
+        }
+        // The wrong leaf is still diagnosed, and the others are combined in the order of the leaf.
+        let invalid = verify_contract_signatures(
+            tx,
+            &signatures,
+            &prevouts,
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        assert_eq!(invalid.len(), 2);
+        assert!(
+            invalid
+                .iter()
+                .all(|invalid| invalid.fault == SignatureFault::Leaf)
+        );
+        let mut reversed = sets[..2].to_vec();
+        reversed.reverse();
+        let reversed = map_contract_signatures(
+            tx,
+            &reversed,
+            &prevouts,
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        let signed = combine_contract_signatures(
+            tx.clone(),
+            &reversed,
+            &locking_script,
+            &taproot_spend_info,
+        )
+        .unwrap();
+        let mut valid = signatures.clone();
+        let x_only_pk_2 = npub_to_x_only_public_key(&npub_2).unwrap();
+        for input_signatures in &mut valid {
+            input_signatures.remove(&x_only_pk_2);
+        }
+        assert_eq!(
+            signed,
+            combine_contract_signatures(tx.clone(), &valid, &locking_script, &taproot_spend_info)
+                .unwrap()
+        );
+
+        // Signatures of a key outside of the escrow, or for another number of inputs.
+        let sets = vec![
+            sign(&nsec_1, EscrowScript::B),
+            sign(&nsec_other, EscrowScript::B),
+        ];
+        assert!(matches!(
+            map_contract_signatures(tx, &sets, &prevouts, &locking_script, &taproot_spend_info),
+            Err(Error::UnknownSignatures(2))
+        ));
+        let sets = vec![sign(&nsec_1, EscrowScript::B)[..1].to_vec()];
+        assert!(matches!(
+            map_contract_signatures(tx, &sets, &prevouts, &locking_script, &taproot_spend_info),
+            Err(Error::WrongInputs(_))
+        ));
+
+        // The same set pasted twice, or two sets of the same key, are not merged.
+        let sets = vec![
+            sign(&nsec_1, EscrowScript::B),
+            sign(&nsec_arb, EscrowScript::B),
+            sign(&nsec_1, EscrowScript::B),
+        ];
+        assert!(matches!(
+            map_contract_signatures(tx, &sets, &prevouts, &locking_script, &taproot_spend_info),
+            Err(Error::DuplicateSignatures(1, 3))
+        ));
+        let sets = vec![
+            sign(&nsec_1, EscrowScript::B),
+            sign(&nsec_1, EscrowScript::A),
+        ];
+        assert!(matches!(
+            map_contract_signatures(tx, &sets, &prevouts, &locking_script, &taproot_spend_info),
+            Err(Error::DuplicateSignatures(1, 2))
+        ));
+
+        // Every signature is checked against its own input: a set mixing two signers is unknown.
+        let mut mixed = sign(&nsec_1, EscrowScript::B);
+        mixed[1] = sign(&nsec_arb, EscrowScript::B)[1];
+        let sets = vec![mixed, sign(&nsec_arb, EscrowScript::B)];
+        assert!(matches!(
+            map_contract_signatures(tx, &sets, &prevouts, &locking_script, &taproot_spend_info),
+            Err(Error::UnknownSignatures(1))
+        ));
+        // As is a set whose signature of the first input is repeated for the second one.
+        let mut repeated = sign(&nsec_1, EscrowScript::B);
+        repeated[1] = repeated[0];
+        assert!(matches!(
+            map_contract_signatures(
+                tx,
+                &[repeated],
+                &prevouts,
+                &locking_script,
+                &taproot_spend_info
+            ),
+            Err(Error::UnknownSignatures(1))
+        ));
+    }
+
+    #[test]
+    fn signable_leaves_from_key_and_sequence() {
+        let (_, npub_1) = generate_nostr_keys();
+        let (_, npub_2) = generate_nostr_keys();
+        let (_, npub_arb) = generate_nostr_keys();
+        let (_, npub_other) = generate_nostr_keys();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(6))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let funding = single_funding(
+            "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+                .parse()
+                .unwrap(),
+            *MULTISIG_AMOUNT,
+        );
+        let leaves = |escrow_script: EscrowScript, npub: &NostrPublicKey| {
+            let psbt = contract_psbt(
+                &contract,
+                escrow_script,
+                &[*MULTISIG_AMOUNT / 2; 2],
+                &funding,
+                FEE,
+                &FeePolicy::Equal,
+                DustPolicy::Reject,
+                Network::Regtest,
+            )
+            .unwrap();
+            signable_leaves(
+                &contract,
+                &npub_to_x_only_public_key(npub).unwrap(),
+                &psbt.unsigned_tx,
+            )
+            .unwrap()
+        };
+
+        // The cooperative transaction does not satisfy the timelock of the dispute leaves.
+        assert_eq!(leaves(EscrowScript::A, &npub_1), vec![EscrowScript::A]);
+        assert_eq!(leaves(EscrowScript::A, &npub_2), vec![EscrowScript::A]);
+        assert!(leaves(EscrowScript::A, &npub_arb).is_empty());
+        // A dispute transaction is not a cooperative one, but only the arbitrator knows which dispute.
+        assert_eq!(leaves(EscrowScript::B, &npub_1), vec![EscrowScript::B]);
+        assert_eq!(leaves(EscrowScript::B, &npub_2), vec![EscrowScript::C]);
+        assert_eq!(
+            leaves(EscrowScript::B, &npub_arb),
+            vec![EscrowScript::B, EscrowScript::C]
+        );
+        assert!(leaves(EscrowScript::B, &npub_other).is_empty());
+
+        // A dispute transaction that can never satisfy the timelock only leaves the cooperative leaf.
+        let dispute_tx = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[*MULTISIG_AMOUNT / 2; 2],
+            &funding,
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            Network::Regtest,
+        )
+        .unwrap()
+        .unsigned_tx;
+        let x_only_pk_1 = npub_to_x_only_public_key(&npub_1).unwrap();
+        let x_only_pk_arb = npub_to_x_only_public_key(&npub_arb).unwrap();
+        let mut short_tx = dispute_tx.clone();
+        short_tx.input[0].sequence = relative::LockTime::from_height(5).to_sequence();
+        let mut version_1_tx = dispute_tx.clone();
+        version_1_tx.version = transaction::Version::ONE;
+        for tx in [&short_tx, &version_1_tx] {
+            assert_eq!(
+                signable_leaves(&contract, &x_only_pk_1, tx).unwrap(),
+                vec![EscrowScript::A]
+            );
+            assert!(
+                signable_leaves(&contract, &x_only_pk_arb, tx)
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+
+        // Every input must satisfy the timelock of the leaf.
+        let mut mixed_tx = dispute_tx;
+        mixed_tx.input.push(TxIn {
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            ..mixed_tx.input[0].clone()
+        });
+        assert!(
+            signable_leaves(&contract, &x_only_pk_arb, &mixed_tx)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn sign_collaborative_musig2_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        // Random keys, so that both parities of the Nostr keys are covered.
+        for _ in 0..8 {
+            let (nsec_1, npub_1) = generate_nostr_keys();
+            let (nsec_2, npub_2) = generate_nostr_keys();
+
+            let contract =
+                EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::MuSig2)
+                    .unwrap();
+            let unsigned = contract_psbt(
+                &contract,
+                EscrowScript::A,
+                &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+                &single_funding(funding_txid, *MULTISIG_AMOUNT),
+                FEE,
+                &FeePolicy::Equal,
+                DustPolicy::Reject,
+                network,
+            )
+            .unwrap();
+            // The cooperative resolution is key path only.
+            assert!(unsigned.inputs[0].tap_scripts.is_empty());
+            assert!(unsigned.inputs[0].tap_merkle_root.is_none());
+
+            // Party 1 starts the session.
+            let (key_agg_ctx_1, prevouts) =
+                musig2_psbt_ctx(&unsigned, 0, &npub_1, &npub_2).unwrap();
+            let sec_nonce =
+                musig2_nonce(&unsigned.unsigned_tx, 0, &nsec_1, &key_agg_ctx_1, &prevouts).unwrap();
+            let pub_nonce_1 = sec_nonce.public_nonce();
+
+            // Party 2 answers.
+            let (key_agg_ctx_2, _) = musig2_psbt_ctx(&unsigned, 0, &npub_1, &npub_2).unwrap();
+            let (pub_nonce_2, partial_signature_2) = musig2_partial_sign(
+                &unsigned.unsigned_tx,
+                0,
+                &nsec_2,
+                &key_agg_ctx_2,
+                &prevouts,
+                &pub_nonce_1,
+            )
+            .unwrap();
+            // Deterministic given the counterparty nonce.
+            let (pub_nonce_2_again, _) = musig2_partial_sign(
+                &unsigned.unsigned_tx,
+                0,
+                &nsec_2,
+                &key_agg_ctx_2,
+                &prevouts,
+                &pub_nonce_1,
+            )
+            .unwrap();
+            assert_eq!(pub_nonce_2, pub_nonce_2_again);
+
+            // Party 1 aggregates.
+            let signed = musig2_aggregate(
+                unsigned.unsigned_tx.clone(),
+                0,
+                &nsec_1,
+                &key_agg_ctx_1,
+                &prevouts,
+                sec_nonce,
+                &pub_nonce_2,
+                partial_signature_2,
+            )
+            .unwrap();
+            trace!(transaction=%consensus::serialize(&signed).as_hex(), "Signed MuSig2 escrow transaction");
+
+            verify_key_path_spends(&signed, &prevouts);
+        }
+    }
+
+    #[test]
+    fn sign_collaborative_musig2_multi_input_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::MuSig2).unwrap();
+        // The escrow is funded by three deposits.
+        let amount = *MULTISIG_AMOUNT / 3;
+        let funding = [0, 1, 2].map(|vout| Utxo {
+            outpoint: OutPoint {
+                txid: "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+                    .parse()
+                    .unwrap(),
+                vout,
+            },
+            value: amount,
+            confirmed: true,
+        });
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[amount * 2, amount],
+            &funding,
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        assert_eq!(unsigned.inputs.len(), 3);
+
+        // One session per input.
+        let sec_nonces = musig2_psbt_nonces(&unsigned, &nsec_1, &npub_1, &npub_2).unwrap();
+        let pub_nonces_1 = sec_nonces
+            .iter()
+            .map(SecNonce::public_nonce)
+            .collect::<Vec<_>>();
+        assert_eq!(pub_nonces_1.len(), 3);
+        let (pub_nonces_2, partial_signatures_2) =
+            musig2_psbt_partial_sign(&unsigned, &nsec_2, &npub_1, &npub_2, &pub_nonces_1).unwrap();
+
+        // Every session needs its own nonce and partial signature.
+        assert!(matches!(
+            musig2_psbt_partial_sign(&unsigned, &nsec_2, &npub_1, &npub_2, &pub_nonces_1[..2]),
+            Err(Error::WrongInputs(_))
+        ));
+        assert!(matches!(
+            musig2_psbt_aggregate(
+                &unsigned,
+                &nsec_1,
+                &npub_1,
+                &npub_2,
+                sec_nonces.clone(),
+                &pub_nonces_2,
+                &partial_signatures_2[..2],
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+        // The partial signatures of one input do not verify for another.
+        let swapped = [
+            partial_signatures_2[1],
+            partial_signatures_2[0],
+            partial_signatures_2[2],
+        ];
+        assert!(matches!(
+            musig2_psbt_aggregate(
+                &unsigned,
+                &nsec_1,
+                &npub_1,
+                &npub_2,
+                sec_nonces.clone(),
+                &pub_nonces_2,
+                &swapped,
+            ),
+            Err(Error::MuSig2Verify(_))
+        ));
+
+        let signed = musig2_psbt_aggregate(
+            &unsigned,
+            &nsec_1,
+            &npub_1,
+            &npub_2,
+            sec_nonces,
+            &pub_nonces_2,
+            &partial_signatures_2,
+        )
+        .unwrap();
+        verify_key_path_spends(&signed, &psbt_prevouts(&unsigned).unwrap());
+    }
+
+    #[test]
+    fn sign_dispute_musig2_flow() {
+        init_tracing();
+
+        let network = Network::Regtest;
+        let (nsec_1, npub_1) = generate_nostr_keys();
+        let (nsec_2, npub_2) = generate_nostr_keys();
+        let (nsec_arb, npub_arb) = generate_nostr_keys();
+        let timelock_duration = relative::LockTime::from_height(6);
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse()
+            .unwrap();
+
+        let contract = EscrowContract::two_party(
+            &npub_1,
+            &npub_2,
+            Some(&npub_arb),
+            Some(timelock_duration),
+            InternalKey::MuSig2,
+        )
+        .unwrap();
+        let unsigned = contract_psbt(
+            &contract,
+            EscrowScript::C,
+            &[*MULTISIG_AMOUNT / 2, *MULTISIG_AMOUNT / 2],
+            &single_funding(funding_txid, *MULTISIG_AMOUNT),
+            FEE,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        // The same escrow as a contract.
+        let musig2_contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(timelock_duration)),
+            InternalKey::MuSig2,
+        )
+        .unwrap();
+        // Only the dispute leaves are in the tree.
+        assert_eq!(unsigned.inputs[0].tap_scripts.len(), 2);
+
+        // Cooperative key path, tweaked by the merkle root of the dispute leaves.
+        let (key_agg_ctx, prevouts) = musig2_psbt_ctx(&unsigned, 0, &npub_1, &npub_2).unwrap();
+        let sec_nonce =
+            musig2_nonce(&unsigned.unsigned_tx, 0, &nsec_1, &key_agg_ctx, &prevouts).unwrap();
+        let (pub_nonce_2, partial_signature_2) = musig2_partial_sign(
+            &unsigned.unsigned_tx,
+            0,
+            &nsec_2,
+            &key_agg_ctx,
+            &prevouts,
+            &sec_nonce.public_nonce(),
+        )
+        .unwrap();
+
+        // A partial signature for another nonce is rejected.
+        let (_, wrong_partial_signature) = musig2_partial_sign(
+            &unsigned.unsigned_tx,
+            0,
+            &nsec_2,
+            &key_agg_ctx,
+            &prevouts,
+            &pub_nonce_2,
+        )
+        .unwrap();
+        assert!(matches!(
+            musig2_aggregate(
+                unsigned.unsigned_tx.clone(),
+                0,
+                &nsec_1,
+                &key_agg_ctx,
+                &prevouts,
+                sec_nonce.clone(),
+                &pub_nonce_2,
+                wrong_partial_signature,
+            ),
+            Err(Error::MuSig2Verify(_))
+        ));
+
+        let signed = musig2_aggregate(
+            unsigned.unsigned_tx.clone(),
+            0,
+            &nsec_1,
+            &key_agg_ctx,
+            &prevouts,
+            sec_nonce,
+            &pub_nonce_2,
+            partial_signature_2,
+        )
+        .unwrap();
+        assert_dummy_weight(&signed, &musig2_contract, EscrowScript::A);
+        verify_key_path_spends(&signed, &prevouts);
+
+        // The arbitrator is not a key of the MuSig2 aggregate.
+        assert!(
+            musig2_partial_sign(
+                &unsigned.unsigned_tx,
+                0,
+                &nsec_arb,
+                &key_agg_ctx,
+                &prevouts,
+                &pub_nonce_2,
+            )
+            .is_err()
+        );
+
+        // The dispute leaves are still a fallback.
+        let mut psbt_2 = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_2, &nsec_2, &contract, network).unwrap(),
+            1
+        );
+        let mut psbt_arb = unsigned.clone();
+        assert_eq!(
+            sign_escrow_psbt(&mut psbt_arb, &nsec_arb, &contract, network).unwrap(),
+            2
+        );
+        psbt_2.combine(psbt_arb).unwrap();
+        let signed = finalize_escrow_psbt(psbt_2).unwrap();
+        let leaf = signed.input[0].witness.taproot_leaf_script().unwrap();
+        assert_eq!(
+            leaf.script,
+            contract_scripts(&contract, EscrowScript::C)
+                .unwrap()
+                .as_script()
+        );
+    }
+
+    #[test]
+    fn musig2_signing_ctx_wrong_internal_key() {
+        let (_, npub_1) = generate_nostr_keys();
+        let (_, npub_2) = generate_nostr_keys();
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let taproot_spend_info = contract_spend_info(&contract).unwrap();
+
+        assert!(matches!(
+            musig2_signing_ctx(
+                &npub_1,
+                &npub_2,
+                taproot_spend_info.internal_key(),
+                taproot_spend_info.merkle_root(),
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+    }
 }
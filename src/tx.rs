@@ -1,171 +1,3113 @@
 //! Creates Taproot Transactions using Nostr keys.
 
+use std::{cmp::Reverse, fmt};
+
 use bitcoin::{
-    Address, Amount, Network, OutPoint, Sequence, Transaction, TxIn, TxOut, Txid, absolute,
+    Address, Amount, FeeRate, Network, OutPoint, Psbt, ScriptBuf, Sequence, TapLeafHash,
+    Transaction, TxIn, TxOut, Weight, Witness, absolute,
+    bip32::{DerivationPath, Fingerprint},
+    psbt,
+    taproot::TaprootSpendInfo,
     transaction,
 };
 #[cfg(debug_assertions)]
 use dioxus::logger::tracing::trace;
 use nostr::key::PublicKey as NostPublicKey;
 
-use crate::{error::Error, util::npub_to_address};
+use crate::{
+    error::Error,
+    scripts::{
+        EscrowContract, EscrowScript, Timelock, contract_scripts, contract_spend_info,
+        leaf_timelock, leaf_x_only_keys,
+    },
+    sign::{dummy_contract_witness, dummy_key_path_witness},
+    util::{npub_to_address, npub_to_x_only_public_key, resolution_address},
+};
+
+/// How the fee of an escrow transaction is split between its outputs.
+///
+/// The shares always add up to the exact fee: the satoshis left over by an integer split
+/// are assigned explicitly, never dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) enum FeePolicy {
+    /// Every output pays the same share, the first outputs pay one more satoshi each for the remainder.
+    #[default]
+    Equal,
+    /// Every output pays a share proportional to its escrow amount,
+    /// the remainder goes one satoshi each to the largest fractional parts, ties to the first outputs.
+    Proportional,
+    /// The output at the index, e.g. the buyer's or the arbitrator fee of a ruling, pays the whole fee.
+    Payer(usize),
+    /// Every output pays a fixed share, that must add up to the fee.
+    Fixed(Vec<Amount>),
+}
+
+impl FeePolicy {
+    /// Splits the `fee` between the outputs of `escrow_amounts`, returning the share of each output.
+    ///
+    /// # Errors
+    ///
+    /// Errors if there are no outputs, if a [`FeePolicy::Payer`] is not an output,
+    /// if the [`FeePolicy::Fixed`] shares are not one per output adding up to the `fee`,
+    /// or if an output cannot afford its share.
+    pub(crate) fn split(
+        &self,
+        fee: Amount,
+        escrow_amounts: &[Amount],
+    ) -> Result<Vec<Amount>, Error> {
+        let outputs = escrow_amounts.len();
+        if outputs == 0 {
+            return Err(Error::FeeSplit(
+                "There are no outputs to pay the fee".to_string(),
+            ));
+        }
+        let fee = fee.to_sat();
+        let shares = match self {
+            Self::Equal => {
+                let (share, remainder) = (fee / outputs as u64, fee % outputs as u64);
+                (0..outputs as u64)
+                    .map(|i| Amount::from_sat(share + u64::from(i < remainder)))
+                    .collect()
+            }
+            Self::Proportional => {
+                let total = escrow_amounts
+                    .iter()
+                    .map(|amount| u128::from(amount.to_sat()))
+                    .sum::<u128>();
+                if total == 0 {
+                    return Err(Error::FeeSplit(
+                        "Cannot split the fee proportionally to zero escrow amounts".to_string(),
+                    ));
+                }
+                let weights = escrow_amounts
+                    .iter()
+                    .map(|amount| amount.to_sat())
+                    .collect::<Vec<_>>();
+                largest_remainder_split(fee, &weights)
+                    .into_iter()
+                    .map(Amount::from_sat)
+                    .collect()
+            }
+            Self::Payer(payer) => {
+                if *payer >= outputs {
+                    return Err(Error::FeeSplit(format!(
+                        "The fee payer {payer} is not one of the {outputs} outputs"
+                    )));
+                }
+                (0..outputs)
+                    .map(|i| {
+                        if i == *payer {
+                            Amount::from_sat(fee)
+                        } else {
+                            Amount::ZERO
+                        }
+                    })
+                    .collect()
+            }
+            Self::Fixed(shares) => {
+                if shares.len() != outputs {
+                    return Err(Error::FeeSplit(format!(
+                        "Expected one fixed fee share per output. Got {} shares and {outputs} outputs",
+                        shares.len()
+                    )));
+                }
+                let total = shares
+                    .iter()
+                    .try_fold(Amount::ZERO, |total, share| total.checked_add(*share));
+                if total != Some(Amount::from_sat(fee)) {
+                    return Err(Error::FeeSplit(format!(
+                        "The fixed fee shares should add up to the fee of {fee} sats"
+                    )));
+                }
+                shares.clone()
+            }
+        };
+        for (i, (share, escrow_amount)) in shares.iter().zip(escrow_amounts).enumerate() {
+            if share > escrow_amount {
+                return Err(Error::FeeSplit(format!(
+                    "Output {i} cannot pay its fee share of {} sats from {} sats",
+                    share.to_sat(),
+                    escrow_amount.to_sat()
+                )));
+            }
+        }
+
+        Ok(shares)
+    }
+}
+
+/// What to do with the outputs of a transaction below the dust limit of their script,
+/// that nodes would not relay.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum DustPolicy {
+    /// Refuses to create the transaction with an [`Error::Dust`].
+    #[default]
+    Reject,
+    /// Drops the dust outputs, their value goes to the miners along with the fee.
+    MergeIntoFee,
+}
+
+impl DustPolicy {
+    /// Applies the policy to the `output` of a transaction.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the first dust output if the policy is [`DustPolicy::Reject`],
+    /// or if every output is dust, since a transaction needs at least one output.
+    pub(crate) fn apply(self, output: Vec<TxOut>) -> Result<Vec<TxOut>, Error> {
+        let dust = output
+            .iter()
+            .enumerate()
+            .find(|(_, txout)| txout.value < txout.script_pubkey.minimal_non_dust())
+            .map(|(index, txout)| Error::Dust {
+                index,
+                value: txout.value,
+                limit: txout.script_pubkey.minimal_non_dust(),
+            });
+        let Some(dust) = dust else {
+            return Ok(output);
+        };
+        if self == Self::Reject {
+            return Err(dust);
+        }
+        let output = output
+            .into_iter()
+            .filter(|txout| txout.value >= txout.script_pubkey.minimal_non_dust())
+            .collect::<Vec<_>>();
+        if output.is_empty() {
+            return Err(dust);
+        }
+        #[cfg(debug_assertions)]
+        trace!(?output, "dropped dust outputs");
+
+        Ok(output)
+    }
+}
+
+/// Splits `total` proportionally to the `weights`, which must not all be zero.
+///
+/// The remainder of the integer split goes one unit each to the largest fractional parts, ties to the first weights,
+/// hence the parts always add up to `total`.
+fn largest_remainder_split(total: u64, weights: &[u64]) -> Vec<u64> {
+    let weight = weights.iter().copied().map(u128::from).sum::<u128>();
+    let exact = weights
+        .iter()
+        .map(|w| u128::from(total) * u128::from(*w))
+        .collect::<Vec<_>>();
+    // Each floor is at most the total, the remainders add up to less than one unit per weight.
+    let mut parts = exact
+        .iter()
+        .map(|e| (e / weight) as u64)
+        .collect::<Vec<_>>();
+    let remainder = total - parts.iter().sum::<u64>();
+    let mut largest = (0..weights.len()).collect::<Vec<_>>();
+    largest.sort_by_key(|&i| Reverse(exact[i] % weight));
+    for &i in largest.iter().take(remainder as usize) {
+        parts[i] += 1;
+    }
+
+    parts
+}
+
+/// The amount paid to the destination of a [`Payout`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PayoutAmount {
+    /// A fixed amount.
+    Fixed(Amount),
+    /// A share of what is left of the escrow after the fee and the fixed payouts,
+    /// in basis points, i.e. hundredths of a percent.
+    Percent(u32),
+}
+
+/// An output of a resolution transaction, e.g. "the buyer gets 70%".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Payout {
+    /// Where the payout goes.
+    pub(crate) destination: Address,
+    /// How much goes there.
+    pub(crate) amount: PayoutAmount,
+}
+
+/// Resolves the amounts of the `payouts` of an escrow of `escrow_value` paying `fee`.
+///
+/// Fixed payouts are paid as is, and the percentages, which must add up to 100%,
+/// share what is left with the same rounding as [`FeePolicy::Proportional`].
+/// Either way the payouts plus the fee are exactly the escrow value.
+///
+/// # Errors
+///
+/// Errors if there are no payouts, if the fixed payouts and the fee exceed the escrow value,
+/// if the percentages do not add up to 100%,
+/// or if there are no percentages and the fixed payouts plus the fee are not the escrow value.
+pub(crate) fn payout_amounts(
+    payouts: &[Payout],
+    escrow_value: Amount,
+    fee: Amount,
+) -> Result<Vec<Amount>, Error> {
+    if payouts.is_empty() {
+        return Err(Error::Payout("There are no payouts".to_string()));
+    }
+    let fixed = payouts
+        .iter()
+        .try_fold(Amount::ZERO, |total, payout| match payout.amount {
+            PayoutAmount::Fixed(amount) => total.checked_add(amount),
+            PayoutAmount::Percent(_) => Some(total),
+        })
+        .ok_or(Error::Rounding)?;
+    let Some(rest) = escrow_value
+        .checked_sub(fee)
+        .and_then(|value| value.checked_sub(fixed))
+    else {
+        return Err(Error::Payout(format!(
+            "The fixed payouts of {} sats and the fee of {} sats exceed the escrow value of {} sats",
+            fixed.to_sat(),
+            fee.to_sat(),
+            escrow_value.to_sat()
+        )));
+    };
+    let basis_points = payouts
+        .iter()
+        .map(|payout| match payout.amount {
+            PayoutAmount::Fixed(_) => 0,
+            PayoutAmount::Percent(basis_points) => u64::from(basis_points),
+        })
+        .collect::<Vec<_>>();
+    match basis_points.iter().sum::<u64>() {
+        0 if rest != Amount::ZERO => {
+            return Err(Error::Payout(format!(
+                "The payouts and the fee add up to {} sats, but the escrow value is {} sats",
+                (escrow_value - rest).to_sat(),
+                escrow_value.to_sat()
+            )));
+        }
+        0 | 10_000 => {}
+        total => {
+            return Err(Error::Payout(format!(
+                "The percentages should add up to 100%. Got {}.{:02}%",
+                total / 100,
+                total % 100
+            )));
+        }
+    }
+    let shares = if rest == Amount::ZERO {
+        vec![0; payouts.len()]
+    } else {
+        largest_remainder_split(rest.to_sat(), &basis_points)
+    };
+
+    Ok(payouts
+        .iter()
+        .zip(shares)
+        .map(|(payout, share)| match payout.amount {
+            PayoutAmount::Fixed(amount) => amount,
+            PayoutAmount::Percent(_) => Amount::from_sat(share),
+        })
+        .collect())
+}
+
+/// The fee of an arbitrator for a ruling, paid to the arbitrator's resolution address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ArbitratorFee {
+    /// The arbitrator to pay.
+    pub(crate) arbitrator: NostPublicKey,
+    /// The fee, either a fixed amount or a percentage of the whole escrow value.
+    ///
+    /// Unlike the percentages of [`payout_amounts`], it is not a share of what is left after the fixed payouts.
+    pub(crate) amount: PayoutAmount,
+}
+
+impl ArbitratorFee {
+    /// The fee on an escrow of `escrow_value`, a percentage is rounded down to the satoshi.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the fee is not less than the `escrow_value`.
+    pub(crate) fn amount(&self, escrow_value: Amount) -> Result<Amount, Error> {
+        let amount = match self.amount {
+            PayoutAmount::Fixed(amount) => amount,
+            PayoutAmount::Percent(basis_points) => Amount::from_sat(
+                (u128::from(escrow_value.to_sat()) * u128::from(basis_points) / 10_000) as u64,
+            ),
+        };
+        if amount >= escrow_value {
+            return Err(Error::Payout(format!(
+                "The arbitrator fee of {} sats should be less than the escrow value of {} sats",
+                amount.to_sat(),
+                escrow_value.to_sat()
+            )));
+        }
+
+        Ok(amount)
+    }
+}
+
+/// Resolves a dispute ruling in favor of the party at the `winner` index of the [`EscrowContract`].
+///
+/// Returns the dispute leaf that the arbitrators and the winner sign,
+/// `B` for the first party and `C` for the second one of a two-party escrow, `B` otherwise,
+/// and the fixed [`Payout`]s of [`payout_psbt`]: the winner gets the escrow after the optional [`ArbitratorFee`],
+/// the arbitrator fee output follows, and the `fee` is split between both outputs by the [`FeePolicy`],
+/// e.g. [`FeePolicy::Payer`] `1` for the arbitrator to pay it.
+///
+/// # Errors
+///
+/// Errors if the contract has no arbitrators, if the `winner` is not a party,
+/// if the arbitrator to pay is not an arbitrator of the contract, if the arbitrator fee is too high,
+/// or if the [`FeePolicy`] cannot split the `fee`.
+pub(crate) fn ruling_payouts(
+    contract: &EscrowContract,
+    winner: usize,
+    arbitrator_fee: Option<&ArbitratorFee>,
+    escrow_value: Amount,
+    fee: Amount,
+    fee_policy: &FeePolicy,
+    network: Network,
+) -> Result<(EscrowScript, Vec<Payout>), Error> {
+    if contract.arbitrators().is_empty() {
+        return Err(Error::WrongInputs(
+            "The escrow has no arbitrators to rule a dispute".to_string(),
+        ));
+    }
+    if winner >= contract.parties().len() {
+        return Err(Error::WrongInputs(format!(
+            "The winner {winner} is not one of the {} parties",
+            contract.parties().len()
+        )));
+    };
+    let escrow_script = match winner {
+        1 if contract.is_two_party() => EscrowScript::C,
+        _ => EscrowScript::B,
+    };
+
+    let mut outputs = vec![(contract.resolution_address(winner, network)?, escrow_value)];
+    if let Some(arbitrator_fee) = arbitrator_fee {
+        if !contract.arbitrators().contains(&arbitrator_fee.arbitrator) {
+            return Err(Error::WrongInputs(format!(
+                "{} is not an arbitrator of the escrow",
+                arbitrator_fee.arbitrator
+            )));
+        }
+        let amount = arbitrator_fee.amount(escrow_value)?;
+        if amount > Amount::ZERO {
+            // The fee is less than the escrow value.
+            outputs[0].1 = escrow_value - amount;
+            outputs.push((
+                npub_to_address(&arbitrator_fee.arbitrator, network)?,
+                amount,
+            ));
+        }
+    }
+    // Only split once every output exists, so that the arbitrator fee output can pay too.
+    let gross_amounts = outputs
+        .iter()
+        .map(|(_, amount)| *amount)
+        .collect::<Vec<_>>();
+    let fee_shares = fee_policy.split(fee, &gross_amounts)?;
+    #[cfg(debug_assertions)]
+    trace!(?fee_shares, "ruling fee shares");
+    let payouts = outputs
+        .into_iter()
+        .zip(fee_shares)
+        .map(|((destination, amount), fee_share)| Payout {
+            destination,
+            // Each share is at most its output amount.
+            amount: PayoutAmount::Fixed(amount - fee_share),
+        })
+        .collect::<Vec<_>>();
+    #[cfg(debug_assertions)]
+    trace!(?escrow_script, ?payouts, "ruling payouts");
+
+    Ok((escrow_script, payouts))
+}
+
+/// An unspent output, e.g. of a resolution address spendable by its key path,
+/// or of an escrow address funding the escrow transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct Utxo {
+    /// The outpoint of the output.
+    pub(crate) outpoint: OutPoint,
+    /// The value of the output.
+    pub(crate) value: Amount,
+    /// Whether the transaction of the output is confirmed.
+    pub(crate) confirmed: bool,
+}
+
+impl Utxo {
+    /// The weight that spending a [`Utxo`] by its key path adds to a transaction.
+    pub(crate) fn input_weight() -> Weight {
+        TxIn {
+            witness: dummy_key_path_witness(),
+            ..Default::default()
+        }
+        .segwit_weight()
+    }
+
+    /// The value of the [`Utxo`] minus the fee of spending it at `fee_rate`,
+    /// or `None` if it is not worth spending.
+    pub(crate) fn effective_value(&self, fee_rate: FeeRate) -> Option<Amount> {
+        let input_fee = fee_rate.fee_wu(Self::input_weight())?;
+        self.value
+            .checked_sub(input_fee)
+            .filter(|value| *value > Amount::ZERO)
+    }
+}
+
+/// The [`Utxo`]s worth sweeping at `fee_rate`, largest first:
+/// those whose value exceeds the fee of spending them, see [`Utxo::effective_value`].
+pub(crate) fn spendable_utxos(utxos: &[Utxo], fee_rate: FeeRate) -> Vec<Utxo> {
+    let mut spendable = utxos
+        .iter()
+        .filter(|utxo| utxo.effective_value(fee_rate).is_some())
+        .copied()
+        .collect::<Vec<_>>();
+    spendable.sort_by_key(|utxo| Reverse(utxo.value));
+    #[cfg(debug_assertions)]
+    trace!(spendable = %spendable.len(), utxos = %utxos.len(), %fee_rate, "spendable utxos");
+
+    spendable
+}
+
+/// Selects the [`Utxo`]s whose [`sweep_tx`] pays at least `target` to the `destination` at `fee_rate`.
+///
+/// The [`spendable_utxos`] are added largest first until they cover the `target`
+/// and the fee of sweeping them, i.e. every UTXO pays for its own input
+/// and the rest of the transaction is paid once.
+///
+/// # Errors
+///
+/// Errors if the spendable UTXOs do not cover the `target` and their fee,
+/// or if what they sweep is dust.
+pub(crate) fn select_utxos(
+    utxos: &[Utxo],
+    target: Amount,
+    destination: &Address,
+    fee_rate: FeeRate,
+) -> Result<Vec<Utxo>, Error> {
+    let mut selected = Vec::new();
+    let mut total = Amount::ZERO;
+    for utxo in spendable_utxos(utxos, fee_rate) {
+        selected.push(utxo);
+        total = total.checked_add(utxo.value).ok_or(Error::Rounding)?;
+        if total < target {
+            continue;
+        }
+        let tx_fee = sweep_fee(&selected, destination, fee_rate)?;
+        if total >= target.checked_add(tx_fee.fee).ok_or(Error::Rounding)? {
+            #[cfg(debug_assertions)]
+            trace!(selected = %selected.len(), %total, %target, %tx_fee, "selected utxos");
+            return Ok(selected);
+        }
+    }
+
+    Err(Error::WrongInputs(format!(
+        "The UTXOs worth spending of {} sats do not cover the amount of {} sats and its fee",
+        total.to_sat(),
+        target.to_sat()
+    )))
+}
 
-/// Creates a [`Transaction`] that swipe the resolution address to a `destination` [`Address`].
+/// Creates a [`Transaction`] that consolidates the `utxos` of the resolution address
+/// into a single output to a `destination` [`Address`], one input per [`Utxo`] in order.
+///
+/// # Errors
 ///
-/// Assumes that the resolution address is derived from the users' Nostr public key
-/// and has received a single input.
-pub(crate) fn resolution_tx(
-    amount: Amount,
-    funding_txid: Txid,
-    vout: u32,
+/// Errors if there are no `utxos`, if the `fee` exceeds their total value,
+/// or if what is left is dust: the only output cannot be merged into the fee.
+pub(crate) fn sweep_tx(
+    utxos: &[Utxo],
     destination: &Address,
     fee: Amount,
-) -> Transaction {
-    // Parse stuff
-    let prevout = OutPoint {
-        txid: funding_txid,
-        vout,
+) -> Result<Transaction, Error> {
+    if utxos.is_empty() {
+        return Err(Error::WrongInputs("No UTXOs to spend".to_string()));
+    }
+    let amount = total_amount(&utxos.iter().map(|utxo| utxo.value).collect::<Vec<_>>())?;
+    let Some(value) = amount.checked_sub(fee) else {
+        return Err(Error::WrongInputs(format!(
+            "The fee of {} sats exceeds the amount of {} sats",
+            fee.to_sat(),
+            amount.to_sat()
+        )));
     };
+    let output = DustPolicy::Reject.apply(vec![TxOut {
+        value,
+        script_pubkey: destination.script_pubkey(),
+    }])?;
 
     // Create the transaction
-    Transaction {
+    Ok(Transaction {
         version: transaction::Version(2),
         lock_time: absolute::LockTime::ZERO,
-        input: vec![TxIn {
-            previous_output: prevout,
-            ..Default::default()
-        }],
-        output: vec![TxOut {
-            value: amount - fee,
-            script_pubkey: destination.script_pubkey(),
-        }],
+        input: utxos
+            .iter()
+            .map(|utxo| TxIn {
+                previous_output: utxo.outpoint,
+                ..Default::default()
+            })
+            .collect(),
+        output,
+    })
+}
+
+/// Creates a multisig [`Transaction`] for collaboration/dispute between N parties,
+/// paying each party's escrow amount in `escrow_amounts` to its resolution address in `destinations`.
+///
+/// The user should also specify the `funding` outputs of the escrow address, see [`escrow_spend_tx`].
+///
+/// The resolution addresses are usually [`EscrowContract::resolution_addresses`],
+/// and the `fee` is split between the parties by the [`FeePolicy`].
+/// The `timelock` sets the input sequence and `lock_time` as in [`escrow_spend_tx`].
+///
+/// Dust outputs are left to the callers' [`DustPolicy`].
+///
+/// # Errors
+///
+/// Errors if `destinations` and `escrow_amounts` have different lengths,
+/// or if the [`FeePolicy`] cannot split the `fee`.
+/// The callers check that the `funding` outputs add up to the escrow, see [`check_funding`].
+pub(crate) fn contract_tx(
+    destinations: &[Address],
+    escrow_amounts: &[Amount],
+    timelock: Option<Timelock>,
+    funding: &[Utxo],
+    fee: Amount,
+    fee_policy: &FeePolicy,
+) -> Result<Transaction, Error> {
+    if destinations.len() != escrow_amounts.len() {
+        return Err(Error::WrongInputs(format!(
+            "Expected one escrow amount per party. Got {} parties and {} amounts",
+            destinations.len(),
+            escrow_amounts.len()
+        )));
     }
+    let fee_shares = fee_policy.split(fee, escrow_amounts)?;
+    #[cfg(debug_assertions)]
+    trace!(?fee_shares, "fee shares");
+
+    let mut output = Vec::with_capacity(destinations.len());
+    for ((resolution_address, escrow_amount), fee_share) in
+        destinations.iter().zip(escrow_amounts).zip(fee_shares)
+    {
+        // Each share is at most its escrow amount.
+        let liquid_escrow_amount = *escrow_amount - fee_share;
+        #[cfg(debug_assertions)]
+        trace!(%liquid_escrow_amount, %resolution_address, "liquid escrow amount");
+        output.push(TxOut {
+            value: liquid_escrow_amount,
+            script_pubkey: resolution_address.script_pubkey(),
+        });
+    }
+
+    Ok(escrow_spend_tx(output, timelock, funding))
+}
+
+/// Creates a [`Transaction`] spending the `funding` outputs of the escrow address to the `output`,
+/// one input per funding output in order.
+///
+/// A [`Timelock::Relative`] timelock is the sequence of every input,
+/// whereas a [`Timelock::Absolute`] timelock is the transaction `lock_time`
+/// with non-final input sequences so that it is enforced.
+/// Without a timelock, the input sequences signal RBF (BIP125).
+fn escrow_spend_tx(
+    output: Vec<TxOut>,
+    timelock: Option<Timelock>,
+    funding: &[Utxo],
+) -> Transaction {
+    let (lock_time, sequence) = match timelock {
+        None => (absolute::LockTime::ZERO, Sequence::ENABLE_RBF_NO_LOCKTIME),
+        Some(Timelock::Relative(lock_time)) => (absolute::LockTime::ZERO, lock_time.to_sequence()),
+        Some(Timelock::Absolute(lock_time)) => (lock_time, Sequence::ENABLE_LOCKTIME_NO_RBF),
+    };
+    #[cfg(debug_assertions)]
+    trace!(%lock_time, %sequence, "timelock");
+
+    Transaction {
+        version: transaction::Version(2),
+        lock_time,
+        input: funding
+            .iter()
+            .map(|utxo| TxIn {
+                previous_output: utxo.outpoint,
+                sequence,
+                ..Default::default()
+            })
+            .collect(),
+        output,
+    }
+}
+
+/// Sums the `funding` outputs of an escrow.
+///
+/// # Errors
+///
+/// Errors if there are no funding outputs.
+pub(crate) fn funding_value(funding: &[Utxo]) -> Result<Amount, Error> {
+    if funding.is_empty() {
+        return Err(Error::WrongInputs(
+            "The escrow has no funding outputs".to_string(),
+        ));
+    }
+
+    total_amount(&funding.iter().map(|utxo| utxo.value).collect::<Vec<_>>())
+}
+
+/// Checks that the `funding` outputs of an escrow add up to its `escrow_value`.
+///
+/// # Errors
+///
+/// Errors if there are no funding outputs, or if they do not add up to the escrow value.
+fn check_funding(funding: &[Utxo], escrow_value: Amount) -> Result<(), Error> {
+    let funding_value = funding_value(funding)?;
+    if funding_value != escrow_value {
+        return Err(Error::WrongInputs(format!(
+            "The funding outputs of {} sats do not add up to the escrow amount of {} sats",
+            funding_value.to_sat(),
+            escrow_value.to_sat()
+        )));
+    }
+
+    Ok(())
 }
 
-/// Creates a 2-of-2/2-of-3 multisig [`Transaction`] for collaboration/dispute between two/three users,
-/// given an escrow amount, and their respective Nostr public keys (`npub`s).
+/// Creates an unsigned [`Psbt`] (BIP174/BIP371) of an [`EscrowContract`] wrapping the [`contract_tx`] transaction.
 ///
-/// The user should also specify the funding [`Txid`] that assumes the vout is always 0.
+/// Every escrow input carries everything that a BIP371-capable signer needs to sign it
+/// without knowing anything about the escrow contract:
+/// the `witness_utxo`, all the `tap_scripts` leaves, the `tap_merkle_root`,
+/// the `tap_internal_key` and the `tap_key_origins` of every key in the Taproot tree.
 ///
-/// The resolution address is the address derived from the users' `npub`s.
+/// With an [`InternalKey::MuSig2`](crate::scripts::InternalKey::MuSig2) internal key,
+/// the cooperative resolution is signed off the [`Psbt`] with the MuSig2 flow in [`crate::sign`].
+///
+/// The parties are paid to their [`EscrowContract::resolution_addresses`].
+///
+/// The transaction spends the `escrow_script` leaf, and has the timelock of that leaf only:
+/// the collaborative leaf `A` (or the MuSig2 key path) signals RBF instead.
 ///
 /// # Errors
 ///
-/// Errors if could not create SegWit-v1 P2TR resolution addresses from supplied `npub`s.
+/// Errors if the contract does not have the `escrow_script` leaf,
+/// if the `funding` outputs do not add up to the `escrow_amounts`,
+/// if could not create the contract Taproot tree or the [`contract_tx`] transaction,
+/// or if the [`DustPolicy`] rejects an output.
 #[expect(clippy::too_many_arguments)]
-pub(crate) fn escrow_tx(
-    npub_1: &NostPublicKey,
-    npub_2: &NostPublicKey,
-    timelock_duration: Option<u32>,
-    escrow_amount_1: Amount,
-    escrow_amount_2: Amount,
-    funding_txid: Txid,
+pub(crate) fn contract_psbt(
+    contract: &EscrowContract,
+    escrow_script: EscrowScript,
+    escrow_amounts: &[Amount],
+    funding: &[Utxo],
     fee: Amount,
+    fee_policy: &FeePolicy,
+    dust_policy: DustPolicy,
     network: Network,
-) -> Result<Transaction, Error> {
-    // Parse stuff
-    let prevout = OutPoint {
-        txid: funding_txid,
-        vout: 0,
-    };
-    let resolution_address_1 = npub_to_address(npub_1, network)?;
-    let resolution_address_2 = npub_to_address(npub_2, network)?;
+) -> Result<Psbt, Error> {
+    let timelock = leaf_timelock(&contract_scripts(contract, escrow_script)?);
+    check_funding(funding, total_amount(escrow_amounts)?)?;
+    let mut tx = contract_tx(
+        &contract.resolution_addresses(network)?,
+        escrow_amounts,
+        timelock,
+        funding,
+        fee,
+        fee_policy,
+    )?;
+    tx.output = dust_policy.apply(tx.output)?;
+    check_timelocks(&tx, timelock)?;
 
-    // Calculate the fees per participant
-    let fees_per_participant = match fee.checked_div(2) {
-        Some(fee) => fee,
-        None => return Err(Error::Rounding),
-    };
+    contract_tx_psbt(contract, tx, funding)
+}
+
+/// Creates an unsigned [`Psbt`] (BIP174/BIP371) of an [`EscrowContract`] spending the `escrow_script` leaf
+/// to arbitrary `payouts`, e.g. an arbitrated ruling or a full refund.
+///
+/// The escrow value is the sum of the `funding` outputs,
+/// and the payouts plus the `fee` add up to it exactly, see [`payout_amounts`].
+///
+/// # Errors
+///
+/// Errors if could not create the contract Taproot tree,
+/// if there are no funding outputs, if the payouts do not add up to the escrow value,
+/// or if the [`DustPolicy`] rejects a payout.
+pub(crate) fn payout_psbt(
+    contract: &EscrowContract,
+    escrow_script: EscrowScript,
+    payouts: &[Payout],
+    funding: &[Utxo],
+    fee: Amount,
+    dust_policy: DustPolicy,
+) -> Result<Psbt, Error> {
+    let escrow_value = funding_value(funding)?;
+    let amounts = payout_amounts(payouts, escrow_value, fee)?;
+    let output = payouts
+        .iter()
+        .zip(amounts)
+        .map(|(payout, value)| TxOut {
+            value,
+            script_pubkey: payout.destination.script_pubkey(),
+        })
+        .collect();
     #[cfg(debug_assertions)]
-    trace!(%fees_per_participant, "fees per participant");
-    let liquid_escrow_amount_1 = match escrow_amount_1.checked_sub(fees_per_participant) {
-        Some(amount) => amount,
-        None => return Err(Error::Rounding),
+    trace!(?output, "payouts");
+    let output = dust_policy.apply(output)?;
+    let timelock = leaf_timelock(&contract_scripts(contract, escrow_script)?);
+    let tx = escrow_spend_tx(output, timelock, funding);
+    check_timelocks(&tx, timelock)?;
+
+    contract_tx_psbt(contract, tx, funding)
+}
+
+/// Creates an unsigned refund [`Psbt`] (BIP174/BIP371) of an [`EscrowContract`]
+/// spending the refund leaf [`EscrowScript::D`](crate::scripts::EscrowScript::D).
+///
+/// If `split` the outputs are the pre-agreed `escrow_amounts` of [`contract_psbt`] paying the `fee` by the [`FeePolicy`],
+/// otherwise the refund party gets the whole escrow and pays the whole `fee`.
+/// Either way the input sequence is the refund timelock.
+///
+/// The refund leaf only checks the refund party's signature, not the outputs,
+/// so the pre-agreed split is a courtesy of the refund party.
+///
+/// # Errors
+///
+/// Errors if the contract has no refund leaf,
+/// if the `funding` outputs do not add up to the `escrow_amounts`,
+/// if could not create the contract Taproot tree or the refund transaction,
+/// or if the [`DustPolicy`] rejects an output.
+#[expect(clippy::too_many_arguments)]
+pub(crate) fn refund_psbt(
+    contract: &EscrowContract,
+    escrow_amounts: &[Amount],
+    split: bool,
+    funding: &[Utxo],
+    fee: Amount,
+    fee_policy: &FeePolicy,
+    dust_policy: DustPolicy,
+    network: Network,
+) -> Result<Psbt, Error> {
+    let Some((refund_party, refund_timelock)) = contract.refund() else {
+        return Err(Error::InvalidEscrowType("D".to_string()));
     };
-    #[cfg(debug_assertions)]
-    trace!(%liquid_escrow_amount_1, "liquid escrow amount 1");
-    let liquid_escrow_amount_2 = match escrow_amount_2.checked_sub(fees_per_participant) {
-        Some(amount) => amount,
-        None => return Err(Error::Rounding),
+    check_funding(funding, total_amount(escrow_amounts)?)?;
+    let mut tx = if split {
+        contract_tx(
+            &contract.resolution_addresses(network)?,
+            escrow_amounts,
+            Some(Timelock::Relative(refund_timelock)),
+            funding,
+            fee,
+            fee_policy,
+        )?
+    } else {
+        // The refund party is a party of the contract, see `EscrowContract::with_refund`.
+        let index = contract
+            .parties()
+            .iter()
+            .position(|npub| npub == refund_party)
+            .unwrap_or_default();
+        contract_tx(
+            &[contract.resolution_address(index, network)?],
+            &[total_amount(escrow_amounts)?],
+            Some(Timelock::Relative(refund_timelock)),
+            funding,
+            fee,
+            &FeePolicy::Equal,
+        )?
     };
-    #[cfg(debug_assertions)]
-    trace!(%liquid_escrow_amount_2, "liquid escrow amount 2");
+    tx.output = dust_policy.apply(tx.output)?;
+    check_timelocks(&tx, Some(Timelock::Relative(refund_timelock)))?;
 
-    let timelock_duration = timelock_duration.unwrap_or_default();
-    #[cfg(debug_assertions)]
-    trace!(%timelock_duration, "timelock duration");
+    contract_tx_psbt(contract, tx, funding)
+}
 
-    // Create the transaction
-    let tx = Transaction {
+/// A party's share of the collaborative funding transaction of an escrow, see [`funding_psbt`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FundingContribution {
+    /// The Nostr public key of the party, whose resolution address holds the `utxos`.
+    pub(crate) npub: NostPublicKey,
+    /// The [`Utxo`]s of the resolution address that the party spends.
+    pub(crate) utxos: Vec<Utxo>,
+    /// The escrow amount that the party deposits.
+    pub(crate) amount: Amount,
+    /// Where the change of the party goes, its resolution address if `None`.
+    pub(crate) change_address: Option<Address>,
+}
+
+/// Creates an unsigned collaborative funding [`Psbt`] (BIP174/BIP371), i.e. a coinjoin,
+/// that pays the escrow amounts of every [`FundingContribution`] to the `escrow_address`
+/// in a single output, followed by the change of every party in party order.
+///
+/// Every party pays the fee of its own inputs and change output at `fee_rate`,
+/// and the fee of the rest of the transaction, including the escrow output, is split equally.
+/// A change below the dust limit is dropped and goes to the fee.
+///
+/// The inputs spend the resolution addresses of the parties by their key path,
+/// so every party signs its own inputs with its `nsec`, see [`sign_funding_psbt`](crate::sign::sign_funding_psbt).
+///
+/// # Errors
+///
+/// Errors if there are no parties, if a party has no UTXOs,
+/// if a change address is not valid for the `network`,
+/// or if the UTXOs of a party do not cover its escrow amount and its fee.
+pub(crate) fn funding_psbt(
+    escrow_address: &Address,
+    contributions: &[FundingContribution],
+    fee_rate: FeeRate,
+    network: Network,
+) -> Result<Psbt, Error> {
+    if contributions.is_empty() {
+        return Err(Error::WrongInputs("No parties fund the escrow".to_string()));
+    }
+    let mut input = Vec::new();
+    let mut prevouts = Vec::new();
+    let mut change_outputs = Vec::with_capacity(contributions.len());
+    for (index, contribution) in contributions.iter().enumerate() {
+        if contribution.utxos.is_empty() {
+            return Err(Error::WrongInputs(format!(
+                "Party {} has no UTXOs to fund the escrow",
+                index + 1
+            )));
+        }
+        let address = npub_to_address(&contribution.npub, network)?;
+        let internal_key = npub_to_x_only_public_key(&contribution.npub)?;
+        let change_address = resolution_address(
+            &contribution.npub,
+            contribution.change_address.as_ref(),
+            network,
+        )?;
+        for utxo in &contribution.utxos {
+            input.push(TxIn {
+                previous_output: utxo.outpoint,
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                ..Default::default()
+            });
+            prevouts.push((
+                TxOut {
+                    value: utxo.value,
+                    script_pubkey: address.script_pubkey(),
+                },
+                internal_key,
+            ));
+        }
+        change_outputs.push(TxOut {
+            value: Amount::ZERO,
+            script_pubkey: change_address.script_pubkey(),
+        });
+    }
+    let escrow_amounts = contributions
+        .iter()
+        .map(|contribution| contribution.amount)
+        .collect::<Vec<_>>();
+    let mut output = vec![TxOut {
+        value: total_amount(&escrow_amounts)?,
+        script_pubkey: escrow_address.script_pubkey(),
+    }];
+    output.extend(change_outputs);
+    let mut tx = Transaction {
         version: transaction::Version(2),
         lock_time: absolute::LockTime::ZERO,
-        input: vec![TxIn {
-            previous_output: prevout,
-            sequence: Sequence::from_consensus(timelock_duration),
-            ..Default::default()
-        }],
-        output: vec![
-            TxOut {
-                value: liquid_escrow_amount_1,
-                script_pubkey: resolution_address_1.script_pubkey(),
-            },
-            TxOut {
-                value: liquid_escrow_amount_2,
-                script_pubkey: resolution_address_2.script_pubkey(),
-            },
-        ],
+        input,
+        output,
     };
 
-    Ok(tx)
+    // Every party pays for what it adds to the transaction, and an equal share of the rest.
+    let witnesses = vec![dummy_key_path_witness(); tx.input.len()];
+    let tx_fee = TxFee::new(&tx, &witnesses, fee_rate)?;
+    let own_fees = contributions
+        .iter()
+        .zip(&tx.output[1..])
+        .map(|(contribution, change)| {
+            let weight = Utxo::input_weight() * contribution.utxos.len() as u64 + change.weight();
+            fee_rate
+                .fee_wu(weight)
+                .ok_or_else(|| Error::InvalidFeeRate(fee_rate.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let shared_fee = tx_fee
+        .fee
+        .checked_sub(total_amount(&own_fees)?)
+        .unwrap_or(Amount::ZERO);
+    let shared_fees = FeePolicy::Equal.split(shared_fee, &escrow_amounts)?;
+    #[cfg(debug_assertions)]
+    trace!(?own_fees, ?shared_fees, "funding fees");
+
+    for (index, (contribution, (own_fee, shared_fee))) in contributions
+        .iter()
+        .zip(own_fees.into_iter().zip(shared_fees))
+        .enumerate()
+    {
+        let inputs = total_amount(
+            &contribution
+                .utxos
+                .iter()
+                .map(|utxo| utxo.value)
+                .collect::<Vec<_>>(),
+        )?;
+        let fee = own_fee + shared_fee;
+        let Some(change) = inputs.checked_sub(contribution.amount + fee) else {
+            return Err(Error::WrongInputs(format!(
+                "The UTXOs of party {} of {} sats do not cover its escrow amount of {} sats and its fee of {} sats",
+                index + 1,
+                inputs.to_sat(),
+                contribution.amount.to_sat(),
+                fee.to_sat()
+            )));
+        };
+        tx.output[index + 1].value = change;
+    }
+    // The escrow output is never dropped, only the dust change.
+    DustPolicy::Reject.apply(tx.output[..1].to_vec())?;
+    tx.output
+        .retain(|txout| txout.value >= txout.script_pubkey.minimal_non_dust());
+
+    let mut psbt = Psbt::from_unsigned_tx(tx)?;
+    for (input, (prevout, internal_key)) in psbt.inputs.iter_mut().zip(prevouts) {
+        input.witness_utxo = Some(prevout);
+        input.tap_internal_key = Some(internal_key);
+    }
+
+    Ok(psbt)
 }
 
-#[cfg(test)]
-mod tests {
-    use bitcoin::{consensus, hex::DisplayHex};
+/// The weight of a [`Transaction`] with the witnesses it will be signed with, and the fee it pays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TxFee {
+    /// Weight of the signed transaction.
+    pub(crate) weight: Weight,
+    /// Fee of the transaction.
+    pub(crate) fee: Amount,
+}
 
-    use crate::util::parse_npub;
+impl TxFee {
+    /// Computes the weight of `tx` once its inputs have the `witnesses`
+    /// and the fee that it should pay at `fee_rate`.
+    ///
+    /// The witnesses are usually placeholders of the exact size,
+    /// e.g. [`dummy_contract_witness`] or [`dummy_key_path_witness`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if there is not one witness per input or if the fee overflows.
+    pub(crate) fn new(
+        tx: &Transaction,
+        witnesses: &[Witness],
+        fee_rate: FeeRate,
+    ) -> Result<Self, Error> {
+        if tx.input.len() != witnesses.len() {
+            return Err(Error::WrongInputs(format!(
+                "Expected one witness per input. Got {} inputs and {} witnesses",
+                tx.input.len(),
+                witnesses.len()
+            )));
+        }
+        let mut tx = tx.clone();
+        for (input, witness) in tx.input.iter_mut().zip(witnesses) {
+            input.witness = witness.clone();
+        }
+        let weight = tx.weight();
+        let fee = fee_rate
+            .fee_wu(weight)
+            .ok_or_else(|| Error::InvalidFeeRate(fee_rate.to_string()))?;
+        #[cfg(debug_assertions)]
+        trace!(%weight, %fee, "transaction fee");
 
-    use super::*;
+        Ok(Self { weight, fee })
+    }
 
-    #[test]
-    fn test_escrow_tx() {
-        let npub_1 =
-            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
-        let npub_2 =
-            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
-        let escrow_amount_1 = Amount::from_sat(50_000_000);
-        let escrow_amount_2 = Amount::from_sat(50_000_000);
-        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
-            .parse::<Txid>()
-            .unwrap();
-        let fee = Amount::from_sat(1_000);
-        let network = Network::Bitcoin;
-        let tx = escrow_tx(
-            &npub_1,
-            &npub_2,
-            None,
-            escrow_amount_1,
-            escrow_amount_2,
-            funding_txid,
-            fee,
-            network,
+    /// Virtual size of the transaction in vbytes, rounded up.
+    pub(crate) fn vsize(&self) -> u64 {
+        self.weight.to_vbytes_ceil()
+    }
+
+    /// The effective fee rate in sat/vB.
+    pub(crate) fn effective_rate(&self) -> f64 {
+        self.fee.to_sat() as f64 / self.vsize() as f64
+    }
+}
+
+impl fmt::Display for TxFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} vB, {} sats ({:.2} sat/vB)",
+            self.vsize(),
+            self.fee.to_sat(),
+            self.effective_rate()
         )
-        .unwrap();
-        println!(
-            "Collaborative Transaction: {}",
-            consensus::serialize(&tx).as_hex()
-        );
+    }
+}
 
-        let resolution_address_1p = npub_to_address(&npub_1, network).unwrap();
-        let resolution_address_2p = npub_to_address(&npub_2, network).unwrap();
+/// Who an output of a [`Transaction`] spending an escrow pays, see [`signing_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputLabel {
+    /// The resolution address of the party at the index.
+    Party(usize),
+    /// The address of the arbitrator at the index, e.g. an [`ArbitratorFee`].
+    Arbitrator(usize),
+    /// An address that is not part of the contract.
+    Unknown,
+}
 
-        assert_eq!(
-            tx.output[0].script_pubkey,
-            resolution_address_1p.script_pubkey()
-        );
-        assert_eq!(
-            tx.output[1].script_pubkey,
-            resolution_address_2p.script_pubkey()
+impl fmt::Display for OutputLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Party(index) => write!(f, "Party {} resolution address", index + 1),
+            Self::Arbitrator(index) => write!(f, "Arbitrator {}", index + 1),
+            Self::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+/// An output of a [`Transaction`] spending an escrow, with who it pays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LabeledOutput {
+    /// The address of the output, if its script is a standard one.
+    pub(crate) address: Option<Address>,
+    /// The value of the output.
+    pub(crate) value: Amount,
+    /// Who the output pays.
+    pub(crate) label: OutputLabel,
+}
+
+/// What a [`Transaction`] spending an escrow does, as shown before signing it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SigningPolicy {
+    /// Every output, in order.
+    pub(crate) outputs: Vec<LabeledOutput>,
+    /// The weight once signed and the fee.
+    pub(crate) fee: TxFee,
+}
+
+/// What the parties and the arbitrators agreed that a [`Transaction`] spending an escrow pays,
+/// see [`SigningPolicy::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct AgreedPayouts {
+    /// The payout of each party before the fee, a missing party is agreed to get nothing.
+    pub(crate) parties: Vec<Amount>,
+    /// The fee of the arbitrators together before the fee.
+    pub(crate) arbitrators: Amount,
+    /// How the fee is split between the paid recipients, in the order of the outputs
+    /// of the transaction builders: the parties, then the arbitrators.
+    pub(crate) fee_policy: FeePolicy,
+    /// The highest fee rate that the transaction may pay.
+    pub(crate) max_fee_rate: FeeRate,
+}
+
+impl SigningPolicy {
+    /// Checks that every output pays a party or an arbitrator of the contract,
+    /// and that each party and the arbitrators together are paid their `agreed` amount.
+    ///
+    /// The agreed amounts must add up to the value of the escrow spent, the fee must not exceed
+    /// the [`AgreedPayouts::max_fee_rate`], and each recipient may only be paid less
+    /// than agreed by its [`AgreedPayouts::fee_policy`] share of the fee,
+    /// so that no value is burned to the fee.
+    ///
+    /// # Errors
+    ///
+    /// Errors with the number of outputs that pay an address outside of the contract,
+    /// if the agreed amounts do not add up to the escrow value, if the fee rate is too high,
+    /// if the fee cannot be split between the paid recipients,
+    /// or if a party or the arbitrators are paid more than agreed or less than agreed minus their fee share.
+    pub(crate) fn check(&self, agreed: &AgreedPayouts) -> Result<(), Error> {
+        let unknown = self
+            .outputs
+            .iter()
+            .filter(|output| output.label == OutputLabel::Unknown)
+            .count();
+        if unknown > 0 {
+            return Err(Error::UnknownOutputs(unknown));
+        }
+
+        let escrow_value = self
+            .outputs
+            .iter()
+            .map(|output| output.value)
+            .sum::<Amount>()
+            + self.fee.fee;
+        let agreed_value = agreed.parties.iter().copied().sum::<Amount>() + agreed.arbitrators;
+        if agreed_value != escrow_value {
+            return Err(Error::UnbalancedPayouts {
+                agreed: agreed_value,
+                escrow: escrow_value,
+            });
+        }
+        let max_fee = agreed
+            .max_fee_rate
+            .fee_wu(self.fee.weight)
+            .ok_or_else(|| Error::InvalidFeeRate(agreed.max_fee_rate.to_string()))?;
+        if self.fee.fee > max_fee {
+            return Err(Error::FeeTooHigh {
+                fee: self.fee.fee,
+                max: max_fee,
+            });
+        }
+
+        let paid_to = |is_paid: &dyn Fn(&OutputLabel) -> bool| {
+            self.outputs
+                .iter()
+                .filter(|output| is_paid(&output.label))
+                .map(|output| output.value)
+                .sum::<Amount>()
+        };
+        let parties = self
+            .outputs
+            .iter()
+            .filter_map(|output| match output.label {
+                OutputLabel::Party(index) => Some(index + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default()
+            .max(agreed.parties.len());
+        let mut recipients = (0..parties)
+            .map(|index| {
+                (
+                    format!("Party {}", index + 1),
+                    paid_to(&|label| *label == OutputLabel::Party(index)),
+                    agreed.parties.get(index).copied().unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+        recipients.push((
+            "The arbitrators".to_string(),
+            paid_to(&|label| matches!(label, OutputLabel::Arbitrator(_))),
+            agreed.arbitrators,
+        ));
+
+        // Only the recipients that are agreed to be paid have an output to pay the fee from.
+        let paid_amounts = recipients
+            .iter()
+            .filter(|(_, _, agreed)| *agreed > Amount::ZERO)
+            .map(|(_, _, agreed)| *agreed)
+            .collect::<Vec<_>>();
+        let mut fee_shares = agreed
+            .fee_policy
+            .split(self.fee.fee, &paid_amounts)?
+            .into_iter();
+        for (recipient, paid, agreed) in recipients {
+            let fee_share = if agreed > Amount::ZERO {
+                fee_shares.next().unwrap_or_default()
+            } else {
+                Amount::ZERO
+            };
+            if paid > agreed || agreed - paid > fee_share {
+                return Err(Error::UnexpectedAmount {
+                    recipient,
+                    paid,
+                    agreed,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Decodes the outputs of a `tx` spending the `escrow_script` leaf of an escrow `contract`
+/// and labels who each one pays, with the fee paid by the `prevouts` of its inputs.
+///
+/// Outputs are labelled as the [`EscrowContract::resolution_address`] of a party,
+/// the key path address of an arbitrator, or [`OutputLabel::Unknown`],
+/// e.g. when a counterparty swapped an output to its own address.
+///
+/// # Errors
+///
+/// Errors if there is not one prevout per input, if the outputs exceed the prevouts,
+/// or if the `escrow_script` is not a leaf of the contract.
+pub(crate) fn signing_policy(
+    tx: &Transaction,
+    prevouts: &[TxOut],
+    contract: &EscrowContract,
+    escrow_script: EscrowScript,
+    network: Network,
+) -> Result<SigningPolicy, Error> {
+    if tx.input.len() != prevouts.len() {
+        return Err(Error::WrongInputs(format!(
+            "Expected one prevout per input. Got {} inputs and {} prevouts",
+            tx.input.len(),
+            prevouts.len()
+        )));
+    }
+    let resolution_addresses = contract.resolution_addresses(network)?;
+    let arbitrator_addresses = contract
+        .arbitrators()
+        .iter()
+        .map(|npub| npub_to_address(npub, network))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let outputs = tx
+        .output
+        .iter()
+        .map(|output| {
+            let is_paid = |address: &Address| address.script_pubkey() == output.script_pubkey;
+            let label = if let Some(index) = resolution_addresses.iter().position(is_paid) {
+                OutputLabel::Party(index)
+            } else if let Some(index) = arbitrator_addresses.iter().position(is_paid) {
+                OutputLabel::Arbitrator(index)
+            } else {
+                OutputLabel::Unknown
+            };
+            LabeledOutput {
+                address: Address::from_script(&output.script_pubkey, network).ok(),
+                value: output.value,
+                label,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let input_value = prevouts.iter().map(|prevout| prevout.value).sum::<Amount>();
+    let output_value = tx.output.iter().map(|output| output.value).sum::<Amount>();
+    let Some(fee) = input_value.checked_sub(output_value) else {
+        return Err(Error::WrongInputs(format!(
+            "The outputs of {} sats exceed the inputs of {} sats",
+            output_value.to_sat(),
+            input_value.to_sat()
+        )));
+    };
+    let mut signed_tx = tx.clone();
+    let witness = dummy_contract_witness(contract, escrow_script)?;
+    for input in &mut signed_tx.input {
+        input.witness = witness.clone();
+    }
+    let fee = TxFee {
+        weight: signed_tx.weight(),
+        fee,
+    };
+    #[cfg(debug_assertions)]
+    trace!(?outputs, %fee, "signing policy");
+
+    Ok(SigningPolicy { outputs, fee })
+}
+
+/// Computes the [`TxFee`] of the key path spend [`sweep_tx`] of the `utxos` at `fee_rate`.
+///
+/// # Errors
+///
+/// Errors if there are no `utxos`, if their total value is dust or if the fee overflows.
+pub(crate) fn sweep_fee(
+    utxos: &[Utxo],
+    destination: &Address,
+    fee_rate: FeeRate,
+) -> Result<TxFee, Error> {
+    let tx = sweep_tx(utxos, destination, Amount::ZERO)?;
+    let witnesses = vec![dummy_key_path_witness(); utxos.len()];
+
+    TxFee::new(&tx, &witnesses, fee_rate)
+}
+
+/// Computes the [`TxFee`] of the [`contract_psbt`] spending the `escrow_script` leaf at `fee_rate`.
+///
+/// The weight does not depend on the [`FeePolicy`] that splits the fee,
+/// and dropping dust outputs only makes the transaction lighter.
+///
+/// # Errors
+///
+/// Errors if could not create the contract transaction or if `escrow_script` is not a leaf of the contract.
+pub(crate) fn contract_fee(
+    contract: &EscrowContract,
+    escrow_script: EscrowScript,
+    escrow_amounts: &[Amount],
+    funding: &[Utxo],
+    fee_rate: FeeRate,
+    network: Network,
+) -> Result<TxFee, Error> {
+    let psbt = contract_psbt(
+        contract,
+        escrow_script,
+        escrow_amounts,
+        funding,
+        Amount::ZERO,
+        &FeePolicy::Equal,
+        DustPolicy::MergeIntoFee,
+        network,
+    )?;
+    let witnesses = vec![dummy_contract_witness(contract, escrow_script)?; funding.len()];
+
+    TxFee::new(&psbt.unsigned_tx, &witnesses, fee_rate)
+}
+
+/// Computes the [`TxFee`] of the [`payout_psbt`] spending the `escrow_script` leaf at `fee_rate`.
+///
+/// # Errors
+///
+/// Errors if `escrow_script` is not a leaf of the contract.
+pub(crate) fn payout_fee(
+    contract: &EscrowContract,
+    escrow_script: EscrowScript,
+    payouts: &[Payout],
+    funding: &[Utxo],
+    fee_rate: FeeRate,
+) -> Result<TxFee, Error> {
+    // The weight does not depend on the amounts.
+    let output = payouts
+        .iter()
+        .map(|payout| TxOut {
+            value: Amount::ZERO,
+            script_pubkey: payout.destination.script_pubkey(),
+        })
+        .collect();
+    let timelock = leaf_timelock(&contract_scripts(contract, escrow_script)?);
+    let tx = escrow_spend_tx(output, timelock, funding);
+    let witnesses = vec![dummy_contract_witness(contract, escrow_script)?; funding.len()];
+
+    TxFee::new(&tx, &witnesses, fee_rate)
+}
+
+/// Computes the [`TxFee`] of the [`refund_psbt`] at `fee_rate`.
+///
+/// Dropping dust outputs only makes the transaction lighter.
+///
+/// # Errors
+///
+/// Errors if the contract has no refund leaf or if could not create the refund transaction.
+pub(crate) fn refund_fee(
+    contract: &EscrowContract,
+    escrow_amounts: &[Amount],
+    split: bool,
+    funding: &[Utxo],
+    fee_rate: FeeRate,
+    network: Network,
+) -> Result<TxFee, Error> {
+    let psbt = refund_psbt(
+        contract,
+        escrow_amounts,
+        split,
+        funding,
+        Amount::ZERO,
+        &FeePolicy::Equal,
+        DustPolicy::MergeIntoFee,
+        network,
+    )?;
+    let witnesses = vec![dummy_contract_witness(contract, EscrowScript::D)?; funding.len()];
+
+    TxFee::new(&psbt.unsigned_tx, &witnesses, fee_rate)
+}
+
+/// The value of the outputs that a [`DustPolicy::MergeIntoFee`] dropped from a [`Psbt`] paying `fee`,
+/// i.e. what the miners get on top of the fee.
+///
+/// # Errors
+///
+/// Errors if the [`Psbt`] is missing a witness UTXO or if its outputs exceed its inputs.
+pub(crate) fn merged_dust(psbt: &Psbt, fee: Amount) -> Result<Amount, Error> {
+    Ok(psbt.fee()?.checked_sub(fee).unwrap_or(Amount::ZERO))
+}
+
+/// Checks that every input of a [`Transaction`] can satisfy the `timelock` of the leaf it spends,
+/// see [`check_timelock`].
+///
+/// # Errors
+///
+/// Errors if the transaction can never satisfy the `timelock`.
+fn check_timelocks(tx: &Transaction, timelock: Option<Timelock>) -> Result<(), Error> {
+    (0..tx.input.len()).try_for_each(|index| check_timelock(tx, index, timelock))
+}
+
+/// Checks that the input `index` of a [`Transaction`] can satisfy the `timelock` of the leaf it spends.
+///
+/// A relative timelock needs a version 2 transaction and an input sequence of the same unit and at least as long (BIP68/BIP112),
+/// an absolute timelock needs a non-final input sequence and a `lock_time` of the same unit and at least as late (BIP65).
+///
+/// # Errors
+///
+/// Errors if the transaction can never satisfy the `timelock`.
+pub(crate) fn check_timelock(
+    tx: &Transaction,
+    index: usize,
+    timelock: Option<Timelock>,
+) -> Result<(), Error> {
+    let Some(input) = tx.input.get(index) else {
+        return Err(Error::WrongInputs(format!(
+            "Transaction has no input {index}"
+        )));
+    };
+    let satisfied = match timelock {
+        None => true,
+        Some(Timelock::Relative(lock_time)) => {
+            tx.version >= transaction::Version::TWO
+                && lock_time.is_implied_by_sequence(input.sequence)
+        }
+        Some(Timelock::Absolute(lock_time)) => {
+            input.sequence.enables_absolute_lock_time() && lock_time.is_implied_by(tx.lock_time)
+        }
+    };
+    #[cfg(debug_assertions)]
+    trace!(%index, ?timelock, sequence = %input.sequence, lock_time = %tx.lock_time, %satisfied, "check timelock");
+
+    if satisfied {
+        Ok(())
+    } else {
+        Err(Error::TimelockMismatch(format!(
+            "input {index} has sequence {} and lock time {}, the leaf needs {timelock:?}",
+            input.sequence, tx.lock_time
+        )))
+    }
+}
+
+/// Wraps a `tx` spending the `funding` outputs of the escrow of `contract` in a [`Psbt`],
+/// one escrow input per funding output.
+fn contract_tx_psbt(
+    contract: &EscrowContract,
+    tx: Transaction,
+    funding: &[Utxo],
+) -> Result<Psbt, Error> {
+    let taproot_spend_info = contract_spend_info(contract)?;
+    #[cfg(debug_assertions)]
+    trace!(?funding, "escrow funding");
+
+    let mut psbt = Psbt::from_unsigned_tx(tx)?;
+    for (input, utxo) in psbt.inputs.iter_mut().zip(funding) {
+        *input = escrow_psbt_input(&taproot_spend_info, utxo.value);
+    }
+
+    Ok(psbt)
+}
+
+/// Sums the `escrow_amounts`.
+fn total_amount(escrow_amounts: &[Amount]) -> Result<Amount, Error> {
+    let mut escrow_amount = Amount::ZERO;
+    for amount in escrow_amounts {
+        escrow_amount = match escrow_amount.checked_add(*amount) {
+            Some(amount) => amount,
+            None => return Err(Error::Rounding),
+        };
+    }
+
+    Ok(escrow_amount)
+}
+
+/// Creates a [`psbt::Input`] spending `amount` from the escrow address of `taproot_spend_info`.
+///
+/// Nostr keys are not derived from a BIP32 master key,
+/// hence the key origins have an empty fingerprint and the master derivation path.
+fn escrow_psbt_input(taproot_spend_info: &TaprootSpendInfo, amount: Amount) -> psbt::Input {
+    let mut input = psbt::Input {
+        witness_utxo: Some(TxOut {
+            value: amount,
+            script_pubkey: ScriptBuf::new_p2tr_tweaked(taproot_spend_info.output_key()),
+        }),
+        tap_internal_key: Some(taproot_spend_info.internal_key()),
+        tap_merkle_root: taproot_spend_info.merkle_root(),
+        ..Default::default()
+    };
+
+    for script_ver in taproot_spend_info.script_map().keys() {
+        let control_block = taproot_spend_info
+            .control_block(script_ver)
+            .expect("leaf must be in the Taproot tree");
+        let leaf_hash = TapLeafHash::from_script(&script_ver.0, script_ver.1);
+        for x_only_pk in leaf_x_only_keys(&script_ver.0) {
+            input
+                .tap_key_origins
+                .entry(x_only_pk)
+                .or_insert_with(|| {
+                    (
+                        Vec::new(),
+                        (Fingerprint::default(), DerivationPath::master()),
+                    )
+                })
+                .0
+                .push(leaf_hash);
+        }
+        input.tap_scripts.insert(control_block, script_ver.clone());
+    }
+
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{Txid, consensus, hex::DisplayHex, relative};
+
+    use crate::{
+        scripts::{InternalKey, contract_address},
+        util::{parse_fee_rate, parse_npub},
+    };
+
+    use super::*;
+
+    /// The single output of `value` at index 0 of `txid` that funds an escrow.
+    fn single_funding(txid: Txid, value: Amount) -> [Utxo; 1] {
+        [Utxo {
+            outpoint: OutPoint { txid, vout: 0 },
+            value,
+            confirmed: true,
+        }]
+    }
+
+    #[test]
+    fn test_escrow_tx() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let escrow_amount_1 = Amount::from_sat(50_000_000);
+        let escrow_amount_2 = Amount::from_sat(50_000_000);
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let fee = Amount::from_sat(1_000);
+        let network = Network::Bitcoin;
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let tx = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[escrow_amount_1, escrow_amount_2],
+            &single_funding(funding_txid, escrow_amount_1 + escrow_amount_2),
+            fee,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap()
+        .unsigned_tx;
+        println!(
+            "Collaborative Transaction: {}",
+            consensus::serialize(&tx).as_hex()
+        );
+        // The collaborative leaf has no timelock.
+        assert_eq!(tx.input[0].sequence, Sequence::ENABLE_RBF_NO_LOCKTIME);
+
+        let resolution_address_1p = npub_to_address(&npub_1, network).unwrap();
+        let resolution_address_2p = npub_to_address(&npub_2, network).unwrap();
+
+        assert_eq!(
+            tx.output[0].script_pubkey,
+            resolution_address_1p.script_pubkey()
+        );
+        assert_eq!(
+            tx.output[1].script_pubkey,
+            resolution_address_2p.script_pubkey()
+        );
+    }
+
+    #[test]
+    fn test_escrow_psbt() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let npub_arb =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        let escrow_amount_1 = Amount::from_sat(50_000_000);
+        let escrow_amount_2 = Amount::from_sat(50_000_000);
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let fee = Amount::from_sat(1_000);
+        let timelock_duration = relative::LockTime::from_height(144);
+        let network = Network::Bitcoin;
+        let contract = EscrowContract::two_party(
+            &npub_1,
+            &npub_2,
+            Some(&npub_arb),
+            Some(timelock_duration),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let psbt = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[escrow_amount_1, escrow_amount_2],
+            &single_funding(funding_txid, escrow_amount_1 + escrow_amount_2),
+            fee,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            network,
+        )
+        .unwrap();
+        assert_eq!(
+            psbt.unsigned_tx.input[0].sequence,
+            timelock_duration.to_sequence()
+        );
+
+        let escrow_address = contract_address(&contract, network).unwrap();
+        let input = &psbt.inputs[0];
+        let witness_utxo = input.witness_utxo.as_ref().unwrap();
+        assert_eq!(witness_utxo.script_pubkey, escrow_address.script_pubkey());
+        assert_eq!(witness_utxo.value, escrow_amount_1 + escrow_amount_2);
+        assert!(input.tap_internal_key.is_some());
+        assert!(input.tap_merkle_root.is_some());
+        assert_eq!(input.tap_scripts.len(), 3);
+        // Both parties are in the A and in one dispute leaf, the arbitrator is in both dispute leaves.
+        assert_eq!(input.tap_key_origins.len(), 3);
+        assert!(
+            input
+                .tap_key_origins
+                .values()
+                .all(|(leaves, _)| leaves.len() == 2)
+        );
+
+        // Round-trips through base64.
+        let parsed = psbt.to_string().parse::<Psbt>().unwrap();
+        assert_eq!(parsed, psbt);
+    }
+
+    #[test]
+    fn test_absolute_timelock_psbt() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let npub_arb =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let lock_time = absolute::LockTime::from_time(1_796_083_200).unwrap();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Absolute(lock_time)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let psbt = contract_psbt(
+            &contract,
+            EscrowScript::B,
+            &[Amount::from_sat(50_000_000); 2],
+            &single_funding(funding_txid, Amount::from_sat(100_000_000)),
+            Amount::from_sat(1_000),
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            Network::Bitcoin,
+        )
+        .unwrap();
+
+        // The lock time is enforced by a non-final sequence.
+        assert_eq!(psbt.unsigned_tx.lock_time, lock_time);
+        assert_eq!(
+            psbt.unsigned_tx.input[0].sequence,
+            Sequence::ENABLE_LOCKTIME_NO_RBF
+        );
+        assert!(
+            psbt.unsigned_tx.input[0]
+                .sequence
+                .enables_absolute_lock_time()
+        );
+        assert!(!psbt.unsigned_tx.input[0].sequence.is_relative_lock_time());
+    }
+
+    #[test]
+    fn test_escrow_tx_timelock() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let npub_arb =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        let timelock_duration = relative::LockTime::from_height(144);
+        let escrow_tx_for = |escrow_script, timelock_duration: Option<relative::LockTime>| {
+            let npub_arbitrator = timelock_duration.map(|_| &npub_arb);
+            let contract = EscrowContract::two_party(
+                &npub_1,
+                &npub_2,
+                npub_arbitrator,
+                timelock_duration,
+                InternalKey::Unspendable,
+            )?;
+            contract_psbt(
+                &contract,
+                escrow_script,
+                &[Amount::from_sat(50_000_000); 2],
+                &single_funding(funding_txid, Amount::from_sat(100_000_000)),
+                Amount::from_sat(1_000),
+                &FeePolicy::Equal,
+                DustPolicy::Reject,
+                Network::Bitcoin,
+            )
+            .map(|psbt| psbt.unsigned_tx)
+        };
+
+        // The dispute leaves get the sequence of their timelock.
+        let tx = escrow_tx_for(EscrowScript::B, Some(timelock_duration)).unwrap();
+        assert_eq!(tx.input[0].sequence, timelock_duration.to_sequence());
+        assert!(check_timelock(&tx, 0, Some(Timelock::Relative(timelock_duration))).is_ok());
+
+        // A dispute leaf needs a timelock.
+        assert!(matches!(
+            escrow_tx_for(EscrowScript::C, None),
+            Err(Error::InvalidEscrowType(_))
+        ));
+
+        // The collaborative transaction cannot spend a timelocked leaf.
+        let tx = escrow_tx_for(EscrowScript::A, None).unwrap();
+        assert!(matches!(
+            check_timelock(&tx, 0, Some(Timelock::Relative(timelock_duration))),
+            Err(Error::TimelockMismatch(_))
+        ));
+        let lock_time = absolute::LockTime::from_height(900_000).unwrap();
+        assert!(matches!(
+            check_timelock(&tx, 0, Some(Timelock::Absolute(lock_time))),
+            Err(Error::TimelockMismatch(_))
+        ));
+        assert!(matches!(
+            check_timelock(&tx, 1, None),
+            Err(Error::WrongInputs(_))
+        ));
+    }
+
+    #[test]
+    fn check_timelock_mismatches() {
+        let relative_lock = relative::LockTime::from_height(144);
+        let absolute_lock = absolute::LockTime::from_height(900_000).unwrap();
+        let tx_with = |version, sequence, lock_time| Transaction {
+            version,
+            lock_time,
+            input: vec![TxIn {
+                sequence,
+                ..Default::default()
+            }],
+            output: vec![],
+        };
+        let is_mismatch = |tx: &Transaction, timelock| {
+            matches!(
+                check_timelock(tx, 0, Some(timelock)),
+                Err(Error::TimelockMismatch(_))
+            )
+        };
+
+        let tx = tx_with(
+            transaction::Version::TWO,
+            relative_lock.to_sequence(),
+            absolute::LockTime::ZERO,
+        );
+        assert!(!is_mismatch(&tx, Timelock::Relative(relative_lock)));
+        // BIP68 is not enforced before version 2.
+        let tx = tx_with(
+            transaction::Version::ONE,
+            relative_lock.to_sequence(),
+            absolute::LockTime::ZERO,
+        );
+        assert!(is_mismatch(&tx, Timelock::Relative(relative_lock)));
+        // A shorter sequence, or one in 512-second intervals.
+        for sequence in [
+            relative::LockTime::from_height(143).to_sequence(),
+            relative::LockTime::from_512_second_intervals(144).to_sequence(),
+            Sequence::MAX,
+        ] {
+            let tx = tx_with(
+                transaction::Version::TWO,
+                sequence,
+                absolute::LockTime::ZERO,
+            );
+            assert!(
+                is_mismatch(&tx, Timelock::Relative(relative_lock)),
+                "{sequence}"
+            );
+        }
+
+        let tx = tx_with(
+            transaction::Version::TWO,
+            Sequence::ENABLE_LOCKTIME_NO_RBF,
+            absolute_lock,
+        );
+        assert!(!is_mismatch(&tx, Timelock::Absolute(absolute_lock)));
+        // A final sequence disables the lock time.
+        let tx = tx_with(transaction::Version::TWO, Sequence::MAX, absolute_lock);
+        assert!(is_mismatch(&tx, Timelock::Absolute(absolute_lock)));
+        // An earlier height, or a timestamp.
+        for lock_time in [
+            absolute::LockTime::from_height(899_999).unwrap(),
+            absolute::LockTime::from_time(1_900_000_000).unwrap(),
+        ] {
+            let tx = tx_with(
+                transaction::Version::TWO,
+                Sequence::ENABLE_LOCKTIME_NO_RBF,
+                lock_time,
+            );
+            assert!(
+                is_mismatch(&tx, Timelock::Absolute(absolute_lock)),
+                "{lock_time}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_contract_fee() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let npub_arb =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let escrow_amounts = [Amount::from_sat(50_000_000); 2];
+        let fee_rate = parse_fee_rate("1.5").unwrap();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(144))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let fee_of = |escrow_script| {
+            contract_fee(
+                &contract,
+                escrow_script,
+                &escrow_amounts,
+                &single_funding(funding_txid, Amount::from_sat(100_000_000)),
+                fee_rate,
+                Network::Bitcoin,
+            )
+            .unwrap()
+        };
+
+        // The dispute leaves are deeper in the tree and have a longer script than the cooperative leaf.
+        let cooperative = fee_of(EscrowScript::A);
+        let dispute = fee_of(EscrowScript::B);
+        assert!(cooperative.weight < dispute.weight);
+        assert_eq!(fee_of(EscrowScript::C), dispute);
+        for tx_fee in [cooperative, dispute] {
+            assert_eq!(
+                tx_fee.fee.to_sat(),
+                (tx_fee.weight.to_wu() * 375).div_ceil(1_000)
+            );
+            assert!(tx_fee.effective_rate() >= 1.49);
+        }
+
+        // The refund leaf is not in the contract.
+        assert!(matches!(
+            contract_fee(
+                &contract,
+                EscrowScript::D,
+                &escrow_amounts,
+                &single_funding(funding_txid, Amount::from_sat(100_000_000)),
+                fee_rate,
+                Network::Bitcoin,
+            ),
+            Err(Error::InvalidEscrowType(_))
+        ));
+    }
+
+    #[test]
+    fn test_sweep_fee() {
+        let npub =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let destination = npub_to_address(&npub, Network::Bitcoin).unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let tx_fee = sweep_fee(
+            &single_funding(funding_txid, Amount::from_sat(100_000)),
+            &destination,
+            FeeRate::from_sat_per_vb_unchecked(2),
+        )
+        .unwrap();
+
+        // A P2TR key path spend to a P2TR output.
+        assert_eq!(tx_fee.weight.to_wu(), 444);
+        assert_eq!(tx_fee.vsize(), 111);
+        assert_eq!(tx_fee.fee, Amount::from_sat(222));
+        assert_eq!(tx_fee.to_string(), "111 vB, 222 sats (2.00 sat/vB)");
+    }
+
+    #[test]
+    fn test_fee_policy_split() {
+        let sats = |amounts: &[u64]| {
+            amounts
+                .iter()
+                .copied()
+                .map(Amount::from_sat)
+                .collect::<Vec<_>>()
+        };
+        let escrow_amounts = sats(&[60_000, 30_000, 10_000]);
+        let fee = Amount::from_sat(1_001);
+
+        // Every policy accounts for every satoshi of the fee.
+        assert_eq!(
+            FeePolicy::Equal.split(fee, &escrow_amounts).unwrap(),
+            sats(&[334, 334, 333])
+        );
+        assert_eq!(
+            FeePolicy::Proportional.split(fee, &escrow_amounts).unwrap(),
+            sats(&[601, 300, 100])
+        );
+        assert_eq!(
+            FeePolicy::Payer(1).split(fee, &escrow_amounts).unwrap(),
+            sats(&[0, 1_001, 0])
+        );
+        assert_eq!(
+            FeePolicy::Fixed(sats(&[1_000, 1, 0]))
+                .split(fee, &escrow_amounts)
+                .unwrap(),
+            sats(&[1_000, 1, 0])
+        );
+
+        // Nothing is silently rounded or overdrawn.
+        for policy in [
+            FeePolicy::Payer(3),
+            FeePolicy::Fixed(sats(&[1_000, 0, 0])),
+            FeePolicy::Fixed(sats(&[1_001, 0])),
+        ] {
+            assert!(matches!(
+                policy.split(fee, &escrow_amounts),
+                Err(Error::FeeSplit(_))
+            ));
+        }
+        assert!(matches!(
+            FeePolicy::Payer(2).split(Amount::from_sat(10_001), &escrow_amounts),
+            Err(Error::FeeSplit(_))
+        ));
+        assert!(matches!(
+            FeePolicy::Proportional.split(fee, &sats(&[0, 0])),
+            Err(Error::FeeSplit(_))
+        ));
+
+        // An odd fee is paid in full by the two-party escrow transaction.
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let contract =
+            EscrowContract::two_party(&npub_1, &npub_2, None, None, InternalKey::Unspendable)
+                .unwrap();
+        let tx = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &[Amount::from_sat(50_000); 2],
+            &single_funding(funding_txid, Amount::from_sat(100_000)),
+            fee,
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            Network::Bitcoin,
+        )
+        .unwrap()
+        .unsigned_tx;
+        let outputs = tx.output.iter().map(|output| output.value).sum::<Amount>();
+        assert_eq!(outputs + fee, Amount::from_sat(100_000));
+    }
+
+    #[test]
+    fn test_payouts() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let npub_arb =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        let address_1 = npub_to_address(&npub_1, Network::Bitcoin).unwrap();
+        let address_2 = npub_to_address(&npub_2, Network::Bitcoin).unwrap();
+        let payout = |destination: &Address, amount| Payout {
+            destination: destination.clone(),
+            amount,
+        };
+        let escrow_value = Amount::from_sat(100_000);
+        let fee = Amount::from_sat(1_001);
+
+        // A 70/30 ruling accounts for every satoshi.
+        let ruling = [
+            payout(&address_1, PayoutAmount::Percent(7_000)),
+            payout(&address_2, PayoutAmount::Percent(3_000)),
+        ];
+        let amounts = payout_amounts(&ruling, escrow_value, fee).unwrap();
+        assert_eq!(
+            amounts,
+            [Amount::from_sat(69_299), Amount::from_sat(29_700)]
+        );
+
+        // A full refund to one party.
+        let refund = [payout(&address_1, PayoutAmount::Percent(10_000))];
+        assert_eq!(
+            payout_amounts(&refund, escrow_value, fee).unwrap(),
+            [escrow_value - fee]
+        );
+
+        // Fixed payouts come first and the percentages share the rest.
+        let mixed = [
+            payout(&address_1, PayoutAmount::Fixed(Amount::from_sat(20_000))),
+            payout(&address_2, PayoutAmount::Percent(5_000)),
+            payout(&address_1, PayoutAmount::Percent(5_000)),
+        ];
+        assert_eq!(
+            payout_amounts(&mixed, escrow_value, fee).unwrap(),
+            [
+                Amount::from_sat(20_000),
+                Amount::from_sat(39_500),
+                Amount::from_sat(39_499)
+            ]
+        );
+
+        // Nothing is silently left over or overdrawn.
+        for payouts in [
+            vec![],
+            vec![payout(&address_1, PayoutAmount::Percent(9_999))],
+            vec![payout(
+                &address_1,
+                PayoutAmount::Fixed(Amount::from_sat(90_000)),
+            )],
+            vec![payout(&address_1, PayoutAmount::Fixed(escrow_value))],
+        ] {
+            assert!(matches!(
+                payout_amounts(&payouts, escrow_value, fee),
+                Err(Error::Payout(_))
+            ));
+        }
+
+        // The payout transaction spends the dispute leaf with its timelock.
+        let timelock = relative::LockTime::from_height(144);
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(timelock)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let fee_rate = parse_fee_rate("2").unwrap();
+        let tx_fee = payout_fee(
+            &contract,
+            EscrowScript::B,
+            &ruling,
+            &single_funding(funding_txid, escrow_value),
+            fee_rate,
+        )
+        .unwrap();
+        let psbt = payout_psbt(
+            &contract,
+            EscrowScript::B,
+            &ruling,
+            &single_funding(funding_txid, escrow_value),
+            tx_fee.fee,
+            DustPolicy::Reject,
+        )
+        .unwrap();
+        assert_eq!(psbt.unsigned_tx.input[0].sequence, timelock.to_sequence());
+        let outputs = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<Amount>();
+        assert_eq!(outputs + tx_fee.fee, escrow_value);
+        assert_eq!(
+            psbt.unsigned_tx.output[0].script_pubkey,
+            address_1.script_pubkey()
+        );
+    }
+
+    #[test]
+    fn test_ruling_payouts() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let npub_arb =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        let network = Network::Bitcoin;
+        let timelock = relative::LockTime::from_height(144);
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(timelock)),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let escrow_value = Amount::from_sat(100_000);
+        let arbitrator_fee = ArbitratorFee {
+            arbitrator: npub_arb,
+            amount: PayoutAmount::Percent(250),
+        };
+
+        let fee = Amount::from_sat(500);
+
+        // The second party wins on its own dispute leaf, pays the arbitrator 2.5% of the escrow and the whole fee.
+        let (escrow_script, payouts) = ruling_payouts(
+            &contract,
+            1,
+            Some(&arbitrator_fee),
+            escrow_value,
+            fee,
+            &FeePolicy::Payer(0),
+            network,
+        )
+        .unwrap();
+        assert_eq!(escrow_script, EscrowScript::C);
+        assert_eq!(
+            payouts,
+            [
+                Payout {
+                    destination: npub_to_address(&npub_2, network).unwrap(),
+                    amount: PayoutAmount::Fixed(Amount::from_sat(97_000)),
+                },
+                Payout {
+                    destination: npub_to_address(&npub_arb, network).unwrap(),
+                    amount: PayoutAmount::Fixed(Amount::from_sat(2_500)),
+                },
+            ]
+        );
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let psbt = payout_psbt(
+            &contract,
+            escrow_script,
+            &payouts,
+            &single_funding(funding_txid, escrow_value),
+            fee,
+            DustPolicy::Reject,
+        )
+        .unwrap();
+        assert_eq!(psbt.unsigned_tx.input[0].sequence, timelock.to_sequence());
+        assert_eq!(psbt.unsigned_tx.output[0].value, Amount::from_sat(97_000));
+        assert_eq!(psbt.unsigned_tx.output[1].value, Amount::from_sat(2_500));
+
+        // The arbitrator fee output pays the whole fee instead.
+        let (_, payouts) = ruling_payouts(
+            &contract,
+            1,
+            Some(&arbitrator_fee),
+            escrow_value,
+            fee,
+            &FeePolicy::Payer(1),
+            network,
+        )
+        .unwrap();
+        let psbt = payout_psbt(
+            &contract,
+            escrow_script,
+            &payouts,
+            &single_funding(funding_txid, escrow_value),
+            fee,
+            DustPolicy::Reject,
+        )
+        .unwrap();
+        assert_eq!(psbt.unsigned_tx.output[0].value, Amount::from_sat(97_500));
+        assert_eq!(psbt.unsigned_tx.output[1].value, Amount::from_sat(2_000));
+
+        // The first party wins on the other leaf, without an arbitrator fee.
+        let (escrow_script, payouts) = ruling_payouts(
+            &contract,
+            0,
+            None,
+            escrow_value,
+            fee,
+            &FeePolicy::Equal,
+            network,
+        )
+        .unwrap();
+        assert_eq!(escrow_script, EscrowScript::B);
+        assert_eq!(
+            payouts,
+            [Payout {
+                destination: npub_to_address(&npub_1, network).unwrap(),
+                amount: PayoutAmount::Fixed(Amount::from_sat(99_500)),
+            }]
+        );
+
+        // Without an arbitrator fee output, the arbitrator cannot pay the fee.
+        assert!(matches!(
+            ruling_payouts(
+                &contract,
+                0,
+                None,
+                escrow_value,
+                fee,
+                &FeePolicy::Payer(1),
+                network
+            ),
+            Err(Error::FeeSplit(_))
+        ));
+        // Nor can it pay more than its fee.
+        let small_fee = ArbitratorFee {
+            amount: PayoutAmount::Fixed(Amount::from_sat(400)),
+            ..arbitrator_fee
+        };
+        assert!(matches!(
+            ruling_payouts(
+                &contract,
+                0,
+                Some(&small_fee),
+                escrow_value,
+                fee,
+                &FeePolicy::Payer(1),
+                network
+            ),
+            Err(Error::FeeSplit(_))
+        ));
+
+        // Only parties win and only arbitrators are paid, less than the escrow.
+        assert!(matches!(
+            ruling_payouts(
+                &contract,
+                2,
+                None,
+                escrow_value,
+                fee,
+                &FeePolicy::Equal,
+                network
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+        let stranger_fee = ArbitratorFee {
+            arbitrator: npub_1,
+            ..arbitrator_fee
+        };
+        assert!(matches!(
+            ruling_payouts(
+                &contract,
+                0,
+                Some(&stranger_fee),
+                escrow_value,
+                fee,
+                &FeePolicy::Equal,
+                network
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+        let whole_fee = ArbitratorFee {
+            amount: PayoutAmount::Fixed(escrow_value),
+            ..arbitrator_fee
+        };
+        assert!(matches!(
+            ruling_payouts(
+                &contract,
+                0,
+                Some(&whole_fee),
+                escrow_value,
+                fee,
+                &FeePolicy::Equal,
+                network
+            ),
+            Err(Error::Payout(_))
+        ));
+        let whole_percent = ArbitratorFee {
+            amount: PayoutAmount::Percent(10_000),
+            ..arbitrator_fee
+        };
+        assert!(matches!(
+            ruling_payouts(
+                &contract,
+                1,
+                Some(&whole_percent),
+                escrow_value,
+                fee,
+                &FeePolicy::Equal,
+                network
+            ),
+            Err(Error::Payout(_))
+        ));
+
+        // A fee below the dust limit cannot be an output of its own.
+        let dust_fee = ArbitratorFee {
+            amount: PayoutAmount::Fixed(Amount::from_sat(100)),
+            ..arbitrator_fee
+        };
+        let (escrow_script, payouts) = ruling_payouts(
+            &contract,
+            1,
+            Some(&dust_fee),
+            escrow_value,
+            fee,
+            &FeePolicy::Payer(0),
+            network,
+        )
+        .unwrap();
+        assert!(matches!(
+            payout_psbt(
+                &contract,
+                escrow_script,
+                &payouts,
+                &single_funding(funding_txid, escrow_value),
+                fee,
+                DustPolicy::Reject,
+            ),
+            Err(Error::Dust { index: 1, .. })
+        ));
+
+        // Without arbitrators, there is no ruling.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            0,
+            0,
+            None,
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        assert!(matches!(
+            ruling_payouts(
+                &contract,
+                0,
+                None,
+                escrow_value,
+                fee,
+                &FeePolicy::Equal,
+                network
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+    }
+
+    #[test]
+    fn test_signing_policy() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let npub_arb =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        let network = Network::Bitcoin;
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![npub_arb],
+            1,
+            1,
+            Some(Timelock::Relative(relative::LockTime::from_height(144))),
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let escrow_value = Amount::from_sat(100_000);
+        let arbitrator_fee = ArbitratorFee {
+            arbitrator: npub_arb,
+            amount: PayoutAmount::Fixed(Amount::from_sat(2_500)),
+        };
+        let fee = Amount::from_sat(500);
+        let (escrow_script, payouts) = ruling_payouts(
+            &contract,
+            1,
+            Some(&arbitrator_fee),
+            escrow_value,
+            fee,
+            &FeePolicy::Payer(0),
+            network,
+        )
+        .unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let psbt = payout_psbt(
+            &contract,
+            escrow_script,
+            &payouts,
+            &single_funding(funding_txid, escrow_value),
+            fee,
+            DustPolicy::Reject,
+        )
+        .unwrap();
+        let prevouts = psbt
+            .inputs
+            .iter()
+            .map(|input| input.witness_utxo.clone().unwrap())
+            .collect::<Vec<_>>();
+
+        let policy = signing_policy(
+            &psbt.unsigned_tx,
+            &prevouts,
+            &contract,
+            escrow_script,
+            network,
+        )
+        .unwrap();
+        let labels = policy
+            .outputs
+            .iter()
+            .map(|output| (output.label, output.value))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels,
+            [
+                (OutputLabel::Party(1), Amount::from_sat(97_000)),
+                (OutputLabel::Arbitrator(0), Amount::from_sat(2_500)),
+            ]
+        );
+        assert_eq!(
+            policy.outputs[0].address,
+            Some(npub_to_address(&npub_2, network).unwrap())
+        );
+        assert_eq!(policy.fee.fee, fee);
+        assert!(policy.fee.weight > psbt.unsigned_tx.weight());
+        let agreed = AgreedPayouts {
+            parties: vec![Amount::ZERO, Amount::from_sat(97_500)],
+            arbitrators: Amount::from_sat(2_500),
+            fee_policy: FeePolicy::Payer(0),
+            max_fee_rate: FeeRate::from_sat_per_vb_unchecked(100),
+        };
+        assert!(policy.check(&agreed).is_ok());
+
+        // The winner only pays its share of the fee.
+        for fee_policy in [FeePolicy::Payer(1), FeePolicy::Equal] {
+            assert!(matches!(
+                policy.check(&AgreedPayouts { fee_policy, ..agreed.clone() }),
+                Err(Error::UnexpectedAmount { recipient, paid, .. })
+                    if recipient == "Party 2" && paid == Amount::from_sat(97_000)
+            ));
+        }
+        // The agreed amounts must add up to the escrow.
+        assert!(matches!(
+            policy.check(&AgreedPayouts {
+                parties: vec![Amount::ZERO, Amount::from_sat(97_000)],
+                ..agreed.clone()
+            }),
+            Err(Error::UnbalancedPayouts { agreed, escrow })
+                if agreed == Amount::from_sat(99_500) && escrow == escrow_value
+        ));
+        // The loser was agreed a share.
+        assert!(matches!(
+            policy.check(&AgreedPayouts {
+                parties: vec![Amount::from_sat(50_000), Amount::from_sat(47_500)],
+                ..agreed.clone()
+            }),
+            Err(Error::UnexpectedAmount { recipient, .. }) if recipient == "Party 1"
+        ));
+        // The arbitrators were agreed a smaller fee.
+        assert!(matches!(
+            policy.check(&AgreedPayouts {
+                parties: vec![Amount::ZERO, Amount::from_sat(99_000)],
+                arbitrators: Amount::from_sat(1_000),
+                ..agreed.clone()
+            }),
+            Err(Error::UnexpectedAmount { .. })
+        ));
+        // The fee rate is above the maximum.
+        assert!(matches!(
+            policy.check(&AgreedPayouts {
+                max_fee_rate: FeeRate::from_sat_per_vb_unchecked(1),
+                ..agreed.clone()
+            }),
+            Err(Error::FeeTooHigh { fee: too_high, .. }) if too_high == fee
+        ));
+        assert_eq!(
+            OutputLabel::Party(1).to_string(),
+            "Party 2 resolution address"
+        );
+
+        // A counterparty swaps the arbitrator fee to its own address.
+        let mut swapped = psbt.unsigned_tx.clone();
+        swapped.output[1].script_pubkey = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+            .parse::<Address<_>>()
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+        let policy =
+            signing_policy(&swapped, &prevouts, &contract, escrow_script, network).unwrap();
+        assert_eq!(policy.outputs[1].label, OutputLabel::Unknown);
+        assert!(matches!(
+            policy.check(&agreed),
+            Err(Error::UnknownOutputs(1))
+        ));
+
+        // 1 sat pays the loser and the rest pays the winner, all to known addresses.
+        let mut shifted = psbt.unsigned_tx.clone();
+        shifted.output[0].value = Amount::from_sat(1);
+        shifted.output.push(TxOut {
+            value: Amount::from_sat(96_999),
+            script_pubkey: contract
+                .resolution_address(0, network)
+                .unwrap()
+                .script_pubkey(),
+        });
+        let policy =
+            signing_policy(&shifted, &prevouts, &contract, escrow_script, network).unwrap();
+        assert!(
+            policy
+                .outputs
+                .iter()
+                .all(|output| output.label != OutputLabel::Unknown)
+        );
+        assert!(matches!(
+            policy.check(&agreed),
+            Err(Error::UnexpectedAmount { recipient, .. }) if recipient == "Party 1"
+        ));
+
+        // Most of the escrow is burned to the fee, the outputs still paying the right addresses.
+        let mut burned = psbt.unsigned_tx.clone();
+        burned.output[0].value = Amount::from_sat(10_000);
+        let policy = signing_policy(&burned, &prevouts, &contract, escrow_script, network).unwrap();
+        assert_eq!(policy.fee.fee, Amount::from_sat(87_500));
+        assert!(matches!(
+            policy.check(&agreed),
+            Err(Error::FeeTooHigh { .. })
+        ));
+        // Even below a high maximum fee rate, the winner's fee share is less than what is burned.
+        assert!(matches!(
+            policy.check(&AgreedPayouts {
+                fee_policy: FeePolicy::Proportional,
+                max_fee_rate: FeeRate::from_sat_per_vb_unchecked(10_000),
+                ..agreed.clone()
+            }),
+            Err(Error::UnexpectedAmount { recipient, .. }) if recipient == "Party 2"
+        ));
+
+        // Outputs worth more than the inputs.
+        swapped.output[0].value = escrow_value;
+        assert!(signing_policy(&swapped, &prevouts, &contract, escrow_script, network).is_err());
+    }
+
+    #[test]
+    fn test_dust_policy() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let destination = npub_to_address(&npub_1, Network::Bitcoin).unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let p2tr_dust_limit = Amount::from_sat(330);
+
+        // The resolution transaction neither underflows nor creates dust.
+        assert!(matches!(
+            sweep_tx(
+                &single_funding(funding_txid, Amount::from_sat(500)),
+                &destination,
+                Amount::from_sat(501)
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+        assert!(matches!(
+            sweep_tx(&single_funding(funding_txid, Amount::from_sat(500)), &destination, Amount::from_sat(171)),
+            Err(Error::Dust { index: 0, value, limit }) if value == Amount::from_sat(329) && limit == p2tr_dust_limit
+        ));
+        let tx = sweep_tx(
+            &single_funding(funding_txid, Amount::from_sat(500)),
+            &destination,
+            Amount::from_sat(170),
+        )
+        .unwrap();
+        assert_eq!(tx.output[0].value, p2tr_dust_limit);
+
+        // A dust output is rejected, or merged into the fee.
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let escrow_amounts = [Amount::from_sat(100_000), Amount::from_sat(500)];
+        let fee = Amount::from_sat(500);
+        let psbt_with = |dust_policy| {
+            contract_psbt(
+                &contract,
+                EscrowScript::A,
+                &escrow_amounts,
+                &single_funding(funding_txid, Amount::from_sat(100_500)),
+                fee,
+                &FeePolicy::Equal,
+                dust_policy,
+                Network::Bitcoin,
+            )
+        };
+        assert!(matches!(
+            psbt_with(DustPolicy::Reject),
+            Err(Error::Dust { index: 1, .. })
+        ));
+        let psbt = psbt_with(DustPolicy::MergeIntoFee).unwrap();
+        assert_eq!(psbt.unsigned_tx.output.len(), 1);
+        assert_eq!(psbt.unsigned_tx.output[0].value, Amount::from_sat(99_750));
+        assert_eq!(merged_dust(&psbt, fee).unwrap(), Amount::from_sat(250));
+
+        // A transaction needs at least one output.
+        assert!(matches!(
+            DustPolicy::MergeIntoFee.apply(vec![TxOut {
+                value: Amount::from_sat(1),
+                script_pubkey: destination.script_pubkey(),
+            }]),
+            Err(Error::Dust { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn dust_limits_per_script() {
+        let p2wpkh = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+            .parse::<Address<_>>()
+            .unwrap()
+            .assume_checked()
+            .script_pubkey();
+        let p2tr = npub_to_address(
+            &parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap(),
+            Network::Bitcoin,
+        )
+        .unwrap()
+        .script_pubkey();
+        let output = |sats, script_pubkey: &ScriptBuf| TxOut {
+            value: Amount::from_sat(sats),
+            script_pubkey: script_pubkey.clone(),
+        };
+
+        // Each output is held to the limit of its own script, exactly at the limit is not dust.
+        let outputs = vec![output(294, &p2wpkh), output(330, &p2tr)];
+        assert_eq!(DustPolicy::Reject.apply(outputs.clone()).unwrap(), outputs);
+        assert!(matches!(
+            DustPolicy::Reject.apply(vec![output(1_000, &p2tr), output(293, &p2wpkh)]),
+            Err(Error::Dust { index: 1, value, limit })
+                if value == Amount::from_sat(293) && limit == Amount::from_sat(294)
+        ));
+        // A P2WPKH amount is dust on a P2TR output.
+        assert!(matches!(
+            DustPolicy::Reject.apply(vec![output(294, &p2tr)]),
+            Err(Error::Dust { index: 0, .. })
+        ));
+        // The first dust output is reported, and every dust output is merged.
+        let outputs = vec![output(1_000, &p2tr), output(1, &p2tr), output(2, &p2wpkh)];
+        assert!(matches!(
+            DustPolicy::Reject.apply(outputs.clone()),
+            Err(Error::Dust { index: 1, .. })
+        ));
+        assert_eq!(
+            DustPolicy::MergeIntoFee.apply(outputs).unwrap(),
+            [output(1_000, &p2tr)]
+        );
+    }
+
+    #[test]
+    fn test_payout_addresses() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let funding_txid = "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+            .parse::<Txid>()
+            .unwrap();
+        let escrow_amounts = [Amount::from_sat(50_000), Amount::from_sat(50_000)];
+        let cold_wallet = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+            .parse::<Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let escrow_address = contract_address(&contract, Network::Bitcoin).unwrap();
+
+        // The payout address replaces the resolution address, but not the escrow address.
+        let contract = contract
+            .with_payout_addresses(vec![None, Some(cold_wallet.clone())])
+            .unwrap();
+        assert_eq!(
+            contract_address(&contract, Network::Bitcoin).unwrap(),
+            escrow_address
+        );
+        let psbt = contract_psbt(
+            &contract,
+            EscrowScript::A,
+            &escrow_amounts,
+            &single_funding(funding_txid, Amount::from_sat(100_000)),
+            Amount::from_sat(500),
+            &FeePolicy::Equal,
+            DustPolicy::Reject,
+            Network::Bitcoin,
+        )
+        .unwrap();
+        assert_eq!(
+            psbt.unsigned_tx.output[0].script_pubkey,
+            npub_to_address(&npub_1, Network::Bitcoin)
+                .unwrap()
+                .script_pubkey()
+        );
+        assert_eq!(
+            psbt.unsigned_tx.output[1].script_pubkey,
+            cold_wallet.script_pubkey()
+        );
+
+        // The payout address is labelled as the party's before signing.
+        let prevouts = psbt
+            .inputs
+            .iter()
+            .map(|input| input.witness_utxo.clone().unwrap())
+            .collect::<Vec<_>>();
+        let policy = signing_policy(
+            &psbt.unsigned_tx,
+            &prevouts,
+            &contract,
+            EscrowScript::A,
+            Network::Bitcoin,
+        )
+        .unwrap();
+        assert_eq!(
+            policy
+                .outputs
+                .iter()
+                .map(|output| output.label)
+                .collect::<Vec<_>>(),
+            [OutputLabel::Party(0), OutputLabel::Party(1)]
+        );
+        assert!(
+            policy
+                .check(&AgreedPayouts {
+                    parties: escrow_amounts.to_vec(),
+                    arbitrators: Amount::ZERO,
+                    fee_policy: FeePolicy::Equal,
+                    max_fee_rate: FeeRate::from_sat_per_vb_unchecked(100),
+                })
+                .is_ok()
+        );
+
+        // The payout address must be of the network.
+        assert!(matches!(
+            contract.resolution_addresses(Network::Testnet),
+            Err(Error::InvalidPayoutAddress(_))
+        ));
+        assert!(matches!(
+            contract.with_payout_addresses(vec![None]),
+            Err(Error::WrongInputs(_))
+        ));
+    }
+
+    #[test]
+    fn test_multi_deposit_escrow() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let escrow_amounts = [Amount::from_sat(60_000), Amount::from_sat(40_000)];
+        let deposit = |txid: &str, vout, value| Utxo {
+            outpoint: OutPoint {
+                txid: txid.parse().unwrap(),
+                vout,
+            },
+            value,
+            confirmed: true,
+        };
+        // The parties deposit from different wallets at different times.
+        let funding = [
+            deposit(
+                "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799",
+                1,
+                escrow_amounts[0],
+            ),
+            deposit(
+                "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+                0,
+                escrow_amounts[1],
+            ),
+        ];
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let escrow_address = contract_address(&contract, Network::Bitcoin).unwrap();
+        let psbt_of = |funding: &[Utxo]| {
+            contract_psbt(
+                &contract,
+                EscrowScript::A,
+                &escrow_amounts,
+                funding,
+                Amount::from_sat(1_000),
+                &FeePolicy::Equal,
+                DustPolicy::Reject,
+                Network::Bitcoin,
+            )
+        };
+
+        // One input per deposit, each with its own prevout.
+        let psbt = psbt_of(&funding).unwrap();
+        assert_eq!(psbt.unsigned_tx.input.len(), 2);
+        for ((input, psbt_input), utxo) in psbt
+            .unsigned_tx
+            .input
+            .iter()
+            .zip(&psbt.inputs)
+            .zip(&funding)
+        {
+            assert_eq!(input.previous_output, utxo.outpoint);
+            assert_eq!(input.sequence, Sequence::ENABLE_RBF_NO_LOCKTIME);
+            let witness_utxo = psbt_input.witness_utxo.as_ref().unwrap();
+            assert_eq!(witness_utxo.value, utxo.value);
+            assert_eq!(witness_utxo.script_pubkey, escrow_address.script_pubkey());
+        }
+        let outputs = psbt
+            .unsigned_tx
+            .output
+            .iter()
+            .map(|output| output.value)
+            .sum::<Amount>();
+        assert_eq!(outputs + Amount::from_sat(1_000), Amount::from_sat(100_000));
+
+        // Every input is signed, so every input adds to the fee.
+        let fee_rate = parse_fee_rate("1").unwrap();
+        let fee_of = |funding: &[Utxo]| {
+            contract_fee(
+                &contract,
+                EscrowScript::A,
+                &escrow_amounts,
+                funding,
+                fee_rate,
+                Network::Bitcoin,
+            )
+            .unwrap()
+        };
+        let single = single_funding(funding[0].outpoint.txid, Amount::from_sat(100_000));
+        let witness = dummy_contract_witness(&contract, EscrowScript::A).unwrap();
+        let input_weight =
+            Weight::from_wu((TxIn::default().base_size() * 4 + witness.size()) as u64);
+        assert_eq!(
+            fee_of(&funding).weight,
+            fee_of(&single).weight + input_weight
+        );
+
+        // The deposits must add up to the escrow.
+        assert!(matches!(psbt_of(&funding[..1]), Err(Error::WrongInputs(_))));
+        assert!(matches!(psbt_of(&[]), Err(Error::WrongInputs(_))));
+    }
+
+    #[test]
+    fn test_funding_psbt() {
+        let npub_1 =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let npub_2 =
+            parse_npub("npub1ykkf8j4mt0z4hfz5eesqck6a9qcearxq2mlk6f78k3yxhjkpqnxqanyg69").unwrap();
+        let contract = EscrowContract::new(
+            vec![npub_1, npub_2],
+            2,
+            vec![],
+            1,
+            1,
+            None,
+            InternalKey::Unspendable,
+        )
+        .unwrap();
+        let escrow_address = contract_address(&contract, Network::Bitcoin).unwrap();
+        let change_address: Address = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+            .parse::<Address<_>>()
+            .unwrap()
+            .assume_checked();
+        let utxo = |vout, sats| Utxo {
+            outpoint: OutPoint {
+                txid: "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+                    .parse()
+                    .unwrap(),
+                vout,
+            },
+            value: Amount::from_sat(sats),
+            confirmed: true,
+        };
+        let contribution = |npub, utxos, sats, change_address| FundingContribution {
+            npub,
+            utxos,
+            amount: Amount::from_sat(sats),
+            change_address,
+        };
+        let fee_rate = parse_fee_rate("2").unwrap();
+        let contributions = [
+            contribution(npub_1, vec![utxo(0, 40_000), utxo(1, 40_000)], 60_000, None),
+            contribution(
+                npub_2,
+                vec![utxo(2, 100_000)],
+                40_000,
+                Some(change_address.clone()),
+            ),
+        ];
+
+        let psbt =
+            funding_psbt(&escrow_address, &contributions, fee_rate, Network::Bitcoin).unwrap();
+        let tx = &psbt.unsigned_tx;
+        assert_eq!(tx.input.len(), 3);
+        assert_eq!(tx.output.len(), 3);
+        // The escrow output pays exactly the escrow amounts.
+        assert_eq!(tx.output[0].value, Amount::from_sat(100_000));
+        assert_eq!(tx.output[0].script_pubkey, escrow_address.script_pubkey());
+        // The change goes to the resolution address, unless a change address is given.
+        assert_eq!(
+            tx.output[1].script_pubkey,
+            npub_to_address(&npub_1, Network::Bitcoin)
+                .unwrap()
+                .script_pubkey()
+        );
+        assert_eq!(tx.output[2].script_pubkey, change_address.script_pubkey());
+        for (input, utxo) in psbt
+            .inputs
+            .iter()
+            .zip(contributions.iter().flat_map(|c| &c.utxos))
+        {
+            assert_eq!(input.witness_utxo.as_ref().unwrap().value, utxo.value);
+            assert!(input.tap_internal_key.is_some());
+        }
+
+        // The fee is exact for the signed transaction, and party 1 pays more for its extra input.
+        let witnesses = vec![dummy_key_path_witness(); tx.input.len()];
+        let tx_fee = TxFee::new(tx, &witnesses, fee_rate).unwrap();
+        assert_eq!(psbt.fee().unwrap(), tx_fee.fee);
+        let fee_1 = Amount::from_sat(80_000 - 60_000) - tx.output[1].value;
+        let fee_2 = Amount::from_sat(100_000 - 40_000) - tx.output[2].value;
+        assert_eq!(fee_1 + fee_2, tx_fee.fee);
+        assert!(fee_1 > fee_2);
+
+        // A party must cover its escrow amount and its fee.
+        let short = [
+            contributions[0].clone(),
+            contribution(npub_2, vec![utxo(2, 40_000)], 40_000, None),
+        ];
+        assert!(matches!(
+            funding_psbt(&escrow_address, &short, fee_rate, Network::Bitcoin),
+            Err(Error::WrongInputs(_))
+        ));
+        assert!(matches!(
+            funding_psbt(&escrow_address, &[], fee_rate, Network::Bitcoin),
+            Err(Error::WrongInputs(_))
+        ));
+        let empty = [contribution(npub_1, vec![], 60_000, None)];
+        assert!(matches!(
+            funding_psbt(&escrow_address, &empty, fee_rate, Network::Bitcoin),
+            Err(Error::WrongInputs(_))
+        ));
+
+        // A dust change goes to the fee.
+        let dust = [
+            contributions[0].clone(),
+            contribution(npub_2, vec![utxo(2, 40_500)], 40_000, None),
+        ];
+        let psbt = funding_psbt(&escrow_address, &dust, fee_rate, Network::Bitcoin).unwrap();
+        assert_eq!(psbt.unsigned_tx.output.len(), 2);
+        assert_eq!(psbt.unsigned_tx.output[0].value, Amount::from_sat(100_000));
+    }
+
+    #[test]
+    fn test_sweep_tx() {
+        let npub =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let destination = npub_to_address(&npub, Network::Bitcoin).unwrap();
+        let fee_rate = FeeRate::from_sat_per_vb(10).unwrap();
+        let utxo = |vout, value| Utxo {
+            outpoint: OutPoint {
+                txid: "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+                    .parse()
+                    .unwrap(),
+                vout,
+            },
+            value: Amount::from_sat(value),
+            confirmed: true,
+        };
+
+        // A key path input weighs 57.5 vB, 575 sats at 10 sat/vB.
+        assert_eq!(Utxo::input_weight(), Weight::from_wu(230));
+        let input_fee = Amount::from_sat(575);
+        let utxos = [utxo(0, 10_000), utxo(1, 575), utxo(2, 20_000), utxo(3, 576)];
+        assert_eq!(utxos[1].effective_value(fee_rate), None);
+        assert_eq!(
+            utxos[3].effective_value(fee_rate),
+            Some(Amount::from_sat(576) - input_fee)
+        );
+        assert_eq!(
+            spendable_utxos(&utxos, fee_rate),
+            vec![utxos[2], utxos[0], utxos[3]]
+        );
+
+        // One input per UTXO, one output with the total value after the fee.
+        let selected = spendable_utxos(&utxos, fee_rate);
+        let tx_fee = sweep_fee(&selected, &destination, fee_rate).unwrap();
+        let tx = sweep_tx(&selected, &destination, tx_fee.fee).unwrap();
+        assert_eq!(tx.input.len(), 3);
+        assert_eq!(tx.output.len(), 1);
+        assert_eq!(tx.output[0].value, Amount::from_sat(30_576) - tx_fee.fee);
+        let single = sweep_fee(&selected[..1], &destination, fee_rate).unwrap();
+        assert_eq!(tx_fee.weight - single.weight, Utxo::input_weight() * 2);
+
+        // A sweep needs a UTXO, and a value above the fee.
+        assert!(matches!(
+            sweep_tx(&[], &destination, Amount::ZERO),
+            Err(Error::WrongInputs(_))
+        ));
+        assert!(matches!(
+            sweep_tx(&selected, &destination, Amount::from_sat(30_577)),
+            Err(Error::WrongInputs(_))
+        ));
+    }
+
+    #[test]
+    fn test_select_utxos() {
+        let npub =
+            parse_npub("npub1lfsec9a40ntx0hjr9wtuchclar7xcyhrf0gngaz3vt5dhnqdndaq099v6c").unwrap();
+        let destination = npub_to_address(&npub, Network::Bitcoin).unwrap();
+        let fee_rate = FeeRate::from_sat_per_vb(10).unwrap();
+        let utxo = |vout, value| Utxo {
+            outpoint: OutPoint {
+                txid: "602ae1accd9626bde16d19cbe8663cbe37a4e95839d0cddb10b84dcc82f07799"
+                    .parse()
+                    .unwrap(),
+                vout,
+            },
+            value: Amount::from_sat(value),
+            confirmed: true,
+        };
+        let utxos = [
+            utxo(0, 10_000),
+            utxo(1, 575),
+            utxo(2, 20_000),
+            utxo(3, 5_000),
+        ];
+        let one_input_fee = sweep_fee(&utxos[..1], &destination, fee_rate).unwrap().fee;
+
+        // The largest UTXO alone covers a small amount.
+        assert_eq!(
+            select_utxos(&utxos, Amount::from_sat(5_000), &destination, fee_rate).unwrap(),
+            [utxos[2]]
+        );
+
+        // Exactly the largest UTXO after its fee, then one more satoshi needs another UTXO.
+        let exact = Amount::from_sat(20_000) - one_input_fee;
+        assert_eq!(
+            select_utxos(&utxos, exact, &destination, fee_rate).unwrap(),
+            [utxos[2]]
+        );
+        let selected =
+            select_utxos(&utxos, exact + Amount::from_sat(1), &destination, fee_rate).unwrap();
+        assert_eq!(selected, [utxos[2], utxos[0]]);
+        let tx_fee = sweep_fee(&selected, &destination, fee_rate).unwrap();
+        let tx = sweep_tx(&selected, &destination, tx_fee.fee).unwrap();
+        assert!(tx.output[0].value > exact);
+
+        // The UTXO not worth its fee is never selected, even if the others fall short.
+        let spendable = Amount::from_sat(35_000)
+            - sweep_fee(&[utxos[2], utxos[0], utxos[3]], &destination, fee_rate)
+                .unwrap()
+                .fee;
+        assert_eq!(
+            select_utxos(&utxos, spendable, &destination, fee_rate).unwrap(),
+            [utxos[2], utxos[0], utxos[3]]
         );
+        assert!(matches!(
+            select_utxos(
+                &utxos,
+                spendable + Amount::from_sat(1),
+                &destination,
+                fee_rate
+            ),
+            Err(Error::WrongInputs(_))
+        ));
+        assert!(matches!(
+            select_utxos(&[], Amount::from_sat(1_000), &destination, fee_rate),
+            Err(Error::WrongInputs(_))
+        ));
     }
 }
@@ -1,10 +1,20 @@
 //! Utility functions for Nostr keys and Bitcoin network.
 
-use bitcoin::{Address, Network, XOnlyPublicKey};
+use std::collections::BTreeMap;
+
+use bitcoin::{
+    Address, CompressedPublicKey, FeeRate, Network, OutPoint, Psbt, PublicKey, Transaction, TxOut,
+    XOnlyPublicKey, absolute, consensus, hex::DisplayHex, relative,
+};
+use musig2::{PartialSignature, PubNonce};
 use nostr::key::{PublicKey as NostrPublicKey, SecretKey as NostrSecretKey};
-use secp256k1::SECP256K1;
+use secp256k1::{SECP256K1, schnorr};
 
-use crate::{error::Error, scripts::EscrowScript};
+use crate::{
+    error::Error,
+    scripts::{EscrowScript, InternalKey, Timelock},
+    tx::DustPolicy,
+};
 
 /// Number of Bitcoin blocks per day assuming 10-minute intervals.
 const BLOCKS_PER_DAY: u32 = 6 * 24;
@@ -12,39 +22,42 @@ const BLOCKS_PER_DAY: u32 = 6 * 24;
 /// Number of Bitcoin blocks per hour assuming 10-minute intervals.
 const BLOCKS_PER_HOUR: u32 = 6;
 
-/// P2TR Transaction virtual bytes for speding from a Nostr derived
-/// P2TR address using the key path spend.
-pub(crate) const P2TR_TX_VBYTE_KEY_PATH: u64 = 111;
+/// Characters of a BIP380 descriptor, in the order of the checksum classes of 32 characters.
+const DESCRIPTOR_INPUT_CHARSET: &str = "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
 
-/// P2TR Transaction virtual bytes for [`EscrowType::A`].
-#[allow(dead_code)]
-pub(crate) const P2TR_TX_VBYTE_A: u64 = 196;
+/// Characters of a BIP380 descriptor checksum.
+const DESCRIPTOR_CHECKSUM_CHARSET: &[u8] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
 
-/// P2TR Transaction virtual bytes for [`EscrowType::B`].
+/// Converts `days` and `hours` to a BIP68 relative [`relative::LockTime`].
 ///
-/// NOTE: the amount is 212.75 but round it up.
-#[allow(dead_code)]
-pub(crate) const P2TR_TX_VBYTE_B: u64 = 213;
-
-/// P2TR Transaction virtual bytes for [`EscrowType::C`].
+/// Block-based locks assume that blocks comes in 10-minute intervals,
+/// and time-based locks are rounded up to the next 512-second interval.
 ///
-/// NOTE: the amount is 212.75 but round it up.
-pub(crate) const P2TR_TX_VBYTE_C: u64 = 213;
-
-/// Converts `days` to blocks assuming that blocks comes in 10-minute intervals.
-pub(crate) fn days_to_blocks(days: u32) -> u32 {
-    days * BLOCKS_PER_DAY
-}
-
-/// Converts `hours` to blocks assuming that blocks comes in 10-minute intervals.
-pub(crate) fn hours_to_blocks(hours: u32) -> u32 {
-    hours * BLOCKS_PER_HOUR
-}
-
-/// Converts `days` and `hours` to blocks assuming that blocks comes in 10-minute intervals.
-#[allow(dead_code)]
-pub(crate) fn days_hours_to_blocks(days: u32, hours: u32) -> u32 {
-    days_to_blocks(days) + hours_to_blocks(hours)
+/// # Errors
+///
+/// Errors if the lock does not fit in the 16 bits of BIP68,
+/// i.e. 65,535 blocks (about 455 days) or 65,535 intervals of 512 seconds (about 388 days).
+pub(crate) fn days_hours_to_relative_lock_time(
+    days: u32,
+    hours: u32,
+    time_based: bool,
+) -> Result<relative::LockTime, Error> {
+    let out_of_range = || Error::InvalidTimelock(format!("{days} days and {hours} hours"));
+    if time_based {
+        let seconds = days
+            .checked_mul(24)
+            .and_then(|h| h.checked_add(hours))
+            .and_then(|h| h.checked_mul(3_600))
+            .ok_or_else(out_of_range)?;
+        relative::LockTime::from_seconds_ceil(seconds).map_err(|_| out_of_range())
+    } else {
+        let blocks = days
+            .checked_mul(BLOCKS_PER_DAY)
+            .and_then(|blocks| blocks.checked_add(hours.checked_mul(BLOCKS_PER_HOUR)?))
+            .ok_or_else(out_of_range)?;
+        let blocks = u16::try_from(blocks).map_err(|_| out_of_range())?;
+        Ok(relative::LockTime::from_height(blocks))
+    }
 }
 
 /// Parses a network string into a [`Network`].
@@ -57,16 +70,153 @@ pub(crate) fn parse_network(network: &str) -> Result<Network, Error> {
     }
 }
 
+/// Parses a fee rate string in sat/vB into a [`FeeRate`].
+///
+/// Fractional rates are supported, rounded up to the next sat per 1,000 weight units.
+pub(crate) fn parse_fee_rate(sat_per_vb: &str) -> Result<FeeRate, Error> {
+    let invalid = || Error::InvalidFeeRate(sat_per_vb.to_string());
+    let sat_per_vb = sat_per_vb.trim().parse::<f64>().map_err(|_| invalid())?;
+    // 1 vbyte is 4 weight units, hence 1 sat/vB is 250 sat/kwu.
+    let sat_per_kwu = (sat_per_vb * 250.0).ceil();
+    if !(1.0..=f64::from(u32::MAX)).contains(&sat_per_kwu) {
+        return Err(invalid());
+    }
+
+    Ok(FeeRate::from_sat_per_kwu(sat_per_kwu as u64))
+}
+
+/// Parses a percentage string with up to two decimals into basis points, i.e. hundredths of a percent.
+///
+/// The percentage should be greater than 0 and at most 100.
+pub(crate) fn parse_percent(percent: &str) -> Result<u32, Error> {
+    let invalid = || Error::Payout(format!("Invalid percentage: {percent}"));
+    let (integer, fraction) = percent
+        .trim()
+        .split_once('.')
+        .unwrap_or((percent.trim(), ""));
+    if fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let integer = integer.parse::<u32>().map_err(|_| invalid())?;
+    let fraction = format!("{fraction:0<2}")
+        .parse::<u32>()
+        .map_err(|_| invalid())?;
+    let basis_points = integer
+        .checked_mul(100)
+        .and_then(|basis_points| basis_points.checked_add(fraction))
+        .filter(|basis_points| (1..=10_000).contains(basis_points))
+        .ok_or_else(invalid)?;
+
+    Ok(basis_points)
+}
+
 /// Parses an escrow type string into a [`EscrowScript`].
 pub(crate) fn parse_escrow_type(escrow_type: &str) -> Result<EscrowScript, Error> {
     match escrow_type {
         "A" => Ok(EscrowScript::A),
         "B" => Ok(EscrowScript::B),
         "C" => Ok(EscrowScript::C),
+        "D" => Ok(EscrowScript::D),
         e => Err(Error::InvalidEscrowType(e.to_string())),
     }
 }
 
+/// Parses a dust policy string into a [`DustPolicy`].
+pub(crate) fn parse_dust_policy(dust_policy: &str) -> Result<DustPolicy, Error> {
+    match dust_policy {
+        "Reject" => Ok(DustPolicy::Reject),
+        "MergeIntoFee" => Ok(DustPolicy::MergeIntoFee),
+        e => Err(Error::WrongInputs(format!("Invalid dust policy: {e}"))),
+    }
+}
+
+/// Parses an internal key string into a [`InternalKey`].
+pub(crate) fn parse_internal_key(internal_key: &str) -> Result<InternalKey, Error> {
+    match internal_key {
+        "Unspendable" => Ok(InternalKey::Unspendable),
+        "MuSig2" => Ok(InternalKey::MuSig2),
+        e => Err(Error::InvalidInternalKey(e.to_string())),
+    }
+}
+
+/// Parses the timelock fields into a [`Timelock`].
+///
+/// `mode` is `Duration` for a relative block-based timelock of `days` and `hours`,
+/// `Time` for a relative time-based timelock of `days` and `hours`,
+/// `Height` for an absolute block height in `absolute`,
+/// or `Date` for an absolute `YYYY-MM-DD` date at midnight UTC in `absolute`.
+pub(crate) fn parse_timelock(
+    mode: &str,
+    days: &str,
+    hours: &str,
+    absolute: &str,
+) -> Result<Timelock, Error> {
+    match mode {
+        "Duration" | "Time" => {
+            let parse = |input: &str| {
+                input
+                    .parse::<u32>()
+                    .map_err(|_| Error::InvalidTimelock(input.to_string()))
+            };
+            Ok(Timelock::Relative(days_hours_to_relative_lock_time(
+                parse(days)?,
+                parse(hours)?,
+                mode == "Time",
+            )?))
+        }
+        "Height" => Ok(Timelock::Absolute(parse_height(absolute)?)),
+        "Date" => Ok(Timelock::Absolute(parse_date(absolute)?)),
+        e => Err(Error::InvalidTimelock(e.to_string())),
+    }
+}
+
+/// Parses a block height into an absolute [`absolute::LockTime`].
+pub(crate) fn parse_height(height: &str) -> Result<absolute::LockTime, Error> {
+    height
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .filter(|height| *height > 0)
+        .and_then(|height| absolute::LockTime::from_height(height).ok())
+        .ok_or_else(|| Error::InvalidTimelock(height.to_string()))
+}
+
+/// Parses a `YYYY-MM-DD` date at midnight UTC into an absolute [`absolute::LockTime`].
+pub(crate) fn parse_date(date: &str) -> Result<absolute::LockTime, Error> {
+    let invalid = || Error::InvalidTimelock(date.to_string());
+    let mut parts = date.trim().splitn(3, '-').map(|part| part.parse::<i64>());
+    let (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid());
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return Err(invalid()),
+    };
+    if !(1970..=2105).contains(&year) || !(1..=days_in_month).contains(&day) {
+        return Err(invalid());
+    }
+
+    // Days since the unix epoch of the proleptic Gregorian calendar.
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let days = year * 365 + year / 4 - year / 100 + year / 400 + day_of_year - 719_468;
+
+    u32::try_from(days * 86_400)
+        .ok()
+        .and_then(|timestamp| absolute::LockTime::from_time(timestamp).ok())
+        .ok_or_else(invalid)
+}
+
 /// Parses a [`NostrPublicKey`] from a string.
 pub(crate) fn parse_npub(input: &str) -> Result<NostrPublicKey, Error> {
     Ok(NostrPublicKey::parse(input)?)
@@ -77,13 +227,156 @@ pub(crate) fn parse_nsec(input: &str) -> Result<NostrSecretKey, Error> {
     Ok(NostrSecretKey::parse(input)?)
 }
 
+/// Parses the [`schnorr::Signature`]s of every escrow input, in input order,
+/// separated by commas or whitespace.
+pub(crate) fn parse_signatures(input: &str) -> Result<Vec<schnorr::Signature>, Error> {
+    let signatures = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|signature| !signature.is_empty())
+        .map(|signature| signature.parse::<schnorr::Signature>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if signatures.is_empty() {
+        return Err(Error::WrongInputs("No signatures".to_string()));
+    }
+
+    Ok(signatures)
+}
+
+/// Parses the MuSig2 [`PubNonce`]s of every escrow input, in input order,
+/// separated by commas or whitespace.
+pub(crate) fn parse_pub_nonces(input: &str) -> Result<Vec<PubNonce>, Error> {
+    let pub_nonces = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|pub_nonce| !pub_nonce.is_empty())
+        .map(PubNonce::from_hex)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::WrongInputs(format!("Invalid MuSig2 public nonce: {e}")))?;
+    if pub_nonces.is_empty() {
+        return Err(Error::WrongInputs("No MuSig2 public nonces".to_string()));
+    }
+
+    Ok(pub_nonces)
+}
+
+/// Parses the MuSig2 [`PartialSignature`]s of every escrow input, in input order,
+/// separated by commas or whitespace.
+pub(crate) fn parse_partial_signatures(input: &str) -> Result<Vec<PartialSignature>, Error> {
+    let partial_signatures = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|partial_signature| !partial_signature.is_empty())
+        .map(PartialSignature::from_hex)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::WrongInputs(format!("Invalid MuSig2 partial signature: {e}")))?;
+    if partial_signatures.is_empty() {
+        return Err(Error::WrongInputs(
+            "No MuSig2 partial signatures".to_string(),
+        ));
+    }
+
+    Ok(partial_signatures)
+}
+
+/// Parses the outputs that a transaction may spend, by their [`OutPoint`],
+/// from a [`Psbt`] with the `witness_utxo` or `non_witness_utxo` of its inputs,
+/// or from the funding [`Transaction`]s in hex separated by commas or whitespace.
+pub(crate) fn parse_spent_outputs(input: &str) -> Result<BTreeMap<OutPoint, TxOut>, Error> {
+    let input = input.trim();
+    if let Ok(psbt) = input.parse::<Psbt>() {
+        let mut outputs = BTreeMap::new();
+        for (txin, psbt_input) in psbt.unsigned_tx.input.iter().zip(&psbt.inputs) {
+            let outpoint = txin.previous_output;
+            let output = psbt_input.witness_utxo.clone().or_else(|| {
+                psbt_input
+                    .non_witness_utxo
+                    .as_ref()
+                    .filter(|tx| tx.compute_txid() == outpoint.txid)
+                    .and_then(|tx| tx.output.get(outpoint.vout as usize).cloned())
+            });
+            if let Some(output) = output {
+                outputs.insert(outpoint, output);
+            }
+        }
+        return Ok(outputs);
+    }
+
+    let txs = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tx| !tx.is_empty())
+        .map(consensus::encode::deserialize_hex::<Transaction>)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::WrongInputs(format!("Invalid funding transaction: {e}")))?;
+    if txs.is_empty() {
+        return Err(Error::WrongInputs("No funding transactions".to_string()));
+    }
+
+    Ok(txs
+        .iter()
+        .flat_map(|tx| {
+            let txid = tx.compute_txid();
+            tx.output.iter().enumerate().map(move |(vout, output)| {
+                (
+                    OutPoint {
+                        txid,
+                        vout: vout as u32,
+                    },
+                    output.clone(),
+                )
+            })
+        })
+        .collect())
+}
+
+/// Parses a [`Transaction`] in hex, or a [`Psbt`] with the outputs that it spends, see [`parse_spent_outputs`].
+///
+/// The transaction of a finalized PSBT has the final witnesses of its inputs.
+pub(crate) fn parse_transaction_or_psbt(
+    input: &str,
+) -> Result<(Transaction, BTreeMap<OutPoint, TxOut>), Error> {
+    let input = input.trim();
+    if let Ok(psbt) = input.parse::<Psbt>() {
+        let spent_outputs = parse_spent_outputs(input)?;
+        return Ok((psbt.extract_tx_unchecked_fee_rate(), spent_outputs));
+    }
+
+    let tx = consensus::encode::deserialize_hex::<Transaction>(input)
+        .map_err(|e| Error::WrongInputs(format!("Invalid transaction: {e}")))?;
+
+    Ok((tx, BTreeMap::new()))
+}
+
+/// Formats the [`schnorr::Signature`]s of every escrow input as [`parse_signatures`] parses them.
+pub(crate) fn format_signatures(signatures: &[schnorr::Signature]) -> String {
+    signatures
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Formats the MuSig2 [`PubNonce`]s of every escrow input as [`parse_pub_nonces`] parses them.
+pub(crate) fn format_pub_nonces(pub_nonces: &[PubNonce]) -> String {
+    pub_nonces
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Formats the MuSig2 [`PartialSignature`]s of every escrow input as [`parse_partial_signatures`] parses them.
+pub(crate) fn format_partial_signatures(partial_signatures: &[PartialSignature]) -> String {
+    partial_signatures
+        .iter()
+        .map(|partial_signature| partial_signature.serialize().to_lower_hex_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 /// Parses a [`NostrPublicKey`] to an [`XOnlyPublicKey`].
 pub(crate) fn npub_to_x_only_public_key(npub: &NostrPublicKey) -> Result<XOnlyPublicKey, Error> {
     Ok(npub.xonly()?)
 }
 
-/// Parses a [`NostrPublicKey`] to an [`XOnlyPublicKey`].
-#[allow(dead_code)]
+/// Derives the [`XOnlyPublicKey`] of a [`NostrSecretKey`].
 pub(crate) fn nsec_to_x_only_public_key(nsec: &NostrSecretKey) -> XOnlyPublicKey {
     let (x_only_pk, _) = nsec.x_only_public_key(SECP256K1);
     x_only_pk
@@ -96,6 +389,146 @@ pub(crate) fn npub_to_address(npub: &NostrPublicKey, network: Network) -> Result
     Ok(address)
 }
 
+/// The [`Address`] that a party is paid to on `network`:
+/// its `payout_address` if any, otherwise the resolution address derived from its `npub`.
+///
+/// # Errors
+///
+/// Errors if the payout address is not valid for `network`.
+pub(crate) fn resolution_address(
+    npub: &NostrPublicKey,
+    payout_address: Option<&Address>,
+    network: Network,
+) -> Result<Address, Error> {
+    match payout_address {
+        Some(address) if address.as_unchecked().is_valid_for_network(network) => {
+            Ok(address.clone())
+        }
+        Some(address) => Err(Error::InvalidPayoutAddress(format!(
+            "{address} is not valid for {network}"
+        ))),
+        None => npub_to_address(npub, network),
+    }
+}
+
+/// Parses a payout address, or a single-key output descriptor, into an [`Address`] for `network`.
+///
+/// The supported descriptors are `addr(ADDRESS)`, `tr(KEY)`, `wpkh(KEY)` and `pkh(KEY)`,
+/// with an optional BIP380 checksum.
+/// `KEY` is a hex public key, that can be x-only for `tr`, with an optional key origin.
+/// Extended keys would need a derivation index, hence they are not supported.
+pub(crate) fn parse_payout_address(
+    payout_address: &str,
+    network: Network,
+) -> Result<Address, Error> {
+    let invalid = |reason: &str| Error::InvalidPayoutAddress(format!("{reason}: {payout_address}"));
+    let parse_address = |address: &str| {
+        address
+            .parse::<Address<_>>()
+            .map_err(|_| invalid("Invalid address"))?
+            .require_network(network)
+            .map_err(|_| invalid("Address is not valid for the network"))
+    };
+    let payout_address = payout_address.trim();
+    if !payout_address.contains('(') {
+        return parse_address(payout_address);
+    }
+
+    let descriptor = match payout_address.split_once('#') {
+        Some((descriptor, checksum)) => {
+            if descriptor_checksum(descriptor).as_deref() != Some(checksum) {
+                return Err(invalid("Invalid descriptor checksum"));
+            }
+            descriptor
+        }
+        None => payout_address,
+    };
+    let Some((function, argument)) = descriptor
+        .strip_suffix(')')
+        .and_then(|descriptor| descriptor.split_once('('))
+    else {
+        return Err(invalid("Invalid descriptor"));
+    };
+    let key = match argument.strip_prefix('[') {
+        Some(origin) => match origin.split_once(']') {
+            Some((_, key)) => key,
+            None => return Err(invalid("Invalid key origin")),
+        },
+        None => argument,
+    };
+    let unsupported_key = || invalid("Only hex public keys are supported");
+    match function {
+        "addr" => parse_address(argument),
+        "tr" => {
+            let x_only_pk = match key.parse::<XOnlyPublicKey>() {
+                Ok(x_only_pk) => x_only_pk,
+                Err(_) => key
+                    .parse::<PublicKey>()
+                    .map_err(|_| unsupported_key())?
+                    .into(),
+            };
+            Ok(Address::p2tr(SECP256K1, x_only_pk, None, network))
+        }
+        "wpkh" => Ok(Address::p2wpkh(
+            &key.parse::<CompressedPublicKey>()
+                .map_err(|_| unsupported_key())?,
+            network,
+        )),
+        "pkh" => Ok(Address::p2pkh(
+            key.parse::<PublicKey>().map_err(|_| unsupported_key())?,
+            network,
+        )),
+        _ => Err(invalid("Unsupported descriptor")),
+    }
+}
+
+/// Computes the BIP380 checksum of a `descriptor`,
+/// or `None` if it has a character that is not allowed in descriptors.
+fn descriptor_checksum(descriptor: &str) -> Option<String> {
+    fn polymod(c: u64, value: u64) -> u64 {
+        const GENERATORS: [u64; 5] = [
+            0xf5_dee5_1989,
+            0xa9_fdca_3312,
+            0x1b_ab10_e32d,
+            0x37_06b1_677a,
+            0x64_4d62_6ffd,
+        ];
+        let c0 = c >> 35;
+        let mut c = ((c & 0x7_ffff_ffff) << 5) ^ value;
+        for (bit, generator) in GENERATORS.into_iter().enumerate() {
+            if (c0 >> bit) & 1 == 1 {
+                c ^= generator;
+            }
+        }
+        c
+    }
+
+    let (mut c, mut class, mut class_count) = (1, 0, 0);
+    for character in descriptor.chars() {
+        let position = DESCRIPTOR_INPUT_CHARSET.find(character)? as u64;
+        c = polymod(c, position & 31);
+        class = class * 3 + (position >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            c = polymod(c, class);
+            (class, class_count) = (0, 0);
+        }
+    }
+    if class_count > 0 {
+        c = polymod(c, class);
+    }
+    for _ in 0..8 {
+        c = polymod(c, 0);
+    }
+    c ^= 1;
+
+    Some(
+        (0..8)
+            .map(|i| DESCRIPTOR_CHECKSUM_CHARSET[((c >> (5 * (7 - i))) & 31) as usize] as char)
+            .collect(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,4 +560,187 @@ mod tests {
         let expected = "bc1pdx0h0xkeyhx79ethugtrutlxvcswffcwa9sx823dyn09wkexdwass7v98m";
         assert_eq!(address.to_string(), expected);
     }
+
+    #[test]
+    fn parse_timelocks() {
+        assert_eq!(
+            parse_timelock("Duration", "1", "2", "").unwrap(),
+            Timelock::Relative(relative::LockTime::from_height(156))
+        );
+        // 26 hours are 182.8125 intervals of 512 seconds.
+        assert_eq!(
+            parse_timelock("Time", "1", "2", "").unwrap(),
+            Timelock::Relative(relative::LockTime::from_512_second_intervals(183))
+        );
+        assert_eq!(
+            parse_timelock("Height", "", "", "900000").unwrap(),
+            Timelock::Absolute(absolute::LockTime::from_height(900_000).unwrap())
+        );
+        assert_eq!(
+            parse_timelock("Date", "", "", "2026-12-01").unwrap(),
+            Timelock::Absolute(absolute::LockTime::from_time(1_796_083_200).unwrap())
+        );
+        assert_eq!(
+            parse_date("2024-02-29").unwrap(),
+            absolute::LockTime::from_time(1_709_164_800).unwrap()
+        );
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("1985-01-01").is_err());
+        assert!(parse_date("tomorrow").is_err());
+        assert!(parse_height("0").is_err());
+        assert!(parse_height("500000000").is_err());
+        assert!(parse_timelock("Never", "", "", "").is_err());
+    }
+
+    #[test]
+    fn relative_lock_time_bounds() {
+        // 455 days are 65,520 blocks, 456 days would wrap the 16 bits of BIP68.
+        assert_eq!(
+            days_hours_to_relative_lock_time(455, 2, false).unwrap(),
+            relative::LockTime::from_height(65_532)
+        );
+        assert!(days_hours_to_relative_lock_time(455, 3, false).is_err());
+        assert!(days_hours_to_relative_lock_time(1_000, 0, false).is_err());
+
+        // 388 days are 65,475 intervals of 512 seconds, 389 days are 65,644.
+        let lock_time = days_hours_to_relative_lock_time(388, 0, true).unwrap();
+        assert_eq!(
+            lock_time,
+            relative::LockTime::from_512_second_intervals(65_475)
+        );
+        assert!(lock_time.to_sequence().is_time_locked());
+        assert!(days_hours_to_relative_lock_time(389, 0, true).is_err());
+        assert!(days_hours_to_relative_lock_time(u32::MAX, 0, true).is_err());
+    }
+
+    #[test]
+    fn relative_lock_time_errors() {
+        // The error names the duration that does not fit.
+        assert!(matches!(
+            days_hours_to_relative_lock_time(456, 0, false),
+            Err(Error::InvalidTimelock(duration)) if duration == "456 days and 0 hours"
+        ));
+        // Hours alone can overflow too, in either unit.
+        assert!(days_hours_to_relative_lock_time(0, u32::MAX, false).is_err());
+        assert!(days_hours_to_relative_lock_time(0, u32::MAX, true).is_err());
+        assert!(days_hours_to_relative_lock_time(0, 24 * 456, false).is_err());
+
+        // The same duration in blocks and in 512-second intervals never imply each other.
+        let blocks = days_hours_to_relative_lock_time(1, 0, false).unwrap();
+        let time = days_hours_to_relative_lock_time(1, 0, true).unwrap();
+        assert!(blocks.to_sequence().is_height_locked());
+        assert!(!time.is_implied_by(blocks));
+        assert!(!blocks.is_implied_by(time));
+    }
+
+    #[test]
+    fn parse_fee_rates() {
+        assert_eq!(
+            parse_fee_rate("1").unwrap(),
+            FeeRate::from_sat_per_vb_unchecked(1)
+        );
+        assert_eq!(parse_fee_rate(" 2.5 ").unwrap().to_sat_per_kwu(), 625);
+        // Rounded up to the next sat/kwu.
+        assert_eq!(parse_fee_rate("0.101").unwrap().to_sat_per_kwu(), 26);
+        for invalid in ["", "0", "-1", "abc", "NaN", "inf", "1e12"] {
+            assert!(parse_fee_rate(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_percents() {
+        assert_eq!(parse_percent("70").unwrap(), 7_000);
+        assert_eq!(parse_percent(" 33.33 ").unwrap(), 3_333);
+        assert_eq!(parse_percent("0.5").unwrap(), 50);
+        assert_eq!(parse_percent("100").unwrap(), 10_000);
+        for invalid in [
+            "", "0", "0.00", "100.01", "-1", "1.234", "1.x", "abc", "1e2",
+        ] {
+            assert!(parse_percent(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_signature_lists() {
+        let signature = "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0";
+        let signatures = parse_signatures(&format!("{signature}, {signature}\n")).unwrap();
+        assert_eq!(signatures.len(), 2);
+        assert_eq!(
+            parse_signatures(&format_signatures(&signatures)).unwrap(),
+            signatures
+        );
+        assert_eq!(parse_signatures(signature).unwrap().len(), 1);
+        for invalid in ["", " , ", "abc", &format!("{signature},abc")] {
+            assert!(parse_signatures(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn parse_payout_addresses() {
+        // The BIP380 example.
+        assert_eq!(
+            descriptor_checksum("raw(deadbeef)").as_deref(),
+            Some("89f8spxm")
+        );
+
+        // A plain address of the network.
+        let address = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu";
+        assert_eq!(
+            parse_payout_address(address, Network::Bitcoin)
+                .unwrap()
+                .to_string(),
+            address
+        );
+        assert!(matches!(
+            parse_payout_address(address, Network::Testnet),
+            Err(Error::InvalidPayoutAddress(_))
+        ));
+
+        // The BIP84 receive address of the same key.
+        let wpkh = "wpkh([73c5da0a/84'/0'/0']0330d54fd0dd420a6e5f8d3624f5f3482cae350f79d5f0753bf5beef9c2d91af3c)";
+        let checksum = descriptor_checksum(wpkh).unwrap();
+        for descriptor in [wpkh.to_string(), format!("{wpkh}#{checksum}")] {
+            assert_eq!(
+                parse_payout_address(&descriptor, Network::Bitcoin)
+                    .unwrap()
+                    .to_string(),
+                address
+            );
+        }
+        assert!(matches!(
+            parse_payout_address(&format!("{wpkh}#qqqqqqqq"), Network::Bitcoin),
+            Err(Error::InvalidPayoutAddress(_))
+        ));
+
+        // A key path only Taproot descriptor pays like the npub of the same key.
+        let npub =
+            parse_npub("npub1tv7hxxwtw4gcz4n6fpduads7lsmynh5pjedgfhvdctnulrz9rsksjx28xe").unwrap();
+        assert_eq!(
+            parse_payout_address(
+                "tr(5b3d7319cb755181567a485bceb61efc3649de81965a84dd8dc2e7cf8c451c2d)",
+                Network::Signet
+            )
+            .unwrap(),
+            npub_to_address(&npub, Network::Signet).unwrap()
+        );
+        assert_eq!(
+            parse_payout_address(&format!("addr({address})"), Network::Bitcoin)
+                .unwrap()
+                .to_string(),
+            address
+        );
+
+        // Extended keys, scripts and garbage are rejected.
+        for invalid in [
+            "tr(xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL/0/*)",
+            "wsh(multi(1,0330d54fd0dd420a6e5f8d3624f5f3482cae350f79d5f0753bf5beef9c2d91af3c))",
+            "wpkh(0330d54f)",
+            "not an address",
+        ] {
+            assert!(matches!(
+                parse_payout_address(invalid, Network::Bitcoin),
+                Err(Error::InvalidPayoutAddress(_))
+            ));
+        }
+    }
 }
@@ -1,25 +1,40 @@
 //! Validation logic for form fields and user input.
 use crate::NETWORK;
 use crate::error::ValidationError;
-use crate::util::{parse_network, parse_npub, parse_nsec};
-use bitcoin::{Address, Amount, FeeRate, Transaction, Txid};
+use crate::util::{
+    parse_date, parse_fee_rate, parse_height, parse_network, parse_npub, parse_nsec,
+    parse_partial_signatures, parse_payout_address, parse_percent, parse_pub_nonces,
+    parse_signatures, parse_spent_outputs, parse_transaction_or_psbt,
+};
+use bitcoin::{Address, Amount, Psbt, Transaction, Txid};
 use dioxus::signals::ReadableExt;
-use secp256k1::schnorr;
 
 /// Represents the type of field to be validated in forms and inputs.
 #[derive(Debug)]
 pub(crate) enum ValidationField {
     Npub,
     Amount,
+    Sats,
+    Percent,
     FeeRate,
     Nsec,
     Txid,
+    Vout,
     Transaction,
+    Psbt,
+    TransactionOrPsbt,
+    SpentOutputs,
     Signature,
+    PubNonce,
+    PartialSignature,
     Address,
+    PayoutAddress,
     Url,
     TimelockDays,
     TimelockHours,
+    TimelockHeight,
+    TimelockDate,
+    Threshold,
 }
 
 /// Validates a given input string according to the specified `ValidationField` type.
@@ -52,17 +67,21 @@ pub(crate) fn validate_input(
             .and_then(|v| Amount::from_btc(v).ok())
             .filter(|a| a.to_btc() >= 0.00000001 && a.to_btc() <= 100.0)
             .is_some(),
-        ValidationField::FeeRate => input
-            .parse::<u64>()
-            .ok()
-            .filter(|v| *v > 0 && FeeRate::from_sat_per_vb(*v).is_some())
-            .is_some(),
+        ValidationField::Sats => input.trim().parse::<u64>().is_ok(),
+        ValidationField::Percent => parse_percent(input).is_ok(),
+        ValidationField::FeeRate => parse_fee_rate(input).is_ok(),
         ValidationField::Nsec => parse_nsec(input).is_ok(),
         ValidationField::Txid => input.parse::<Txid>().is_ok(),
+        ValidationField::Vout => input.trim().parse::<u32>().is_ok(),
         ValidationField::Transaction => {
             bitcoin::consensus::encode::deserialize_hex::<Transaction>(input).is_ok()
         }
-        ValidationField::Signature => input.parse::<schnorr::Signature>().is_ok(),
+        ValidationField::Psbt => input.trim().parse::<Psbt>().is_ok(),
+        ValidationField::TransactionOrPsbt => parse_transaction_or_psbt(input).is_ok(),
+        ValidationField::SpentOutputs => parse_spent_outputs(input).is_ok(),
+        ValidationField::Signature => parse_signatures(input).is_ok(),
+        ValidationField::PubNonce => parse_pub_nonces(input).is_ok(),
+        ValidationField::PartialSignature => parse_partial_signatures(input).is_ok(),
         ValidationField::Address => input
             .parse::<Address<_>>()
             .and_then(|a| {
@@ -72,26 +91,50 @@ pub(crate) fn validate_input(
                 )
             })
             .is_ok(),
+        ValidationField::PayoutAddress => parse_payout_address(
+            input,
+            parse_network(&NETWORK.try_read().expect("Failed to read network"))
+                .expect("Failed to parse network"),
+        )
+        .is_ok(),
         ValidationField::Url => input.starts_with("http://") || input.starts_with("https://"),
-        ValidationField::TimelockDays => {
-            input.parse::<u32>().ok().filter(|d| *d <= 1_000).is_some()
-        }
+        ValidationField::TimelockDays => input.parse::<u32>().ok().filter(|d| *d <= 455).is_some(),
         ValidationField::TimelockHours => input.parse::<u32>().ok().filter(|h| *h < 24).is_some(),
+        ValidationField::TimelockHeight => parse_height(input).is_ok(),
+        ValidationField::TimelockDate => parse_date(input).is_ok(),
+        ValidationField::Threshold => input
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|t| *t > 0)
+            .is_some(),
     };
 
     if !is_valid {
         return Err(match field {
             ValidationField::Npub => ValidationError::InvalidNpub,
             ValidationField::Amount => ValidationError::InvalidAmount,
+            ValidationField::Sats => ValidationError::InvalidSats,
+            ValidationField::Percent => ValidationError::InvalidPercent,
             ValidationField::FeeRate => ValidationError::InvalidFeeRate,
             ValidationField::Nsec => ValidationError::InvalidNsec,
             ValidationField::Txid => ValidationError::InvalidTxid,
+            ValidationField::Vout => ValidationError::InvalidVout,
             ValidationField::Transaction => ValidationError::InvalidTransaction,
+            ValidationField::Psbt => ValidationError::InvalidPsbt,
+            ValidationField::TransactionOrPsbt => ValidationError::InvalidTransactionOrPsbt,
+            ValidationField::SpentOutputs => ValidationError::InvalidSpentOutputs,
             ValidationField::Signature => ValidationError::InvalidSignature,
+            ValidationField::PubNonce => ValidationError::InvalidPubNonce,
+            ValidationField::PartialSignature => ValidationError::InvalidPartialSignature,
             ValidationField::Address => ValidationError::InvalidAddress,
+            ValidationField::PayoutAddress => ValidationError::InvalidPayoutAddress,
             ValidationField::Url => ValidationError::InvalidUrl,
             ValidationField::TimelockDays => ValidationError::InvalidTimelockDays,
             ValidationField::TimelockHours => ValidationError::InvalidTimelockHours,
+            ValidationField::TimelockHeight => ValidationError::InvalidTimelockHeight,
+            ValidationField::TimelockDate => ValidationError::InvalidTimelockDate,
+            ValidationField::Threshold => ValidationError::InvalidThreshold,
         });
     }
 